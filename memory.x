@@ -53,8 +53,7 @@ SECTIONS
     .text :
     {
       . = ALIGN(4);               /* Align to 4 bytes */
-      LONG(_start_of_stack);      /* Initial stack pointer value for vector table (first entry) */
-      KEEP(*(.isr_vector));       /* Keep the interrupt vector table (provided by cortex-m-rt) */
+      KEEP(*(.isr_vector));       /* Vector table, initial stack pointer first (startup code) */
       *(.text)                    /* Main code from our Rust application */
       *(.text.*)                  /* Any additional code sections (e.g., from dependencies) */
       . = ALIGN(4);
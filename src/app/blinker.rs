@@ -0,0 +1,148 @@
+//! Non-blocking LED blink patterns.
+//!
+//! A `Blinker` is polled from the main loop with the current time from
+//! `millis()` and switches its LED whenever the current step of its pattern
+//! has elapsed, so nothing stalls in `delay_ms`.
+//!
+//! A pattern is a list of (LED on, duration) steps. Durations are counted in
+//! units of `period_ms`, so the same pattern can run faster or slower.
+//!
+//! Example:
+//! ```ignore
+//! let mut status = Blinker::with_pattern(LED_GREEN, 150, BlinkPattern::Heartbeat);
+//! status.start(millis());
+//! loop {
+//!     status.update(millis());
+//!     // ... other work, never blocking for long ...
+//! }
+//! ```
+
+use crate::app::led::Led;
+
+/// Steps of `BlinkPattern::Steady`: on and off for one period each.
+const STEADY_STEPS: [(bool, u32); 2] = [(true, 1), (false, 1)];
+
+/// Steps of `BlinkPattern::Heartbeat`: two short flashes, then a long pause.
+const HEARTBEAT_STEPS: [(bool, u32); 4] = [(true, 1), (false, 1), (true, 1), (false, 5)];
+
+/// Steps of `BlinkPattern::Sos`: `... --- ...` in Morse timing (dot = 1,
+/// dash = 3, gap inside a letter = 1, between letters = 3, between words = 7).
+const SOS_STEPS: [(bool, u32); 18] = [
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 3), // S
+    (true, 3),
+    (false, 1),
+    (true, 3),
+    (false, 1),
+    (true, 3),
+    (false, 3), // O
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 1),
+    (true, 1),
+    (false, 7), // S
+];
+
+/// Blink pattern a `Blinker` steps through.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BlinkPattern {
+    /// Toggle every period
+    Steady,
+    /// Double blink followed by a pause
+    Heartbeat,
+    /// Morse code SOS
+    Sos,
+}
+
+impl BlinkPattern {
+    /// Returns the (LED on, duration in periods) steps of the pattern.
+    pub const fn steps(self) -> &'static [(bool, u32)] {
+        match self {
+            BlinkPattern::Steady => &STEADY_STEPS,
+            BlinkPattern::Heartbeat => &HEARTBEAT_STEPS,
+            BlinkPattern::Sos => &SOS_STEPS,
+        }
+    }
+}
+
+/// Drives an LED through a `BlinkPattern` without blocking.
+pub struct Blinker {
+    /// The LED to blink
+    pub led: Led,
+    /// Duration of one pattern unit in milliseconds
+    pub period_ms: u32,
+    /// Time (from `millis()`) of the last LED change
+    pub last_toggle: u32,
+    /// Pattern being played
+    pattern: BlinkPattern,
+    /// Index of the current step in the pattern
+    step: usize,
+}
+
+impl Blinker {
+    /// Creates a blinker that toggles `led` every `period_ms`.
+    pub const fn new(led: Led, period_ms: u32) -> Self {
+        Self::with_pattern(led, period_ms, BlinkPattern::Steady)
+    }
+
+    /// Creates a blinker that plays `pattern` with units of `period_ms`.
+    pub const fn with_pattern(led: Led, period_ms: u32, pattern: BlinkPattern) -> Self {
+        Blinker {
+            led,
+            period_ms,
+            last_toggle: 0,
+            pattern,
+            step: 0,
+        }
+    }
+
+    /// Restarts the pattern at its first step.
+    ///
+    /// # Arguments
+    /// * `now_ms` - Current time, from `millis()`
+    pub fn start(&mut self, now_ms: u32) {
+        self.step = 0;
+        self.last_toggle = now_ms;
+        self.led.set(self.pattern.steps()[0].0);
+    }
+
+    /// Switches to another pattern and restarts it.
+    pub fn set_pattern(&mut self, pattern: BlinkPattern, now_ms: u32) {
+        self.pattern = pattern;
+        self.start(now_ms);
+    }
+
+    /// Returns the pattern being played.
+    pub fn pattern(&self) -> BlinkPattern {
+        self.pattern
+    }
+
+    /// Advances the pattern if the current step has elapsed.
+    ///
+    /// Call it from the main loop as often as possible; the LED timing is
+    /// only as accurate as the time between two calls. The wrapping
+    /// subtraction keeps it working when `millis()` wraps around.
+    ///
+    /// # Arguments
+    /// * `now_ms` - Current time, from `millis()`
+    ///
+    /// # Returns
+    /// * `true` if the LED was switched
+    pub fn update(&mut self, now_ms: u32) -> bool {
+        let steps = self.pattern.steps();
+        let (_, units) = steps[self.step];
+        if now_ms.wrapping_sub(self.last_toggle) < units.saturating_mul(self.period_ms) {
+            return false;
+        }
+
+        self.step = (self.step + 1) % steps.len();
+        self.last_toggle = now_ms;
+        self.led.set(steps[self.step].0);
+        true
+    }
+}
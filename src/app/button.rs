@@ -0,0 +1,106 @@
+//! Push button input with debounce and edge detection.
+//!
+//! Mechanical contacts bounce for a few milliseconds when pressed or
+//! released. `Button::is_pressed_debounced()` only reports a press after
+//! several consecutive samples agree, and `Button::poll_edge()` reports each
+//! press/release once when called periodically (every 10..20 ms, longer than
+//! the bounce time).
+//!
+//! Example (user button B1 on PA0, pressed = high):
+//! ```ignore
+//! dwt_init(); // for delay_us
+//! let mut button = Button::new(GPIOA_BASE, GPIO_PIN_0, true);
+//! button.init();
+//! loop {
+//!     if button.poll_edge() == Some(Edge::Pressed) {
+//!         led_toggle();
+//!     }
+//!     delay_ms(10);
+//! }
+//! ```
+
+use crate::bsw::dwt_cm4::delay_us;
+use crate::bsw::gpio::{GpioMode, GpioPull, gpio_get_pin_state, gpio_set_mode, gpio_set_pull};
+use crate::bsw::rcc::rcc_enable_gpio_clock;
+
+/// State change reported by `Button::poll_edge()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Edge {
+    Pressed,
+    Released,
+}
+
+/// A push button on a GPIO input pin.
+pub struct Button {
+    /// The base address of the GPIO port (e.g., `GPIOA_BASE`)
+    pub port: u32,
+    /// The pin number (0..15)
+    pub pin: u32,
+    /// `true` if the pin reads high while pressed
+    pub active_high: bool,
+    /// Pressed state seen by the last `poll_edge()` call
+    last_pressed: bool,
+}
+
+impl Button {
+    /// Creates a button handle. Call `init()` before use.
+    pub const fn new(port: u32, pin: u32, active_high: bool) -> Self {
+        Button {
+            port,
+            pin,
+            active_high,
+            last_pressed: false,
+        }
+    }
+
+    /// Configures the pin as input with a pull towards the released level
+    /// and samples the initial state for `poll_edge()`.
+    pub fn init(&mut self) {
+        rcc_enable_gpio_clock(self.port);
+        gpio_set_mode(self.port, self.pin, GpioMode::Input);
+        let pull = if self.active_high {
+            GpioPull::PullDown
+        } else {
+            GpioPull::PullUp
+        };
+        gpio_set_pull(self.port, self.pin, pull);
+        self.last_pressed = self.is_pressed();
+    }
+
+    /// Returns the raw pressed state (single IDR read, may bounce).
+    pub fn is_pressed(&self) -> bool {
+        gpio_get_pin_state(self.port, self.pin) == self.active_high
+    }
+
+    /// Returns true only if `samples` reads, `interval_us` apart, all see the
+    /// button pressed.
+    ///
+    /// # Arguments
+    /// * `samples`     - Number of reads (at least 1)
+    /// * `interval_us` - Time between two reads in microseconds (needs `dwt_init`)
+    pub fn is_pressed_debounced(&self, samples: u32, interval_us: u32) -> bool {
+        for i in 0..samples.max(1) {
+            if i > 0 {
+                delay_us(interval_us);
+            }
+            if !self.is_pressed() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns the edge since the previous call, if the state changed.
+    pub fn poll_edge(&mut self) -> Option<Edge> {
+        let pressed = self.is_pressed();
+        if pressed == self.last_pressed {
+            return None;
+        }
+        self.last_pressed = pressed;
+        Some(if pressed {
+            Edge::Pressed
+        } else {
+            Edge::Released
+        })
+    }
+}
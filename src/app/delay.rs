@@ -0,0 +1,64 @@
+//! Delay traits
+//!
+//! Drivers that need to wait take a `DelayMs`/`DelayUs` implementation
+//! instead of calling `systick_delay::delay_ms` or `dwt_cm4::delay_us`
+//! directly, so the timing source can be chosen by the application (or
+//! replaced by a mock that only records the requested delays).
+//!
+//! - `SysTickDelay`: millisecond delays from the SysTick tick (`systick_init`)
+//! - `DwtDelay`: microsecond (and millisecond) delays from the DWT cycle
+//!   counter (`dwt_init`)
+//!
+//! Example
+//! ```ignore
+//! fn sensor_reset(delay: &mut impl DelayMs) {
+//!     // ... pull reset low
+//!     delay.delay_ms(10);
+//!     // ... release reset
+//! }
+//!
+//! sensor_reset(&mut SysTickDelay);
+//! ```
+
+use crate::app::systick_delay;
+use crate::bsw::dwt_cm4;
+
+/// Blocking delay with millisecond resolution.
+pub trait DelayMs {
+    /// Waits for at least `ms` milliseconds.
+    fn delay_ms(&mut self, ms: u32);
+}
+
+/// Blocking delay with microsecond resolution.
+pub trait DelayUs {
+    /// Waits for at least `us` microseconds.
+    fn delay_us(&mut self, us: u32);
+}
+
+/// Delays based on the SysTick millisecond tick. Requires `systick_init`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SysTickDelay;
+
+impl DelayMs for SysTickDelay {
+    fn delay_ms(&mut self, ms: u32) {
+        systick_delay::delay_ms(ms);
+    }
+}
+
+/// Delays based on the DWT cycle counter. Requires `dwt_init`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DwtDelay;
+
+impl DelayUs for DwtDelay {
+    fn delay_us(&mut self, us: u32) {
+        dwt_cm4::delay_us(us);
+    }
+}
+
+impl DelayMs for DwtDelay {
+    fn delay_ms(&mut self, ms: u32) {
+        for _ in 0..ms {
+            dwt_cm4::delay_us(1000);
+        }
+    }
+}
@@ -1,34 +1,84 @@
 use crate::bsw::gpio::{
-    GPIO_PIN_13, GpioType, PinState, gpio_set_mode_output, gpio_set_pin_state, gpio_set_type,
+    GPIO_PIN_13, GPIO_PIN_14, GpioType, PinState, gpio_set_mode_output, gpio_set_pin_state,
+    gpio_set_type, gpio_toggle_pin,
 };
 use crate::bsw::rcc::rcc_enable_gpio_clock;
 use crate::bsw::reg_mcu_stm32f429zi::*;
 
-// Initialize the user GREEN LED GPIO pin ( PORT G, PIN 13 )
-pub fn led_init() {
-    // 1. Turn on the clock for the GPIO port
-    rcc_enable_gpio_clock(GPIOG_BASE);
+/// An LED on a GPIO pin.
+///
+/// `active_high` tells how the LED is wired: `true` if driving the pin high
+/// turns it on (LED to ground), `false` if driving it low does (LED to VDD).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Led {
+    pub port: u32,
+    pub pin: u32,
+    pub active_high: bool,
+}
+
+/// User GREEN LED LD3 (PORT G, PIN 13).
+pub const LED_GREEN: Led = Led::new(GPIOG_BASE, GPIO_PIN_13, true);
+/// User RED LED LD4 (PORT G, PIN 14).
+pub const LED_RED: Led = Led::new(GPIOG_BASE, GPIO_PIN_14, true);
+
+impl Led {
+    pub const fn new(port: u32, pin: u32, active_high: bool) -> Self {
+        Led {
+            port,
+            pin,
+            active_high,
+        }
+    }
+
+    // Configure the LED pin as output (the LED stays off)
+    pub fn init(&self) {
+        // 1. Turn on the clock for the GPIO port
+        rcc_enable_gpio_clock(self.port);
+
+        // 2. Start with the LED off, then set the pin as an output
+        self.off();
+        gpio_set_mode_output(self.port, self.pin);
 
-    // 2. Set the pin as an output (so you can control it)
-    gpio_set_mode_output(GPIOG_BASE, GPIO_PIN_13);
+        // 3. Make sure the pin is in push-pull mode (best for LEDs)
+        gpio_set_type(self.port, self.pin, GpioType::PushPull);
+    }
 
-    // 3. Make sure the pin is in push-pull mode (best for LEDs)
-    gpio_set_type(GPIOG_BASE, GPIO_PIN_13, GpioType::PushPull);
+    pub fn on(&self) {
+        self.set(true);
+    }
 
-    // 4. (Optional) Set the output speed if needed
+    pub fn off(&self) {
+        self.set(false);
+    }
+
+    pub fn toggle(&self) {
+        gpio_toggle_pin(self.port, self.pin);
+    }
+
+    // Turn the LED on (true) or off (false), whatever the wiring
+    pub fn set(&self, state: bool) {
+        let level = if state == self.active_high {
+            PinState::High
+        } else {
+            PinState::Low
+        };
+        gpio_set_pin_state(self.port, self.pin, level);
+    }
+}
+
+// Initialize the user GREEN LED GPIO pin ( PORT G, PIN 13 )
+pub fn led_init() {
+    LED_GREEN.init();
 }
 
 pub fn led_on() {
-    // Code to turn on the LED
-    gpio_set_pin_state(GPIOG_BASE, GPIO_PIN_13, PinState::High);
+    LED_GREEN.on();
 }
 
 pub fn led_off() {
-    // Code to turn off the LED
-    gpio_set_pin_state(GPIOG_BASE, GPIO_PIN_13, PinState::Low);
+    LED_GREEN.off();
 }
 
 pub fn led_toggle() {
-    // Code to toggle the LED state
-    gpio_set_pin_state(GPIOG_BASE, GPIO_PIN_13, PinState::Toggle);
+    LED_GREEN.toggle();
 }
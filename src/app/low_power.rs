@@ -0,0 +1,96 @@
+//! Low-power run mode for power profiling and battery demos.
+//!
+//! `pwr_enter_low_power_run()` remembers the current PLL clock, drops SYSCLK
+//! to the 16 MHz HSI with the PLL off and puts the regulator into its
+//! lowest Run configuration (`pwr_enable_low_power_run`).
+//! `pwr_exit_low_power_run()` brings the saved PLL clock back through
+//! `system_clock_configure`.
+//!
+//! Constraints while in low-power run:
+//! - The STM32F429 has no LPRUN regulator mode (that is an STM32L feature).
+//!   The lowest Run consumption is voltage scale 3 with over-drive off,
+//!   which limits HCLK to 120 MHz; here it is 16 MHz.
+//! - With the PLL off there is no 48 MHz clock: USB, SDIO and RNG stop.
+//! - The APB prescalers are kept, so PCLK1/PCLK2 drop with HCLK. Drivers
+//!   that derive their timing from the bus clock (USART baud rate, I2C
+//!   timing, timer prescalers) must be reconfigured after each switch.
+//! - SysTick is reprogrammed for the new HCLK, so `millis` and the delays
+//!   keep their rate.
+//!
+//! Example:
+//! ```ignore
+//! pwr_enter_low_power_run()?;
+//! // ... measure the idle current ...
+//! pwr_exit_low_power_run()?;
+//! ```
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::app::system_clock::*;
+use crate::app::systick_delay::systick_set_clock_hz;
+use crate::bsw::error::Result;
+use crate::bsw::flash::flash_configure_for_clock;
+use crate::bsw::pwr::pwr_enable_low_power_run;
+use crate::bsw::rcc::*;
+use crate::bsw::reg_mcu_stm32f429zi::*;
+use crate::bsw::reg_utils::*;
+
+/// SYSCLK to restore on exit; 0 if the PLL was not running on entry.
+static SAVED_SYSCLK_HZ: AtomicU32 = AtomicU32::new(0);
+/// PLL source to restore on exit (true = HSE).
+static SAVED_SOURCE_HSE: AtomicBool = AtomicBool::new(false);
+
+/// Switches to the low-power run configuration (16 MHz HSI, scale 3).
+///
+/// The PLL configuration in effect is saved for `pwr_exit_low_power_run`.
+/// Calling it again while already in low-power run keeps the first saved
+/// configuration.
+///
+/// # Returns
+/// * The bus clocks in low-power run
+/// * `Err(BswError::Timeout)` if SYSCLK did not switch to HSI; the clock
+///   configuration is then unchanged
+pub fn pwr_enter_low_power_run() -> Result<ClockFrequencies> {
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    if reg_read_bits(cfgr_addr, RCC_CFGR_SWS_POS, 2) == RCC_CFGR_SWS_PLL {
+        let pllcfgr_addr = (RCC_BASE + RCC_PLLCFGR) as *mut u32;
+        SAVED_SOURCE_HSE.store(
+            reg_read_bit(pllcfgr_addr, RCC_PLLCFGR_PLLSRC_POS),
+            Ordering::Relaxed,
+        );
+        SAVED_SYSCLK_HZ.store(rcc_get_sysclk_hz(), Ordering::Relaxed);
+    }
+
+    // Flash wait states stay at the PLL setting until HCLK has dropped
+    rcc_switch_to_hsi()?;
+    rcc_enable_power_clock();
+    pwr_enable_low_power_run();
+    flash_configure_for_clock(rcc_get_hclk_hz(), BOARD_VOLTAGE);
+    systick_set_clock_hz(rcc_get_hclk_hz());
+
+    Ok(system_clock_frequencies())
+}
+
+/// Restores the PLL clock saved by `pwr_enter_low_power_run`.
+///
+/// Does nothing (apart from returning the current clocks) if the PLL was
+/// not running on entry or low-power run was not entered.
+///
+/// # Returns
+/// * The restored bus clocks
+/// * The error of `system_clock_configure`; SYSCLK then stays on HSI
+pub fn pwr_exit_low_power_run() -> Result<ClockFrequencies> {
+    let sysclk_hz = SAVED_SYSCLK_HZ.swap(0, Ordering::Relaxed);
+    if sysclk_hz == 0 {
+        return Ok(system_clock_frequencies());
+    }
+
+    let source = if SAVED_SOURCE_HSE.load(Ordering::Relaxed) {
+        PllSource::Hse
+    } else {
+        PllSource::Hsi
+    };
+    let result = system_clock_configure(SysClockConfig { source, sysclk_hz });
+    systick_set_clock_hz(rcc_get_hclk_hz());
+    result
+}
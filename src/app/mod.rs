@@ -1,3 +1,11 @@
+pub mod blinker;
+pub mod button;
+pub mod delay;
 pub mod led;
+pub mod low_power;
+pub mod set_timers;
+pub mod soft_encoder;
 pub mod system_clock;
 pub mod systick_delay;
+#[cfg(feature = "watchdog-selftest")]
+pub mod watchdog_selftest;
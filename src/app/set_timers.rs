@@ -1,4 +1,48 @@
-fn configure_timer_interrupt() {
-    // Configure the timer interrupt for toggling the LED
-    // This is a placeholder; actual implementation will depend on the MCU and peripherals
-}
\ No newline at end of file
+//! Periodic tick from the basic timer TIM7, independent of SysTick.
+//!
+//! Example of combining `timer_basic_init` with the NVIC: TIM7 raises its
+//! update interrupt at the requested rate and `TIM7_Handler` counts ticks.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::bsw::nvic_cm4::nvic_enable_irq;
+use crate::bsw::rcc::rcc_get_apb1_timer_clk_hz;
+use crate::bsw::reg_mcu_stm32f429zi::*;
+use crate::bsw::tim::{timer_basic_init, timer_clear_update_flag};
+
+/// Ticks counted by `TIM7_Handler` since `timer_tick_init`.
+static TIMER_TICKS: AtomicU32 = AtomicU32::new(0);
+
+/// TIM7 counter clock after the prescaler.
+const TIMER_TICK_CLOCK_HZ: u32 = 1_000_000;
+
+/// Starts TIM7 as a periodic interrupt source.
+///
+/// # Arguments
+/// * `tick_hz` - Interrupt rate (16 Hz..1 MHz, e.g., 1000 for a 1 ms tick)
+pub fn timer_tick_init(tick_hz: u32) {
+    assert!(
+        (16..=TIMER_TICK_CLOCK_HZ).contains(&tick_hz),
+        "tick rate must be 16 Hz..1 MHz"
+    );
+
+    // 1 MHz counter clock, e.g. 90 MHz / 90; period in microseconds
+    let prescaler = rcc_get_apb1_timer_clk_hz() / TIMER_TICK_CLOCK_HZ - 1;
+    let period = TIMER_TICK_CLOCK_HZ / tick_hz - 1;
+
+    TIMER_TICKS.store(0, Ordering::Relaxed);
+    timer_basic_init(TIM7_BASE, prescaler, period);
+    nvic_enable_irq(IRQn::TIM7);
+}
+
+/// Returns the number of ticks since `timer_tick_init`.
+pub fn timer_ticks() -> u32 {
+    TIMER_TICKS.load(Ordering::Relaxed)
+}
+
+/// TIM7 update interrupt: clears UIF and counts the tick.
+#[unsafe(no_mangle)]
+pub extern "C" fn TIM7_Handler() {
+    timer_clear_update_flag(TIM7_BASE);
+    TIMER_TICKS.fetch_add(1, Ordering::Relaxed);
+}
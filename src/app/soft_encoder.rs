@@ -0,0 +1,186 @@
+//! Software quadrature encoder decoder.
+//!
+//! Fallback for when no timer is free for the hardware encoder mode: the A and
+//! B signals are connected to two GPIO pins with EXTI interrupts on both edges,
+//! and each edge calls `SoftEncoder::on_edge()`.
+//!
+//! Decoding uses the standard 16-entry state-transition table indexed by
+//! `(previous AB << 2) | current AB`. Valid Gray-code steps count +1/-1; a
+//! transition where both signals changed at once means an edge was missed
+//! (bounce, interrupt latency too high) and is counted as an error instead.
+//!
+//! The count changes by 4 per full quadrature cycle (one detent on most
+//! mechanical encoders). Which direction counts up depends on the wiring;
+//! swap A and B to invert it.
+//!
+//! Example (EXTI lines for PA1/PA2 configured for both edges):
+//! ```ignore
+//! let mut enc = SoftEncoder::new(GpioPin::new(GPIOA_BASE, 1), GpioPin::new(GPIOA_BASE, 2));
+//! enc.init();
+//! // In the EXTI1 and EXTI2 handlers:
+//! enc.on_edge();
+//! // In the main loop:
+//! let position = enc.read();
+//! ```
+
+use crate::bsw::gpio::GpioPin;
+
+/// Marker for an invalid transition in `TRANSITION_TABLE`.
+const INVALID: i8 = 2;
+
+/// Count delta for each `(previous << 2) | current` state pair, where a state
+/// is `(A << 1) | B`. Forward sequence: 00 -> 01 -> 11 -> 10 -> 00.
+const TRANSITION_TABLE: [i8; 16] = [
+    0,       // 00 -> 00
+    1,       // 00 -> 01
+    -1,      // 00 -> 10
+    INVALID, // 00 -> 11
+    -1,      // 01 -> 00
+    0,       // 01 -> 01
+    INVALID, // 01 -> 10
+    1,       // 01 -> 11
+    1,       // 10 -> 00
+    INVALID, // 10 -> 01
+    0,       // 10 -> 10
+    -1,      // 10 -> 11
+    INVALID, // 11 -> 00
+    -1,      // 11 -> 01
+    1,       // 11 -> 10
+    0,       // 11 -> 11
+];
+
+/// Quadrature decoder driven by GPIO edge interrupts.
+pub struct SoftEncoder {
+    /// Channel A input pin
+    pub a: GpioPin,
+    /// Channel B input pin
+    pub b: GpioPin,
+    /// Current position
+    count: i32,
+    /// Last sampled state `(A << 1) | B`
+    state: u8,
+    /// Number of invalid transitions seen (missed edges)
+    errors: u32,
+}
+
+impl SoftEncoder {
+    /// Creates a decoder for the given A/B pins. Call `init()` before use.
+    pub const fn new(a: GpioPin, b: GpioPin) -> Self {
+        SoftEncoder {
+            a,
+            b,
+            count: 0,
+            state: 0,
+            errors: 0,
+        }
+    }
+
+    /// Samples the current pin levels as the starting state.
+    ///
+    /// The pins must already be configured as inputs (with pull-ups for
+    /// open-collector encoders).
+    pub fn init(&mut self) {
+        self.state = Self::encode(self.a.read(), self.b.read());
+    }
+
+    /// Reads the pins and updates the count. Call from the EXTI handler(s)
+    /// of both the A and the B pin.
+    pub fn on_edge(&mut self) {
+        let (a, b) = (self.a.read(), self.b.read());
+        self.step(a, b);
+    }
+
+    /// Feeds one sampled A/B state into the decoder (pure state machine,
+    /// used by `on_edge`).
+    pub fn step(&mut self, a: bool, b: bool) {
+        let new_state = Self::encode(a, b);
+        let index = ((self.state << 2) | new_state) as usize;
+
+        match TRANSITION_TABLE[index] {
+            INVALID => self.errors = self.errors.wrapping_add(1),
+            delta => self.count = self.count.wrapping_add(delta as i32),
+        }
+        self.state = new_state;
+    }
+
+    /// Returns the current position count.
+    pub fn read(&self) -> i32 {
+        self.count
+    }
+
+    /// Sets the position count (e.g., 0 for homing).
+    pub fn set(&mut self, count: i32) {
+        self.count = count;
+    }
+
+    /// Returns the number of invalid transitions seen since creation.
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// Packs the two signals into a 2-bit state.
+    const fn encode(a: bool, b: bool) -> u8 {
+        ((a as u8) << 1) | (b as u8)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Unit tests (host): cargo test --target x86_64-unknown-linux-gnu
+// -----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsw::reg_mcu_stm32f429zi::GPIOA_BASE;
+
+    /// Forward sequence as (A, B): 00 -> 01 -> 11 -> 10 -> 00.
+    const FORWARD: [(bool, bool); 4] = [(false, true), (true, true), (true, false), (false, false)];
+
+    /// Decoder starting in state 00 (`step` never touches the pins).
+    fn encoder() -> SoftEncoder {
+        SoftEncoder::new(GpioPin::new(GPIOA_BASE, 1), GpioPin::new(GPIOA_BASE, 2))
+    }
+
+    #[test]
+    fn full_forward_cycle_counts_four() {
+        let mut enc = encoder();
+        for (a, b) in FORWARD {
+            enc.step(a, b);
+        }
+        assert_eq!(enc.read(), 4);
+        assert_eq!(enc.errors(), 0);
+    }
+
+    #[test]
+    fn full_reverse_cycle_counts_minus_four() {
+        let mut enc = encoder();
+        // 00 -> 10 -> 11 -> 01 -> 00
+        for (a, b) in [(true, false), (true, true), (false, true), (false, false)] {
+            enc.step(a, b);
+        }
+        assert_eq!(enc.read(), -4);
+        assert_eq!(enc.errors(), 0);
+    }
+
+    #[test]
+    fn two_bit_jump_is_an_error_not_a_count() {
+        let mut enc = encoder();
+        enc.step(true, true); // 00 -> 11
+        assert_eq!(enc.read(), 0);
+        assert_eq!(enc.errors(), 1);
+
+        // Decoding continues from the new state: 11 -> 10 is forward
+        enc.step(true, false);
+        assert_eq!(enc.read(), 1);
+        assert_eq!(enc.errors(), 1);
+    }
+
+    #[test]
+    fn repeated_state_does_not_count() {
+        let mut enc = encoder();
+        enc.step(false, true);
+        enc.step(false, true);
+        enc.step(false, true);
+        assert_eq!(enc.read(), 1);
+        assert_eq!(enc.errors(), 0);
+    }
+}
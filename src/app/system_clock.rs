@@ -1,22 +1,119 @@
-use crate::bsw::flash::flash_set_wait_states;
-use crate::bsw::gpio::{
-    GPIO_PIN_8, PinSpeed, gpio_set_af, gpio_set_mode_alternate, gpio_set_speed,
+use crate::bsw::error::{BswError, Result};
+use crate::bsw::flash::{
+    VoltageRange, flash_configure_for_clock, flash_set_wait_states, flash_wait_states_for,
 };
+use crate::bsw::gpio::{GPIO_PIN_8, gpio_config_clock_out};
 use crate::bsw::pwr::*;
 use crate::bsw::rcc::*;
 use crate::bsw::reg_mcu_stm32f429zi::*;
 
-pub fn system_clock_setup() {
-    // Program flash wait states
-    flash_set_wait_states(5);
+/// Highest SYSCLK per regulator voltage scale (without over-drive).
+const SCALE2_MAX_HZ: u32 = 144_000_000;
+const SCALE1_MAX_HZ: u32 = 168_000_000;
+/// Supply of the STM32F429I-DISC1 (3 V).
+pub const BOARD_VOLTAGE: VoltageRange = VoltageRange::V2_7To3_6;
 
-    // Over drive settings
+/// Requested system clock.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SysClockConfig {
+    /// PLL input clock
+    pub source: PllSource,
+    /// SYSCLK (= HCLK), a multiple of 1 MHz up to 180 MHz
+    pub sysclk_hz: u32,
+}
+
+impl SysClockConfig {
+    /// 180 MHz from HSI, the maximum of the STM32F429 (needs over-drive).
+    pub const HSI_180MHZ: Self = SysClockConfig {
+        source: PllSource::Hsi,
+        sysclk_hz: 180_000_000,
+    };
+    /// 168 MHz from the 8 MHz HSE crystal (USB-friendly, no over-drive).
+    pub const HSE_168MHZ: Self = SysClockConfig {
+        source: PllSource::Hse,
+        sysclk_hz: 168_000_000,
+    };
+    /// 100 MHz from HSI at the lowest regulator voltage (scale 3).
+    pub const HSI_100MHZ: Self = SysClockConfig {
+        source: PllSource::Hsi,
+        sysclk_hz: 100_000_000,
+    };
+}
+
+/// Bus clocks in effect after `system_clock_configure`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ClockFrequencies {
+    pub sysclk_hz: u32,
+    pub hclk_hz: u32,
+    pub pclk1_hz: u32,
+    pub pclk2_hz: u32,
+}
+
+/// Switches SYSCLK to the PLL at the requested frequency.
+///
+/// Derives everything else from the target: the regulator voltage scale
+/// (scale 3 up to 120 MHz, scale 2 up to 144 MHz, scale 1 above),
+/// over-drive above 168 MHz, the flash wait states (with prefetch and
+/// caches on) and the PLL/bus dividers (see `rcc_configure_pll`). Wait
+/// states are raised before and lowered after the switch, so flash is
+/// never read too fast.
+///
+/// # Arguments
+/// * `target` - PLL source and SYSCLK
+///
+/// # Returns
+/// * The resulting bus clocks, read back from RCC
+/// * `Err(BswError::InvalidArgument)` if the PLL cannot make `sysclk_hz`,
+///   `Err(BswError::Timeout)` or `Err(BswError::NotReady)` if a
+///   clock or the over-drive did not come up. SYSCLK then stays on HSI.
+pub fn system_clock_configure(target: SysClockConfig) -> Result<ClockFrequencies> {
+    if target.sysclk_hz == 0 || target.sysclk_hz > 180_000_000 {
+        return Err(BswError::InvalidArgument);
+    }
+
+    let vos = if target.sysclk_hz <= PWR_SCALE3_MAX_HZ {
+        PWR_VOS_SCALE3
+    } else if target.sysclk_hz <= SCALE2_MAX_HZ {
+        PWR_VOS_SCALE2
+    } else {
+        PWR_VOS_SCALE1
+    };
+    let overdrive = target.sysclk_hz > SCALE1_MAX_HZ;
+
+    // Enough wait states for both the current and the new clock
+    flash_set_wait_states(flash_wait_states_for(
+        rcc_get_hclk_hz().max(target.sysclk_hz),
+        BOARD_VOLTAGE,
+    ));
+
+    // VOS and over-drive can only change while the PLL is off
+    rcc_switch_to_hsi()?;
     rcc_enable_power_clock();
-    pwr_set_regulator_voltage_scale(3);
-    pwr_enable_overdrive();
+    pwr_disable_overdrive();
+    pwr_set_regulator_voltage_scale(vos);
+    if overdrive {
+        pwr_enable_overdrive()?;
+    }
+
+    rcc_configure_pll(target.source, target.sysclk_hz)?;
+    flash_configure_for_clock(target.sysclk_hz, BOARD_VOLTAGE);
+
+    Ok(system_clock_frequencies())
+}
 
-    // Set PLL
-    rcc_configure_pll_180mhz();
+/// Returns the bus clocks currently in effect, read back from RCC.
+pub fn system_clock_frequencies() -> ClockFrequencies {
+    ClockFrequencies {
+        sysclk_hz: rcc_get_sysclk_hz(),
+        hclk_hz: rcc_get_hclk_hz(),
+        pclk1_hz: rcc_get_pclk1_hz(),
+        pclk2_hz: rcc_get_pclk2_hz(),
+    }
+}
+
+/// Brings SYSCLK up to 180 MHz. On error SYSCLK stays on the 16 MHz HSI.
+pub fn system_clock_setup() -> Result<()> {
+    system_clock_configure(SysClockConfig::HSI_180MHZ).map(|_| ())
 }
 
 // Clock-out capability
@@ -24,14 +121,11 @@ pub fn system_clock_output_pa8() {
     // Turn on the clock for the GPIO port
     rcc_enable_gpio_clock(GPIOA_BASE);
 
-    // Configure PA8 as alternate function (AF) for the clock output
-    gpio_set_mode_alternate(GPIOA_BASE, GPIO_PIN_8);
-
-    // Set output speed to very high
-    gpio_set_speed(GPIOA_BASE, GPIO_PIN_8, PinSpeed::VeryHigh);
-
-    // Set AF0 (MCO1) for PA8; see datasheet Table 12. STM32F427xx and STM32F429xx alternate function mapping
-    gpio_set_af(GPIOA_BASE, GPIO_PIN_8, 0b0000);
+    // Configure PA8 as AF0 (MCO1): push-pull, very high speed, no pull.
+    // Every pin setting is written so a stale open-drain/pull from an earlier
+    // use of PA8 cannot distort the clock output.
+    // See datasheet Table 12. STM32F427xx and STM32F429xx alternate function mapping
+    gpio_config_clock_out(GPIOA_BASE, GPIO_PIN_8, 0b0000);
 
     // Enable the clock output
     rcc_enable_mco1_output(McoSource::PLL, Div::Div4);
@@ -1,43 +1,386 @@
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
 use crate::bsw::reg_cpu_cortex_m4::*;
 use crate::bsw::reg_utils::*;
 
+const SYSTICK_COUNTFLAG_POS: u32 = 16;
 const SYSTICK_CLKSOURCE_POS: u32 = 2;
+const SYSTICK_TICKINT_POS: u32 = 1;
 const SYSTICK_ENABLE_POS: u32 = 0;
-const SYSTICK_COUNTFLAG_POS: u32 = 16;
 
-/// Initialize the SysTick timer for 1ms tick (AHB/8 clock source, disabled by default)
-pub fn systick_init() {
+/// SysTick clock source (CLKSOURCE bit)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SystickClkSource {
+    /// Processor clock (HCLK): finest resolution
+    Core,
+    /// External reference clock HCLK/8
+    CoreDiv8,
+}
+
+impl SystickClkSource {
+    /// Divider from HCLK to the SysTick counter clock
+    const fn divider(self) -> u32 {
+        match self {
+            SystickClkSource::Core => 1,
+            SystickClkSource::CoreDiv8 => 8,
+        }
+    }
+}
+
+/// HCLK divider of the selected clock source, set by `systick_init`.
+static SYSTICK_DIVIDER: AtomicU32 = AtomicU32::new(8);
+/// True while the SysTick exception (TICKINT) is enabled.
+static SYSTICK_INTERRUPT: AtomicBool = AtomicBool::new(false);
+
+/// Milliseconds since `systick_init`, incremented by `SysTick_Handler`.
+static TICK_MS: AtomicU32 = AtomicU32::new(0);
+/// SysTick clock ticks per millisecond (reload + 1), set by `systick_init`.
+static TICKS_PER_MS: AtomicU32 = AtomicU32::new(1);
+
+/// Largest SysTick reload value (STRVR is 24 bits wide).
+const SYSTICK_RELOAD_MAX: u32 = 0x00FF_FFFF;
+
+/// Initialize SysTick for a 1 ms period with interrupt
+///
+/// `hclk_hz` is the current HCLK, e.g. `rcc_get_hclk_hz()`; the reload is
+/// computed against HCLK divided by the selected clock source. Call
+/// `systick_enable_interrupt(false)` afterwards for polled operation.
+pub fn systick_init(source: SystickClkSource, hclk_hz: u32) {
     unsafe {
         // Disable SysTick
         reg_write(STCSR_BASE as *mut u32, 0x00);
-        // Set reload value to max (24 bits)
-        reg_write(STRVR_BASE as *mut u32, 0x00FF_FFFF);
-        // Clear current value
+    }
+
+    SYSTICK_DIVIDER.store(source.divider(), Ordering::Relaxed);
+    systick_set_clock_hz(hclk_hz);
+
+    // Select the clock source (CLKSOURCE bit 2: 1 = HCLK, 0 = HCLK/8)
+    reg_set_bit(
+        STCSR_BASE as *mut u32,
+        SYSTICK_CLKSOURCE_POS,
+        source == SystickClkSource::Core,
+    );
+    // Enable the SysTick exception (set TICKINT bit, bit 1)
+    systick_enable_interrupt(true);
+    // Enable SysTick (set ENABLE bit, bit 0)
+    reg_set_bit(STCSR_BASE as *mut u32, SYSTICK_ENABLE_POS, true);
+}
+
+/// Enable or disable the SysTick exception (TICKINT)
+///
+/// Without the interrupt, `millis`/`micros` stop advancing and `delay_ms`
+/// polls COUNTFLAG instead (one flag per 1 ms period).
+pub fn systick_enable_interrupt(enable: bool) {
+    SYSTICK_INTERRUPT.store(enable, Ordering::Relaxed);
+    reg_set_bit(STCSR_BASE as *mut u32, SYSTICK_TICKINT_POS, enable);
+}
+
+/// Recompute the 1 ms reload for a new HCLK frequency
+///
+/// Call after changing the clock tree (e.g. `rcc_configure_pll`), otherwise
+/// the tick, `millis` and all delays run at the old rate.
+/// Example: 180_000_000 / 8 / 1000 = 22_500 ticks per ms (AHB/8),
+/// 180_000_000 / 1000 = 180_000 ticks per ms (core clock).
+/// A reload that does not fit the 24-bit counter is clamped (the tick then
+/// gets longer than 1 ms); this needs HCLK > 16.7 GHz even with the core clock.
+pub fn systick_set_clock_hz(hclk_hz: u32) {
+    let divider = SYSTICK_DIVIDER.load(Ordering::Relaxed);
+    let ticks_per_ms = (hclk_hz / divider / 1000).clamp(1, SYSTICK_RELOAD_MAX + 1);
+    TICKS_PER_MS.store(ticks_per_ms, Ordering::Relaxed);
+
+    unsafe {
+        reg_write(STRVR_BASE as *mut u32, ticks_per_ms - 1);
+        // Clear current value so the new period starts now
         reg_write(STCVR_BASE as *mut u32, 0x00);
+    }
+}
+
+/// SysTick exception: advances the millisecond counter
+#[unsafe(no_mangle)]
+pub extern "C" fn SysTick_Handler() {
+    TICK_MS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Milliseconds since `systick_init` (wraps after ~49.7 days)
+pub fn millis() -> u32 {
+    TICK_MS.load(Ordering::Relaxed)
+}
 
-        // Select AHB/8 as clock source (clear CLKSOURCE bit, bit 2)
-        reg_clear_bit(STCSR_BASE as *mut u32, SYSTICK_CLKSOURCE_POS);
-        // Enable SysTick (set ENABLE bit, bit 0)
-        reg_set_bit(STCSR_BASE as *mut u32, SYSTICK_ENABLE_POS, true);
+/// Microseconds since `systick_init`
+///
+/// Combines the millisecond counter with the current SysTick value. The
+/// counter is read before and after the SysTick value and the read is retried
+/// if a tick happened in between, so the result is never torn. Inside a
+/// critical section (SysTick masked) the result can lag by up to 1 ms.
+pub fn micros() -> u64 {
+    let ticks_per_ms = TICKS_PER_MS.load(Ordering::Relaxed);
+    loop {
+        let ms = TICK_MS.load(Ordering::Acquire);
+        // SysTick counts down from reload to 0
+        let remaining = unsafe { reg_read(STCVR_BASE as *mut u32) };
+        if TICK_MS.load(Ordering::Acquire) == ms {
+            let elapsed = ticks_per_ms - 1 - remaining.min(ticks_per_ms - 1);
+            return ms as u64 * 1000 + (elapsed as u64 * 1000) / ticks_per_ms as u64;
+        }
     }
 }
 
-/// Delay for approximately 1 millisecond (assuming 180 MHz system clock, AHB/8)
+/// Delay for approximately 1 millisecond
 pub fn delay_one_ms() {
-    unsafe {
-        // Set reload for 1ms: 180_000_000 / 8 / 1000 = 22_500
-        reg_write(STRVR_BASE as *mut u32, 22_500 - 1);
-        reg_write(STCVR_BASE as *mut u32, 0x00);
+    delay_ms(1);
+}
+
+/// Delay for t milliseconds (busy-waits on the tick counter)
+///
+/// Waits for t full tick periods, so the delay is between t and t + 1 ms.
+/// In polled mode (interrupt disabled) it counts COUNTFLAG wraps instead.
+pub fn delay_ms(t: u32) {
+    if !SYSTICK_INTERRUPT.load(Ordering::Relaxed) {
+        // COUNTFLAG is cleared by reading STCSR: drop a stale flag first
+        let _ = reg_read_bit(STCSR_BASE as *mut u32, SYSTICK_COUNTFLAG_POS);
+        for _ in 0..=t {
+            while !reg_read_bit(STCSR_BASE as *mut u32, SYSTICK_COUNTFLAG_POS) {}
+        }
+        return;
+    }
 
-        // Wait for COUNTFLAG (bit 16) to be set
-        while !reg_read_bit(STCSR_BASE as *mut u32, SYSTICK_COUNTFLAG_POS) {}
+    let start = millis();
+    while millis().wrapping_sub(start) <= t {}
+}
+
+/// Delay for t milliseconds, sleeping (WFI) between ticks
+///
+/// Same timing as `delay_ms`, but the core halts until the next interrupt
+/// instead of spinning, which saves power. Requires the SysTick interrupt.
+pub fn delay_ms_wfi(t: u32) {
+    let start = millis();
+    while millis().wrapping_sub(start) <= t {
+        #[cfg(target_arch = "arm")]
+        unsafe {
+            core::arch::asm!("wfi");
+        }
+    }
+}
+
+/// Splits a long SysTick count into counter periods that fit STRVR
+///
+/// Returns `(full, rest)`: `full` periods of `SYSTICK_RELOAD_MAX + 1` ticks
+/// followed by one period of `rest` ticks (0 = none). A single leftover tick
+/// is rounded up to 2, the shortest period the counter supports (reload 1).
+/// Example: 40_000_000 ticks = 2 * 16_777_216 + 6_445_568.
+pub const fn systick_split_ticks(ticks: u64) -> (u64, u32) {
+    let period = SYSTICK_RELOAD_MAX as u64 + 1;
+    let full = ticks / period;
+    let rest = (ticks % period) as u32;
+    if rest == 1 { (full, 2) } else { (full, rest) }
+}
+
+/// Runs the SysTick counter for `periods` periods of `reload + 1` ticks
+fn systick_count_periods_on(regs: &mut impl RegisterBus, reload: u32, periods: u64) {
+    regs.write(STRVR_BASE, reload);
+    // Writing STCVR clears the counter and COUNTFLAG: the first period starts now
+    regs.write(STCVR_BASE, 0x00);
+    for _ in 0..periods {
+        while regs.read_bits(STCSR_BASE, SYSTICK_COUNTFLAG_POS, 1) == 0 {}
     }
 }
 
-/// Delay for t milliseconds
-pub fn delay_ms(mut t: u32) {
-    while t > 0 {
-        delay_one_ms();
-        t -= 1;
+/// Delay for at least `cycles` HCLK cycles, also beyond the 24-bit counter
+///
+/// The count is converted to SysTick ticks for the selected clock source
+/// (rounded up, so CoreDiv8 has a resolution of 8 cycles) and split with
+/// `systick_split_ticks` into periods that each fit the reload register;
+/// every period ends with one COUNTFLAG event.
+/// Example: 10 s at 180 MHz on the core clock = 1_800_000_000 cycles
+/// = 107 full periods + 4_837_888 ticks.
+///
+/// SysTick is taken over for the duration: TICKINT is off, then the 1 ms
+/// reload is restored. With the interrupt enabled, `millis` is advanced by
+/// the whole milliseconds delayed, as if the ticks had run. In polled mode
+/// nothing drives `millis` and it is left unchanged, like with `delay_ms`.
+/// Requires `systick_init`; do not call from an interrupt handler that may
+/// preempt another user of SysTick.
+pub fn delay_cycles(cycles: u64) {
+    delay_cycles_on(&mut MmioBus, cycles);
+}
+
+/// `delay_cycles` through a register bus (host tests use a `MockBus`).
+pub fn delay_cycles_on(regs: &mut impl RegisterBus, cycles: u64) {
+    let divider = SYSTICK_DIVIDER.load(Ordering::Relaxed) as u64;
+    let ticks = cycles.div_ceil(divider);
+    let (full, rest) = systick_split_ticks(ticks);
+    let interrupt = SYSTICK_INTERRUPT.load(Ordering::Relaxed);
+
+    // No tick interrupts while the reload does not match the 1 ms period
+    regs.set_bit(STCSR_BASE, SYSTICK_TICKINT_POS, false);
+
+    if rest != 0 {
+        systick_count_periods_on(regs, rest - 1, 1);
+    }
+    if full != 0 {
+        systick_count_periods_on(regs, SYSTICK_RELOAD_MAX, full);
+    }
+
+    let ticks_per_ms = TICKS_PER_MS.load(Ordering::Relaxed);
+    regs.write(STRVR_BASE, ticks_per_ms - 1);
+    regs.write(STCVR_BASE, 0x00);
+    if interrupt {
+        TICK_MS.fetch_add((ticks / ticks_per_ms as u64) as u32, Ordering::Relaxed);
+    }
+    regs.set_bit(STCSR_BASE, SYSTICK_TICKINT_POS, interrupt);
+}
+
+/// Non-blocking timeout on the millisecond tick
+///
+/// Polling loops check `expired()` instead of blocking in `delay_ms`, so a
+/// state machine can keep doing other work while it waits. Requires the
+/// SysTick interrupt; correct as long as it is polled within ~24.8 days.
+///
+/// ```ignore
+/// let timeout = Timeout::after_ms(50);
+/// while !uart_tx_done() {
+///     if timeout.expired() {
+///         return Err(BswError::Timeout);
+///     }
+/// }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Timeout {
+    start: u32,
+    duration_ms: u32,
+}
+
+impl Timeout {
+    /// Starts a timeout that expires after at least `ms` milliseconds
+    pub fn after_ms(ms: u32) -> Self {
+        Timeout {
+            start: millis(),
+            duration_ms: ms,
+        }
+    }
+
+    /// True once the full duration has passed (same rounding as `delay_ms`)
+    pub fn expired(&self) -> bool {
+        millis().wrapping_sub(self.start) > self.duration_ms
+    }
+
+    /// Milliseconds left until expiry (0 once expired)
+    pub fn remaining_ms(&self) -> u32 {
+        let elapsed = millis().wrapping_sub(self.start);
+        self.duration_ms.saturating_add(1).saturating_sub(elapsed)
+    }
+}
+
+/// Measures elapsed time on the millisecond tick
+#[derive(Copy, Clone, Debug)]
+pub struct Stopwatch {
+    start: u32,
+}
+
+impl Stopwatch {
+    /// Starts measuring from the current tick
+    pub fn start() -> Self {
+        Stopwatch { start: millis() }
+    }
+
+    /// Milliseconds since `start` (or the last `restart`)
+    pub fn elapsed_ms(&self) -> u32 {
+        millis().wrapping_sub(self.start)
+    }
+
+    /// Returns the elapsed time and starts a new measurement
+    pub fn restart(&mut self) -> u32 {
+        let now = millis();
+        let elapsed = now.wrapping_sub(self.start);
+        self.start = now;
+        elapsed
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Unit tests (host): cargo test --target x86_64-unknown-linux-gnu
+// -----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERIOD: u64 = SYSTICK_RELOAD_MAX as u64 + 1;
+
+    #[test]
+    fn split_short_counts_need_one_period() {
+        assert_eq!(systick_split_ticks(0), (0, 0));
+        assert_eq!(systick_split_ticks(1), (0, 2));
+        assert_eq!(systick_split_ticks(2), (0, 2));
+        assert_eq!(systick_split_ticks(180_000), (0, 180_000));
+        assert_eq!(systick_split_ticks(PERIOD - 1), (0, SYSTICK_RELOAD_MAX));
+    }
+
+    #[test]
+    fn split_long_counts_use_full_periods() {
+        assert_eq!(systick_split_ticks(PERIOD), (1, 0));
+        assert_eq!(systick_split_ticks(PERIOD + 1), (1, 2));
+        assert_eq!(systick_split_ticks(40_000_000), (2, 6_445_568));
+        assert_eq!(systick_split_ticks(1_800_000_000), (107, 4_837_888));
+    }
+
+    /// SysTick registers where COUNTFLAG is always set; logs STRVR writes.
+    #[derive(Default)]
+    struct SysTickBus {
+        regs: MockBus,
+        reloads: Vec<u32>,
+    }
+
+    impl RegisterBus for SysTickBus {
+        fn read(&self, addr: u32) -> u32 {
+            let value = self.regs.read(addr);
+            if addr == STCSR_BASE {
+                value | (1 << SYSTICK_COUNTFLAG_POS)
+            } else {
+                value
+            }
+        }
+
+        fn write(&mut self, addr: u32, val: u32) {
+            if addr == STRVR_BASE {
+                self.reloads.push(val);
+            }
+            self.regs.write(addr, val);
+        }
+    }
+
+    #[test]
+    fn delay_cycles_beyond_24_bits() {
+        // Core clock at 180 MHz: 180_000 ticks per ms
+        SYSTICK_DIVIDER.store(1, Ordering::Relaxed);
+        TICKS_PER_MS.store(180_000, Ordering::Relaxed);
+        let cycles = 40_000_000; // ~222 ms, more than 2^24 ticks
+
+        // Polled mode: millis() is not driven, so it must not jump
+        SYSTICK_INTERRUPT.store(false, Ordering::Relaxed);
+        let before = millis();
+        let mut regs = SysTickBus::default();
+        delay_cycles_on(&mut regs, cycles);
+        assert_eq!(
+            regs.reloads,
+            [6_445_568 - 1, SYSTICK_RELOAD_MAX, 180_000 - 1]
+        );
+        assert_eq!(regs.regs.read(STCSR_BASE) & (1 << SYSTICK_TICKINT_POS), 0);
+        assert_eq!(millis(), before);
+
+        // Interrupt mode: millis() advances by the whole ms delayed
+        SYSTICK_INTERRUPT.store(true, Ordering::Relaxed);
+        let mut regs = SysTickBus::default();
+        delay_cycles_on(&mut regs, cycles);
+        assert_ne!(regs.regs.read(STCSR_BASE) & (1 << SYSTICK_TICKINT_POS), 0);
+        assert_eq!(millis().wrapping_sub(before), 222);
+    }
+
+    #[test]
+    fn split_covers_the_requested_ticks() {
+        for ticks in [3, PERIOD * 5 + 17, u32::MAX as u64, 1 << 40, u64::MAX] {
+            let (full, rest) = systick_split_ticks(ticks);
+            assert!(rest <= SYSTICK_RELOAD_MAX);
+            assert_eq!(full as u128 * PERIOD as u128 + rest as u128, ticks as u128);
+        }
     }
 }
@@ -0,0 +1,85 @@
+//! Watchdog reset self-test (feature `watchdog-selftest`).
+//!
+//! Proves the whole safety chain works on real hardware: the IWDG really
+//! resets the chip, the backup register survives that reset, and the boot
+//! code can tell a watchdog reset apart from any other reset.
+//!
+//! Sequence
+//! 1. First boot: no marker in RTC_BKP0R -> write the marker, start the IWDG
+//!    with a ~100 ms timeout and stop feeding it.
+//! 2. The IWDG resets the MCU.
+//! 3. Second boot: marker found -> check the IWDG reset flag in RCC_CSR,
+//!    report PASS/FAIL on USART1 (ST-LINK virtual COM port, 115200 8N1),
+//!    clear the marker and the reset flags, and continue normal boot.
+
+use crate::bsw::gpio::{GPIO_PIN_9, gpio_config_usart_tx};
+use crate::bsw::pwr::pwr_enable_backup_access;
+use crate::bsw::rcc::*;
+use crate::bsw::reg_mcu_stm32f429zi::*;
+use crate::bsw::rtc::{rtc_backup_read, rtc_backup_write};
+use crate::bsw::uart::{usart_flush, usart_init, usart_write_str};
+use crate::bsw::wdg::{IwdgPrescaler, iwdg_start};
+
+/// Backup register used to remember that the test is in progress.
+const SELFTEST_BKP_INDEX: u32 = 0;
+/// Marker value written before the deliberate watchdog reset.
+const SELFTEST_MARKER: u32 = 0x5AFE_D06E;
+
+/// USART used for the report (USART1: PA9 TX, AF7).
+const SELFTEST_USART: u32 = USART1_BASE;
+const SELFTEST_BAUD: u32 = 115_200;
+
+/// IWDG prescaler /32 and reload 99: (32 * 100) / 32 kHz = ~100 ms.
+const SELFTEST_IWDG_PRESCALER: IwdgPrescaler = IwdgPrescaler::Div32;
+const SELFTEST_IWDG_RELOAD: u32 = 99;
+
+/// Runs (or completes) the watchdog self-test. Call right after the clock setup.
+///
+/// The baud rate is derived from the PCLK2 actually running, so the report
+/// also works when the clock setup fell back to HSI. The IWDG timeout does
+/// not depend on it (the IWDG runs from the LSI).
+///
+/// Returns normally only on the boot *after* the deliberate reset, once the
+/// result has been reported.
+pub fn watchdog_selftest() {
+    // Report channel
+    rcc_enable_gpio_clock(GPIOA_BASE);
+    gpio_config_usart_tx(GPIOA_BASE, GPIO_PIN_9, 7);
+    usart_init(SELFTEST_USART, rcc_get_pclk2_hz(), SELFTEST_BAUD);
+
+    // Backup registers are write protected after reset
+    rcc_enable_power_clock();
+    pwr_enable_backup_access();
+
+    if rtc_backup_read(SELFTEST_BKP_INDEX) == SELFTEST_MARKER {
+        // Second boot: evaluate and clean up
+        let iwdg_reset = rcc_reset_flag_is_set(RCC_CSR_IWDGRSTF_POS);
+        rtc_backup_write(SELFTEST_BKP_INDEX, 0);
+        rcc_clear_reset_flags();
+
+        if iwdg_reset {
+            usart_write_str(SELFTEST_USART, "watchdog selftest: PASS (IWDG reset)\r\n");
+        } else {
+            usart_write_str(
+                SELFTEST_USART,
+                "watchdog selftest: FAIL (no IWDG reset flag)\r\n",
+            );
+        }
+        usart_flush(SELFTEST_USART);
+        return;
+    }
+
+    // First boot: arm the test
+    rcc_clear_reset_flags();
+    rtc_backup_write(SELFTEST_BKP_INDEX, SELFTEST_MARKER);
+    usart_write_str(
+        SELFTEST_USART,
+        "watchdog selftest: armed, expecting reset\r\n",
+    );
+    usart_flush(SELFTEST_USART);
+
+    iwdg_start(SELFTEST_IWDG_PRESCALER, SELFTEST_IWDG_RELOAD);
+
+    // Deliberately never feed the watchdog
+    loop {}
+}
@@ -3,3 +3,539 @@
 // -----------------------------------------------------------------------------
 // Minimal helpers for ADC1–ADC3: clock enable, channel setup, and conversions.
 // Uses raw MMIO; ensure RCC APB2 ADC clock is enabled before access.
+//
+// Reference: STM32F429 Reference Manual, section 13.13 (ADC registers)
+// -----------------------------------------------------------------------------
+
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use crate::bsw::dma::*; // DMA stream configuration
+use crate::bsw::rcc::*; // RCC peripheral clock enable
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// ADC Register Offsets (relative to ADCx base address)
+// -----------------------------------------------------------------------------
+pub const ADC_SR: u32 = 0x00; // Status register
+pub const ADC_CR1: u32 = 0x04; // Control register 1
+pub const ADC_CR2: u32 = 0x08; // Control register 2
+pub const ADC_SMPR1: u32 = 0x0C; // Sample time register 1 (channels 10..18)
+pub const ADC_SMPR2: u32 = 0x10; // Sample time register 2 (channels 0..9)
+pub const ADC_JOFR1: u32 = 0x14; // Injected channel data offset register 1
+pub const ADC_HTR: u32 = 0x24; // Watchdog higher threshold register
+pub const ADC_LTR: u32 = 0x28; // Watchdog lower threshold register
+pub const ADC_SQR1: u32 = 0x2C; // Regular sequence register 1 (L, SQ13..SQ16)
+pub const ADC_SQR2: u32 = 0x30; // Regular sequence register 2 (SQ7..SQ12)
+pub const ADC_SQR3: u32 = 0x34; // Regular sequence register 3 (SQ1..SQ6)
+pub const ADC_JSQR: u32 = 0x38; // Injected sequence register
+pub const ADC_DR: u32 = 0x4C; // Regular data register
+
+// -----------------------------------------------------------------------------
+// ADC Common Register Offsets (relative to ADC_COMMON_BASE)
+// -----------------------------------------------------------------------------
+pub const ADC_CSR: u32 = 0x00; // Common status register
+pub const ADC_CCR: u32 = 0x04; // Common control register
+pub const ADC_CDR: u32 = 0x08; // Common regular data register for dual/triple modes
+
+// -----------------------------------------------------------------------------
+// ADC Register Bits
+// -----------------------------------------------------------------------------
+pub const ADC_SR_EOC_POS: u32 = 1; // Regular channel end of conversion
+pub const ADC_SR_OVR_POS: u32 = 5; // Overrun
+pub const ADC_CR1_SCAN_POS: u32 = 8; // Scan mode
+pub const ADC_CR2_ADON_POS: u32 = 0; // A/D converter ON
+pub const ADC_CR2_CONT_POS: u32 = 1; // Continuous conversion
+pub const ADC_CR2_DMA_POS: u32 = 8; // Direct memory access mode
+pub const ADC_CR2_DDS_POS: u32 = 9; // DMA requests after the last transfer
+pub const ADC_CR2_EOCS_POS: u32 = 10; // End of conversion selection (1 = after each conversion)
+pub const ADC_CR2_EXTSEL_POS: u32 = 24; // External event select for regular group EXTSEL[3:0]
+pub const ADC_CR2_EXTEN_POS: u32 = 28; // External trigger enable for regular channels EXTEN[1:0]
+pub const ADC_CR2_SWSTART_POS: u32 = 30; // Start conversion of regular channels
+pub const ADC_CCR_MULTI_POS: u32 = 0; // Multi ADC mode selection MULTI[4:0]
+pub const ADC_CCR_ADCPRE_POS: u32 = 16; // ADC prescaler (PCLK2 / 2, 4, 6, 8)
+pub const ADC_CCR_VBATE_POS: u32 = 22; // VBAT channel enable (shares channel 18)
+pub const ADC_CCR_TSVREFE_POS: u32 = 23; // Temperature sensor and Vrefint enable
+
+/// Highest regular channel number (channels 16..18 are internal).
+pub const ADC_CHANNEL_MAX: u8 = 18;
+/// Maximum length of a regular sequence.
+pub const ADC_SEQUENCE_MAX: usize = 16;
+
+/// MULTI[4:0] value: ADC1 and ADC2 working independently.
+pub const ADC_MULTI_INDEPENDENT: u32 = 0b00000;
+/// MULTI[4:0] value: dual mode, regular simultaneous mode only.
+pub const ADC_MULTI_DUAL_REG_SIMULT: u32 = 0b00110;
+
+/// ADCPRE value: PCLK2 / 4 (22.5 MHz at PCLK2 = 90 MHz, below the 36 MHz max).
+const ADC_PRESCALER_DIV4: u32 = 0b01;
+
+// -----------------------------------------------------------------------------
+// Enable ADC Clock
+// -----------------------------------------------------------------------------
+/// Enables the APB2 clock of an ADC instance (ADC1EN/ADC2EN/ADC3EN, bits 8/9/10).
+///
+/// # Arguments
+/// * `adc_base` - The base address of the ADC (`ADC1_BASE`, `ADC2_BASE` or `ADC3_BASE`)
+pub fn adc_enable_clock(adc_base: u32) {
+    match adc_base {
+        ADC1_BASE => rcc_enable_peripheral(RccBus::Apb2, 8),
+        ADC2_BASE => rcc_enable_peripheral(RccBus::Apb2, 9),
+        ADC3_BASE => rcc_enable_peripheral(RccBus::Apb2, 10),
+        _ => {
+            // Unknown ADC: do nothing
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Single-Channel Conversion
+// -----------------------------------------------------------------------------
+/// Sampling time of a channel (SMPx[2:0] in SMPR1/SMPR2), in ADC clock cycles.
+///
+/// Longer times suit high-impedance sources; total conversion time is the
+/// sampling time plus 12 cycles (12-bit resolution).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AdcSampleTime {
+    Cycles3 = 0b000,
+    Cycles15 = 0b001,
+    Cycles28 = 0b010,
+    Cycles56 = 0b011,
+    Cycles84 = 0b100,
+    Cycles112 = 0b101,
+    Cycles144 = 0b110,
+    Cycles480 = 0b111,
+}
+
+/// Enables the clock of an ADC instance and switches it on (ADON).
+///
+/// Also sets the common ADC prescaler to PCLK2 / 4 (22.5 MHz at 90 MHz).
+///
+/// # Arguments
+/// * `adc_base` - The base address of the ADC (`ADC1_BASE`, `ADC2_BASE` or `ADC3_BASE`)
+pub fn adc_init(adc_base: u32) {
+    adc_enable_clock(adc_base);
+
+    let ccr_addr = (ADC_COMMON_BASE + ADC_CCR) as *mut u32;
+    reg_set_bits(ccr_addr, ADC_PRESCALER_DIV4, ADC_CCR_ADCPRE_POS, 2);
+
+    reg_set_bit((adc_base + ADC_CR2) as *mut u32, ADC_CR2_ADON_POS, true);
+}
+
+/// Sets the sampling time of a channel.
+///
+/// # Arguments
+/// * `adc_base`    - The base address of the ADC
+/// * `channel`     - Channel number (0..=18)
+/// * `sample_time` - Sampling time (see `AdcSampleTime`)
+pub fn adc_config_channel(adc_base: u32, channel: u8, sample_time: AdcSampleTime) {
+    assert!(channel <= ADC_CHANNEL_MAX, "channel must be 0..=18");
+
+    // Channels 0..9 are in SMPR2, 10..18 in SMPR1, 3 bits each
+    let channel = channel as u32;
+    let (reg, slot) = if channel < 10 {
+        ((adc_base + ADC_SMPR2) as *mut u32, channel)
+    } else {
+        ((adc_base + ADC_SMPR1) as *mut u32, channel - 10)
+    };
+    reg_set_bits(reg, sample_time as u32, slot * 3, 3);
+}
+
+/// Converts one channel and returns the 12-bit result.
+///
+/// Programs a regular sequence of length 1, starts it with SWSTART and waits
+/// for EOC. Reading DR clears EOC.
+///
+/// # Arguments
+/// * `adc_base` - The base address of the ADC (initialized with `adc_init`)
+/// * `channel`  - Channel number (0..=18)
+pub fn adc_read_blocking(adc_base: u32, channel: u8) -> u16 {
+    adc_set_regular_sequence(adc_base, &[channel]);
+
+    // Single conversion, no scan
+    reg_set_bit((adc_base + ADC_CR1) as *mut u32, ADC_CR1_SCAN_POS, false);
+    let cr2_addr = (adc_base + ADC_CR2) as *mut u32;
+    reg_set_bit(cr2_addr, ADC_CR2_CONT_POS, false);
+
+    reg_set_bit(cr2_addr, ADC_CR2_SWSTART_POS, true);
+    let _ = reg_wait_bit((adc_base + ADC_SR) as *mut u32, ADC_SR_EOC_POS, true, 0);
+
+    (unsafe { reg_read((adc_base + ADC_DR) as *mut u32) } & 0xFFFF) as u16
+}
+
+// -----------------------------------------------------------------------------
+// Regular Sequence
+// -----------------------------------------------------------------------------
+/// Programs the regular conversion sequence of an ADC.
+///
+/// The sequence length goes to L[3:0] in SQR1 (length - 1); the channel
+/// numbers go to SQ1..SQ6 (SQR3), SQ7..SQ12 (SQR2) and SQ13..SQ16 (SQR1),
+/// 5 bits each.
+///
+/// # Arguments
+/// * `adc_base` - The base address of the ADC
+/// * `channels` - Channel numbers in conversion order (1..=16 entries, each 0..=18)
+pub fn adc_set_regular_sequence(adc_base: u32, channels: &[u8]) {
+    assert!(
+        !channels.is_empty() && channels.len() <= ADC_SEQUENCE_MAX,
+        "sequence length must be 1..=16"
+    );
+
+    let sqr1_addr = (adc_base + ADC_SQR1) as *mut u32;
+    let sqr2_addr = (adc_base + ADC_SQR2) as *mut u32;
+    let sqr3_addr = (adc_base + ADC_SQR3) as *mut u32;
+
+    for (rank, &channel) in channels.iter().enumerate() {
+        assert!(channel <= ADC_CHANNEL_MAX, "channel must be 0..=18");
+        let rank = rank as u32;
+        let (reg, slot) = match rank {
+            0..=5 => (sqr3_addr, rank),
+            6..=11 => (sqr2_addr, rank - 6),
+            _ => (sqr1_addr, rank - 12),
+        };
+        reg_set_bits(reg, channel as u32, slot * 5, 5);
+    }
+
+    // L[3:0] (bits 23:20) = number of conversions - 1
+    reg_set_bits(sqr1_addr, channels.len() as u32 - 1, 20, 4);
+}
+
+// -----------------------------------------------------------------------------
+// Dual ADC Regular Simultaneous Mode
+// -----------------------------------------------------------------------------
+/// Configures ADC1 (master) and ADC2 (slave) for dual regular simultaneous mode.
+///
+/// Both ADCs convert their sequences in lockstep, started by a single
+/// SWSTART on ADC1, so sample N of ADC1 and sample N of ADC2 are taken at
+/// the same instant (e.g. voltage and current of the same phase).
+///
+/// Notes
+/// - Both sequences must have the same length.
+/// - Use the same sample time on paired channels, otherwise the conversions
+///   drift apart.
+/// - The two ADCs must not convert the same channel at the same time.
+///
+/// # Arguments
+/// * `channels_adc1` - Regular sequence for ADC1
+/// * `channels_adc2` - Regular sequence for ADC2
+pub fn adc_multi_simultaneous(channels_adc1: &[u8], channels_adc2: &[u8]) {
+    assert!(
+        channels_adc1.len() == channels_adc2.len(),
+        "both sequences must have the same length"
+    );
+
+    adc_enable_clock(ADC1_BASE);
+    adc_enable_clock(ADC2_BASE);
+
+    let ccr_addr = (ADC_COMMON_BASE + ADC_CCR) as *mut u32;
+
+    // The multi mode must only be changed while both ADCs are off
+    for adc_base in [ADC1_BASE, ADC2_BASE] {
+        reg_clear_bit((adc_base + ADC_CR2) as *mut u32, ADC_CR2_ADON_POS);
+    }
+
+    reg_set_bits(ccr_addr, ADC_PRESCALER_DIV4, ADC_CCR_ADCPRE_POS, 2);
+    reg_set_bits(ccr_addr, ADC_MULTI_DUAL_REG_SIMULT, ADC_CCR_MULTI_POS, 5);
+
+    for (adc_base, channels) in [(ADC1_BASE, channels_adc1), (ADC2_BASE, channels_adc2)] {
+        adc_set_regular_sequence(adc_base, channels);
+
+        // Scan the whole sequence, EOC after every conversion so each pair
+        // can be read from CDR, single (not continuous) conversion
+        reg_set_bit((adc_base + ADC_CR1) as *mut u32, ADC_CR1_SCAN_POS, true);
+        let cr2_addr = (adc_base + ADC_CR2) as *mut u32;
+        reg_set_bit(cr2_addr, ADC_CR2_CONT_POS, false);
+        reg_set_bit(cr2_addr, ADC_CR2_EOCS_POS, true);
+        reg_set_bit(cr2_addr, ADC_CR2_ADON_POS, true);
+    }
+}
+
+/// Starts one simultaneous conversion of both sequences (SWSTART on the master).
+pub fn adc_multi_start() {
+    reg_set_bit((ADC1_BASE + ADC_CR2) as *mut u32, ADC_CR2_SWSTART_POS, true);
+}
+
+/// Waits for the next conversion pair and returns it as `(adc1, adc2)`.
+///
+/// Reads the common data register: DATA1 (ADC1) in bits 15:0 and DATA2 (ADC2)
+/// in bits 31:16, i.e. both samples with a single 32-bit access.
+pub fn adc_multi_read_pair() -> (u16, u16) {
+    let _ = reg_wait_bit((ADC1_BASE + ADC_SR) as *mut u32, ADC_SR_EOC_POS, true, 0);
+    let _ = reg_wait_bit((ADC2_BASE + ADC_SR) as *mut u32, ADC_SR_EOC_POS, true, 0);
+
+    let cdr = unsafe { reg_read_ro((ADC_COMMON_BASE + ADC_CDR) as ReadOnlyRegister) };
+
+    // Reading CDR clears neither EOC flag (only an ADC_DR read does). Clear
+    // both, or the next call would see EOC already set and return stale data.
+    // SR flags are rc_w0: writing 1 to the other bits leaves them unchanged.
+    for adc_base in [ADC1_BASE, ADC2_BASE] {
+        reg_set_val(
+            (adc_base + ADC_SR) as *mut u32,
+            !(1 << ADC_SR_EOC_POS) & 0x3F,
+        );
+    }
+
+    ((cdr & 0xFFFF) as u16, (cdr >> 16) as u16)
+}
+
+/// Converts both configured sequences once and stores the sample pairs.
+///
+/// # Arguments
+/// * `results` - One entry per sequence rank; its length must match the
+///   configured sequence length
+pub fn adc_multi_read(results: &mut [(u16, u16)]) {
+    adc_multi_start();
+    for pair in results.iter_mut() {
+        *pair = adc_multi_read_pair();
+    }
+}
+
+// -----------------------------------------------------------------------------
+// External (Timer) Trigger
+// -----------------------------------------------------------------------------
+/// External trigger sources for the regular group (EXTSEL[3:0] in CR2).
+///
+/// EXTSEL encoding on the STM32F429 (RM0090 13.13.3):
+/// ```text
+/// 0000 TIM1_CC1   0100 TIM2_CC3    1000 TIM3_TRGO   1100 TIM5_CC3
+/// 0001 TIM1_CC2   0101 TIM2_CC4    1001 TIM4_CC4    1101 TIM8_CC1
+/// 0010 TIM1_CC3   0110 TIM2_TRGO   1010 TIM5_CC1    1110 TIM8_TRGO
+/// 0011 TIM2_CC2   0111 TIM3_CC1    1011 TIM5_CC2    1111 EXTI line 11
+/// ```
+/// Only TIM2, TIM3 and TIM8 can trigger through TRGO; use
+/// `timer_trgo_init(.., TimMasterMode::Update)` on one of them for a fixed
+/// sample rate.
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AdcTrigger {
+    Tim1_CC1 = 0b0000,
+    Tim1_CC2 = 0b0001,
+    Tim1_CC3 = 0b0010,
+    Tim2_CC2 = 0b0011,
+    Tim2_CC3 = 0b0100,
+    Tim2_CC4 = 0b0101,
+    Tim2_TRGO = 0b0110,
+    Tim3_CC1 = 0b0111,
+    Tim3_TRGO = 0b1000,
+    Tim4_CC4 = 0b1001,
+    Tim5_CC1 = 0b1010,
+    Tim5_CC2 = 0b1011,
+    Tim5_CC3 = 0b1100,
+    Tim8_CC1 = 0b1101,
+    Tim8_TRGO = 0b1110,
+    Exti11 = 0b1111,
+}
+
+/// Trigger edge selection (EXTEN[1:0] in CR2).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AdcTriggerEdge {
+    Disabled = 0b00, // Software start only (SWSTART)
+    Rising = 0b01,
+    Falling = 0b10,
+    Both = 0b11,
+}
+
+/// Starts regular conversions on an external (timer) trigger instead of SWSTART.
+///
+/// Every trigger edge converts the configured regular sequence once, so the
+/// sample rate is exactly the trigger rate, free of software jitter. Pair
+/// with DMA to collect the samples.
+///
+/// Example (10 kHz sampling of channel 0 with TIM2 at 90 MHz):
+/// ```ignore
+/// adc_set_regular_sequence(ADC1_BASE, &[0]);
+/// adc_trigger_from_timer(ADC1_BASE, AdcTrigger::Tim2_TRGO, AdcTriggerEdge::Rising);
+/// timer_trgo_init(TIM2_BASE, 0, 8_999, TimMasterMode::Update);
+/// ```
+///
+/// # Arguments
+/// * `adc_base` - The base address of the ADC
+/// * `source`   - Trigger source (EXTSEL)
+/// * `edge`     - Trigger edge (EXTEN); `Disabled` returns to software start
+pub fn adc_trigger_from_timer(adc_base: u32, source: AdcTrigger, edge: AdcTriggerEdge) {
+    adc_enable_clock(adc_base);
+
+    let cr2_addr = (adc_base + ADC_CR2) as *mut u32;
+    reg_set_bits(cr2_addr, source as u32, ADC_CR2_EXTSEL_POS, 4);
+    reg_set_bits(cr2_addr, edge as u32, ADC_CR2_EXTEN_POS, 2);
+
+    // Convert one sequence per trigger (no continuous mode), EOC after each
+    // conversion so DMA/interrupts see every sample
+    reg_set_bit(cr2_addr, ADC_CR2_CONT_POS, false);
+    reg_set_bit(cr2_addr, ADC_CR2_EOCS_POS, true);
+    reg_set_bit(cr2_addr, ADC_CR2_ADON_POS, true);
+}
+
+// -----------------------------------------------------------------------------
+// Continuous Scan with DMA
+// -----------------------------------------------------------------------------
+/// Returns the DMA2 (stream, channel) serving an ADC.
+fn adc_dma_stream(adc_base: u32) -> (u32, u32) {
+    match adc_base {
+        ADC1_BASE => (0, 0), // DMA2 stream 0, channel 0
+        ADC2_BASE => (2, 1), // DMA2 stream 2, channel 1
+        ADC3_BASE => (1, 2), // DMA2 stream 1, channel 2
+        _ => panic!("adc_base must be ADC1_BASE, ADC2_BASE or ADC3_BASE"),
+    }
+}
+
+/// Starts converting a channel sequence over and over, with DMA2 writing
+/// each result into `buffer` (circularly, in sequence order).
+///
+/// The buffer is written by DMA in the background for as long as the scan
+/// runs, hence `'static` and atomics: declare it as e.g.
+/// `static SAMPLES: [AtomicU16; 3] = [const { AtomicU16::new(0) }; 3];`
+/// and read it with `adc_latest`. `buffer[i]` always holds the most recent
+/// conversion of `channels[i]`. Set the sample times first
+/// (`adc_config_channel`); they determine the update rate.
+///
+/// # Arguments
+/// * `adc_base` - The base address of the ADC (initialized with `adc_init`)
+/// * `channels` - Channel numbers in conversion order (1..=16 entries)
+/// * `buffer`   - One entry per channel
+pub fn adc_start_scan_dma(adc_base: u32, channels: &[u8], buffer: &'static [AtomicU16]) {
+    assert!(
+        buffer.len() == channels.len(),
+        "buffer needs one entry per channel"
+    );
+    let (stream, dma_channel) = adc_dma_stream(adc_base);
+
+    adc_stop_scan_dma(adc_base);
+    adc_set_regular_sequence(adc_base, channels);
+
+    // DMA2: ADC_DR -> buffer, half-words, circular
+    dma_enable_clock(DMA2_BASE);
+    dma_clear_flags(DMA2_BASE, stream);
+    reg_set_val(
+        dma_stream_reg(DMA2_BASE, stream, DMA_SXPAR),
+        adc_base + ADC_DR,
+    );
+    reg_set_val(
+        dma_stream_reg(DMA2_BASE, stream, DMA_SXM0AR),
+        buffer.as_ptr() as u32,
+    );
+    reg_set_val(
+        dma_stream_reg(DMA2_BASE, stream, DMA_SXNDTR),
+        buffer.len() as u32,
+    );
+    reg_set_val(dma_stream_reg(DMA2_BASE, stream, DMA_SXFCR), 0); // Direct mode
+    let dma_cr_addr = dma_stream_reg(DMA2_BASE, stream, DMA_SXCR);
+    reg_set_val(
+        dma_cr_addr,
+        (dma_channel << DMA_SXCR_CHSEL_POS)
+            | (DMA_SIZE_HALF_WORD << DMA_SXCR_MSIZE_POS)
+            | (DMA_SIZE_HALF_WORD << DMA_SXCR_PSIZE_POS)
+            | (1 << DMA_SXCR_MINC_POS)
+            | (1 << DMA_SXCR_CIRC_POS)
+            | (DMA_DIR_PERIPH_TO_MEM << DMA_SXCR_DIR_POS),
+    );
+    reg_set_bit(dma_cr_addr, DMA_SXCR_EN_POS, true);
+
+    // ADC: scan the sequence continuously, one DMA request per conversion
+    reg_set_bit((adc_base + ADC_CR1) as *mut u32, ADC_CR1_SCAN_POS, true);
+    let cr2_addr = (adc_base + ADC_CR2) as *mut u32;
+    reg_set_bits(cr2_addr, 0, ADC_CR2_EXTEN_POS, 2); // Software start
+    reg_set_bit(cr2_addr, ADC_CR2_CONT_POS, true);
+    reg_set_bit(cr2_addr, ADC_CR2_DMA_POS, true);
+    reg_set_bit(cr2_addr, ADC_CR2_DDS_POS, true);
+    reg_set_bit(cr2_addr, ADC_CR2_ADON_POS, true);
+    reg_set_bit(cr2_addr, ADC_CR2_SWSTART_POS, true);
+}
+
+/// Stops a scan started with `adc_start_scan_dma` (ADC and DMA stream).
+///
+/// # Arguments
+/// * `adc_base` - The base address of the ADC
+pub fn adc_stop_scan_dma(adc_base: u32) {
+    let (stream, _) = adc_dma_stream(adc_base);
+
+    let cr2_addr = (adc_base + ADC_CR2) as *mut u32;
+    reg_set_bit(cr2_addr, ADC_CR2_CONT_POS, false);
+    reg_set_bit(cr2_addr, ADC_CR2_DMA_POS, false);
+    reg_set_bit(cr2_addr, ADC_CR2_DDS_POS, false);
+    dma_stream_disable(DMA2_BASE, stream);
+    // A stopped DMA leaves OVR set, which would block the next scan
+    reg_clear_bit((adc_base + ADC_SR) as *mut u32, ADC_SR_OVR_POS);
+}
+
+/// Returns the most recent conversion of `channels[index]` from a scan buffer.
+///
+/// # Arguments
+/// * `buffer` - Buffer passed to `adc_start_scan_dma`
+/// * `index`  - Position of the channel in the scanned sequence
+pub fn adc_latest(buffer: &[AtomicU16], index: usize) -> u16 {
+    buffer[index].load(Ordering::Relaxed)
+}
+
+// -----------------------------------------------------------------------------
+// Internal Temperature Sensor and Vrefint
+// -----------------------------------------------------------------------------
+// On the STM32F429 the temperature sensor is ADC1_IN18 (shared with VBAT/4,
+// which must then be off) and Vrefint is ADC1_IN17. Both need a sampling time
+// of at least 10 us: 480 cycles (21 us at the 22.5 MHz ADC clock) is used.
+// Factory calibration values (12-bit raw readings at VDDA = 3.3 V) are in
+// the system memory (datasheet 6.3.22 and 6.3.24).
+pub const ADC_CHANNEL_VREFINT: u8 = 17;
+pub const ADC_CHANNEL_TEMPERATURE: u8 = 18;
+
+const VREFINT_CAL_ADDR: u32 = 0x1FFF_7A2A; // Vrefint raw at 30 degC, VDDA 3.3 V
+const TS_CAL1_ADDR: u32 = 0x1FFF_7A2C; // Sensor raw at 30 degC, VDDA 3.3 V
+const TS_CAL2_ADDR: u32 = 0x1FFF_7A2E; // Sensor raw at 110 degC, VDDA 3.3 V
+const TS_CAL1_TEMP_C: f32 = 30.0;
+const TS_CAL2_TEMP_C: f32 = 110.0;
+const CALIBRATION_VDDA_MV: u32 = 3300;
+
+/// Enables the internal channels on ADC1 and waits for the sensor start-up
+/// time (10 us).
+fn adc_enable_internal_channels() {
+    let ccr_addr = (ADC_COMMON_BASE + ADC_CCR) as *mut u32;
+    if reg_read_bit(ccr_addr, ADC_CCR_TSVREFE_POS) {
+        return;
+    }
+    reg_set_bit(ccr_addr, ADC_CCR_VBATE_POS, false);
+    reg_set_bit(ccr_addr, ADC_CCR_TSVREFE_POS, true);
+    for _ in 0..(rcc_get_hclk_hz() / 1_000_000) * 10 {
+        core::hint::spin_loop();
+    }
+}
+
+/// Reads a 16-bit factory calibration value.
+fn adc_calibration_value(addr: u32) -> u16 {
+    unsafe { core::ptr::read_volatile(addr as *const u16) }
+}
+
+/// Converts one internal channel on ADC1 with the long sampling time.
+fn adc_read_internal(channel: u8) -> u16 {
+    adc_init(ADC1_BASE);
+    adc_enable_internal_channels();
+    adc_config_channel(ADC1_BASE, channel, AdcSampleTime::Cycles480);
+    adc_read_blocking(ADC1_BASE, channel)
+}
+
+/// Measures the analog supply (VDDA = VREF+ on the DISC1) in millivolts.
+///
+/// Compares the Vrefint reading with its factory value taken at 3.3 V.
+/// Uses ADC1 (a scan running on ADC1 is disturbed).
+pub fn adc_read_vref_mv() -> u32 {
+    let raw = adc_read_internal(ADC_CHANNEL_VREFINT) as u32;
+    if raw == 0 {
+        return 0;
+    }
+    CALIBRATION_VDDA_MV * adc_calibration_value(VREFINT_CAL_ADDR) as u32 / raw
+}
+
+/// Measures the die temperature in degrees Celsius.
+///
+/// Interpolates between the two factory calibration points (30 and
+/// 110 degC), after scaling the reading to the 3.3 V calibration supply with
+/// `adc_read_vref_mv`. Typical accuracy is a few degrees; the die runs
+/// warmer than the ambient air. Uses ADC1.
+pub fn adc_read_temperature_c() -> f32 {
+    let vdda_mv = adc_read_vref_mv();
+    let raw = adc_read_internal(ADC_CHANNEL_TEMPERATURE) as f32;
+    let raw_at_cal_vdda = raw * vdda_mv as f32 / CALIBRATION_VDDA_MV as f32;
+
+    let cal1 = adc_calibration_value(TS_CAL1_ADDR) as f32;
+    let cal2 = adc_calibration_value(TS_CAL2_ADDR) as f32;
+    (raw_at_cal_vdda - cal1) * (TS_CAL2_TEMP_C - TS_CAL1_TEMP_C) / (cal2 - cal1) + TS_CAL1_TEMP_C
+}
@@ -3,3 +3,394 @@
 // -----------------------------------------------------------------------------
 // Minimal helpers for CAN1/CAN2: clock init, GPIO AF config, filter setup, TX/RX.
 // Uses raw MMIO (direct memory-mapped I/O); enable RCC APB1 clocks and configure GPIO pins before use.
+//
+// Bit timing: one bit = (1 + seg1 + seg2) time quanta, one quantum =
+// (prescaler) / PCLK1. Example at PCLK1 = 45 MHz: prescaler 5, seg1 6,
+// seg2 2 -> 9 MHz / 9 tq = 500 kbit/s, sample point at 77.8 %.
+//
+// The 28 acceptance filter banks are shared by CAN1 and CAN2 and are only
+// accessible through CAN1.
+//
+// Reference: STM32F429 Reference Manual, section 32.9 (bxCAN registers)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::rcc::*; // RCC peripheral clock enable
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// CAN Register Offsets (relative to CANx base address)
+// -----------------------------------------------------------------------------
+pub const CAN_MCR: u32 = 0x000; // Master control register
+pub const CAN_MSR: u32 = 0x004; // Master status register
+pub const CAN_TSR: u32 = 0x008; // Transmit status register
+pub const CAN_RF0R: u32 = 0x00C; // Receive FIFO 0 register
+pub const CAN_RF1R: u32 = 0x010; // Receive FIFO 1 register
+pub const CAN_IER: u32 = 0x014; // Interrupt enable register
+pub const CAN_ESR: u32 = 0x018; // Error status register
+pub const CAN_BTR: u32 = 0x01C; // Bit timing register
+
+// TX mailboxes 0..2: offset + 0x10 * mailbox
+pub const CAN_TIXR: u32 = 0x180; // TX mailbox identifier register
+pub const CAN_TDTXR: u32 = 0x184; // TX mailbox data length control and time stamp register
+pub const CAN_TDLXR: u32 = 0x188; // TX mailbox data low register (bytes 0..3)
+pub const CAN_TDHXR: u32 = 0x18C; // TX mailbox data high register (bytes 4..7)
+
+// RX FIFO 0/1 output mailbox: offset + 0x10 * fifo
+pub const CAN_RIXR: u32 = 0x1B0; // RX FIFO mailbox identifier register
+pub const CAN_RDTXR: u32 = 0x1B4; // RX FIFO mailbox data length control and time stamp register
+pub const CAN_RDLXR: u32 = 0x1B8; // RX FIFO mailbox data low register
+pub const CAN_RDHXR: u32 = 0x1BC; // RX FIFO mailbox data high register
+
+// Filter registers (CAN1 only)
+pub const CAN_FMR: u32 = 0x200; // Filter master register
+pub const CAN_FM1R: u32 = 0x204; // Filter mode register (0 = mask mode)
+pub const CAN_FS1R: u32 = 0x20C; // Filter scale register (1 = 32-bit)
+pub const CAN_FFA1R: u32 = 0x214; // Filter FIFO assignment register (0 = FIFO 0)
+pub const CAN_FA1R: u32 = 0x21C; // Filter activation register
+pub const CAN_F0R1: u32 = 0x240; // Filter bank 0 register 1 (bank n: + 8 * n)
+pub const CAN_F0R2: u32 = 0x244; // Filter bank 0 register 2
+
+// -----------------------------------------------------------------------------
+// CAN Register Bits
+// -----------------------------------------------------------------------------
+pub const CAN_MCR_INRQ_POS: u32 = 0; // Initialization request
+pub const CAN_MCR_SLEEP_POS: u32 = 1; // Sleep mode request
+pub const CAN_MSR_INAK_POS: u32 = 0; // Initialization acknowledge
+pub const CAN_MSR_SLAK_POS: u32 = 1; // Sleep acknowledge
+pub const CAN_TSR_TME0_POS: u32 = 26; // Transmit mailbox 0 empty (TME1/TME2 follow)
+pub const CAN_RF0R_FMP0_POS: u32 = 0; // FIFO 0 message pending (2 bits)
+pub const CAN_RF0R_RFOM0_POS: u32 = 5; // Release FIFO 0 output mailbox
+pub const CAN_TIXR_TXRQ_POS: u32 = 0; // Transmit mailbox request
+pub const CAN_TIXR_IDE_POS: u32 = 2; // Identifier extension (1 = 29-bit ID)
+pub const CAN_FMR_FINIT_POS: u32 = 0; // Filter init mode
+pub const CAN_BTR_LBKM_POS: u32 = 30; // Loop back mode
+pub const CAN_BTR_SILM_POS: u32 = 31; // Silent mode
+
+/// Highest 11-bit standard identifier; larger IDs are sent as 29-bit extended.
+pub const CAN_STD_ID_MAX: u32 = 0x7FF;
+
+/// Number of acceptance filter banks (shared by CAN1 and CAN2).
+pub const CAN_FILTER_BANKS: u32 = 28;
+
+/// Timeout for the INAK/SLAK handshakes.
+const CAN_ACK_TIMEOUT: u32 = 100_000;
+
+/// Operating mode (LBKM/SILM bits in BTR).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CanMode {
+    /// Normal bus operation
+    Normal,
+    /// Frames are received back internally, nothing is needed on the pins;
+    /// TX is still driven onto the bus
+    Loopback,
+    /// Listen only: no ACK or frames are sent
+    Silent,
+    /// Loopback without driving the bus (self-test with the bus connected)
+    SilentLoopback,
+}
+
+// -----------------------------------------------------------------------------
+// Initialization
+// -----------------------------------------------------------------------------
+/// Initializes a CAN controller.
+///
+/// Exits sleep mode, enters initialization mode, programs the bit timing and
+/// mode, then leaves initialization mode. The controller joins the bus after
+/// 11 recessive bits.
+///
+/// # Arguments
+/// * `can_base`  - CAN1_BASE or CAN2_BASE
+/// * `prescaler` - Baud rate prescaler (1..=1024)
+/// * `seg1`      - Time segment 1 in quanta (1..=16)
+/// * `seg2`      - Time segment 2 in quanta (1..=8)
+/// * `sjw`       - Resynchronization jump width in quanta (1..=4)
+/// * `mode`      - Normal, loopback or silent operation
+///
+/// # Returns
+/// * `true` if the controller acknowledged both mode changes
+pub fn can_init(
+    can_base: u32,
+    prescaler: u32,
+    seg1: u32,
+    seg2: u32,
+    sjw: u32,
+    mode: CanMode,
+) -> bool {
+    assert!(
+        (1..=1024).contains(&prescaler),
+        "prescaler must be 1..=1024"
+    );
+    assert!((1..=16).contains(&seg1), "seg1 must be 1..=16");
+    assert!((1..=8).contains(&seg2), "seg2 must be 1..=8");
+    assert!((1..=4).contains(&sjw), "sjw must be 1..=4");
+
+    // CAN2 is a slave of CAN1 (shared filters): its clock needs CAN1's too
+    rcc_enable_peripheral(RccBus::Apb1, 25); // CAN1EN
+    if can_base == CAN2_BASE {
+        rcc_enable_peripheral(RccBus::Apb1, 26); // CAN2EN
+    }
+
+    let mcr_addr = (can_base + CAN_MCR) as *mut u32;
+    let msr_addr = (can_base + CAN_MSR) as *mut u32;
+
+    // Leave sleep mode (the reset state) and request initialization mode
+    reg_set_bit(mcr_addr, CAN_MCR_SLEEP_POS, false);
+    reg_set_bit(mcr_addr, CAN_MCR_INRQ_POS, true);
+    if !reg_wait_bit(msr_addr, CAN_MSR_INAK_POS, true, CAN_ACK_TIMEOUT)
+        || !reg_wait_bit(msr_addr, CAN_MSR_SLAK_POS, false, CAN_ACK_TIMEOUT)
+    {
+        return false;
+    }
+
+    // BTR fields hold value - 1: BRP[9:0], TS1[19:16], TS2[22:20], SJW[25:24]
+    let (silent, loopback) = match mode {
+        CanMode::Normal => (false, false),
+        CanMode::Loopback => (false, true),
+        CanMode::Silent => (true, false),
+        CanMode::SilentLoopback => (true, true),
+    };
+    let btr = (prescaler - 1)
+        | ((seg1 - 1) << 16)
+        | ((seg2 - 1) << 20)
+        | ((sjw - 1) << 24)
+        | ((loopback as u32) << CAN_BTR_LBKM_POS)
+        | ((silent as u32) << CAN_BTR_SILM_POS);
+    reg_set_val((can_base + CAN_BTR) as *mut u32, btr);
+
+    // Back to normal mode
+    reg_set_bit(mcr_addr, CAN_MCR_INRQ_POS, false);
+    reg_wait_bit(msr_addr, CAN_MSR_INAK_POS, false, CAN_ACK_TIMEOUT)
+}
+
+// -----------------------------------------------------------------------------
+// Acceptance Filters
+// -----------------------------------------------------------------------------
+// Each bank has two 32-bit registers (FR1, FR2) whose meaning depends on the
+// mode and scale:
+//
+//   Mask, 32-bit:  FR1 = identifier, FR2 = mask (1 = bit must match)
+//   List, 32-bit:  FR1, FR2 = two identifiers that are accepted
+//   Mask, 16-bit:  FRx = mask << 16 | identifier (two ID/mask pairs)
+//   List, 16-bit:  FRx = id_b << 16 | id_a (four identifiers)
+//
+// The values use the RIxR layout; build them with `can_filter_std32`,
+// `can_filter_ext32` and `can_filter_std16`. By default (FMR.CAN2SB = 14)
+// banks 0..13 belong to CAN1 and 14..27 to CAN2.
+
+/// Filter mode (FM1R).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CanFilterMode {
+    /// Identifier + mask: accepts every ID matching the masked bits
+    Mask,
+    /// Identifier list: accepts exactly the listed IDs
+    List,
+}
+
+/// Filter scale (FS1R).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CanFilterScale {
+    /// Two 16-bit filters per register (standard IDs only)
+    Dual16,
+    /// One 32-bit filter per register (standard or extended IDs)
+    Single32,
+}
+
+/// Receive FIFO a filter bank delivers its matches to (FFA1R).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CanFifo {
+    Fifo0,
+    Fifo1,
+}
+
+/// 32-bit filter value matching a standard (11-bit) identifier.
+pub const fn can_filter_std32(id: u32) -> u32 {
+    (id & CAN_STD_ID_MAX) << 21
+}
+
+/// 32-bit filter value matching an extended (29-bit) identifier (IDE set).
+pub const fn can_filter_ext32(id: u32) -> u32 {
+    ((id & 0x1FFF_FFFF) << 3) | (1 << CAN_TIXR_IDE_POS)
+}
+
+/// 16-bit filter value matching a standard (11-bit) identifier.
+pub const fn can_filter_std16(id: u32) -> u32 {
+    (id & CAN_STD_ID_MAX) << 5
+}
+
+/// Configures and activates one acceptance filter bank.
+///
+/// Enters filter init mode (FINIT), which pauses reception on both
+/// controllers, deactivates the bank, programs mode, scale, FIFO and the
+/// two filter registers, activates it and leaves init mode.
+///
+/// Example: accept the standard IDs 0x100..0x10F into FIFO 1:
+/// ```ignore
+/// can_config_filter(
+///     1,
+///     CanFilterMode::Mask,
+///     CanFilterScale::Single32,
+///     CanFifo::Fifo1,
+///     can_filter_std32(0x100),
+///     can_filter_std32(0x7F0) | (1 << CAN_TIXR_IDE_POS), // IDE must be 0
+/// );
+/// ```
+///
+/// # Arguments
+/// * `bank`  - Filter bank (0..27)
+/// * `mode`  - Mask or identifier list mode
+/// * `scale` - One 32-bit or two 16-bit filters per register
+/// * `fifo`  - FIFO that receives the matching frames
+/// * `id1`   - Value for FR1 (see the table above)
+/// * `id2`   - Value for FR2
+pub fn can_config_filter(
+    bank: u32,
+    mode: CanFilterMode,
+    scale: CanFilterScale,
+    fifo: CanFifo,
+    id1: u32,
+    id2: u32,
+) {
+    assert!(bank < CAN_FILTER_BANKS, "filter bank must be 0..27");
+
+    let fmr_addr = (CAN1_BASE + CAN_FMR) as *mut u32;
+    let fa1r_addr = (CAN1_BASE + CAN_FA1R) as *mut u32;
+
+    reg_set_bit(fmr_addr, CAN_FMR_FINIT_POS, true);
+
+    reg_set_bit(fa1r_addr, bank, false); // Deactivate while editing
+    reg_set_bit(
+        (CAN1_BASE + CAN_FM1R) as *mut u32,
+        bank,
+        mode == CanFilterMode::List,
+    );
+    reg_set_bit(
+        (CAN1_BASE + CAN_FS1R) as *mut u32,
+        bank,
+        scale == CanFilterScale::Single32,
+    );
+    reg_set_bit(
+        (CAN1_BASE + CAN_FFA1R) as *mut u32,
+        bank,
+        fifo == CanFifo::Fifo1,
+    );
+    reg_set_val((CAN1_BASE + CAN_F0R1 + 8 * bank) as *mut u32, id1);
+    reg_set_val((CAN1_BASE + CAN_F0R2 + 8 * bank) as *mut u32, id2);
+    reg_set_bit(fa1r_addr, bank, true); // Activate
+
+    reg_set_bit(fmr_addr, CAN_FMR_FINIT_POS, false);
+}
+
+/// Deactivates a filter bank; frames it matched are no longer received.
+pub fn can_disable_filter(bank: u32) {
+    assert!(bank < CAN_FILTER_BANKS, "filter bank must be 0..27");
+
+    let fmr_addr = (CAN1_BASE + CAN_FMR) as *mut u32;
+    reg_set_bit(fmr_addr, CAN_FMR_FINIT_POS, true);
+    reg_set_bit((CAN1_BASE + CAN_FA1R) as *mut u32, bank, false);
+    reg_set_bit(fmr_addr, CAN_FMR_FINIT_POS, false);
+}
+
+/// Configures filter bank 0 to accept every frame into FIFO 0.
+///
+/// Filter bank 0 is set to 32-bit mask mode with an all-zero mask, i.e.
+/// no identifier bit has to match.
+pub fn can_set_filter_accept_all() {
+    can_config_filter(
+        0,
+        CanFilterMode::Mask,
+        CanFilterScale::Single32,
+        CanFifo::Fifo0,
+        0,
+        0,
+    );
+}
+
+// -----------------------------------------------------------------------------
+// Transmit / Receive
+// -----------------------------------------------------------------------------
+/// Queues a data frame in the first empty TX mailbox.
+///
+/// # Arguments
+/// * `can_base` - CAN1_BASE or CAN2_BASE
+/// * `id`       - Identifier; up to 0x7FF is sent as standard (11-bit),
+///   larger values as extended (29-bit)
+/// * `data`     - Payload, 0..=8 bytes
+///
+/// # Returns
+/// * `false` if all three mailboxes are busy (nothing was queued)
+pub fn can_transmit(can_base: u32, id: u32, data: &[u8]) -> bool {
+    assert!(data.len() <= 8, "CAN frames carry at most 8 bytes");
+    assert!(id <= 0x1FFF_FFFF, "CAN identifiers are at most 29 bits");
+
+    let tsr = unsafe { reg_read_ro((can_base + CAN_TSR) as ReadOnlyRegister) };
+    let Some(mailbox) = (0..3).find(|mb| tsr & (1 << (CAN_TSR_TME0_POS + mb)) != 0) else {
+        return false;
+    };
+    let mb_offset = mailbox * 0x10;
+
+    let mut bytes = [0u8; 8];
+    bytes[..data.len()].copy_from_slice(data);
+    reg_set_val(
+        (can_base + CAN_TDLXR + mb_offset) as *mut u32,
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+    );
+    reg_set_val(
+        (can_base + CAN_TDHXR + mb_offset) as *mut u32,
+        u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+    );
+    reg_set_bits(
+        (can_base + CAN_TDTXR + mb_offset) as *mut u32,
+        data.len() as u32,
+        0,
+        4,
+    );
+
+    // STID in bits 31:21, or EXID in bits 31:3 with IDE; TXRQ last
+    let tir = if id <= CAN_STD_ID_MAX {
+        id << 21
+    } else {
+        (id << 3) | (1 << CAN_TIXR_IDE_POS)
+    };
+    reg_set_val(
+        (can_base + CAN_TIXR + mb_offset) as *mut u32,
+        tir | (1 << CAN_TIXR_TXRQ_POS),
+    );
+
+    true
+}
+
+/// Reads the oldest frame from FIFO 0, if any, and releases it.
+///
+/// # Arguments
+/// * `can_base` - CAN1_BASE or CAN2_BASE
+/// * `buf`      - Receives the payload (up to 8 bytes; excess is dropped)
+///
+/// # Returns
+/// * `Some((id, len))` with the identifier and payload length, or `None` if
+///   FIFO 0 is empty
+pub fn can_receive(can_base: u32, buf: &mut [u8]) -> Option<(u32, usize)> {
+    let rf0r_addr = (can_base + CAN_RF0R) as *mut u32;
+    if reg_read_bits(rf0r_addr, CAN_RF0R_FMP0_POS, 2) == 0 {
+        return None;
+    }
+
+    let rir = unsafe { reg_read((can_base + CAN_RIXR) as *mut u32) };
+    let id = if rir & (1 << CAN_TIXR_IDE_POS) != 0 {
+        rir >> 3
+    } else {
+        rir >> 21
+    };
+    let dlc = (reg_read_bits((can_base + CAN_RDTXR) as *mut u32, 0, 4) as usize).min(8);
+
+    let low = unsafe { reg_read((can_base + CAN_RDLXR) as *mut u32) }.to_le_bytes();
+    let high = unsafe { reg_read((can_base + CAN_RDHXR) as *mut u32) }.to_le_bytes();
+    for (i, byte) in buf.iter_mut().take(dlc).enumerate() {
+        *byte = if i < 4 { low[i] } else { high[i - 4] };
+    }
+
+    // Release the output mailbox so the next frame moves up
+    reg_set_bit(rf0r_addr, CAN_RF0R_RFOM0_POS, true);
+
+    Some((id, dlc))
+}
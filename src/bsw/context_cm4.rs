@@ -0,0 +1,263 @@
+// -----------------------------------------------------------------------------
+// STM32F429 Context switch (SVCall + PendSV) utilities
+// -----------------------------------------------------------------------------
+//
+// This module provides the two core primitives needed to build a small
+// preemptive scheduler on top of this crate:
+//
+// - `SVCall_Handler` starts the very first task (`context_start`).
+// - `PendSV_Handler` saves the outgoing task's software-stacked registers
+//   (r4-r11, EXC_RETURN and, if the task used the FPU, s16-s31), switches PSP
+//   to the incoming task's stack and restores its registers.
+//
+// Stack pointers (MSP vs PSP)
+// - After reset the core runs in Thread mode on the Main Stack Pointer (MSP),
+//   which is initialized from `_start_of_stack` (top of SRAM1).
+// - Tasks run in Thread mode on the Process Stack Pointer (PSP), each on its
+//   own `Task::stack`. The switch to PSP happens when `SVCall_Handler` returns
+//   with EXC_RETURN = 0xFFFF_FFFD.
+// - Exception handlers (including PendSV/SVCall and every IRQ) always run on
+//   MSP, so the SRAM1 stack must still be large enough for the deepest
+//   interrupt nesting. Task stacks only need to hold the task's own frames
+//   plus one exception frame.
+//
+// What is NOT provided here: a scheduling policy. Deciding which task runs
+// next (round robin from SysTick, priorities, ...) is left to the caller,
+// who calls `context_switch_to` with the chosen task.
+//
+// Reference: ARMv7-M Architecture Reference Manual, B1.5 (Exception model)
+// -----------------------------------------------------------------------------
+
+#[cfg(target_arch = "arm")]
+use core::arch::naked_asm;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::bsw::reg_cpu_cortex_m4::*; // Cortex-M4 core register addresses
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// Core register bits used by the context switch
+// -----------------------------------------------------------------------------
+const ICSR_PENDSVSET_POS: u32 = 28; // ICSR: set PendSV pending
+const SHPR3_PRI_14_POS: u32 = 16; // SHPR3: PendSV priority field (bits 23:16)
+const PENDSV_PRIORITY_LOWEST: u32 = 0xFF; // Lowest possible exception priority
+
+/// EXC_RETURN value: return to Thread mode, use PSP, basic (non-FPU) frame.
+pub const EXC_RETURN_THREAD_PSP: u32 = 0xFFFF_FFFD;
+
+/// Initial xPSR value for a task: only the Thumb bit (T, bit 24) set.
+pub const XPSR_THUMB: u32 = 1 << 24;
+
+/// Number of words in the hardware-stacked exception frame (r0-r3, r12, lr, pc, xPSR).
+const HW_FRAME_WORDS: usize = 8;
+/// Number of words pushed by `PendSV_Handler` (r4-r11 plus EXC_RETURN).
+const SW_FRAME_WORDS: usize = 9;
+
+/// Minimum stack size (in words) accepted by `Task::new`.
+pub const TASK_MIN_STACK_WORDS: usize = HW_FRAME_WORDS + SW_FRAME_WORDS + 2;
+
+// -----------------------------------------------------------------------------
+// Context pointers shared with the assembly handlers
+// -----------------------------------------------------------------------------
+// Both point to the `sp` field of a `Task`. `CONTEXT_CURRENT` is where the
+// outgoing PSP is saved; `CONTEXT_NEXT` is where the incoming PSP is loaded
+// from. A null `CONTEXT_CURRENT` means "no task running yet".
+static CONTEXT_CURRENT: AtomicPtr<u32> = AtomicPtr::new(ptr::null_mut());
+static CONTEXT_NEXT: AtomicPtr<u32> = AtomicPtr::new(ptr::null_mut());
+
+// -----------------------------------------------------------------------------
+// Task
+// -----------------------------------------------------------------------------
+/// A task that can be switched to by `PendSV_Handler`.
+///
+/// Each task owns a private stack. `Task::new` builds an initial exception
+/// frame on top of it so that the first switch "returns" into `entry`.
+pub struct Task {
+    /// The task's private stack (used through PSP).
+    pub stack: &'static mut [u32],
+    /// Task entry point. If it ever returns, the task parks in `task_exit`.
+    pub entry: fn(),
+    /// Saved process stack pointer (valid while the task is not running).
+    sp: u32,
+}
+
+impl Task {
+    /// Creates a task and initializes its stack frame.
+    ///
+    /// # Arguments
+    /// * `stack` - Private stack for the task (at least `TASK_MIN_STACK_WORDS` words)
+    /// * `entry` - Function the task starts executing
+    pub fn new(stack: &'static mut [u32], entry: fn()) -> Self {
+        let mut task = Task {
+            stack,
+            entry,
+            sp: 0,
+        };
+        task.init_stack_frame();
+        task
+    }
+
+    /// (Re)builds the initial stack frame so the task starts again at `entry`.
+    ///
+    /// Frame layout, from the saved `sp` upwards:
+    /// ```text
+    /// r4 r5 r6 r7 r8 r9 r10 r11 EXC_RETURN | r0 r1 r2 r3 r12 lr pc xPSR
+    /// \------- restored by PendSV -------/ \---- popped by hardware ----/
+    /// ```
+    /// The hardware part starts on an 8-byte boundary as the architecture requires.
+    pub fn init_stack_frame(&mut self) {
+        assert!(
+            self.stack.len() >= TASK_MIN_STACK_WORDS,
+            "task stack too small"
+        );
+
+        // Index one past the last word, rounded down so the frame is 8-byte aligned.
+        let base = self.stack.as_ptr() as usize;
+        let mut top = self.stack.len();
+        if (base + top * 4) & 0x7 != 0 {
+            top -= 1;
+        }
+
+        let hw = top - HW_FRAME_WORDS;
+        self.stack[hw] = 0; // r0
+        self.stack[hw + 1] = 0; // r1
+        self.stack[hw + 2] = 0; // r2
+        self.stack[hw + 3] = 0; // r3
+        self.stack[hw + 4] = 0; // r12
+        self.stack[hw + 5] = task_exit as *const () as u32; // lr
+        self.stack[hw + 6] = self.entry as *const () as u32; // pc
+        self.stack[hw + 7] = XPSR_THUMB; // xPSR
+
+        let sw = hw - SW_FRAME_WORDS;
+        for word in &mut self.stack[sw..sw + 8] {
+            *word = 0; // r4-r11
+        }
+        self.stack[sw + 8] = EXC_RETURN_THREAD_PSP;
+
+        self.sp = (base + sw * 4) as u32;
+    }
+}
+
+/// Landing pad for tasks whose entry function returns.
+extern "C" fn task_exit() {
+    loop {}
+}
+
+// -----------------------------------------------------------------------------
+// Public API
+// -----------------------------------------------------------------------------
+/// Requests a PendSV exception (sets PENDSVSET in ICSR).
+///
+/// PendSV runs once no other exception is active, which is what makes it the
+/// right place to perform the actual context switch.
+pub fn pendsv_trigger() {
+    // ICSR is write-1-to-act: writing zeros to the other bits has no effect
+    reg_set_val(ICSR_BASE as *mut u32, 1 << ICSR_PENDSVSET_POS);
+}
+
+/// Starts the first task. Never returns.
+///
+/// Sets PendSV to the lowest priority (so it never preempts an IRQ) and
+/// issues `svc 0`; `SVCall_Handler` then switches Thread mode onto the task's
+/// PSP. From this point on, `main`'s MSP stack is only used by handlers.
+///
+/// # Safety
+/// `PendSV_Handler` keeps a pointer to `first` and saves the task's stack
+/// pointer through it on every later switch. `first` must not be moved or
+/// dropped for the rest of the program (e.g. a `static`).
+pub unsafe fn context_start(first: &mut Task) -> ! {
+    reg_set_bits(
+        SHPR3_BASE as *mut u32,
+        PENDSV_PRIORITY_LOWEST,
+        SHPR3_PRI_14_POS,
+        8,
+    );
+
+    CONTEXT_CURRENT.store(ptr::null_mut(), Ordering::SeqCst);
+    CONTEXT_NEXT.store(ptr::addr_of_mut!(first.sp), Ordering::SeqCst);
+
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        core::arch::asm!("svc 0");
+    }
+
+    // Not reached: SVCall_Handler does not return here.
+    loop {}
+}
+
+/// Schedules a switch to `next`; the switch happens when PendSV runs.
+///
+/// Safe to call from Thread mode (a task) or from an interrupt handler
+/// (e.g., a SysTick-driven round robin).
+///
+/// # Safety
+/// `next` is stored for `PendSV_Handler`, which restores from it and later
+/// saves into it. It must not be moved or dropped while it is scheduled or
+/// running, and must have been set up with `Task::new`.
+pub unsafe fn context_switch_to(next: &mut Task) {
+    CONTEXT_NEXT.store(ptr::addr_of_mut!(next.sp), Ordering::SeqCst);
+    pendsv_trigger();
+}
+
+// -----------------------------------------------------------------------------
+// Exception handlers
+// -----------------------------------------------------------------------------
+/// SVCall handler: loads the first task's context and returns into it on PSP.
+#[cfg(target_arch = "arm")]
+#[unsafe(naked)]
+#[unsafe(no_mangle)]
+pub extern "C" fn SVCall_Handler() {
+    naked_asm!(
+        "ldr r3, ={next}",
+        "ldr r1, [r3]",              // r1 = &next.sp
+        "ldr r2, ={current}",
+        "str r1, [r2]",              // current = next
+        "ldr r0, [r1]",              // r0 = next.sp
+        "ldmia r0!, {{r4-r11, lr}}", // restore r4-r11 and EXC_RETURN
+        "msr psp, r0",
+        "isb",
+        "bx lr",                     // return to Thread mode on PSP
+        next = sym CONTEXT_NEXT,
+        current = sym CONTEXT_CURRENT,
+    );
+}
+
+/// PendSV handler: saves the current task and restores `CONTEXT_NEXT`.
+///
+/// FPU registers s16-s31 are only saved/restored when the task's EXC_RETURN
+/// says an extended (FPU) frame was stacked (bit 4 clear).
+#[cfg(target_arch = "arm")]
+#[unsafe(naked)]
+#[unsafe(no_mangle)]
+pub extern "C" fn PendSV_Handler() {
+    naked_asm!(
+        ".fpu fpv4-sp-d16",          // allow the FPU instructions below to assemble
+        "cpsid i",
+        "mrs r0, psp",
+        "isb",
+        "ldr r2, ={current}",
+        "ldr r1, [r2]",              // r1 = &current.sp (null before the first task)
+        "cbz r1, 1f",
+        "tst lr, #0x10",
+        "it eq",
+        "vstmdbeq r0!, {{s16-s31}}", // FPU context in use: save callee-saved FP regs
+        "stmdb r0!, {{r4-r11, lr}}",
+        "str r0, [r1]",              // current.sp = psp
+        "1:",
+        "ldr r3, ={next}",
+        "ldr r1, [r3]",              // r1 = &next.sp
+        "str r1, [r2]",              // current = next
+        "ldr r0, [r1]",              // r0 = next.sp
+        "ldmia r0!, {{r4-r11, lr}}",
+        "tst lr, #0x10",
+        "it eq",
+        "vldmiaeq r0!, {{s16-s31}}",
+        "msr psp, r0",
+        "isb",
+        "cpsie i",
+        "bx lr",
+        next = sym CONTEXT_NEXT,
+        current = sym CONTEXT_CURRENT,
+    );
+}
@@ -1,3 +1,85 @@
 // -----------------------------------------------------------------------------
 // STM32F429 CRC (Cyclic Redundancy Check) utilities
 // -----------------------------------------------------------------------------
+//
+// This module wraps the CRC calculation unit. The hardware is fixed to:
+//
+// - Polynomial 0x04C11DB7 (the Ethernet / CRC-32 polynomial)
+// - Initial value 0xFFFF_FFFF (loaded by `crc_reset`)
+// - 32-bit input words, processed MSB first; no input/output bit reversal
+// - No final XOR
+//
+// This is the CRC-32/MPEG-2 algorithm over the bytes of each word in
+// big-endian order. Words read from memory on this little-endian CPU are
+// therefore processed as byte-swapped groups of 4: to match a host tool
+// working on the byte stream, either byte-swap each word (`u32::from_be_bytes`
+// on the 4 bytes) or compute the host-side CRC on the same words.
+// The result is NOT the zlib/PNG CRC-32 (that one reflects bits and inverts
+// the result).
+//
+// Test vector: `crc_compute(&[0x1234_5678]) == 0xDF8A_8A2B`
+//
+// Example: verify a firmware image whose CRC was appended by the build
+// ```
+// crc_init();
+// let image: &[u32] = /* image words, without the CRC word */;
+// assert_eq!(crc_compute(image), expected_crc);
+// ```
+//
+// Reference: STM32F429 Reference Manual, section 4 (CRC calculation unit)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::rcc::{RccBus, rcc_enable_peripheral};
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// CRC Register Offsets (relative to CRC_BASE)
+// -----------------------------------------------------------------------------
+pub const CRC_DR: u32 = 0x00; // Data register (write: input, read: result)
+pub const CRC_IDR: u32 = 0x04; // Independent data register (8-bit scratch)
+pub const CRC_CR: u32 = 0x08; // Control register
+
+// -----------------------------------------------------------------------------
+// CRC Register Bits
+// -----------------------------------------------------------------------------
+pub const CRC_CR_RESET_POS: u32 = 0; // Reset DR to 0xFFFF_FFFF
+
+/// RCC_AHB1ENR bit of the CRC clock.
+const RCC_AHB1ENR_CRCEN_POS: u32 = 12;
+
+/// Enables the CRC unit clock and resets the calculation.
+pub fn crc_init() {
+    rcc_enable_peripheral(RccBus::Ahb1, RCC_AHB1ENR_CRCEN_POS);
+    crc_reset();
+}
+
+/// Starts a new calculation (DR = 0xFFFF_FFFF).
+pub fn crc_reset() {
+    reg_set_val((CRC_BASE + CRC_CR) as *mut u32, 1 << CRC_CR_RESET_POS);
+}
+
+/// Adds one word to the running calculation.
+pub fn crc_feed_word(w: u32) {
+    reg_set_val((CRC_BASE + CRC_DR) as *mut u32, w);
+}
+
+/// Returns the CRC of all words fed since the last reset.
+pub fn crc_value() -> u32 {
+    unsafe { reg_read((CRC_BASE + CRC_DR) as *mut u32) }
+}
+
+/// Computes the CRC of a word buffer from a fresh start.
+///
+/// # Arguments
+/// * `data` - Words to process (see the byte ordering notes at the top)
+///
+/// # Returns
+/// * The CRC-32/MPEG-2 value of `data`
+pub fn crc_compute(data: &[u32]) -> u32 {
+    crc_reset();
+    for &w in data {
+        crc_feed_word(w);
+    }
+    crc_value()
+}
@@ -0,0 +1,70 @@
+// -----------------------------------------------------------------------------
+// STM32F429 Critical Section (PRIMASK) utilities
+// -----------------------------------------------------------------------------
+//
+// This module masks and unmasks interrupts through PRIMASK. With PRIMASK set,
+// every exception with configurable priority (all IRQs, SysTick, PendSV,
+// SVCall) is held pending; only NMI and HardFault can still run.
+//
+// `with_critical_section` saves and restores the previous PRIMASK, so it can
+// be nested and used from interrupt handlers. Keep the closures short: every
+// interrupt is delayed until the critical section ends.
+//
+// Reference: Cortex-M4 Devices Generic User Guide, 2.1.3 (PRIMASK) and
+//            3.12.2 (CPS)
+// -----------------------------------------------------------------------------
+
+#[cfg(target_arch = "arm")]
+use core::arch::asm;
+
+/// Masks all configurable-priority interrupts (CPSID i).
+pub fn disable_interrupts() {
+    // No `nomem`: the asm also acts as a compiler barrier, so memory accesses
+    // are not moved out of the masked region
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        asm!("cpsid i", options(nostack, preserves_flags))
+    };
+}
+
+/// Unmasks interrupts (CPSIE i).
+///
+/// Only call this if interrupts were enabled before the matching
+/// `disable_interrupts`; otherwise prefer `with_critical_section`.
+pub fn enable_interrupts() {
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        asm!("cpsie i", options(nostack, preserves_flags))
+    };
+}
+
+/// Returns true if interrupts are currently masked (PRIMASK = 1).
+pub fn interrupts_masked() -> bool {
+    // Host builds (unit tests) have no PRIMASK: report "not masked"
+    #[cfg(not(target_arch = "arm"))]
+    let primask: u32 = 0;
+    #[cfg(target_arch = "arm")]
+    let primask: u32;
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        asm!("mrs {}, PRIMASK", out(reg) primask, options(nomem, nostack, preserves_flags))
+    };
+    primask & 1 != 0
+}
+
+/// Runs `f` with interrupts masked and restores the previous PRIMASK state.
+///
+/// # Arguments
+/// * `f` - Code to run atomically with respect to interrupt handlers
+///
+/// # Returns
+/// * The value returned by `f`
+pub fn with_critical_section<R>(f: impl FnOnce() -> R) -> R {
+    let was_masked = interrupts_masked();
+    disable_interrupts();
+    let result = f();
+    if !was_masked {
+        enable_interrupts();
+    }
+    result
+}
@@ -1,3 +1,142 @@
 // -----------------------------------------------------------------------------
 // STM32F429 DAC (Digital-to-Analog Converter) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions for the two 12-bit
+// DAC channels (right-aligned data):
+//
+//   Channel 1 -> PA4        Channel 2 -> PA5
+//
+// Without a trigger (the `dac_init` default) a value written to DHR12Rx
+// appears on the output one APB1 clock later. With a trigger enabled, the
+// value (or the next triangle step) is only transferred on the trigger, e.g.
+// `dac_software_trigger`.
+//
+// Reference: STM32F429 Reference Manual, section 14.5 (DAC registers)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::gpio::{GPIO_PIN_4, GPIO_PIN_5, GpioMode, gpio_set_mode};
+use crate::bsw::rcc::{RccBus, rcc_enable_gpio_clock, rcc_enable_peripheral};
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// DAC Register Offsets (relative to DAC_BASE)
+// -----------------------------------------------------------------------------
+pub const DAC_CR: u32 = 0x00; // Control register
+pub const DAC_SWTRIGR: u32 = 0x04; // Software trigger register
+pub const DAC_DHR12R1: u32 = 0x08; // Channel 1 12-bit right-aligned data holding register
+pub const DAC_DHR12R2: u32 = 0x14; // Channel 2 12-bit right-aligned data holding register
+pub const DAC_DOR1: u32 = 0x2C; // Channel 1 data output register
+pub const DAC_DOR2: u32 = 0x30; // Channel 2 data output register
+pub const DAC_SR: u32 = 0x34; // Status register
+
+// -----------------------------------------------------------------------------
+// DAC_CR Bits (channel 1; channel 2 uses the same layout shifted by 16)
+// -----------------------------------------------------------------------------
+pub const DAC_CR_EN_POS: u32 = 0; // Channel enable
+pub const DAC_CR_BOFF_POS: u32 = 1; // Output buffer disable
+pub const DAC_CR_TEN_POS: u32 = 2; // Trigger enable
+pub const DAC_CR_TSEL_POS: u32 = 3; // Trigger selection (3 bits)
+pub const DAC_CR_WAVE_POS: u32 = 6; // Noise/triangle wave generation (2 bits)
+pub const DAC_CR_MAMP_POS: u32 = 8; // Mask/amplitude selector (4 bits)
+
+/// Largest 12-bit DAC value (output = VREF+).
+pub const DAC_VALUE_MAX: u16 = 0x0FFF;
+
+/// TSEL value selecting the software trigger.
+const DAC_TSEL_SOFTWARE: u32 = 0b111;
+/// WAVE value selecting triangle generation.
+const DAC_WAVE_TRIANGLE: u32 = 0b10;
+/// RCC_APB1ENR bit of the DAC clock.
+const RCC_APB1ENR_DACEN_POS: u32 = 29;
+
+/// Returns the bit offset of a channel's fields in DAC_CR.
+fn dac_cr_shift(channel: u32) -> u32 {
+    assert!(channel == 1 || channel == 2, "DAC channel must be 1 or 2");
+    (channel - 1) * 16
+}
+
+// -----------------------------------------------------------------------------
+// Initialize DAC
+// -----------------------------------------------------------------------------
+/// Enables the DAC clock, sets the channel pin to analog and enables the
+/// channel (output buffer on, no trigger, no wave generation).
+///
+/// # Arguments
+/// * `channel` - DAC channel (1 = PA4, 2 = PA5)
+pub fn dac_init(channel: u32) {
+    let shift = dac_cr_shift(channel);
+
+    rcc_enable_peripheral(RccBus::Apb1, RCC_APB1ENR_DACEN_POS);
+
+    // Analog mode disconnects the digital input/output from the pin
+    let pin = if channel == 1 { GPIO_PIN_4 } else { GPIO_PIN_5 };
+    rcc_enable_gpio_clock(GPIOA_BASE);
+    gpio_set_mode(GPIOA_BASE, pin, GpioMode::Analog);
+
+    let cr_addr = (DAC_BASE + DAC_CR) as *mut u32;
+    reg_set_bits(cr_addr, 0, shift, 16);
+    reg_set_bit(cr_addr, shift + DAC_CR_EN_POS, true);
+}
+
+// -----------------------------------------------------------------------------
+// Output
+// -----------------------------------------------------------------------------
+/// Writes a 12-bit value to a channel (DHR12Rx).
+///
+/// # Arguments
+/// * `channel` - DAC channel (1 or 2)
+/// * `value`   - Output value (0..=4095; output = VREF+ * value / 4096)
+pub fn dac_write(channel: u32, value: u16) {
+    dac_cr_shift(channel);
+    assert!(value <= DAC_VALUE_MAX, "DAC value must fit in 12 bits");
+
+    let dhr_offset = if channel == 1 {
+        DAC_DHR12R1
+    } else {
+        DAC_DHR12R2
+    };
+    reg_set_val((DAC_BASE + dhr_offset) as *mut u32, value as u32);
+}
+
+/// Returns the value currently driven by a channel (DORx).
+pub fn dac_read_output(channel: u32) -> u16 {
+    dac_cr_shift(channel);
+    let dor_offset = if channel == 1 { DAC_DOR1 } else { DAC_DOR2 };
+    (unsafe { reg_read((DAC_BASE + dor_offset) as *mut u32) } & 0x0FFF) as u16
+}
+
+/// Starts triangle wave generation, advanced by `dac_software_trigger`.
+///
+/// Each trigger moves the output one step up (then down) between `base`
+/// and `base + 2^(amplitude_bits + 1) - 1`.
+///
+/// # Arguments
+/// * `channel`        - DAC channel (1 or 2)
+/// * `base`           - Bottom of the triangle (DHR12Rx value)
+/// * `amplitude_bits` - MAMP value (0..11): amplitude = 2^(amplitude_bits + 1) - 1
+pub fn dac_write_triangle(channel: u32, base: u16, amplitude_bits: u32) {
+    let shift = dac_cr_shift(channel);
+    assert!(amplitude_bits <= 11, "triangle amplitude must be 0..11");
+
+    // The channel must be disabled while the wave settings change
+    let cr_addr = (DAC_BASE + DAC_CR) as *mut u32;
+    reg_set_bit(cr_addr, shift + DAC_CR_EN_POS, false);
+    reg_set_bits(cr_addr, DAC_TSEL_SOFTWARE, shift + DAC_CR_TSEL_POS, 3);
+    reg_set_bits(cr_addr, DAC_WAVE_TRIANGLE, shift + DAC_CR_WAVE_POS, 2);
+    reg_set_bits(cr_addr, amplitude_bits, shift + DAC_CR_MAMP_POS, 4);
+    reg_set_bit(cr_addr, shift + DAC_CR_TEN_POS, true);
+    reg_set_bit(cr_addr, shift + DAC_CR_EN_POS, true);
+
+    dac_write(channel, base);
+}
+
+/// Fires the software trigger of a channel (SWTRIGx).
+///
+/// Requires the channel's trigger to be enabled with the software source
+/// (e.g., by `dac_write_triangle`); the bit clears itself.
+pub fn dac_software_trigger(channel: u32) {
+    dac_cr_shift(channel);
+    reg_set_bit((DAC_BASE + DAC_SWTRIGR) as *mut u32, channel - 1, true);
+}
@@ -1,3 +1,255 @@
 // -----------------------------------------------------------------------------
 // STM32F429 DCMI (Digital Camera Memory Interface) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions to capture frames
+// from an 8-bit parallel camera into memory through DMA2.
+//
+// Data path: every 4 received bytes are packed into one 32-bit word of
+// DCMI_DR, which DMA2 stream 1 (channel 1, the DCMI request) moves into the
+// frame buffer. A frame of W x H pixels at 2 bytes per pixel (RGB565/YUV422)
+// therefore needs W * H / 2 words.
+//
+// The camera pins (PIXCLK, HSYNC, VSYNC, D0..D7) must be configured
+// separately as alternate function AF13, and the camera sensor itself set
+// up (usually over I2C/SCCB) before capturing.
+//
+// Reference: STM32F429 Reference Manual, section 15.8 (DCMI registers)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::dma::*; // DMA stream configuration
+use crate::bsw::rcc::*; // RCC clock enable
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// DCMI Register Offsets (relative to DCMI_BASE)
+// -----------------------------------------------------------------------------
+pub const DCMI_CR: u32 = 0x00; // Control register
+pub const DCMI_SR: u32 = 0x04; // Status register
+pub const DCMI_RIS: u32 = 0x08; // Raw interrupt status register
+pub const DCMI_IER: u32 = 0x0C; // Interrupt enable register
+pub const DCMI_MIS: u32 = 0x10; // Masked interrupt status register
+pub const DCMI_ICR: u32 = 0x14; // Interrupt clear register
+pub const DCMI_ESCR: u32 = 0x18; // Embedded synchronization code register
+pub const DCMI_ESUR: u32 = 0x1C; // Embedded synchronization unmask register
+pub const DCMI_CWSTRT: u32 = 0x20; // Crop window start
+pub const DCMI_CWSIZE: u32 = 0x24; // Crop window size
+pub const DCMI_DR: u32 = 0x28; // Data register
+
+// -----------------------------------------------------------------------------
+// DCMI Bit Positions
+// -----------------------------------------------------------------------------
+// CR
+pub const DCMI_CR_CAPTURE_POS: u32 = 0; // Capture enable
+pub const DCMI_CR_CM_POS: u32 = 1; // Capture mode (1 = snapshot)
+pub const DCMI_CR_ESS_POS: u32 = 4; // Embedded synchronization select
+pub const DCMI_CR_PCKPOL_POS: u32 = 5; // Pixel clock polarity (1 = rising edge)
+pub const DCMI_CR_HSPOL_POS: u32 = 6; // HSYNC polarity (1 = active high)
+pub const DCMI_CR_VSPOL_POS: u32 = 7; // VSYNC polarity (1 = active high)
+pub const DCMI_CR_EDM_POS: u32 = 10; // Extended data mode (2 bits, 00 = 8 bit)
+pub const DCMI_CR_ENABLE_POS: u32 = 14; // DCMI enable
+
+// RIS / IER / MIS / ICR
+pub const DCMI_FRAME_POS: u32 = 0; // Capture complete
+pub const DCMI_OVR_POS: u32 = 1; // Overrun (data lost, DMA too slow)
+pub const DCMI_ERR_POS: u32 = 2; // Synchronization error (embedded sync)
+pub const DCMI_VSYNC_POS: u32 = 3; // VSYNC (start of frame)
+pub const DCMI_LINE_POS: u32 = 4; // End of line
+
+/// All flags in DCMI_ICR.
+const DCMI_ALL_FLAGS: u32 = 0x1F;
+
+/// DMA stream and channel carrying the DCMI request.
+const DCMI_DMA_STREAM: u32 = 1;
+const DCMI_DMA_CHANNEL: u32 = 1;
+
+/// DCMI clock enable bit in RCC_AHB2ENR.
+const RCC_AHB2ENR_DCMIEN_BIT: u32 = 0;
+
+/// Polls of DCMI_RIS before a capture times out.
+const DCMI_CAPTURE_TIMEOUT_CYCLES: u32 = 50_000_000;
+
+// -----------------------------------------------------------------------------
+// DCMI Configuration
+// -----------------------------------------------------------------------------
+/// Frame/line synchronization method.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DcmiSync {
+    /// HSYNC/VSYNC pins; `true` = active high (the level during blanking)
+    Hardware {
+        hsync_active_high: bool,
+        vsync_active_high: bool,
+    },
+    /// Codes embedded in the data stream (ITU-R BT.656 style)
+    Embedded {
+        frame_start: u8,
+        line_start: u8,
+        line_end: u8,
+        frame_end: u8,
+    },
+}
+
+/// Capture mode.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DcmiCaptureMode {
+    /// Capture frames until CAPTURE is cleared
+    Continuous,
+    /// Capture a single frame, then CAPTURE clears itself
+    Snapshot,
+}
+
+/// DCMI configuration (8-bit data bus).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DcmiConfig {
+    pub sync: DcmiSync,
+    pub mode: DcmiCaptureMode,
+    /// Sample data on the rising (true) or falling (false) PIXCLK edge
+    pub pclk_rising: bool,
+}
+
+/// Reasons a frame capture failed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DcmiError {
+    /// Data arrived faster than DMA could move it
+    Overrun,
+    /// Embedded synchronization codes out of order
+    SyncError,
+    /// The DMA stream reported an error
+    Dma(DmaError),
+    /// No complete frame within the timeout (camera not running?)
+    Timeout,
+}
+
+// -----------------------------------------------------------------------------
+// Initialize DCMI
+// -----------------------------------------------------------------------------
+/// Enables the DCMI and DMA2 clocks and configures the interface.
+///
+/// # Arguments
+/// * `config` - Synchronization, capture mode and pixel clock edge
+pub fn dcmi_init(config: DcmiConfig) {
+    rcc_enable_peripheral(RccBus::Ahb2, RCC_AHB2ENR_DCMIEN_BIT);
+    dma_enable_clock(DMA2_BASE);
+
+    let cr_addr = (DCMI_BASE + DCMI_CR) as *mut u32;
+    reg_set_val(cr_addr, 0); // Disabled while configuring
+
+    let mut cr = ((config.pclk_rising as u32) << DCMI_CR_PCKPOL_POS)
+        | (((config.mode == DcmiCaptureMode::Snapshot) as u32) << DCMI_CR_CM_POS);
+    match config.sync {
+        DcmiSync::Hardware {
+            hsync_active_high,
+            vsync_active_high,
+        } => {
+            cr |= ((hsync_active_high as u32) << DCMI_CR_HSPOL_POS)
+                | ((vsync_active_high as u32) << DCMI_CR_VSPOL_POS);
+        }
+        DcmiSync::Embedded {
+            frame_start,
+            line_start,
+            line_end,
+            frame_end,
+        } => {
+            cr |= 1 << DCMI_CR_ESS_POS;
+            reg_set_val(
+                (DCMI_BASE + DCMI_ESCR) as *mut u32,
+                ((frame_end as u32) << 24)
+                    | ((line_end as u32) << 16)
+                    | ((line_start as u32) << 8)
+                    | frame_start as u32,
+            );
+            // Compare all bits of every code
+            reg_set_val((DCMI_BASE + DCMI_ESUR) as *mut u32, 0xFFFF_FFFF);
+        }
+    }
+
+    reg_set_val(cr_addr, cr);
+    reg_set_bit(cr_addr, DCMI_CR_ENABLE_POS, true);
+}
+
+// -----------------------------------------------------------------------------
+// Frame Capture
+// -----------------------------------------------------------------------------
+/// Captures one frame into `buffer` and waits until it is complete.
+///
+/// Capture starts at the next frame start; the frame must fit into the
+/// buffer (surplus data ends the DMA transfer early and is lost). In
+/// continuous mode, capture is stopped again after the frame.
+///
+/// # Arguments
+/// * `buffer` - Destination, 1..65535 words in SRAM (not CCM RAM)
+///
+/// # Returns
+/// * `Ok(())` once the frame is in `buffer`, otherwise the `DcmiError`
+pub fn dcmi_capture_frame(buffer: &mut [u32]) -> Result<(), DcmiError> {
+    assert!(
+        !buffer.is_empty() && buffer.len() as u32 <= DMA_MAX_TRANSFER_ITEMS,
+        "DCMI buffer must be 1..65535 words"
+    );
+
+    // DMA2 stream 1: DCMI_DR -> buffer, 32-bit words, FIFO mode
+    dma_stream_disable(DMA2_BASE, DCMI_DMA_STREAM);
+    dma_clear_flags(DMA2_BASE, DCMI_DMA_STREAM);
+    reg_set_val(
+        dma_stream_reg(DMA2_BASE, DCMI_DMA_STREAM, DMA_SXPAR),
+        DCMI_BASE + DCMI_DR,
+    );
+    reg_set_val(
+        dma_stream_reg(DMA2_BASE, DCMI_DMA_STREAM, DMA_SXM0AR),
+        buffer.as_mut_ptr() as u32,
+    );
+    reg_set_val(
+        dma_stream_reg(DMA2_BASE, DCMI_DMA_STREAM, DMA_SXNDTR),
+        buffer.len() as u32,
+    );
+    reg_set_val(
+        dma_stream_reg(DMA2_BASE, DCMI_DMA_STREAM, DMA_SXFCR),
+        1 << DMA_SXFCR_DMDIS_POS,
+    );
+    let dma_cr_addr = dma_stream_reg(DMA2_BASE, DCMI_DMA_STREAM, DMA_SXCR);
+    reg_set_val(
+        dma_cr_addr,
+        (DCMI_DMA_CHANNEL << DMA_SXCR_CHSEL_POS)
+            | (DMA_SIZE_WORD << DMA_SXCR_MSIZE_POS)
+            | (DMA_SIZE_WORD << DMA_SXCR_PSIZE_POS)
+            | (1 << DMA_SXCR_MINC_POS)
+            | (DMA_DIR_PERIPH_TO_MEM << DMA_SXCR_DIR_POS),
+    );
+    reg_set_bit(dma_cr_addr, DMA_SXCR_EN_POS, true);
+
+    // Start capturing at the next frame
+    let cr_addr = (DCMI_BASE + DCMI_CR) as *mut u32;
+    let ris_addr = (DCMI_BASE + DCMI_RIS) as *mut u32;
+    reg_set_val((DCMI_BASE + DCMI_ICR) as *mut u32, DCMI_ALL_FLAGS);
+    reg_set_bit(cr_addr, DCMI_CR_CAPTURE_POS, true);
+
+    let mut cycles = 0;
+    let result = loop {
+        let ris = unsafe { reg_read_ro(ris_addr) };
+        if ris & (1 << DCMI_OVR_POS) != 0 {
+            break Err(DcmiError::Overrun);
+        }
+        if ris & (1 << DCMI_ERR_POS) != 0 {
+            break Err(DcmiError::SyncError);
+        }
+        if let Some(err) = dma_take_error(DMA2_BASE, DCMI_DMA_STREAM) {
+            break Err(DcmiError::Dma(err));
+        }
+        if ris & (1 << DCMI_FRAME_POS) != 0 {
+            break Ok(());
+        }
+        cycles += 1;
+        if cycles >= DCMI_CAPTURE_TIMEOUT_CYCLES {
+            break Err(DcmiError::Timeout);
+        }
+    };
+
+    // Stop (a no-op after a snapshot) and release the DMA stream
+    reg_set_bit(cr_addr, DCMI_CR_CAPTURE_POS, false);
+    reg_set_val((DCMI_BASE + DCMI_ICR) as *mut u32, DCMI_ALL_FLAGS);
+    dma_stream_disable(DMA2_BASE, DCMI_DMA_STREAM);
+    dma_clear_flags(DMA2_BASE, DCMI_DMA_STREAM);
+
+    result
+}
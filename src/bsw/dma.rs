@@ -1,3 +1,370 @@
 // -----------------------------------------------------------------------------
 // STM32F429 DMA (Direct Memory Access) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions for the two DMA
+// controllers (DMA1, DMA2), each with 8 streams.
+//
+// Every stream has five status flags, packed in groups of 6 bits into the
+// low (LISR: streams 0..3) and high (HISR: streams 4..7) interrupt status
+// registers, and cleared through LIFCR/HIFCR at the same bit positions:
+//
+//   stream:      0/4   1/5   2/6   3/7
+//   group start:  0     6    16    22
+//
+//   within a group: FEIF +0, DMEIF +2, TEIF +3, HTIF +4, TCIF +5
+//
+// Reference: STM32F429 Reference Manual, section 10.5 (DMA registers)
+// -----------------------------------------------------------------------------
+
+use core::hint;
+
+use crate::bsw::rcc::*; // RCC clock enable
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// DMA Register Offsets (relative to DMA1_BASE / DMA2_BASE)
+// -----------------------------------------------------------------------------
+pub const DMA_LISR: u32 = 0x00; // Low interrupt status register (streams 0..3)
+pub const DMA_HISR: u32 = 0x04; // High interrupt status register (streams 4..7)
+pub const DMA_LIFCR: u32 = 0x08; // Low interrupt flag clear register
+pub const DMA_HIFCR: u32 = 0x0C; // High interrupt flag clear register
+
+// Stream registers: offset + 0x18 * stream
+pub const DMA_SXCR: u32 = 0x10; // Stream x configuration register
+pub const DMA_SXNDTR: u32 = 0x14; // Stream x number of data register
+pub const DMA_SXPAR: u32 = 0x18; // Stream x peripheral address register
+pub const DMA_SXM0AR: u32 = 0x1C; // Stream x memory 0 address register
+pub const DMA_SXM1AR: u32 = 0x20; // Stream x memory 1 address register
+pub const DMA_SXFCR: u32 = 0x24; // Stream x FIFO control register
+pub const DMA_STREAM_STRIDE: u32 = 0x18; // Distance between stream register blocks
+
+pub const DMA_STREAM_COUNT: u32 = 8;
+
+// -----------------------------------------------------------------------------
+// DMA Bit Positions
+// -----------------------------------------------------------------------------
+// Status flags, relative to the stream's group start in LISR/HISR
+pub const DMA_FEIF_OFFSET: u32 = 0; // FIFO error
+pub const DMA_DMEIF_OFFSET: u32 = 2; // Direct mode error
+pub const DMA_TEIF_OFFSET: u32 = 3; // Transfer error
+pub const DMA_HTIF_OFFSET: u32 = 4; // Half transfer
+pub const DMA_TCIF_OFFSET: u32 = 5; // Transfer complete
+
+// SxCR
+pub const DMA_SXCR_EN_POS: u32 = 0; // Stream enable
+pub const DMA_SXCR_DMEIE_POS: u32 = 1; // Direct mode error interrupt enable
+pub const DMA_SXCR_TEIE_POS: u32 = 2; // Transfer error interrupt enable
+pub const DMA_SXCR_HTIE_POS: u32 = 3; // Half transfer interrupt enable
+pub const DMA_SXCR_TCIE_POS: u32 = 4; // Transfer complete interrupt enable
+pub const DMA_SXCR_DIR_POS: u32 = 6; // Data transfer direction (2 bits)
+pub const DMA_SXCR_CIRC_POS: u32 = 8; // Circular mode
+pub const DMA_SXCR_PINC_POS: u32 = 9; // Peripheral increment mode
+pub const DMA_SXCR_MINC_POS: u32 = 10; // Memory increment mode
+pub const DMA_SXCR_PSIZE_POS: u32 = 11; // Peripheral data size (2 bits)
+pub const DMA_SXCR_MSIZE_POS: u32 = 13; // Memory data size (2 bits)
+pub const DMA_SXCR_CHSEL_POS: u32 = 25; // Channel selection (3 bits)
+
+// SxCR DIR values
+pub const DMA_DIR_PERIPH_TO_MEM: u32 = 0b00;
+pub const DMA_DIR_MEM_TO_PERIPH: u32 = 0b01;
+pub const DMA_DIR_MEM_TO_MEM: u32 = 0b10;
+
+// SxCR PSIZE/MSIZE values
+pub const DMA_SIZE_BYTE: u32 = 0b00;
+pub const DMA_SIZE_HALF_WORD: u32 = 0b01;
+pub const DMA_SIZE_WORD: u32 = 0b10;
+
+// SxFCR
+pub const DMA_SXFCR_DMDIS_POS: u32 = 2; // Direct mode disable (FIFO mode)
+pub const DMA_SXFCR_FEIE_POS: u32 = 7; // FIFO error interrupt enable
+
+/// Maximum number of data items per transfer (NDTR is 16 bits).
+pub const DMA_MAX_TRANSFER_ITEMS: u32 = 0xFFFF;
+
+/// Polls of the status register before a memory-to-memory transfer times out.
+const DMA_MEM_TO_MEM_TIMEOUT_CYCLES: u32 = 1_000_000;
+
+/// All five flags of one stream, relative to its group start.
+const DMA_STREAM_FLAGS_MASK: u32 = 0b11_1101;
+
+// -----------------------------------------------------------------------------
+// DMA Errors
+// -----------------------------------------------------------------------------
+/// Reasons a DMA transfer did not complete cleanly.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DmaError {
+    /// TEIF: bus error on a source/destination access; the stream is disabled
+    /// by hardware
+    Transfer,
+    /// FEIF: FIFO overrun/underrun, or a FIFO threshold/burst misconfiguration
+    Fifo,
+    /// DMEIF: direct mode error (peripheral request lost while the previous
+    /// data was not yet transferred)
+    DirectMode,
+    /// Neither TCIF nor an error flag appeared within the timeout
+    Timeout,
+}
+
+// -----------------------------------------------------------------------------
+// Enable DMA Clock
+// -----------------------------------------------------------------------------
+/// Enables the AHB1 clock of a DMA controller.
+///
+/// # Arguments
+/// * `dma_base` - DMA1_BASE or DMA2_BASE
+pub fn dma_enable_clock(dma_base: u32) {
+    match dma_base {
+        DMA1_BASE => rcc_enable_peripheral(RccBus::Ahb1, 21), // DMA1EN
+        DMA2_BASE => rcc_enable_peripheral(RccBus::Ahb1, 22), // DMA2EN
+        _ => {
+            // Unknown DMA: do nothing
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Stream Register Addressing
+// -----------------------------------------------------------------------------
+/// Returns the address of a stream register.
+///
+/// # Arguments
+/// * `dma_base` - DMA1_BASE or DMA2_BASE
+/// * `stream`   - Stream number (0..7)
+/// * `offset`   - Stream register offset (e.g., `DMA_SXCR`)
+pub fn dma_stream_reg(dma_base: u32, stream: u32, offset: u32) -> *mut u32 {
+    assert!(stream < DMA_STREAM_COUNT, "DMA stream must be 0..7");
+    (dma_base + offset + DMA_STREAM_STRIDE * stream) as *mut u32
+}
+
+/// Returns the status and clear register addresses and the bit position of
+/// the stream's flag group.
+fn dma_flag_location(dma_base: u32, stream: u32) -> (*mut u32, *mut u32, u32) {
+    assert!(stream < DMA_STREAM_COUNT, "DMA stream must be 0..7");
+
+    let (isr, ifcr) = if stream < 4 {
+        (DMA_LISR, DMA_LIFCR)
+    } else {
+        (DMA_HISR, DMA_HIFCR)
+    };
+    let shift = match stream % 4 {
+        0 => 0,
+        1 => 6,
+        2 => 16,
+        _ => 22,
+    };
+
+    (
+        (dma_base + isr) as *mut u32,
+        (dma_base + ifcr) as *mut u32,
+        shift,
+    )
+}
+
+// -----------------------------------------------------------------------------
+// Status Flags
+// -----------------------------------------------------------------------------
+/// Returns the stream's five status flags, shifted down so that the
+/// `DMA_*IF_OFFSET` constants index them directly.
+pub fn dma_read_flags(dma_base: u32, stream: u32) -> u32 {
+    let (isr, _, shift) = dma_flag_location(dma_base, stream);
+    reg_read_val_masked(isr, DMA_STREAM_FLAGS_MASK, shift)
+}
+
+/// Clears all five status flags of a stream.
+///
+/// Must be done before re-enabling a stream; EN cannot be set while any of
+/// its flags is still pending.
+pub fn dma_clear_flags(dma_base: u32, stream: u32) {
+    let (_, ifcr, shift) = dma_flag_location(dma_base, stream);
+    // IFCR is write-1-to-clear: writing zeros to other streams' bits has no effect
+    reg_set_val(ifcr, DMA_STREAM_FLAGS_MASK << shift);
+}
+
+/// Decodes a stream's flags into an error, if any.
+///
+/// Transfer errors are reported first since they stop the stream; direct
+/// mode and FIFO errors leave it running with possibly lost/duplicated data.
+fn dma_decode_error(flags: u32) -> Option<DmaError> {
+    if flags & (1 << DMA_TEIF_OFFSET) != 0 {
+        Some(DmaError::Transfer)
+    } else if flags & (1 << DMA_DMEIF_OFFSET) != 0 {
+        Some(DmaError::DirectMode)
+    } else if flags & (1 << DMA_FEIF_OFFSET) != 0 {
+        Some(DmaError::Fifo)
+    } else {
+        None
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Wait for Transfer Completion
+// -----------------------------------------------------------------------------
+/// Waits for a stream's transfer to complete, checking the error flags too.
+///
+/// A bare TCIF poll never sees a transfer error (TCIF is then never set and
+/// the loop hangs) and silently accepts FIFO/direct mode errors. Here every
+/// flag is checked on each poll. On return, the stream's flags are cleared.
+///
+/// # Arguments
+/// * `dma_base`       - DMA1_BASE or DMA2_BASE
+/// * `stream`         - Stream number (0..7)
+/// * `timeout_cycles` - Max polls before giving up (0 = wait forever)
+///
+/// # Returns
+/// * `Ok(())` on TCIF, otherwise the `DmaError` that ended the wait
+pub fn dma_wait_complete(dma_base: u32, stream: u32, timeout_cycles: u32) -> Result<(), DmaError> {
+    let mut cycles = 0;
+
+    let result = loop {
+        let flags = dma_read_flags(dma_base, stream);
+        if let Some(err) = dma_decode_error(flags) {
+            break Err(err);
+        }
+        if flags & (1 << DMA_TCIF_OFFSET) != 0 {
+            break Ok(());
+        }
+        hint::spin_loop();
+        if timeout_cycles > 0 {
+            cycles += 1;
+            if cycles >= timeout_cycles {
+                break Err(DmaError::Timeout);
+            }
+        }
+    };
+
+    dma_clear_flags(dma_base, stream);
+    result
+}
+
+/// Returns the pending error of a stream, if any, and clears its flags.
+///
+/// Intended for the stream's interrupt handler (see
+/// `dma_enable_error_interrupts`).
+pub fn dma_take_error(dma_base: u32, stream: u32) -> Option<DmaError> {
+    let err = dma_decode_error(dma_read_flags(dma_base, stream));
+    if err.is_some() {
+        dma_clear_flags(dma_base, stream);
+    }
+    err
+}
+
+// -----------------------------------------------------------------------------
+// Error Interrupts
+// -----------------------------------------------------------------------------
+/// Enables the transfer error, direct mode error and FIFO error interrupts.
+///
+/// The stream's IRQ (e.g., `IRQn::DMA2_Stream0`) must also be enabled in the
+/// NVIC. Configure the stream while it is disabled (EN = 0).
+///
+/// # Arguments
+/// * `dma_base` - DMA1_BASE or DMA2_BASE
+/// * `stream`   - Stream number (0..7)
+pub fn dma_enable_error_interrupts(dma_base: u32, stream: u32) {
+    let cr_addr = dma_stream_reg(dma_base, stream, DMA_SXCR);
+    reg_set_bit(cr_addr, DMA_SXCR_TEIE_POS, true);
+    reg_set_bit(cr_addr, DMA_SXCR_DMEIE_POS, true);
+    reg_set_bit(
+        dma_stream_reg(dma_base, stream, DMA_SXFCR),
+        DMA_SXFCR_FEIE_POS,
+        true,
+    );
+}
+
+// -----------------------------------------------------------------------------
+// Memory-to-Memory Transfer
+// -----------------------------------------------------------------------------
+/// Disables a stream and waits until the hardware has actually stopped it.
+///
+/// EN reads back as 1 until any ongoing transfer has finished, and none of
+/// the stream's registers can be written before that.
+///
+/// # Arguments
+/// * `dma_base` - DMA1_BASE or DMA2_BASE
+/// * `stream`   - Stream number (0..7)
+pub fn dma_stream_disable(dma_base: u32, stream: u32) {
+    let cr_addr = dma_stream_reg(dma_base, stream, DMA_SXCR);
+    reg_set_bit(cr_addr, DMA_SXCR_EN_POS, false);
+    let _ = reg_wait_bit(
+        cr_addr,
+        DMA_SXCR_EN_POS,
+        false,
+        DMA_MEM_TO_MEM_TIMEOUT_CYCLES,
+    );
+}
+
+/// Copies `len` bytes from `src` to `dst` with a DMA stream and waits for it.
+///
+/// Only DMA2 can do memory-to-memory transfers (DMA1 has no memory port on
+/// the peripheral side). The stream runs in FIFO mode with byte-sized,
+/// incrementing source (PAR) and destination (M0AR) addresses. Both buffers
+/// must be reachable by DMA2: SRAM and flash are, the CCM RAM at 0x1000_0000
+/// is not.
+///
+/// # Arguments
+/// * `dma_base` - DMA2_BASE
+/// * `stream`   - Stream number (0..7), must not be in use
+/// * `src`      - Source buffer
+/// * `dst`      - Destination buffer, at least `len` bytes
+/// * `len`      - Number of bytes (1..=`DMA_MAX_TRANSFER_ITEMS`)
+///
+/// # Returns
+/// * `Ok(())` once the transfer completed, otherwise the `DmaError`. On an
+///   error the stream is disabled before returning, so it no longer writes
+///   to `dst`
+///
+/// # Safety
+/// - `src` must be valid for `len` bytes of reads and `dst` for `len` bytes
+///   of writes, both in memory DMA2 can reach (not CCM RAM).
+/// - Nothing else may access `dst` until the call returns: the DMA writes
+///   to it behind the compiler's back.
+pub unsafe fn dma_mem_to_mem(
+    dma_base: u32,
+    stream: u32,
+    src: *const u8,
+    dst: *mut u8,
+    len: u32,
+) -> Result<(), DmaError> {
+    assert!(
+        dma_base == DMA2_BASE,
+        "memory-to-memory transfers need DMA2"
+    );
+    assert!(
+        len > 0 && len <= DMA_MAX_TRANSFER_ITEMS,
+        "DMA transfer length must be 1..65535"
+    );
+
+    dma_enable_clock(dma_base);
+    dma_stream_disable(dma_base, stream);
+    dma_clear_flags(dma_base, stream);
+
+    // In memory-to-memory mode PAR is the source and M0AR the destination
+    reg_set_val(dma_stream_reg(dma_base, stream, DMA_SXPAR), src as u32);
+    reg_set_val(dma_stream_reg(dma_base, stream, DMA_SXM0AR), dst as u32);
+    reg_set_val(dma_stream_reg(dma_base, stream, DMA_SXNDTR), len);
+
+    // Direct mode is not allowed for memory-to-memory transfers
+    reg_set_val(
+        dma_stream_reg(dma_base, stream, DMA_SXFCR),
+        1 << DMA_SXFCR_DMDIS_POS,
+    );
+
+    // Channel 0 (no request line is used), single transfers, normal mode
+    let cr = (0 << DMA_SXCR_CHSEL_POS)
+        | (DMA_SIZE_BYTE << DMA_SXCR_MSIZE_POS)
+        | (DMA_SIZE_BYTE << DMA_SXCR_PSIZE_POS)
+        | (1 << DMA_SXCR_MINC_POS)
+        | (1 << DMA_SXCR_PINC_POS)
+        | (DMA_DIR_MEM_TO_MEM << DMA_SXCR_DIR_POS);
+    let cr_addr = dma_stream_reg(dma_base, stream, DMA_SXCR);
+    reg_set_val(cr_addr, cr);
+    reg_set_bit(cr_addr, DMA_SXCR_EN_POS, true);
+
+    let result = dma_wait_complete(dma_base, stream, DMA_MEM_TO_MEM_TIMEOUT_CYCLES);
+    if result.is_err() {
+        // Timeout or transfer error: stop the stream before `dst` is handed back
+        dma_stream_disable(dma_base, stream);
+    }
+    result
+}
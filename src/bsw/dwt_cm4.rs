@@ -1,3 +1,70 @@
 // -----------------------------------------------------------------------------
 // STM32F429 DWT (Data Watchpoint and Trace) utilities
 // -----------------------------------------------------------------------------
+//
+// This module uses the DWT cycle counter (CYCCNT) of the Cortex-M4 for
+// cycle-accurate time measurement and microsecond delays.
+//
+// CYCCNT counts core clock (HCLK) cycles and wraps every 2^32 cycles
+// (~23.9 s at 180 MHz). Differences computed with `wrapping_sub` are correct
+// across one wrap, which is all the delay loop needs.
+//
+// Reference: ARMv7-M Architecture Reference Manual, C1.8 (DWT)
+// -----------------------------------------------------------------------------
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::bsw::rcc::rcc_get_hclk_hz; // Current core clock
+use crate::bsw::reg_cpu_cortex_m4::*; // Cortex-M4 core register addresses
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// DWT Register Bits
+// -----------------------------------------------------------------------------
+const DEMCR_TRCENA_POS: u32 = 24; // DEMCR: global enable for DWT and ITM
+const DWT_CTRL_CYCCNTENA_POS: u32 = 0; // DWT_CTRL: enable CYCCNT
+
+/// Core clock cycles per microsecond, captured by `dwt_init`.
+static DWT_CYCLES_PER_US: AtomicU32 = AtomicU32::new(1);
+
+// -----------------------------------------------------------------------------
+// Cycle Counter
+// -----------------------------------------------------------------------------
+/// Enables the trace block and starts the cycle counter from 0.
+///
+/// Records the current HCLK for `delay_us`; call again after changing the
+/// clock configuration.
+pub fn dwt_init() {
+    reg_set_bit(DEMCR_BASE as *mut u32, DEMCR_TRCENA_POS, true);
+    reg_set_val(DWT_CYCCNT_BASE as *mut u32, 0);
+    reg_set_bit(DWT_CTRL_BASE as *mut u32, DWT_CTRL_CYCCNTENA_POS, true);
+
+    DWT_CYCLES_PER_US.store((rcc_get_hclk_hz() / 1_000_000).max(1), Ordering::Relaxed);
+}
+
+/// Returns the current cycle count.
+pub fn dwt_cycles() -> u32 {
+    unsafe { reg_read(DWT_CYCCNT_BASE as *mut u32) }
+}
+
+// -----------------------------------------------------------------------------
+// Microsecond Delay
+// -----------------------------------------------------------------------------
+/// Busy-waits for at least `us` microseconds.
+///
+/// Accurate to a few cycles (plus any interrupt time), independent of
+/// compiler optimization. Requires `dwt_init`.
+///
+/// # Arguments
+/// * `us` - Delay in microseconds
+pub fn delay_us(us: u32) {
+    let mut remaining = us as u64 * DWT_CYCLES_PER_US.load(Ordering::Relaxed) as u64;
+
+    // Wait in chunks below 2^31 cycles so each `wrapping_sub` stays unambiguous
+    while remaining > 0 {
+        let chunk = remaining.min(0x7FFF_FFFF) as u32;
+        let start = dwt_cycles();
+        while dwt_cycles().wrapping_sub(start) < chunk {}
+        remaining -= chunk as u64;
+    }
+}
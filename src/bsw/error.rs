@@ -0,0 +1,43 @@
+// -----------------------------------------------------------------------------
+// STM32F429 BSW error type
+// -----------------------------------------------------------------------------
+//
+// This module provides the error type shared by bsw functions that can fail
+// in a generic way (a flag never set, a peripheral not ready, an argument the
+// hardware cannot do). Functions return `bsw::error::Result<T>` instead of
+// discarding the outcome of `reg_wait_bit`, so the application decides what
+// to do, e.g. keep running on HSI when the PLL does not lock.
+//
+// Drivers with failure modes worth telling apart (e.g. `I2cError`,
+// `DmaError`, `RccError`) keep their own error enum.
+// -----------------------------------------------------------------------------
+
+use core::fmt;
+
+/// Generic bsw failure.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BswError {
+    /// A status flag did not reach the expected state within the timeout
+    Timeout,
+    /// A clock, oscillator or peripheral the operation depends on is not ready
+    NotReady,
+    /// An argument is out of range for the hardware
+    InvalidArgument,
+    /// The peripheral reported an error
+    HardwareError,
+}
+
+impl fmt::Display for BswError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            BswError::Timeout => "timeout",
+            BswError::NotReady => "not ready",
+            BswError::InvalidArgument => "invalid argument",
+            BswError::HardwareError => "hardware error",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Result of a fallible bsw function.
+pub type Result<T> = core::result::Result<T, BswError>;
@@ -1,3 +1,121 @@
 // -----------------------------------------------------------------------------
 // STM32F429 EXTI (External Interrupt/Event Controller) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions for the EXTI
+// controller. Lines 0..15 are the GPIO pins; lines 16..22 are internal
+// sources:
+//
+//   16 PVD output            20 USB OTG HS wakeup
+//   17 RTC alarm             21 RTC tamper and timestamp
+//   18 USB OTG FS wakeup     22 RTC wakeup
+//   19 Ethernet wakeup
+//
+// A GPIO pin is routed to its EXTI line through SYSCFG_EXTICR1..4 (see
+// `syscfg_exti_select`): line n can be driven by pin n of exactly one port
+// at a time.
+//
+// Example: interrupt on the user button (PA0, pressed = high)
+// ```
+// rcc_enable_gpio_clock(GPIOA_BASE);
+// gpio_set_mode(GPIOA_BASE, GPIO_PIN_0, GpioMode::Input);
+// exti_configure_line(0, GPIOA_BASE, ExtiTrigger::Rising);
+// nvic_enable_irq(IRQn::EXTI0);
+// // ... and in EXTI0_Handler: exti_clear_pending(0);
+// ```
+//
+// Reference: STM32F429 Reference Manual, sections 9.2 (SYSCFG_EXTICR) and
+//            12.3 (EXTI registers)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+use crate::bsw::syscfg::syscfg_exti_select;
+
+// -----------------------------------------------------------------------------
+// EXTI Register Offsets (relative to EXTI_BASE)
+// -----------------------------------------------------------------------------
+pub const EXTI_IMR: u32 = 0x00; // Interrupt mask register
+pub const EXTI_EMR: u32 = 0x04; // Event mask register
+pub const EXTI_RTSR: u32 = 0x08; // Rising trigger selection register
+pub const EXTI_FTSR: u32 = 0x0C; // Falling trigger selection register
+pub const EXTI_SWIER: u32 = 0x10; // Software interrupt event register
+pub const EXTI_PR: u32 = 0x14; // Pending register
+
+/// Number of EXTI lines (0..22).
+pub const EXTI_LINE_COUNT: u32 = 23;
+
+// Internal lines
+pub const EXTI_LINE_PVD: u32 = 16;
+pub const EXTI_LINE_RTC_ALARM: u32 = 17;
+pub const EXTI_LINE_RTC_WAKEUP: u32 = 22;
+
+/// Edge(s) that set an EXTI line pending.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExtiTrigger {
+    Rising,
+    Falling,
+    Both,
+}
+
+// -----------------------------------------------------------------------------
+// Trigger and Mask Configuration
+// -----------------------------------------------------------------------------
+/// Selects the trigger edge(s) of an EXTI line.
+///
+/// # Arguments
+/// * `line`    - EXTI line (0..22)
+/// * `trigger` - Rising, falling or both edges
+pub fn exti_set_trigger(line: u32, trigger: ExtiTrigger) {
+    assert!(line < EXTI_LINE_COUNT, "EXTI line must be 0..22");
+
+    let rising = matches!(trigger, ExtiTrigger::Rising | ExtiTrigger::Both);
+    let falling = matches!(trigger, ExtiTrigger::Falling | ExtiTrigger::Both);
+    reg_set_bit((EXTI_BASE + EXTI_RTSR) as *mut u32, line, rising);
+    reg_set_bit((EXTI_BASE + EXTI_FTSR) as *mut u32, line, falling);
+}
+
+/// Routes a GPIO port to an EXTI line and enables its interrupt.
+///
+/// Programs SYSCFG_EXTICRx (enabling the SYSCFG clock), selects the trigger
+/// edge(s) and unmasks the line in IMR. The NVIC interrupt (EXTI0..4,
+/// EXTI9_5 or EXTI15_10) still has to be enabled by the caller.
+///
+/// # Arguments
+/// * `line`    - EXTI line = pin number (0..15)
+/// * `port`    - The base address of the GPIO port (e.g., `GPIOA_BASE`)
+/// * `trigger` - Rising, falling or both edges
+pub fn exti_configure_line(line: u32, port: u32, trigger: ExtiTrigger) {
+    syscfg_exti_select(line, port);
+    exti_set_trigger(line, trigger);
+    exti_enable_interrupt(line);
+}
+
+/// Unmasks the interrupt request of an EXTI line (IMR).
+pub fn exti_enable_interrupt(line: u32) {
+    assert!(line < EXTI_LINE_COUNT, "EXTI line must be 0..22");
+    reg_set_bit((EXTI_BASE + EXTI_IMR) as *mut u32, line, true);
+}
+
+/// Masks the interrupt request of an EXTI line (IMR).
+pub fn exti_disable_interrupt(line: u32) {
+    assert!(line < EXTI_LINE_COUNT, "EXTI line must be 0..22");
+    reg_set_bit((EXTI_BASE + EXTI_IMR) as *mut u32, line, false);
+}
+
+// -----------------------------------------------------------------------------
+// Pending Flags
+// -----------------------------------------------------------------------------
+/// Returns true if an EXTI line is pending.
+pub fn exti_is_pending(line: u32) -> bool {
+    assert!(line < EXTI_LINE_COUNT, "EXTI line must be 0..22");
+    reg_read_bit((EXTI_BASE + EXTI_PR) as *mut u32, line)
+}
+
+/// Clears the pending flag of an EXTI line. Call this in the line's handler,
+/// otherwise the interrupt re-enters immediately.
+pub fn exti_clear_pending(line: u32) {
+    assert!(line < EXTI_LINE_COUNT, "EXTI line must be 0..22");
+    // PR is write-1-to-clear: a read-modify-write would clear other lines too
+    reg_set_val((EXTI_BASE + EXTI_PR) as *mut u32, 1 << line);
+}
@@ -0,0 +1,245 @@
+// -----------------------------------------------------------------------------
+// STM32F429 Fault (HardFault/MemManage/BusFault/UsageFault) utilities
+// -----------------------------------------------------------------------------
+//
+// This module replaces the endless-loop fault handlers with handlers that
+// collect the fault state into a `FaultInfo` and pass it to a user hook:
+//
+// - CFSR: MemManage (bits 7:0), BusFault (bits 15:8), UsageFault (bits 31:16)
+// - HFSR: why a fault escalated to HardFault
+// - MMFAR/BFAR: faulting data address, only valid if MMARVALID/BFARVALID
+// - The exception frame stacked by hardware: PC of the faulting
+//   instruction, LR and xPSR of the interrupted code
+//
+// MemManage/BusFault/UsageFault escalate to HardFault unless enabled with
+// `fault_enable_handlers`.
+//
+// Reference: Cortex-M4 Devices Generic User Guide, 4.3.10-4.3.13
+// -----------------------------------------------------------------------------
+
+#[cfg(target_arch = "arm")]
+use core::arch::naked_asm;
+use core::fmt;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::bsw::reg_cpu_cortex_m4::*; // Cortex-M4 core register addresses
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// Fault Status Bits
+// -----------------------------------------------------------------------------
+pub const CFSR_MMARVALID_POS: u32 = 7; // MMFAR holds a valid address
+pub const CFSR_BFARVALID_POS: u32 = 15; // BFAR holds a valid address
+pub const HFSR_VECTTBL_POS: u32 = 1; // Vector table read fault
+pub const HFSR_FORCED_POS: u32 = 30; // Escalated configurable fault
+
+const SHCSR_MEMFAULTENA_POS: u32 = 16; // MemManage handler enable
+const SHCSR_BUSFAULTENA_POS: u32 = 17; // BusFault handler enable
+const SHCSR_USGFAULTENA_POS: u32 = 18; // UsageFault handler enable
+
+/// CFSR bits and their meaning (ARM names).
+const CFSR_BITS: [(u32, &str); 17] = [
+    (0, "IACCVIOL: instruction access violation"),
+    (1, "DACCVIOL: data access violation"),
+    (3, "MUNSTKERR: MemManage fault on unstacking"),
+    (4, "MSTKERR: MemManage fault on stacking"),
+    (
+        5,
+        "MLSPERR: MemManage fault during FP lazy state preservation",
+    ),
+    (8, "IBUSERR: instruction bus error"),
+    (9, "PRECISERR: precise data bus error"),
+    (10, "IMPRECISERR: imprecise data bus error"),
+    (11, "UNSTKERR: BusFault on unstacking"),
+    (12, "STKERR: BusFault on stacking"),
+    (13, "LSPERR: BusFault during FP lazy state preservation"),
+    (16, "UNDEFINSTR: undefined instruction"),
+    (17, "INVSTATE: invalid state (Thumb bit clear)"),
+    (18, "INVPC: invalid PC load on exception return"),
+    (19, "NOCP: coprocessor (FPU) disabled or absent"),
+    (24, "UNALIGNED: unaligned access"),
+    (25, "DIVBYZERO: divide by zero"),
+];
+
+// -----------------------------------------------------------------------------
+// Fault Information
+// -----------------------------------------------------------------------------
+/// Snapshot of the fault state, passed to the fault hook.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FaultInfo {
+    /// Exception number (3 = HardFault, 4 = MemManage, 5 = BusFault, 6 = UsageFault)
+    pub exception: u32,
+    /// Configurable Fault Status Register
+    pub cfsr: u32,
+    /// HardFault Status Register
+    pub hfsr: u32,
+    /// Faulting data address of a MemManage fault, if valid
+    pub mmfar: Option<u32>,
+    /// Faulting data address of a precise BusFault, if valid
+    pub bfar: Option<u32>,
+    /// Stack pointer at the time of the fault (address of the exception frame)
+    pub sp: u32,
+    /// Stacked PC: the instruction that faulted (or was about to execute)
+    pub pc: u32,
+    /// Stacked LR of the faulting code
+    pub lr: u32,
+    /// Stacked xPSR of the faulting code
+    pub xpsr: u32,
+}
+
+impl FaultInfo {
+    /// Reads the fault registers and the stacked exception frame.
+    ///
+    /// # Safety
+    /// `frame` must point to a hardware exception frame (r0-r3, r12, lr, pc, xPSR).
+    unsafe fn capture(frame: *const u32, exception: u32) -> Self {
+        let cfsr = unsafe { reg_read_ro(CFSR_BASE as ReadOnlyRegister) };
+        let mmfar = unsafe { reg_read_ro(MMFAR_BASE as ReadOnlyRegister) };
+        let bfar = unsafe { reg_read_ro(BFAR_BASE as ReadOnlyRegister) };
+
+        FaultInfo {
+            exception,
+            cfsr,
+            hfsr: unsafe { reg_read_ro(HFSR_BASE as ReadOnlyRegister) },
+            mmfar: (cfsr & (1 << CFSR_MMARVALID_POS) != 0).then_some(mmfar),
+            bfar: (cfsr & (1 << CFSR_BFARVALID_POS) != 0).then_some(bfar),
+            sp: frame as u32,
+            lr: unsafe { frame.add(5).read_volatile() },
+            pc: unsafe { frame.add(6).read_volatile() },
+            xpsr: unsafe { frame.add(7).read_volatile() },
+        }
+    }
+
+    /// Calls `f` with a description of every CFSR fault bit that is set.
+    pub fn for_each_cause(&self, mut f: impl FnMut(&'static str)) {
+        for &(bit, name) in CFSR_BITS.iter() {
+            if self.cfsr & (1 << bit) != 0 {
+                f(name);
+            }
+        }
+    }
+
+    /// True if a configurable fault escalated to HardFault (HFSR.FORCED).
+    pub fn is_forced(&self) -> bool {
+        self.hfsr & (1 << HFSR_FORCED_POS) != 0
+    }
+
+    /// True if the HardFault was caused by a vector table read (HFSR.VECTTBL).
+    pub fn is_vector_table_fault(&self) -> bool {
+        self.hfsr & (1 << HFSR_VECTTBL_POS) != 0
+    }
+}
+
+impl fmt::Display for FaultInfo {
+    /// Multi-line human readable report.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.exception {
+            3 => "HardFault",
+            4 => "MemManage",
+            5 => "BusFault",
+            6 => "UsageFault",
+            _ => "Fault",
+        };
+        writeln!(
+            f,
+            "{} at pc={:#010x} lr={:#010x} xpsr={:#010x} sp={:#010x}",
+            name, self.pc, self.lr, self.xpsr, self.sp
+        )?;
+        writeln!(f, "CFSR={:#010x} HFSR={:#010x}", self.cfsr, self.hfsr)?;
+        if self.is_forced() {
+            writeln!(f, "  FORCED: escalated configurable fault")?;
+        }
+        if self.is_vector_table_fault() {
+            writeln!(f, "  VECTTBL: vector table read fault")?;
+        }
+        let mut result = Ok(());
+        self.for_each_cause(|cause| {
+            if result.is_ok() {
+                result = writeln!(f, "  {}", cause);
+            }
+        });
+        result?;
+        if let Some(addr) = self.mmfar {
+            writeln!(f, "MMFAR={:#010x}", addr)?;
+        }
+        if let Some(addr) = self.bfar {
+            writeln!(f, "BFAR={:#010x}", addr)?;
+        }
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Fault Hook
+// -----------------------------------------------------------------------------
+/// User fault hook; null means "no hook".
+static FAULT_HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Installs the function called by `on_hard_fault` (e.g., to log the
+/// `FaultInfo` or to save it to a backup register before a reset).
+///
+/// The hook runs in the fault handler: keep it simple and avoid anything
+/// that can fault again. If it returns, the CPU halts in a loop.
+pub fn fault_set_hook(hook: fn(&FaultInfo)) {
+    FAULT_HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Called by every fault handler with the decoded fault state.
+///
+/// Runs the hook installed with `fault_set_hook`, if any.
+pub fn on_hard_fault(info: &FaultInfo) {
+    let hook = FAULT_HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: only ever stored from a `fn(&FaultInfo)` in `fault_set_hook`
+        let hook: fn(&FaultInfo) = unsafe { core::mem::transmute(hook) };
+        hook(info);
+    }
+}
+
+/// Enables the dedicated MemManage, BusFault and UsageFault handlers
+/// (otherwise these faults escalate to HardFault).
+pub fn fault_enable_handlers() {
+    let shcsr_addr = SHCSR_BASE as *mut u32;
+    reg_set_bit(shcsr_addr, SHCSR_MEMFAULTENA_POS, true);
+    reg_set_bit(shcsr_addr, SHCSR_BUSFAULTENA_POS, true);
+    reg_set_bit(shcsr_addr, SHCSR_USGFAULTENA_POS, true);
+}
+
+// -----------------------------------------------------------------------------
+// Exception Handlers
+// -----------------------------------------------------------------------------
+/// Common Rust part of the fault handlers.
+///
+/// `frame` is the stack pointer in use when the fault happened, `ipsr` the
+/// exception number of the active fault.
+extern "C" fn fault_entry(frame: *const u32, ipsr: u32) -> ! {
+    let info = unsafe { FaultInfo::capture(frame, ipsr & 0x1FF) };
+    on_hard_fault(&info);
+    loop {}
+}
+
+/// Defines a naked fault handler that passes the exception frame (from MSP
+/// or PSP, depending on EXC_RETURN bit 2) and IPSR to `fault_entry`.
+macro_rules! fault_handler {
+    ($handler_name:ident) => {
+        #[cfg(target_arch = "arm")]
+        #[unsafe(naked)]
+        #[unsafe(no_mangle)]
+        pub extern "C" fn $handler_name() {
+            naked_asm!(
+                "tst lr, #4",
+                "ite eq",
+                "mrseq r0, msp",
+                "mrsne r0, psp",
+                "mrs r1, ipsr",
+                "b {entry}",
+                entry = sym fault_entry,
+            );
+        }
+    };
+}
+
+fault_handler!(HardFault_Handler);
+fault_handler!(MemManage_Handler);
+fault_handler!(BusFault_Handler);
+fault_handler!(UsageFault_Handler);
@@ -1,6 +1,20 @@
 // -----------------------------------------------------------------------------
 // STM32F429 FLASH (Flash Memory) utilities
 // -----------------------------------------------------------------------------
+//
+// Wait states, the ART accelerator (prefetch, instruction/data caches), plus
+// sector erase and word programming of the 2 MB main flash.
+//
+// Sector layout (dual bank, 2 MB):
+//   Bank 1: sectors 0-3 16 KB, sector 4 64 KB, sectors 5-11 128 KB
+//   Bank 2: sectors 12-15 16 KB, sector 16 64 KB, sectors 17-23 128 KB
+//
+// Programming sequence: `flash_unlock()`, `flash_erase_sector()` /
+// `flash_program_word()`, `flash_lock()`. Erasing or programming the bank the
+// code runs from stalls instruction fetches until the operation is done.
+//
+// Reference: STM32F429 Reference Manual, section 3 (Embedded Flash memory)
+// -----------------------------------------------------------------------------
 
 use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
 use crate::bsw::reg_utils::*; // Register access helper functions
@@ -16,6 +30,85 @@ pub const FLASH_CR: u32 = 0x10;
 pub const FLASH_OPTCR: u32 = 0x14;
 pub const FLASH_OPTCR1: u32 = 0x18;
 
+// Layout check (RM0090 3.9.10): contiguous words from ACR to OPTCR1
+const _: () = {
+    assert!(FLASH_KEYR == FLASH_ACR + 4 && FLASH_OPTKEYR == FLASH_KEYR + 4);
+    assert!(FLASH_SR == FLASH_OPTKEYR + 4 && FLASH_CR == FLASH_SR + 4);
+    assert!(FLASH_OPTCR == FLASH_CR + 4 && FLASH_OPTCR1 == FLASH_OPTCR + 4);
+    assert!(FLASH_ACR == 0x00 && FLASH_CR == 0x10);
+};
+
+// -----------------------------------------------------------------------------
+// FLASH Memory Region
+// -----------------------------------------------------------------------------
+pub const FLASH_MEM_BASE: u32 = 0x0800_0000; // Main flash start address
+pub const FLASH_MEM_SIZE: u32 = 0x0020_0000; // 2 MB
+pub const FLASH_SECTOR_COUNT: u32 = 24; // 12 sectors per bank
+
+// -----------------------------------------------------------------------------
+// FLASH_ACR Bits
+// -----------------------------------------------------------------------------
+pub const FLASH_ACR_LATENCY_POS: u32 = 0; // Latency (wait states, bits 3:0)
+pub const FLASH_ACR_PRFTEN_POS: u32 = 8; // Prefetch enable
+pub const FLASH_ACR_ICEN_POS: u32 = 9; // Instruction cache enable
+pub const FLASH_ACR_DCEN_POS: u32 = 10; // Data cache enable
+pub const FLASH_ACR_ICRST_POS: u32 = 11; // Instruction cache reset (only while ICEN = 0)
+pub const FLASH_ACR_DCRST_POS: u32 = 12; // Data cache reset (only while DCEN = 0)
+
+// -----------------------------------------------------------------------------
+// FLASH_KEYR Unlock Keys
+// -----------------------------------------------------------------------------
+pub const FLASH_KEY1: u32 = 0x4567_0123;
+pub const FLASH_KEY2: u32 = 0xCDEF_89AB;
+
+// -----------------------------------------------------------------------------
+// FLASH_SR Bits
+// -----------------------------------------------------------------------------
+pub const FLASH_SR_EOP_POS: u32 = 0; // End of operation
+pub const FLASH_SR_OPERR_POS: u32 = 1; // Operation error
+pub const FLASH_SR_WRPERR_POS: u32 = 4; // Write protection error
+pub const FLASH_SR_PGAERR_POS: u32 = 5; // Programming alignment error
+pub const FLASH_SR_PGPERR_POS: u32 = 6; // Programming parallelism error
+pub const FLASH_SR_PGSERR_POS: u32 = 7; // Programming sequence error
+pub const FLASH_SR_BSY_POS: u32 = 16; // Operation in progress
+
+/// All error flags of FLASH_SR (write-1-to-clear).
+const FLASH_SR_ERRORS: u32 = (1 << FLASH_SR_OPERR_POS)
+    | (1 << FLASH_SR_WRPERR_POS)
+    | (1 << FLASH_SR_PGAERR_POS)
+    | (1 << FLASH_SR_PGPERR_POS)
+    | (1 << FLASH_SR_PGSERR_POS);
+
+// -----------------------------------------------------------------------------
+// FLASH_CR Bits
+// -----------------------------------------------------------------------------
+pub const FLASH_CR_PG_POS: u32 = 0; // Programming
+pub const FLASH_CR_SER_POS: u32 = 1; // Sector erase
+pub const FLASH_CR_SNB_POS: u32 = 3; // Sector number (bits 7:3)
+pub const FLASH_CR_PSIZE_POS: u32 = 8; // Program size (bits 9:8)
+pub const FLASH_CR_STRT_POS: u32 = 16; // Start erase
+pub const FLASH_CR_LOCK_POS: u32 = 31; // Lock
+
+/// PSIZE value for 32-bit (x32) parallelism, valid for VDD 2.7 V..3.6 V.
+const FLASH_PSIZE_X32: u32 = 0b10;
+
+/// Errors reported by erase/program operations.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FlashError {
+    /// FLASH_CR is still locked (call `flash_unlock` first)
+    Locked,
+    /// Address outside the main flash or not word aligned
+    InvalidAddress,
+    /// Sector number out of range (0..23)
+    InvalidSector,
+    /// The target sector is write protected (WRPERR)
+    WriteProtected,
+    /// Alignment, parallelism or sequence error (PGAERR/PGPERR/PGSERR)
+    Programming,
+    /// Operation error (OPERR)
+    Operation,
+}
+
 /// Sets the number of wait states for the FLASH memory.
 ///
 /// # Arguments
@@ -26,3 +119,231 @@ pub fn flash_set_wait_states(ws: u32) {
     let flash_acr_addr = (FLASH_INTERFACE_BASE + FLASH_ACR) as *mut u32;
     reg_set_bits(flash_acr_addr, ws & 0x0F, 0, 4);
 }
+
+/// Supply voltage range of the device, which limits HCLK per wait state.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum VoltageRange {
+    /// 2.7 V..3.6 V (the DISC1 runs at 3 V)
+    V2_7To3_6,
+    /// 2.4 V..2.7 V
+    V2_4To2_7,
+    /// 2.1 V..2.4 V
+    V2_1To2_4,
+    /// 1.8 V..2.1 V (prefetch must stay off)
+    V1_8To2_1,
+}
+
+impl VoltageRange {
+    /// HCLK range covered by one wait state (RM0090 Table 10).
+    const fn hz_per_wait_state(self) -> u32 {
+        match self {
+            VoltageRange::V2_7To3_6 => 30_000_000,
+            VoltageRange::V2_4To2_7 => 24_000_000,
+            VoltageRange::V2_1To2_4 => 22_000_000,
+            VoltageRange::V1_8To2_1 => 20_000_000,
+        }
+    }
+}
+
+/// Returns the flash wait states needed to run at `hclk_hz`.
+///
+/// Implements RM0090 Table 10: e.g. at 2.7..3.6 V, 0 WS up to 30 MHz,
+/// 5 WS for 150..180 MHz.
+///
+/// # Arguments
+/// * `hclk_hz`       - AHB clock the CPU reads the flash with
+/// * `voltage_range` - Supply voltage range
+///
+/// # Returns
+/// * LATENCY value for FLASH_ACR (0..15)
+pub const fn flash_wait_states_for(hclk_hz: u32, voltage_range: VoltageRange) -> u32 {
+    let ws = hclk_hz.saturating_sub(1) / voltage_range.hz_per_wait_state();
+    if ws > 15 { 15 } else { ws }
+}
+
+/// Sets the wait states for `hclk_hz` and enables prefetch and the
+/// instruction/data caches.
+///
+/// Call it before raising HCLK (and again after lowering it): too few wait
+/// states make the CPU read wrong instructions, typically ending in a
+/// HardFault.
+///
+/// # Arguments
+/// * `hclk_hz` - AHB clock the CPU reads the flash with
+/// * `voltage` - Supply voltage range
+pub fn flash_configure_for_clock(hclk_hz: u32, voltage: VoltageRange) {
+    flash_set_wait_states(flash_wait_states_for(hclk_hz, voltage));
+
+    if voltage == VoltageRange::V1_8To2_1 {
+        flash_enable_caches();
+    } else {
+        flash_enable_acceleration();
+    }
+}
+
+// -----------------------------------------------------------------------------
+// ART Accelerator (prefetch and caches)
+// -----------------------------------------------------------------------------
+// With 5 wait states at 180 MHz, every flash access that misses costs six
+// cycles. The instruction cache (64 lines of 128 bits), the data cache (8
+// lines) and the prefetch buffer hide most of that for straight-line code and
+// constant tables; running from flash without them is several times slower.
+//
+// The caches do not notice flash changes. After erasing/programming code or
+// constants that were already read, flush them with `flash_reset_caches`.
+
+/// Enables the instruction and data caches (ICEN, DCEN).
+pub fn flash_enable_caches() {
+    let flash_acr_addr = (FLASH_INTERFACE_BASE + FLASH_ACR) as *mut u32;
+    reg_set_bit(flash_acr_addr, FLASH_ACR_ICEN_POS, true);
+    reg_set_bit(flash_acr_addr, FLASH_ACR_DCEN_POS, true);
+}
+
+/// Enables the prefetch buffer (PRFTEN). Not allowed at 1.8..2.1 V.
+pub fn flash_enable_prefetch() {
+    let flash_acr_addr = (FLASH_INTERFACE_BASE + FLASH_ACR) as *mut u32;
+    reg_set_bit(flash_acr_addr, FLASH_ACR_PRFTEN_POS, true);
+}
+
+/// Enables prefetch and both caches.
+pub fn flash_enable_acceleration() {
+    flash_enable_prefetch();
+    flash_enable_caches();
+}
+
+/// Invalidates both caches and enables them again.
+///
+/// The reset bits only work while the caches are disabled, so they are
+/// switched off, reset and switched back on.
+pub fn flash_reset_caches() {
+    let flash_acr_addr = (FLASH_INTERFACE_BASE + FLASH_ACR) as *mut u32;
+    reg_set_bit(flash_acr_addr, FLASH_ACR_ICEN_POS, false);
+    reg_set_bit(flash_acr_addr, FLASH_ACR_DCEN_POS, false);
+
+    reg_set_bit(flash_acr_addr, FLASH_ACR_ICRST_POS, true);
+    reg_set_bit(flash_acr_addr, FLASH_ACR_DCRST_POS, true);
+    reg_set_bit(flash_acr_addr, FLASH_ACR_ICRST_POS, false);
+    reg_set_bit(flash_acr_addr, FLASH_ACR_DCRST_POS, false);
+
+    flash_enable_caches();
+}
+
+// -----------------------------------------------------------------------------
+// Lock / Unlock
+// -----------------------------------------------------------------------------
+/// Unlocks FLASH_CR by writing the KEY1/KEY2 sequence to FLASH_KEYR.
+///
+/// Does nothing if FLASH_CR is already unlocked (a second key sequence would
+/// cause a bus error until the next reset).
+pub fn flash_unlock() {
+    let flash_cr_addr = (FLASH_INTERFACE_BASE + FLASH_CR) as *mut u32;
+    if reg_read_bit(flash_cr_addr, FLASH_CR_LOCK_POS) {
+        let flash_keyr_addr = (FLASH_INTERFACE_BASE + FLASH_KEYR) as *mut u32;
+        reg_set_val(flash_keyr_addr, FLASH_KEY1);
+        reg_set_val(flash_keyr_addr, FLASH_KEY2);
+    }
+}
+
+/// Locks FLASH_CR again; erase/program is rejected until the next unlock.
+pub fn flash_lock() {
+    let flash_cr_addr = (FLASH_INTERFACE_BASE + FLASH_CR) as *mut u32;
+    reg_set_bit(flash_cr_addr, FLASH_CR_LOCK_POS, true);
+}
+
+/// Returns true if FLASH_CR is locked.
+pub fn flash_is_locked() -> bool {
+    reg_read_bit(
+        (FLASH_INTERFACE_BASE + FLASH_CR) as *mut u32,
+        FLASH_CR_LOCK_POS,
+    )
+}
+
+// -----------------------------------------------------------------------------
+// Erase / Program
+// -----------------------------------------------------------------------------
+/// Erases one flash sector (all bytes read back as 0xFF).
+///
+/// # Arguments
+/// * `sector` - Sector number (0..23, see the layout at the top of this file)
+///
+/// # Returns
+/// * `Ok(())` when the erase completed, or the error reported in FLASH_SR
+pub fn flash_erase_sector(sector: u32) -> Result<(), FlashError> {
+    if sector >= FLASH_SECTOR_COUNT {
+        return Err(FlashError::InvalidSector);
+    }
+    flash_begin_operation()?;
+
+    // Bank 2 sectors are encoded as 0b1_xxxx (SNB = 16 + sector - 12)
+    let snb = if sector < 12 { sector } else { sector + 4 };
+
+    let flash_cr_addr = (FLASH_INTERFACE_BASE + FLASH_CR) as *mut u32;
+    reg_set_bits(flash_cr_addr, FLASH_PSIZE_X32, FLASH_CR_PSIZE_POS, 2);
+    reg_set_bits(flash_cr_addr, snb, FLASH_CR_SNB_POS, 5);
+    reg_set_bit(flash_cr_addr, FLASH_CR_SER_POS, true);
+    reg_set_bit(flash_cr_addr, FLASH_CR_STRT_POS, true);
+
+    let result = flash_end_operation();
+    reg_set_bit(flash_cr_addr, FLASH_CR_SER_POS, false);
+    result
+}
+
+/// Programs one 32-bit word. The target word must be erased (0xFFFF_FFFF).
+///
+/// # Arguments
+/// * `addr` - Word-aligned address inside the main flash
+/// * `val`  - Value to program
+///
+/// # Returns
+/// * `Ok(())` when the write completed, or the error reported in FLASH_SR
+pub fn flash_program_word(addr: u32, val: u32) -> Result<(), FlashError> {
+    if !(FLASH_MEM_BASE..FLASH_MEM_BASE + FLASH_MEM_SIZE).contains(&addr) || !addr.is_multiple_of(4)
+    {
+        return Err(FlashError::InvalidAddress);
+    }
+    flash_begin_operation()?;
+
+    let flash_cr_addr = (FLASH_INTERFACE_BASE + FLASH_CR) as *mut u32;
+    reg_set_bits(flash_cr_addr, FLASH_PSIZE_X32, FLASH_CR_PSIZE_POS, 2);
+    reg_set_bit(flash_cr_addr, FLASH_CR_PG_POS, true);
+
+    unsafe { reg_write(addr as *mut u32, val) };
+
+    let result = flash_end_operation();
+    reg_set_bit(flash_cr_addr, FLASH_CR_PG_POS, false);
+    result
+}
+
+/// Checks the lock, waits for a previous operation and clears stale flags.
+fn flash_begin_operation() -> Result<(), FlashError> {
+    if flash_is_locked() {
+        return Err(FlashError::Locked);
+    }
+    let flash_sr_addr = (FLASH_INTERFACE_BASE + FLASH_SR) as *mut u32;
+    let _ = reg_wait_bit(flash_sr_addr, FLASH_SR_BSY_POS, false, 0);
+    // SR flags are write-1-to-clear
+    reg_set_val(flash_sr_addr, FLASH_SR_ERRORS | (1 << FLASH_SR_EOP_POS));
+    Ok(())
+}
+
+/// Waits for BSY to clear and converts FLASH_SR error flags to a `FlashError`.
+fn flash_end_operation() -> Result<(), FlashError> {
+    let flash_sr_addr = (FLASH_INTERFACE_BASE + FLASH_SR) as *mut u32;
+    let _ = reg_wait_bit(flash_sr_addr, FLASH_SR_BSY_POS, false, 0);
+
+    let sr = unsafe { reg_read_ro(flash_sr_addr) };
+    reg_set_val(flash_sr_addr, sr & FLASH_SR_ERRORS);
+
+    if sr & (1 << FLASH_SR_WRPERR_POS) != 0 {
+        Err(FlashError::WriteProtected)
+    } else if sr
+        & ((1 << FLASH_SR_PGAERR_POS) | (1 << FLASH_SR_PGPERR_POS) | (1 << FLASH_SR_PGSERR_POS))
+        != 0
+    {
+        Err(FlashError::Programming)
+    } else if sr & (1 << FLASH_SR_OPERR_POS) != 0 {
+        Err(FlashError::Operation)
+    } else {
+        Ok(())
+    }
+}
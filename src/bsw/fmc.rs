@@ -1,3 +1,262 @@
 // -----------------------------------------------------------------------------
 // STM32F429 FMC (Flexible Memory Controller) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions for the SDRAM
+// controller of the FMC, set up for the STM32F429I-DISC1 board:
+//
+// - SDRAM: IS42S16400J, 64 Mbit = 8 MB, 16-bit data bus, 4 internal banks,
+//   4096 rows (12 bits) x 256 columns (8 bits).
+// - Wired to SDRAM bank 2 of the FMC (SDCKE1 = PB5, SDNE1 = PB6), which is
+//   mapped at 0xD000_0000 (`SDRAM_BASE`), not at bank 1's 0xC000_0000.
+// - SDCLK = HCLK / 2 (90 MHz at the 180 MHz system clock).
+//
+// After `fmc_sdram_gpio_init_disc1` and `fmc_sdram_init`, the SDRAM is
+// plain memory, e.g. for an LCD framebuffer:
+// ```
+// fmc_sdram_gpio_init_disc1();
+// fmc_sdram_init(SdramTiming::DISC1_IS42S16400J);
+// let fb = SDRAM_BASE as *mut u16; // 240 x 320 RGB565 = 150 KB
+// ```
+//
+// Some fields only exist in the bank 1 registers and apply to both banks:
+// SDCLK, RBURST and RPIPE in SDCR1, TRC and TRP in SDTR1.
+//
+// Reference: STM32F429 Reference Manual, section 37.7 (SDRAM controller)
+// -----------------------------------------------------------------------------
+
+use core::hint;
+
+use crate::bsw::gpio::*; // GPIO alternate function configuration
+use crate::bsw::rcc::*; // RCC clock enable
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// FMC SDRAM Register Offsets (relative to FMC_CTRL_BASE)
+// -----------------------------------------------------------------------------
+pub const FMC_SDCR1: u32 = 0x140; // SDRAM control register, bank 1
+pub const FMC_SDCR2: u32 = 0x144; // SDRAM control register, bank 2
+pub const FMC_SDTR1: u32 = 0x148; // SDRAM timing register, bank 1
+pub const FMC_SDTR2: u32 = 0x14C; // SDRAM timing register, bank 2
+pub const FMC_SDCMR: u32 = 0x150; // SDRAM command mode register
+pub const FMC_SDRTR: u32 = 0x154; // SDRAM refresh timer register
+pub const FMC_SDSR: u32 = 0x158; // SDRAM status register
+
+// -----------------------------------------------------------------------------
+// FMC SDRAM Bit Positions
+// -----------------------------------------------------------------------------
+// SDCR
+pub const FMC_SDCR_NC_POS: u32 = 0; // Column address bits (2 bits, 00 = 8)
+pub const FMC_SDCR_NR_POS: u32 = 2; // Row address bits (2 bits, 01 = 12)
+pub const FMC_SDCR_MWID_POS: u32 = 4; // Memory data bus width (2 bits, 01 = 16)
+pub const FMC_SDCR_NB_POS: u32 = 6; // Internal banks (1 = 4 banks)
+pub const FMC_SDCR_CAS_POS: u32 = 7; // CAS latency (2 bits)
+pub const FMC_SDCR_SDCLK_POS: u32 = 10; // SDRAM clock (2 bits, 10 = HCLK/2)
+pub const FMC_SDCR_RBURST_POS: u32 = 12; // Burst read
+pub const FMC_SDCR_RPIPE_POS: u32 = 13; // Read pipe delay (2 bits)
+
+// SDCMR
+pub const FMC_SDCMR_MODE_POS: u32 = 0; // Command mode (3 bits)
+pub const FMC_SDCMR_CTB2_POS: u32 = 3; // Command target bank 2
+pub const FMC_SDCMR_CTB1_POS: u32 = 4; // Command target bank 1
+pub const FMC_SDCMR_NRFS_POS: u32 = 5; // Number of auto-refresh - 1 (4 bits)
+pub const FMC_SDCMR_MRD_POS: u32 = 9; // Mode register definition (13 bits)
+
+// SDRTR
+pub const FMC_SDRTR_COUNT_POS: u32 = 1; // Refresh timer count (13 bits)
+
+// SDSR
+pub const FMC_SDSR_BUSY_POS: u32 = 5; // Command in progress
+
+// SDCMR MODE values
+const FMC_CMD_NORMAL: u32 = 0b000;
+const FMC_CMD_CLOCK_ENABLE: u32 = 0b001;
+const FMC_CMD_PRECHARGE_ALL: u32 = 0b010;
+const FMC_CMD_AUTO_REFRESH: u32 = 0b011;
+const FMC_CMD_LOAD_MODE: u32 = 0b100;
+
+/// Start address of the DISC1 SDRAM (FMC SDRAM bank 2).
+pub const SDRAM_BASE: u32 = FMC_BANK6_BASE;
+/// Size of the DISC1 SDRAM in bytes.
+pub const SDRAM_SIZE: u32 = 8 * 1024 * 1024;
+
+/// FMC clock enable bit in RCC_AHB3ENR.
+const RCC_AHB3ENR_FMCEN_BIT: u32 = 0;
+
+/// Polls of SDSR BUSY before a command is considered stuck.
+const FMC_COMMAND_TIMEOUT_CYCLES: u32 = 100_000;
+
+// -----------------------------------------------------------------------------
+// SDRAM Timing
+// -----------------------------------------------------------------------------
+/// SDRAM timings in SDCLK cycles (1..16) and refresh settings.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SdramTiming {
+    /// TMRD: load mode register to active
+    pub load_to_active: u8,
+    /// TXSR: exit self-refresh to active
+    pub exit_self_refresh: u8,
+    /// TRAS: minimum self-refresh period
+    pub self_refresh: u8,
+    /// TRC: row cycle (refresh to activate)
+    pub row_cycle: u8,
+    /// TWR: write recovery
+    pub write_recovery: u8,
+    /// TRP: precharge to activate
+    pub rp_delay: u8,
+    /// TRCD: activate to read/write
+    pub rcd_delay: u8,
+    /// CAS latency (1..3)
+    pub cas_latency: u8,
+    /// SDRTR COUNT: refresh interval in SDCLK cycles, minus a 20 cycle margin
+    pub refresh_count: u16,
+}
+
+impl SdramTiming {
+    /// IS42S16400J-7 on the DISC1 at SDCLK = 90 MHz (11.1 ns per cycle).
+    ///
+    /// Refresh: 64 ms / 4096 rows = 15.62 us = 1406 cycles, minus 20.
+    pub const DISC1_IS42S16400J: SdramTiming = SdramTiming {
+        load_to_active: 2,
+        exit_self_refresh: 7,
+        self_refresh: 4,
+        row_cycle: 7,
+        write_recovery: 2,
+        rp_delay: 2,
+        rcd_delay: 2,
+        cas_latency: 3,
+        refresh_count: 1386,
+    };
+}
+
+// -----------------------------------------------------------------------------
+// GPIO Configuration (STM32F429I-DISC1)
+// -----------------------------------------------------------------------------
+/// FMC pins of the DISC1 SDRAM, per port (AF12).
+const SDRAM_PINS_DISC1: [(u32, u16); 6] = [
+    (GPIOB_BASE, 0b0000_0000_0110_0000), // SDCKE1, SDNE1
+    (GPIOC_BASE, 0b0000_0000_0000_0001), // SDNWE
+    (GPIOD_BASE, 0b1100_0111_0000_0011), // D0..D3, D13..D15
+    (GPIOE_BASE, 0b1111_1111_1000_0011), // NBL0, NBL1, D4..D12
+    (GPIOF_BASE, 0b1111_1000_0011_1111), // A0..A9, SDNRAS
+    (GPIOG_BASE, 0b1000_0001_0011_0011), // A10, A11, BA0, BA1, SDCLK, SDNCAS
+];
+
+/// Configures all SDRAM pins of the DISC1 for the FMC (AF12).
+pub fn fmc_sdram_gpio_init_disc1() {
+    for (port, pins) in SDRAM_PINS_DISC1 {
+        rcc_enable_gpio_clock(port);
+        for pin in 0..16 {
+            if pins & (1 << pin) != 0 {
+                gpio_config_af_full(
+                    port,
+                    pin,
+                    12,
+                    GpioType::PushPull,
+                    PinSpeed::VeryHigh,
+                    GpioPull::None,
+                );
+            }
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Initialize SDRAM
+// -----------------------------------------------------------------------------
+/// Issues an SDRAM command to bank 2 and waits until the controller is idle.
+fn fmc_sdram_command(mode: u32, auto_refresh: u32, mode_register: u32) {
+    let sdsr_addr = (FMC_CTRL_BASE + FMC_SDSR) as *mut u32;
+    let _ = reg_wait_bit(
+        sdsr_addr,
+        FMC_SDSR_BUSY_POS,
+        false,
+        FMC_COMMAND_TIMEOUT_CYCLES,
+    );
+
+    reg_set_val(
+        (FMC_CTRL_BASE + FMC_SDCMR) as *mut u32,
+        (mode_register << FMC_SDCMR_MRD_POS)
+            | ((auto_refresh - 1) << FMC_SDCMR_NRFS_POS)
+            | (1 << FMC_SDCMR_CTB2_POS)
+            | (mode << FMC_SDCMR_MODE_POS),
+    );
+    let _ = reg_wait_bit(
+        sdsr_addr,
+        FMC_SDSR_BUSY_POS,
+        false,
+        FMC_COMMAND_TIMEOUT_CYCLES,
+    );
+}
+
+/// Initializes the DISC1 SDRAM (FMC SDRAM bank 2).
+///
+/// Programs the controller (geometry, CAS latency, SDCLK = HCLK/2, timings),
+/// runs the JEDEC power-up sequence (clock enable, 100 us wait, precharge
+/// all, 8 auto-refresh cycles, load mode register) and starts the refresh
+/// timer. HCLK must already be at its final frequency: the timings are in
+/// SDCLK cycles. The pins must be configured first
+/// (`fmc_sdram_gpio_init_disc1`).
+///
+/// # Arguments
+/// * `config` - SDRAM timings (e.g., `SdramTiming::DISC1_IS42S16400J`)
+pub fn fmc_sdram_init(config: SdramTiming) {
+    assert!(
+        (1..=3).contains(&config.cas_latency),
+        "CAS latency must be 1..3"
+    );
+
+    rcc_enable_peripheral(RccBus::Ahb3, RCC_AHB3ENR_FMCEN_BIT);
+
+    // SDCLK, burst read and read pipe are only in SDCR1, for both banks
+    reg_set_val(
+        (FMC_CTRL_BASE + FMC_SDCR1) as *mut u32,
+        (0b10 << FMC_SDCR_SDCLK_POS) | (0b01 << FMC_SDCR_RPIPE_POS),
+    );
+    reg_set_val(
+        (FMC_CTRL_BASE + FMC_SDCR2) as *mut u32,
+        ((config.cas_latency as u32) << FMC_SDCR_CAS_POS)
+            | (1 << FMC_SDCR_NB_POS)
+            | (0b01 << FMC_SDCR_MWID_POS)
+            | (0b01 << FMC_SDCR_NR_POS)
+            | (0b00 << FMC_SDCR_NC_POS),
+    );
+
+    // Each field holds cycles - 1; TRC and TRP are only in SDTR1
+    let field = |cycles: u8, pos: u32| ((cycles.clamp(1, 16) as u32) - 1) << pos;
+    reg_set_val(
+        (FMC_CTRL_BASE + FMC_SDTR1) as *mut u32,
+        field(config.row_cycle, 12) | field(config.rp_delay, 20),
+    );
+    reg_set_val(
+        (FMC_CTRL_BASE + FMC_SDTR2) as *mut u32,
+        field(config.load_to_active, 0)
+            | field(config.exit_self_refresh, 4)
+            | field(config.self_refresh, 8)
+            | field(config.write_recovery, 16)
+            | field(config.rcd_delay, 24),
+    );
+
+    // Power-up: SDCLK on, then at least 100 us of NOPs
+    fmc_sdram_command(FMC_CMD_CLOCK_ENABLE, 1, 0);
+    for _ in 0..(rcc_get_hclk_hz() / 1_000_000) * 100 {
+        hint::spin_loop();
+    }
+    fmc_sdram_command(FMC_CMD_PRECHARGE_ALL, 1, 0);
+    fmc_sdram_command(FMC_CMD_AUTO_REFRESH, 8, 0);
+
+    // Mode register: burst length 1, sequential, CAS latency, single write burst
+    let mode_register = ((config.cas_latency as u32) << 4) | (1 << 9);
+    fmc_sdram_command(FMC_CMD_LOAD_MODE, 1, mode_register);
+
+    reg_set_bits(
+        (FMC_CTRL_BASE + FMC_SDRTR) as *mut u32,
+        config.refresh_count as u32,
+        FMC_SDRTR_COUNT_POS,
+        13,
+    );
+
+    // Back to normal mode; the first accesses may now start
+    fmc_sdram_command(FMC_CMD_NORMAL, 1, 0);
+}
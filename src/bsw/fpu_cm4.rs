@@ -1,3 +1,47 @@
 // -----------------------------------------------------------------------------
 // STM32F429 FPU (Floating Point Unit) utilities
 // -----------------------------------------------------------------------------
+//
+// The Cortex-M4F FPU (FPv4-SP, single precision) is disabled after reset.
+// Every FPU instruction then raises a UsageFault (NOCP), escalated to a
+// HardFault while UsageFault is not enabled. Because this crate is built for
+// the hard-float target (thumbv7em-none-eabihf), the compiler emits FPU
+// instructions for any `f32`/`f64` code, so the FPU has to be enabled before
+// such code runs. `Reset_Handler` does it first thing (feature `fpu`, on by
+// default).
+//
+// Reference: Cortex-M4 Devices Generic User Guide, section 4.6 (FPU)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::reg_cpu_cortex_m4::*; // Cortex-M4 core register addresses
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// CPACR Bit Positions
+// -----------------------------------------------------------------------------
+pub const CPACR_CP10_POS: u32 = 20; // Coprocessor 10 access (2 bits)
+pub const CPACR_CP11_POS: u32 = 22; // Coprocessor 11 access (2 bits)
+
+/// CPACR access value: privileged and unprivileged (full) access.
+const CPACR_FULL_ACCESS: u32 = 0b11;
+
+/// Grants full access to the FPU (CP10 and CP11 in CPACR).
+///
+/// Both coprocessor fields must be set to the same value. The barriers make
+/// sure no FPU instruction executes before the new access rights apply.
+pub fn fpu_enable() {
+    let cpacr_addr = CPACR_BASE as *mut u32;
+    reg_set_bits(cpacr_addr, CPACR_FULL_ACCESS, CPACR_CP10_POS, 2);
+    reg_set_bits(cpacr_addr, CPACR_FULL_ACCESS, CPACR_CP11_POS, 2);
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        core::arch::asm!("dsb", "isb");
+    }
+}
+
+/// Returns true if the FPU is accessible (CP10 and CP11 full access).
+pub fn fpu_is_enabled() -> bool {
+    let cpacr_addr = CPACR_BASE as *mut u32;
+    reg_read_bits(cpacr_addr, CPACR_CP10_POS, 2) == CPACR_FULL_ACCESS
+        && reg_read_bits(cpacr_addr, CPACR_CP11_POS, 2) == CPACR_FULL_ACCESS
+}
@@ -8,7 +8,9 @@
 // Reference: STM32F429 Reference Manual, section 8.4 (GPIO registers, page 284)
 // -----------------------------------------------------------------------------
 
-// use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::critical_cm4::with_critical_section; // Masks IRQs around the LCKR sequence
+use crate::bsw::error::{self, BswError}; // Checked alternate function selection
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
 use crate::bsw::reg_utils::*; // Register access helper functions
 
 // -----------------------------------------------------------------------------
@@ -55,6 +57,40 @@ pub const GPIO_PIN_13_MASK: u16 = 1 << 13;
 pub const GPIO_PIN_14_MASK: u16 = 1 << 14;
 pub const GPIO_PIN_15_MASK: u16 = 1 << 15;
 
+// -----------------------------------------------------------------------------
+// Bonded-Out Pins (STM32F429ZIT6, LQFP144, on the STM32F429I-DISC1)
+// -----------------------------------------------------------------------------
+// The register layout has 16 pins for every port GPIOA..GPIOK, but the
+// LQFP144 package only bonds out PA..PG completely and PH0/PH1 (the HSE
+// oscillator pins). GPIOI, GPIOJ and GPIOK have no pins on this package.
+// Writing the registers of a missing pin has no effect, which makes such a
+// mistake hard to spot.
+
+/// Returns the mask of pins that exist on the package for a GPIO port
+/// (bit n = pin n), or 0 for an unknown/unbonded port.
+pub const fn gpio_bonded_pins(port: u32) -> u16 {
+    match port {
+        GPIOA_BASE | GPIOB_BASE | GPIOC_BASE | GPIOD_BASE | GPIOE_BASE | GPIOF_BASE
+        | GPIOG_BASE => 0xFFFF,
+        GPIOH_BASE => GPIO_PIN_0_MASK | GPIO_PIN_1_MASK,
+        _ => 0,
+    }
+}
+
+/// Checks in debug builds that `pin` is 0..15 and bonded out on `port`.
+///
+/// Called by the GPIO helpers below. Compiles to nothing in release builds,
+/// where an out-of-range pin still trips the field-width asserts of
+/// `reg_set_bits`.
+#[inline(always)]
+pub fn gpio_validate_pin(port: u32, pin: u32) {
+    debug_assert!(pin < 16, "pin must be 0..15");
+    debug_assert!(
+        pin >= 16 || gpio_bonded_pins(port) & (1 << pin) != 0,
+        "pin is not bonded out on this port"
+    );
+}
+
 // -----------------------------------------------------------------------------
 // GPIO Register Offsets (relative to GPIOx base address)
 // -----------------------------------------------------------------------------
@@ -70,6 +106,97 @@ pub const GPIOX_LCKR: u32 = 0x1C; // GPIO port configuration lock register
 pub const GPIOX_AFRL: u32 = 0x20; // GPIO alternate function low register
 pub const GPIOX_AFRH: u32 = 0x24; // GPIO alternate function high register
 
+// Layout checks (RM0090 8.4.11): the port registers are contiguous words,
+// and the ports themselves are 0x400 apart (GPIOA..GPIOK).
+const _: () = {
+    let offsets = [
+        GPIOX_MODER,
+        GPIOX_OTYPER,
+        GPIOX_OSPEEDR,
+        GPIOX_PUPDR,
+        GPIOX_IDR,
+        GPIOX_ODR,
+        GPIOX_BSRR,
+        GPIOX_LCKR,
+        GPIOX_AFRL,
+        GPIOX_AFRH,
+    ];
+    let mut i = 0;
+    while i < offsets.len() {
+        assert!(offsets[i] == i as u32 * 4);
+        i += 1;
+    }
+    assert!(GPIOX_BSRR == 0x18);
+    assert!(GpioPort::B.base() == GPIOB_BASE && GpioPort::K.base() == GPIOK_BASE);
+};
+
+// -----------------------------------------------------------------------------
+// GPIO Port Enumeration
+// -----------------------------------------------------------------------------
+// The ports are GPIO_PORT_STRIDE apart starting at GPIOA_BASE, and the port
+// index (A = 0 .. K = 10) is also its GPIOxEN bit in RCC_AHB1ENR and its
+// value in the SYSCFG_EXTICR fields.
+
+/// Address stride between two GPIO ports (GPIOA..GPIOK).
+pub const GPIO_PORT_STRIDE: u32 = 0x400;
+
+/// A GPIO port; the discriminant is the port index.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GpioPort {
+    A = 0,
+    B = 1,
+    C = 2,
+    D = 3,
+    E = 4,
+    F = 5,
+    G = 6,
+    H = 7,
+    I = 8,
+    J = 9,
+    K = 10,
+}
+
+impl GpioPort {
+    /// All ports, in index order.
+    pub const ALL: [GpioPort; 11] = [
+        GpioPort::A,
+        GpioPort::B,
+        GpioPort::C,
+        GpioPort::D,
+        GpioPort::E,
+        GpioPort::F,
+        GpioPort::G,
+        GpioPort::H,
+        GpioPort::I,
+        GpioPort::J,
+        GpioPort::K,
+    ];
+
+    /// Base address of the port's registers (e.g. `GPIOA_BASE`).
+    pub const fn base(self) -> u32 {
+        GPIOA_BASE + self as u32 * GPIO_PORT_STRIDE
+    }
+
+    /// GPIOxEN bit of the port in RCC_AHB1ENR.
+    pub const fn rcc_bit(self) -> u32 {
+        self as u32
+    }
+
+    /// Returns the port at a base address, or `None` if `base` is not one.
+    pub const fn from_base(base: u32) -> Option<Self> {
+        if base < GPIOA_BASE || !(base - GPIOA_BASE).is_multiple_of(GPIO_PORT_STRIDE) {
+            return None;
+        }
+        let index = ((base - GPIOA_BASE) / GPIO_PORT_STRIDE) as usize;
+        if index < Self::ALL.len() {
+            Some(Self::ALL[index])
+        } else {
+            None
+        }
+    }
+}
+
 // -----------------------------------------------------------------------------
 // GPIO Mode Enumeration
 // -----------------------------------------------------------------------------
@@ -93,6 +220,7 @@ pub enum GpioMode {
 // GPIO Output Type Enumeration
 // -----------------------------------------------------------------------------
 // This enum selects the output driver type for a GPIO pin.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum GpioType {
     /// Output push-pull (reset state) - 0
     PushPull = 0,
@@ -113,11 +241,149 @@ pub enum PinState {
     Toggle,
 }
 
+// -----------------------------------------------------------------------------
+// GPIO Output Speed Enumeration
+// -----------------------------------------------------------------------------
+// This enum selects the output slew rate, written to the OSPEEDR register.
+// Maximum frequencies are for VDD >= 2.7 V and a 50 pF load.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum PinSpeed {
-    Low,
-    Medium,
-    High,
-    VeryHigh,
+    /// Low speed, up to 2 MHz (reset state for most pins) - 0b00
+    Low = 0,
+    /// Medium speed, up to 25 MHz - 0b01
+    Medium = 1,
+    /// High speed, up to 50 MHz - 0b10
+    High = 2,
+    /// Very high speed, up to 100 MHz - 0b11
+    VeryHigh = 3,
+}
+
+impl PinSpeed {
+    /// Converts a 2-bit OSPEEDR field to a `PinSpeed`.
+    pub const fn from_bits(bits: u32) -> Self {
+        match bits & 0b11 {
+            0b00 => PinSpeed::Low,
+            0b01 => PinSpeed::Medium,
+            0b10 => PinSpeed::High,
+            _ => PinSpeed::VeryHigh,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// GPIO Pull-up/Pull-down Enumeration
+// -----------------------------------------------------------------------------
+// This enum selects the internal pull resistor, written to the PUPDR register.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GpioPull {
+    /// No pull-up, no pull-down (reset state for most pins) - 0b00
+    None = 0,
+    /// Pull-up - 0b01
+    PullUp = 1,
+    /// Pull-down - 0b10
+    PullDown = 2,
+}
+
+// -----------------------------------------------------------------------------
+// GPIO Pin Handle
+// -----------------------------------------------------------------------------
+/// A single GPIO pin: port base address plus pin number.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GpioPin {
+    /// The base address of the GPIO port (e.g., `GPIOA_BASE`)
+    pub port: u32,
+    /// The pin number (0..15)
+    pub pin: u32,
+}
+
+impl GpioPin {
+    /// Creates a pin handle, e.g. `GpioPin::new(GPIOG_BASE, GPIO_PIN_13)`.
+    pub const fn new(port: u32, pin: u32) -> Self {
+        assert!(pin < 16, "pin must be 0..15");
+        GpioPin { port, pin }
+    }
+
+    /// Reads the input level of the pin (IDR).
+    pub fn read(&self) -> bool {
+        gpio_get_pin_state(self.port, self.pin)
+    }
+
+    /// Sets the pin mode (input/output/alternate/analog).
+    pub fn set_mode(&self, mode: GpioMode) {
+        gpio_set_mode(self.port, self.pin, mode);
+    }
+
+    /// Drives the pin high (atomic BSRR write).
+    pub fn set_high(&self) {
+        gpio_write_pins(self.port, 1 << self.pin, 0);
+    }
+
+    /// Drives the pin low (atomic BSRR write).
+    pub fn set_low(&self) {
+        gpio_write_pins(self.port, 0, 1 << self.pin);
+    }
+
+    /// Inverts the output level of the pin.
+    pub fn toggle(&self) {
+        gpio_toggle_pin(self.port, self.pin);
+    }
+
+    /// Selects pull-up, pull-down or no pull.
+    pub fn set_pull(&self, pull: GpioPull) {
+        gpio_set_pull(self.port, self.pin, pull);
+    }
+
+    /// Sets the output speed.
+    pub fn set_speed(&self, speed: PinSpeed) {
+        gpio_set_speed(self.port, self.pin, speed);
+    }
+
+    /// Returns the configured output speed.
+    pub fn speed(&self) -> PinSpeed {
+        gpio_get_speed(self.port, self.pin)
+    }
+
+    /// Selects the alternate function (0..15); the mode must be `Alternate`.
+    pub fn set_af(&self, af: u32) {
+        gpio_set_af(self.port, self.pin, af);
+    }
+}
+
+// -----------------------------------------------------------------------------
+// embedded-hal Digital Pin Traits (feature "embedded-hal")
+// -----------------------------------------------------------------------------
+// Lets drivers written against embedded-hal 1.0 use a `GpioPin`. The pin
+// mode is not changed: configure it as output/input first. Register access
+// cannot fail, so the error type is `Infallible`.
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::digital::ErrorType for GpioPin {
+    type Error = core::convert::Infallible;
+}
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::digital::OutputPin for GpioPin {
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        GpioPin::set_high(self);
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        GpioPin::set_low(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::digital::InputPin for GpioPin {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.read())
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.read())
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -132,6 +398,8 @@ pub enum PinSpeed {
 ///
 /// This function modifies the MODER register for the selected pin.
 pub fn gpio_set_mode(port: u32, pin: u32, mode: GpioMode) {
+    gpio_validate_pin(port, pin);
+
     let gpio_mode_reg_addr = (port + GPIOX_MODER) as *mut u32;
     let bit_position = pin * 2;
     let mode_value = mode as u32;
@@ -191,6 +459,8 @@ pub fn gpio_set_mode_analog(port: u32, pin: u32) {
 ///
 /// This function modifies the OTYPER register for the selected pin.
 pub fn gpio_set_type(port: u32, pin: u32, ty: GpioType) {
+    gpio_validate_pin(port, pin);
+
     let gpio_type_reg_addr = (port + GPIOX_OTYPER) as *mut u32;
     let bit_position = pin;
     let type_value = ty as u32;
@@ -209,6 +479,8 @@ pub fn gpio_set_type(port: u32, pin: u32, ty: GpioType) {
 /// * `pin` - The pin number (0-15).
 /// * `pin_state` - The desired state (`PinState::High`, `PinState::Low`, or `PinState::Toggle`).
 pub fn gpio_set_pin_state(port: u32, pin: u32, pin_state: PinState) {
+    gpio_validate_pin(port, pin);
+
     let gpio_bsrr_addr = (port + GPIOX_BSRR) as *mut u32;
 
     match pin_state {
@@ -220,20 +492,95 @@ pub fn gpio_set_pin_state(port: u32, pin: u32, pin_state: PinState) {
             // Set the pin low by writing to the BSRR register (upper 16 bits)
             reg_set_val(gpio_bsrr_addr, 1 << (pin + 16));
         }
-        PinState::Toggle => {
-            // Read the current state and flip it
-            let gpio_odr_addr = (port + GPIOX_ODR) as *mut u32;
-            if reg_read_bit(gpio_odr_addr, pin) {
-                // If currently high, set low
-                reg_set_val(gpio_bsrr_addr, 1 << (pin + 16));
-            } else {
-                // If currently low, set high
-                reg_set_val(gpio_bsrr_addr, 1 << pin);
-            }
-        }
+        PinState::Toggle => gpio_toggle_pin(port, pin),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Atomic Pin Updates (BSRR)
+// -----------------------------------------------------------------------------
+// A write to BSRR only affects the pins whose bits are 1, and the hardware
+// applies all of them in the same cycle. No read-modify-write of ODR is
+// involved, so a BSRR store cannot clobber a pin changed concurrently by an
+// interrupt handler, and needs no critical section.
+
+/// Sets and resets several pins of one port in a single atomic store.
+///
+/// If a pin is in both masks, set wins (BSRR priority).
+///
+/// # Arguments
+/// * `port`       - The base address of the GPIO port
+/// * `set_mask`   - Pins to drive high (bit n = pin n, see `GPIO_PIN_x_MASK`)
+/// * `reset_mask` - Pins to drive low
+pub fn gpio_write_pins(port: u32, set_mask: u16, reset_mask: u16) {
+    let gpio_bsrr_addr = (port + GPIOX_BSRR) as *mut u32;
+    // BS0..BS15 in the low half, BR0..BR15 in the high half
+    reg_set_val(
+        gpio_bsrr_addr,
+        ((reset_mask as u32) << 16) | set_mask as u32,
+    );
+}
+
+/// Toggles one output pin.
+///
+/// ODR is read once and the matching set or reset bit is written to BSRR,
+/// so only this pin can change; other pins of the port are never written.
+///
+/// # Arguments
+/// * `port` - The base address of the GPIO port
+/// * `pin`  - The pin number (0..15)
+pub fn gpio_toggle_pin(port: u32, pin: u32) {
+    assert!(pin < 16, "pin must be 0..15");
+    gpio_validate_pin(port, pin);
+
+    let gpio_odr_addr = (port + GPIOX_ODR) as *mut u32;
+    let mask = 1u16 << pin;
+    if reg_read_bit(gpio_odr_addr, pin) {
+        gpio_write_pins(port, 0, mask);
+    } else {
+        gpio_write_pins(port, mask, 0);
     }
 }
 
+// -----------------------------------------------------------------------------
+// Port-Wide Access
+// -----------------------------------------------------------------------------
+/// Writes all 16 output pins of a port at once (ODR).
+///
+/// This overwrites every pin, including pins used by other code; use
+/// `gpio_write_port_masked` to change only some of them.
+///
+/// # Arguments
+/// * `port`  - The base address of the GPIO port
+/// * `value` - Output levels (bit n = pin n)
+pub fn gpio_write_port(port: u32, value: u16) {
+    reg_set_val((port + GPIOX_ODR) as *mut u32, value as u32);
+}
+
+/// Reads the input levels of all 16 pins of a port (IDR).
+///
+/// # Arguments
+/// * `port` - The base address of the GPIO port
+///
+/// # Returns
+/// * Input levels (bit n = pin n)
+pub fn gpio_read_port(port: u32) -> u16 {
+    (unsafe { reg_read_ro((port + GPIOX_IDR) as ReadOnlyRegister) } & 0xFFFF) as u16
+}
+
+/// Writes only the pins selected by `mask`, in one atomic BSRR store.
+///
+/// Pins in `mask` are set where `value` has a 1 and reset where it has a 0;
+/// pins outside `mask` keep their level.
+///
+/// # Arguments
+/// * `port`  - The base address of the GPIO port
+/// * `value` - Output levels (bit n = pin n)
+/// * `mask`  - Pins to update
+pub fn gpio_write_port_masked(port: u32, value: u16, mask: u16) {
+    gpio_write_pins(port, value & mask, !value & mask);
+}
+
 // -----------------------------------------------------------------------------
 // Read GPIO Pin State
 // -----------------------------------------------------------------------------
@@ -246,6 +593,8 @@ pub fn gpio_set_pin_state(port: u32, pin: u32, pin_state: PinState) {
 /// # Returns
 /// * `true` if the pin is high, `false` if it is low.
 pub fn gpio_get_pin_state(port: u32, pin: u32) -> bool {
+    gpio_validate_pin(port, pin);
+
     let gpio_idr_addr = (port + GPIOX_IDR) as *mut u32;
     reg_read_bit(gpio_idr_addr, pin)
 }
@@ -261,6 +610,8 @@ pub fn gpio_get_pin_state(port: u32, pin: u32) -> bool {
 /// * `pin`  - The pin number (0..15)
 /// * `af`   - The alternate function number (0..15)
 pub fn gpio_set_af(port: u32, pin: u32, af: u32) {
+    gpio_validate_pin(port, pin);
+
     let gpio_afrl = (port + GPIOX_AFRL) as *mut u32;
     let gpio_afrh = (port + GPIOX_AFRH) as *mut u32;
 
@@ -281,11 +632,661 @@ pub fn gpio_set_af(port: u32, pin: u32, af: u32) {
 /// * `pin`   - The pin number (0..15)
 /// * `speed` - The desired speed as a `PinSpeed` enum
 pub fn gpio_set_speed(port: u32, pin: u32, speed: PinSpeed) {
-    let gpio_ospeedr = (port + GPIOX_OSPEEDR) as *mut u32;
-    match speed {
-        PinSpeed::Low => reg_set_bits(gpio_ospeedr, 0b00, pin * 2, 2),
-        PinSpeed::Medium => reg_set_bits(gpio_ospeedr, 0b01, pin * 2, 2),
-        PinSpeed::High => reg_set_bits(gpio_ospeedr, 0b10, pin * 2, 2),
-        PinSpeed::VeryHigh => reg_set_bits(gpio_ospeedr, 0b11, pin * 2, 2),
+    gpio_set_speed_on(&mut MmioBus, port, pin, speed);
+}
+
+/// Sets the output speed of a pin through a register bus.
+///
+/// Same as `gpio_set_speed`; with a `MockBus` the OSPEEDR update can be
+/// checked in unit tests. The field of pin `n` is bits `2n+1 : 2n`.
+///
+/// # Arguments
+/// * `regs`  - Register bus to access the GPIO port through
+/// * `port`  - The base address of the GPIO port
+/// * `pin`   - The pin number (0..15)
+/// * `speed` - The desired speed as a `PinSpeed` enum
+pub fn gpio_set_speed_on(regs: &mut impl RegisterBus, port: u32, pin: u32, speed: PinSpeed) {
+    assert!(pin < 16, "pin must be 0..15");
+    gpio_validate_pin(port, pin);
+
+    regs.set_bits(port + GPIOX_OSPEEDR, speed as u32, pin * 2, 2);
+}
+
+/// Returns the output speed configured for a specific GPIO pin.
+///
+/// # Arguments
+/// * `port` - The base address of the GPIO port
+/// * `pin`  - The pin number (0..15)
+///
+/// # Returns
+/// * The `PinSpeed` currently programmed in OSPEEDR
+pub fn gpio_get_speed(port: u32, pin: u32) -> PinSpeed {
+    gpio_get_speed_on(&MmioBus, port, pin)
+}
+
+/// Returns the output speed of a pin, read through a register bus.
+///
+/// # Arguments
+/// * `regs` - Register bus to access the GPIO port through
+/// * `port` - The base address of the GPIO port
+/// * `pin`  - The pin number (0..15)
+pub fn gpio_get_speed_on(regs: &impl RegisterBus, port: u32, pin: u32) -> PinSpeed {
+    assert!(pin < 16, "pin must be 0..15");
+    gpio_validate_pin(port, pin);
+
+    PinSpeed::from_bits(regs.read_bits(port + GPIOX_OSPEEDR, pin * 2, 2))
+}
+
+/// Sets the internal pull-up/pull-down resistor for a specific GPIO pin.
+///
+/// # Arguments
+/// * `port` - The base address of the GPIO port
+/// * `pin`  - The pin number (0..15)
+/// * `pull` - The desired pull configuration as a `GpioPull` enum
+///
+/// This function modifies the 2-bit PUPDR field of the selected pin
+/// (bits `pin*2+1 : pin*2`). Inputs without an external resistor, such as a
+/// button to VDD/GND, need a pull so they never float.
+pub fn gpio_set_pull(port: u32, pin: u32, pull: GpioPull) {
+    assert!(pin < 16, "pin must be 0..15");
+    gpio_validate_pin(port, pin);
+
+    let gpio_pupdr = (port + GPIOX_PUPDR) as *mut u32;
+    reg_set_bits(gpio_pupdr, pull as u32, pin * 2, 2);
+}
+
+// -----------------------------------------------------------------------------
+// Configuration Lock (LCKR)
+// -----------------------------------------------------------------------------
+// Once locked, MODER, OTYPER, OSPEEDR, PUPDR and AFR of a pin can no longer be
+// changed until the next reset. The lock key (LCKK, bit 16) freezes the
+// whole LCKR register, so a port can only be locked once: all pins that need
+// protection on a port must be locked in the same call.
+const GPIO_LCKR_LCKK_POS: u32 = 16; // Lock key
+
+/// Locks the configuration of the given pins of a port until the next reset.
+///
+/// Performs the LCKK key sequence (write 1, write 0, write 1, read, read).
+/// Any other access to LCKR in between aborts the sequence, so it runs with
+/// interrupts masked.
+///
+/// # Arguments
+/// * `port` - The base address of the GPIO port
+/// * `mask` - Pins to lock (bit n = pin n, e.g. `GPIO_PIN_13_MASK`)
+///
+/// # Returns
+/// * `true` if the lock is active (LCKK reads back as 1)
+pub fn gpio_lock_pins(port: u32, mask: u16) -> bool {
+    let gpio_lckr = (port + GPIOX_LCKR) as *mut u32;
+    let key = 1 << GPIO_LCKR_LCKK_POS;
+
+    with_critical_section(|| {
+        reg_set_val(gpio_lckr, key | mask as u32);
+        reg_set_val(gpio_lckr, mask as u32);
+        reg_set_val(gpio_lckr, key | mask as u32);
+        let _ = unsafe { reg_read(gpio_lckr) };
+        reg_read_bit(gpio_lckr, GPIO_LCKR_LCKK_POS)
+    })
+}
+
+/// Locks the configuration of a single pin until the next reset.
+///
+/// See `gpio_lock_pins`: after this call no other pin of the port can be
+/// locked anymore.
+///
+/// # Arguments
+/// * `port` - The base address of the GPIO port
+/// * `pin`  - The pin number (0..15)
+pub fn gpio_lock_pin(port: u32, pin: u32) -> bool {
+    assert!(pin < 16, "pin must be 0..15");
+    gpio_validate_pin(port, pin);
+
+    gpio_lock_pins(port, 1 << pin)
+}
+
+/// Returns true if the configuration of a pin is locked.
+pub fn gpio_is_locked(port: u32, pin: u32) -> bool {
+    gpio_validate_pin(port, pin);
+
+    let gpio_lckr = (port + GPIOX_LCKR) as *mut u32;
+    reg_read_bit(gpio_lckr, GPIO_LCKR_LCKK_POS) && reg_read_bit(gpio_lckr, pin)
+}
+
+// -----------------------------------------------------------------------------
+// Alternate Function Pin Defaults
+// -----------------------------------------------------------------------------
+// The electrical settings a pin needs depend on the peripheral driving it.
+// These presets encode the reference-manual recommendations so that a pin
+// handed over to a peripheral is always fully configured.
+
+/// Output type, speed and pull settings applied to a pin in alternate function mode.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AfPinConfig {
+    /// Output driver type (OTYPER)
+    pub otype: GpioType,
+    /// Output speed (OSPEEDR)
+    pub speed: PinSpeed,
+    /// Internal pull resistor (PUPDR)
+    pub pull: GpioPull,
+}
+
+/// I2C SCL/SDA: open-drain (the bus is wired-AND) with the internal pull-up.
+/// The internal pull-up (~40 kOhm) is only enough for short, slow buses; add
+/// external resistors for 400 kHz or long traces.
+pub const AF_CONFIG_I2C: AfPinConfig = AfPinConfig {
+    otype: GpioType::OpenDrain,
+    speed: PinSpeed::High,
+    pull: GpioPull::PullUp,
+};
+
+/// SPI SCK/MISO/MOSI: push-pull, very high speed for fast clock edges.
+pub const AF_CONFIG_SPI: AfPinConfig = AfPinConfig {
+    otype: GpioType::PushPull,
+    speed: PinSpeed::VeryHigh,
+    pull: GpioPull::None,
+};
+
+/// USART TX/RX: push-pull, high speed, pull-up so the line idles high (mark)
+/// when nothing is connected.
+pub const AF_CONFIG_USART: AfPinConfig = AfPinConfig {
+    otype: GpioType::PushPull,
+    speed: PinSpeed::High,
+    pull: GpioPull::PullUp,
+};
+
+/// Timer channels and clock outputs (MCO): push-pull, very high speed, no pull.
+pub const AF_CONFIG_CLOCK_OUT: AfPinConfig = AfPinConfig {
+    otype: GpioType::PushPull,
+    speed: PinSpeed::VeryHigh,
+    pull: GpioPull::None,
+};
+
+/// Configures a pin for an alternate function with explicit electrical settings.
+///
+/// Use this to override the per-peripheral defaults, e.g. to disable the
+/// internal I2C pull-up when the board has external resistors.
+///
+/// # Arguments
+/// * `port`   - The base address of the GPIO port
+/// * `pin`    - The pin number (0..15)
+/// * `af`     - The alternate function number (0..15)
+/// * `config` - Output type, speed and pull settings (see `AfPinConfig`)
+pub fn gpio_config_af_with(port: u32, pin: u32, af: u32, config: AfPinConfig) {
+    gpio_config_af_full(port, pin, af, config.otype, config.speed, config.pull);
+}
+
+/// Configures every register that defines a pin's alternate function state.
+///
+/// AFR, OTYPER, OSPEEDR, PUPDR and MODER are all written, so nothing from a
+/// previous use of the pin leaks through. For example, a pin that was an I2C
+/// line (open-drain) and is reused as MCO or a timer output must be switched
+/// back to push-pull, or the clock edges are lost.
+///
+/// # Arguments
+/// * `port`  - The base address of the GPIO port
+/// * `pin`   - The pin number (0..15)
+/// * `af`    - The alternate function number (0..15)
+/// * `otype` - Output driver type (push-pull or open-drain)
+/// * `speed` - Output speed
+/// * `pull`  - Internal pull resistor
+pub fn gpio_config_af_full(
+    port: u32,
+    pin: u32,
+    af: u32,
+    otype: GpioType,
+    speed: PinSpeed,
+    pull: GpioPull,
+) {
+    assert!(pin < 16, "pin must be 0..15");
+    assert!(af < 16, "alternate function must be 0..15");
+
+    // Select the function and set the electrical properties before switching
+    // the pin to AF mode, so the peripheral never sees a half-configured pin.
+    gpio_set_af(port, pin, af);
+    gpio_set_type(port, pin, otype);
+    gpio_set_speed(port, pin, speed);
+    gpio_set_pull(port, pin, pull);
+    gpio_set_mode_alternate(port, pin);
+}
+
+/// Configures a pin as a clock or timer output (push-pull, very high speed, no pull).
+pub fn gpio_config_clock_out(port: u32, pin: u32, af: u32) {
+    gpio_config_af_with(port, pin, af, AF_CONFIG_CLOCK_OUT);
+}
+
+/// Configures a pin as SPI SCK (push-pull, very high speed, no pull).
+pub fn gpio_config_spi_sck(port: u32, pin: u32, af: u32) {
+    gpio_config_af_with(port, pin, af, AF_CONFIG_SPI);
+}
+
+/// Configures a pin as SPI MISO (push-pull, very high speed, no pull).
+pub fn gpio_config_spi_miso(port: u32, pin: u32, af: u32) {
+    gpio_config_af_with(port, pin, af, AF_CONFIG_SPI);
+}
+
+/// Configures a pin as SPI MOSI (push-pull, very high speed, no pull).
+pub fn gpio_config_spi_mosi(port: u32, pin: u32, af: u32) {
+    gpio_config_af_with(port, pin, af, AF_CONFIG_SPI);
+}
+
+/// Configures a pin as I2C SCL or SDA (open-drain, high speed, pull-up).
+///
+/// Both I2C lines need the same settings, so one call covers either pin.
+/// High (not very high) speed is deliberate: I2C runs at 400 kHz at most
+/// and slower edges ring less on the open-drain bus.
+///
+/// # Arguments
+/// * `port` - The base address of the GPIO port
+/// * `pin`  - The pin number (0..15)
+/// * `af`   - The alternate function number (AF4 for I2C1..I2C3)
+pub fn gpio_config_i2c_pin(port: u32, pin: u32, af: u32) {
+    gpio_config_af_with(port, pin, af, AF_CONFIG_I2C);
+}
+
+/// Configures a pin as I2C SCL (same as `gpio_config_i2c_pin`).
+pub fn gpio_config_i2c_scl(port: u32, pin: u32, af: u32) {
+    gpio_config_i2c_pin(port, pin, af);
+}
+
+/// Configures a pin as I2C SDA (same as `gpio_config_i2c_pin`).
+pub fn gpio_config_i2c_sda(port: u32, pin: u32, af: u32) {
+    gpio_config_i2c_pin(port, pin, af);
+}
+
+/// Configures a pin as USART TX (push-pull, high speed, pull-up).
+pub fn gpio_config_usart_tx(port: u32, pin: u32, af: u32) {
+    gpio_config_af_with(port, pin, af, AF_CONFIG_USART);
+}
+
+/// Configures a pin as USART RX (push-pull, high speed, pull-up).
+pub fn gpio_config_usart_rx(port: u32, pin: u32, af: u32) {
+    gpio_config_af_with(port, pin, af, AF_CONFIG_USART);
+}
+
+// -----------------------------------------------------------------------------
+// Alternate Function Table
+// -----------------------------------------------------------------------------
+// Which AF number connects a pin to a peripheral is fixed per pin (datasheet
+// DS9405, Table 12 "Alternate function mapping"). A wrong number leaves the
+// peripheral silently disconnected, so `gpio_set_af_checked` looks the
+// number up instead of taking it from the caller. The table covers the
+// peripherals this crate drives, for the pins bonded out on the LQFP144.
+
+/// Peripheral a pin can be connected to through its alternate function.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GpioFunction {
+    Mco1,
+    Mco2,
+    Tim1,
+    Tim2,
+    Tim3,
+    Tim4,
+    Tim5,
+    Tim8,
+    Tim9,
+    I2c1,
+    I2c2,
+    I2c3,
+    Spi1,
+    Spi2,
+    Spi3,
+    Spi4,
+    Spi5,
+    Spi6,
+    Usart1,
+    Usart2,
+    Usart3,
+    Uart4,
+    Uart5,
+    Usart6,
+    Can1,
+    Can2,
+    /// USB OTG_FS core
+    OtgFs,
+    /// USB OTG_HS core with its embedded full-speed PHY
+    OtgHsFs,
+}
+
+/// One row of `GPIO_AF_TABLE`: `function` is reachable on `port`/`pin` as `af`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GpioAfEntry {
+    pub port: u32,
+    pub pin: u32,
+    pub function: GpioFunction,
+    pub af: u32,
+}
+
+const fn gpio_af_entry(port: u32, pin: u32, function: GpioFunction, af: u32) -> GpioAfEntry {
+    GpioAfEntry {
+        port,
+        pin,
+        function,
+        af,
+    }
+}
+
+/// Alternate function assignments, grouped by peripheral.
+#[rustfmt::skip]
+pub const GPIO_AF_TABLE: [GpioAfEntry; 151] = [
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_8, GpioFunction::Mco1, 0), // PA8 MCO1
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_9, GpioFunction::Mco2, 0), // PC9 MCO2
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_8, GpioFunction::Tim1, 1), // PA8 CH1
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_9, GpioFunction::Tim1, 1), // PA9 CH2
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_10, GpioFunction::Tim1, 1), // PA10 CH3
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_11, GpioFunction::Tim1, 1), // PA11 CH4
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_9, GpioFunction::Tim1, 1), // PE9 CH1
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_11, GpioFunction::Tim1, 1), // PE11 CH2
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_13, GpioFunction::Tim1, 1), // PE13 CH3
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_14, GpioFunction::Tim1, 1), // PE14 CH4
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_7, GpioFunction::Tim1, 1), // PA7 CH1N
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_13, GpioFunction::Tim1, 1), // PB13 CH1N
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_0, GpioFunction::Tim1, 1), // PB0 CH2N
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_14, GpioFunction::Tim1, 1), // PB14 CH2N
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_1, GpioFunction::Tim1, 1), // PB1 CH3N
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_15, GpioFunction::Tim1, 1), // PB15 CH3N
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_0, GpioFunction::Tim2, 1), // PA0 CH1/ETR
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_5, GpioFunction::Tim2, 1), // PA5 CH1/ETR
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_15, GpioFunction::Tim2, 1), // PA15 CH1/ETR
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_1, GpioFunction::Tim2, 1), // PA1 CH2
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_3, GpioFunction::Tim2, 1), // PB3 CH2
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_2, GpioFunction::Tim2, 1), // PA2 CH3
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_10, GpioFunction::Tim2, 1), // PB10 CH3
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_3, GpioFunction::Tim2, 1), // PA3 CH4
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_11, GpioFunction::Tim2, 1), // PB11 CH4
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_6, GpioFunction::Tim3, 2), // PA6 CH1
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_4, GpioFunction::Tim3, 2), // PB4 CH1
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_6, GpioFunction::Tim3, 2), // PC6 CH1
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_7, GpioFunction::Tim3, 2), // PA7 CH2
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_5, GpioFunction::Tim3, 2), // PB5 CH2
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_7, GpioFunction::Tim3, 2), // PC7 CH2
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_0, GpioFunction::Tim3, 2), // PB0 CH3
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_8, GpioFunction::Tim3, 2), // PC8 CH3
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_1, GpioFunction::Tim3, 2), // PB1 CH4
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_9, GpioFunction::Tim3, 2), // PC9 CH4
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_6, GpioFunction::Tim4, 2), // PB6 CH1
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_12, GpioFunction::Tim4, 2), // PD12 CH1
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_7, GpioFunction::Tim4, 2), // PB7 CH2
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_13, GpioFunction::Tim4, 2), // PD13 CH2
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_8, GpioFunction::Tim4, 2), // PB8 CH3
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_14, GpioFunction::Tim4, 2), // PD14 CH3
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_9, GpioFunction::Tim4, 2), // PB9 CH4
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_15, GpioFunction::Tim4, 2), // PD15 CH4
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_0, GpioFunction::Tim5, 2), // PA0 CH1
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_1, GpioFunction::Tim5, 2), // PA1 CH2
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_2, GpioFunction::Tim5, 2), // PA2 CH3
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_3, GpioFunction::Tim5, 2), // PA3 CH4
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_6, GpioFunction::Tim8, 3), // PC6 CH1
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_7, GpioFunction::Tim8, 3), // PC7 CH2
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_8, GpioFunction::Tim8, 3), // PC8 CH3
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_9, GpioFunction::Tim8, 3), // PC9 CH4
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_5, GpioFunction::Tim8, 3), // PA5 CH1N
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_7, GpioFunction::Tim8, 3), // PA7 CH1N
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_0, GpioFunction::Tim8, 3), // PB0 CH2N
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_14, GpioFunction::Tim8, 3), // PB14 CH2N
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_1, GpioFunction::Tim8, 3), // PB1 CH3N
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_15, GpioFunction::Tim8, 3), // PB15 CH3N
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_2, GpioFunction::Tim9, 3), // PA2 CH1
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_5, GpioFunction::Tim9, 3), // PE5 CH1
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_3, GpioFunction::Tim9, 3), // PA3 CH2
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_6, GpioFunction::Tim9, 3), // PE6 CH2
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_6, GpioFunction::I2c1, 4), // PB6 SCL
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_8, GpioFunction::I2c1, 4), // PB8 SCL
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_7, GpioFunction::I2c1, 4), // PB7 SDA
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_9, GpioFunction::I2c1, 4), // PB9 SDA
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_10, GpioFunction::I2c2, 4), // PB10 SCL
+    gpio_af_entry(GPIOF_BASE, GPIO_PIN_1, GpioFunction::I2c2, 4), // PF1 SCL
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_11, GpioFunction::I2c2, 4), // PB11 SDA
+    gpio_af_entry(GPIOF_BASE, GPIO_PIN_0, GpioFunction::I2c2, 4), // PF0 SDA
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_8, GpioFunction::I2c3, 4), // PA8 SCL
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_9, GpioFunction::I2c3, 4), // PC9 SDA
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_4, GpioFunction::Spi1, 5), // PA4 NSS
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_15, GpioFunction::Spi1, 5), // PA15 NSS
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_5, GpioFunction::Spi1, 5), // PA5 SCK
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_3, GpioFunction::Spi1, 5), // PB3 SCK
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_6, GpioFunction::Spi1, 5), // PA6 MISO
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_4, GpioFunction::Spi1, 5), // PB4 MISO
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_7, GpioFunction::Spi1, 5), // PA7 MOSI
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_5, GpioFunction::Spi1, 5), // PB5 MOSI
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_9, GpioFunction::Spi2, 5), // PB9 NSS
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_12, GpioFunction::Spi2, 5), // PB12 NSS
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_10, GpioFunction::Spi2, 5), // PB10 SCK
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_13, GpioFunction::Spi2, 5), // PB13 SCK
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_3, GpioFunction::Spi2, 5), // PD3 SCK
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_14, GpioFunction::Spi2, 5), // PB14 MISO
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_2, GpioFunction::Spi2, 5), // PC2 MISO
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_15, GpioFunction::Spi2, 5), // PB15 MOSI
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_3, GpioFunction::Spi2, 5), // PC3 MOSI
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_4, GpioFunction::Spi3, 6), // PA4 NSS
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_15, GpioFunction::Spi3, 6), // PA15 NSS
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_3, GpioFunction::Spi3, 6), // PB3 SCK
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_10, GpioFunction::Spi3, 6), // PC10 SCK
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_4, GpioFunction::Spi3, 6), // PB4 MISO
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_11, GpioFunction::Spi3, 6), // PC11 MISO
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_5, GpioFunction::Spi3, 6), // PB5 MOSI
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_12, GpioFunction::Spi3, 6), // PC12 MOSI
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_4, GpioFunction::Spi4, 5), // PE4 NSS
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_11, GpioFunction::Spi4, 5), // PE11 NSS
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_2, GpioFunction::Spi4, 5), // PE2 SCK
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_12, GpioFunction::Spi4, 5), // PE12 SCK
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_5, GpioFunction::Spi4, 5), // PE5 MISO
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_13, GpioFunction::Spi4, 5), // PE13 MISO
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_6, GpioFunction::Spi4, 5), // PE6 MOSI
+    gpio_af_entry(GPIOE_BASE, GPIO_PIN_14, GpioFunction::Spi4, 5), // PE14 MOSI
+    gpio_af_entry(GPIOF_BASE, GPIO_PIN_6, GpioFunction::Spi5, 5), // PF6 NSS
+    gpio_af_entry(GPIOF_BASE, GPIO_PIN_7, GpioFunction::Spi5, 5), // PF7 SCK
+    gpio_af_entry(GPIOF_BASE, GPIO_PIN_8, GpioFunction::Spi5, 5), // PF8 MISO
+    gpio_af_entry(GPIOF_BASE, GPIO_PIN_9, GpioFunction::Spi5, 5), // PF9 MOSI
+    gpio_af_entry(GPIOF_BASE, GPIO_PIN_11, GpioFunction::Spi5, 5), // PF11 MOSI
+    gpio_af_entry(GPIOG_BASE, GPIO_PIN_8, GpioFunction::Spi6, 5), // PG8 NSS
+    gpio_af_entry(GPIOG_BASE, GPIO_PIN_13, GpioFunction::Spi6, 5), // PG13 SCK
+    gpio_af_entry(GPIOG_BASE, GPIO_PIN_12, GpioFunction::Spi6, 5), // PG12 MISO
+    gpio_af_entry(GPIOG_BASE, GPIO_PIN_14, GpioFunction::Spi6, 5), // PG14 MOSI
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_9, GpioFunction::Usart1, 7), // PA9 TX
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_6, GpioFunction::Usart1, 7), // PB6 TX
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_10, GpioFunction::Usart1, 7), // PA10 RX
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_7, GpioFunction::Usart1, 7), // PB7 RX
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_2, GpioFunction::Usart2, 7), // PA2 TX
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_5, GpioFunction::Usart2, 7), // PD5 TX
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_3, GpioFunction::Usart2, 7), // PA3 RX
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_6, GpioFunction::Usart2, 7), // PD6 RX
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_10, GpioFunction::Usart3, 7), // PB10 TX
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_10, GpioFunction::Usart3, 7), // PC10 TX
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_8, GpioFunction::Usart3, 7), // PD8 TX
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_11, GpioFunction::Usart3, 7), // PB11 RX
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_11, GpioFunction::Usart3, 7), // PC11 RX
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_9, GpioFunction::Usart3, 7), // PD9 RX
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_0, GpioFunction::Uart4, 8), // PA0 TX
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_10, GpioFunction::Uart4, 8), // PC10 TX
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_1, GpioFunction::Uart4, 8), // PA1 RX
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_11, GpioFunction::Uart4, 8), // PC11 RX
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_12, GpioFunction::Uart5, 8), // PC12 TX
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_2, GpioFunction::Uart5, 8), // PD2 RX
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_6, GpioFunction::Usart6, 8), // PC6 TX
+    gpio_af_entry(GPIOG_BASE, GPIO_PIN_14, GpioFunction::Usart6, 8), // PG14 TX
+    gpio_af_entry(GPIOC_BASE, GPIO_PIN_7, GpioFunction::Usart6, 8), // PC7 RX
+    gpio_af_entry(GPIOG_BASE, GPIO_PIN_9, GpioFunction::Usart6, 8), // PG9 RX
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_12, GpioFunction::Can1, 9), // PA12 TX
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_9, GpioFunction::Can1, 9), // PB9 TX
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_1, GpioFunction::Can1, 9), // PD1 TX
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_11, GpioFunction::Can1, 9), // PA11 RX
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_8, GpioFunction::Can1, 9), // PB8 RX
+    gpio_af_entry(GPIOD_BASE, GPIO_PIN_0, GpioFunction::Can1, 9), // PD0 RX
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_6, GpioFunction::Can2, 9), // PB6 TX
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_13, GpioFunction::Can2, 9), // PB13 TX
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_5, GpioFunction::Can2, 9), // PB5 RX
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_12, GpioFunction::Can2, 9), // PB12 RX
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_11, GpioFunction::OtgFs, 10), // PA11 DM
+    gpio_af_entry(GPIOA_BASE, GPIO_PIN_12, GpioFunction::OtgFs, 10), // PA12 DP
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_14, GpioFunction::OtgHsFs, 12), // PB14 DM
+    gpio_af_entry(GPIOB_BASE, GPIO_PIN_15, GpioFunction::OtgHsFs, 12), // PB15 DP
+];
+
+/// Looks up the AF number that connects `function` to a pin.
+///
+/// # Arguments
+/// * `port`     - The base address of the GPIO port
+/// * `pin`      - The pin number (0..15)
+/// * `function` - The peripheral the pin should be connected to
+///
+/// # Returns
+/// * `Some(af)` with the alternate function number
+/// * `None` if the pin cannot be used for that peripheral (or is not in the table)
+pub const fn gpio_af_lookup(port: u32, pin: u32, function: GpioFunction) -> Option<u32> {
+    let mut i = 0;
+    while i < GPIO_AF_TABLE.len() {
+        let entry = &GPIO_AF_TABLE[i];
+        if entry.port == port && entry.pin == pin && entry.function as u32 == function as u32 {
+            return Some(entry.af);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Selects the alternate function of a pin by peripheral instead of AF number.
+///
+/// Only AFR is written (like `gpio_set_af`); the pin mode and electrical
+/// settings are left to the caller.
+///
+/// # Arguments
+/// * `port`     - The base address of the GPIO port
+/// * `pin`      - The pin number (0..15)
+/// * `function` - The peripheral the pin should be connected to
+///
+/// # Returns
+/// * `Ok(())` once AFR holds the AF number for `function`
+/// * `Err(BswError::InvalidArgument)` if the pin cannot do that function;
+///   AFR is left unchanged
+pub fn gpio_set_af_checked(port: u32, pin: u32, function: GpioFunction) -> error::Result<()> {
+    let af = gpio_af_lookup(port, pin, function).ok_or(BswError::InvalidArgument)?;
+    gpio_set_af(port, pin, af);
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Unit tests (host): cargo test --target x86_64-unknown-linux-gnu
+// -----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OSPEEDR: u32 = GPIOA_BASE + GPIOX_OSPEEDR;
+
+    #[test]
+    fn speed_field_of_each_pin() {
+        for pin in 0..16 {
+            let mut regs = MockBus::default();
+            gpio_set_speed_on(&mut regs, GPIOA_BASE, pin, PinSpeed::VeryHigh);
+            assert_eq!(regs.read(OSPEEDR), 0b11 << (pin * 2), "pin {pin}");
+        }
+    }
+
+    #[test]
+    fn speed_encodings() {
+        let mut regs = MockBus::default();
+        for (speed, bits) in [
+            (PinSpeed::Low, 0b00),
+            (PinSpeed::Medium, 0b01),
+            (PinSpeed::High, 0b10),
+            (PinSpeed::VeryHigh, 0b11),
+        ] {
+            gpio_set_speed_on(&mut regs, GPIOA_BASE, 5, speed);
+            assert_eq!(regs.read(OSPEEDR), bits << 10);
+            assert_eq!(gpio_get_speed_on(&regs, GPIOA_BASE, 5), speed);
+        }
+    }
+
+    #[test]
+    fn speed_keeps_other_pins() {
+        let mut regs = MockBus::default();
+        regs.write(OSPEEDR, 0xFFFF_FFFF);
+        gpio_set_speed_on(&mut regs, GPIOA_BASE, 15, PinSpeed::Low);
+        assert_eq!(regs.read(OSPEEDR), 0x3FFF_FFFF);
+        assert_eq!(gpio_get_speed_on(&regs, GPIOA_BASE, 14), PinSpeed::VeryHigh);
+        assert_eq!(gpio_get_speed_on(&regs, GPIOA_BASE, 15), PinSpeed::Low);
+    }
+
+    #[test]
+    fn port_base_and_rcc_bit() {
+        assert_eq!(GpioPort::A.base(), GPIOA_BASE);
+        assert_eq!(GpioPort::G.base(), GPIOG_BASE);
+        assert_eq!(GpioPort::G.rcc_bit(), 6);
+        for (index, port) in GpioPort::ALL.iter().enumerate() {
+            assert_eq!(port.rcc_bit(), index as u32);
+            assert_eq!(GpioPort::from_base(port.base()), Some(*port));
+        }
+    }
+
+    #[test]
+    fn port_from_base_rejects_other_addresses() {
+        assert_eq!(GpioPort::from_base(GPIOA_BASE + 4), None);
+        assert_eq!(GpioPort::from_base(GPIOK_BASE + GPIO_PORT_STRIDE), None);
+        assert_eq!(GpioPort::from_base(USART2_BASE), None);
+    }
+
+    #[test]
+    fn af_lookup_board_pins() {
+        assert_eq!(
+            gpio_af_lookup(GPIOA_BASE, GPIO_PIN_8, GpioFunction::Mco1),
+            Some(0)
+        );
+        assert_eq!(
+            gpio_af_lookup(GPIOA_BASE, GPIO_PIN_8, GpioFunction::I2c3),
+            Some(4)
+        );
+        assert_eq!(
+            gpio_af_lookup(GPIOA_BASE, GPIO_PIN_9, GpioFunction::Usart1),
+            Some(7)
+        );
+        assert_eq!(
+            gpio_af_lookup(GPIOF_BASE, GPIO_PIN_7, GpioFunction::Spi5),
+            Some(5)
+        );
+        assert_eq!(
+            gpio_af_lookup(GPIOB_BASE, GPIO_PIN_3, GpioFunction::Spi3),
+            Some(6)
+        );
+        assert_eq!(
+            gpio_af_lookup(GPIOB_BASE, GPIO_PIN_15, GpioFunction::OtgHsFs),
+            Some(12)
+        );
+    }
+
+    #[test]
+    fn af_lookup_rejects_wrong_pin() {
+        assert_eq!(
+            gpio_af_lookup(GPIOG_BASE, GPIO_PIN_13, GpioFunction::Usart1),
+            None
+        );
+        assert_eq!(
+            gpio_af_lookup(GPIOA_BASE, GPIO_PIN_9, GpioFunction::Usart2),
+            None
+        );
+    }
+
+    #[test]
+    fn af_table_is_consistent() {
+        for (i, entry) in GPIO_AF_TABLE.iter().enumerate() {
+            assert!(entry.af < 16, "row {i}");
+            assert!(
+                gpio_bonded_pins(entry.port) & (1 << entry.pin) != 0,
+                "row {i}"
+            );
+            // Each pin/function pair appears once
+            for other in &GPIO_AF_TABLE[i + 1..] {
+                assert!(
+                    (other.port, other.pin, other.function)
+                        != (entry.port, entry.pin, entry.function),
+                    "row {i} duplicated"
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "pin must be 0..15")]
+    fn speed_rejects_pin_16() {
+        let mut regs = MockBus::default();
+        gpio_set_speed_on(&mut regs, GPIOA_BASE, 16, PinSpeed::Low);
     }
 }
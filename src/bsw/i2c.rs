@@ -1,3 +1,281 @@
 // -----------------------------------------------------------------------------
 // STM32F429 I2C (Inter-Integrated Circuit) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides a polled I2C master driver (7-bit addresses) for the
+// standard (100 kHz) and fast (400 kHz) modes. Every wait is bounded by
+// `I2C_TIMEOUT_CYCLES` polls, so a stuck bus returns `I2cError::Timeout`
+// instead of hanging.
+//
+// SCL/SDA must be configured separately as open-drain alternate function
+// AF4, e.g. for I2C3 on the STM32F429I-DISC1 (PA8 = SCL, PC9 = SDA).
+//
+// Reference: STM32F429 Reference Manual, section 27.6 (I2C registers)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::rcc::{RccBus, rcc_enable_peripheral};
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// I2C Register Offsets (relative to I2Cx base address)
+// -----------------------------------------------------------------------------
+pub const I2C_CR1: u32 = 0x00; // Control register 1
+pub const I2C_CR2: u32 = 0x04; // Control register 2
+pub const I2C_OAR1: u32 = 0x08; // Own address register 1
+pub const I2C_OAR2: u32 = 0x0C; // Own address register 2
+pub const I2C_DR: u32 = 0x10; // Data register
+pub const I2C_SR1: u32 = 0x14; // Status register 1
+pub const I2C_SR2: u32 = 0x18; // Status register 2
+pub const I2C_CCR: u32 = 0x1C; // Clock control register
+pub const I2C_TRISE: u32 = 0x20; // Rise time register
+pub const I2C_FLTR: u32 = 0x24; // Filter register
+
+// -----------------------------------------------------------------------------
+// I2C Register Bits
+// -----------------------------------------------------------------------------
+pub const I2C_CR1_PE_POS: u32 = 0; // Peripheral enable
+pub const I2C_CR1_START_POS: u32 = 8; // Start generation
+pub const I2C_CR1_STOP_POS: u32 = 9; // Stop generation
+pub const I2C_CR1_ACK_POS: u32 = 10; // Acknowledge enable
+pub const I2C_CR1_SWRST_POS: u32 = 15; // Software reset
+pub const I2C_SR1_SB_POS: u32 = 0; // Start bit generated
+pub const I2C_SR1_ADDR_POS: u32 = 1; // Address sent
+pub const I2C_SR1_BTF_POS: u32 = 2; // Byte transfer finished
+pub const I2C_SR1_RXNE_POS: u32 = 6; // Data register not empty
+pub const I2C_SR1_TXE_POS: u32 = 7; // Data register empty
+pub const I2C_SR1_BERR_POS: u32 = 8; // Bus error
+pub const I2C_SR1_ARLO_POS: u32 = 9; // Arbitration lost
+pub const I2C_SR1_AF_POS: u32 = 10; // Acknowledge failure
+pub const I2C_SR2_BUSY_POS: u32 = 1; // Bus busy
+pub const I2C_CCR_FS_POS: u32 = 15; // Fast mode selection
+
+/// Maximum number of status polls before an operation fails with `Timeout`.
+pub const I2C_TIMEOUT_CYCLES: u32 = 100_000;
+
+/// Bus speed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum I2cSpeed {
+    /// 100 kHz
+    Standard,
+    /// 400 kHz (duty cycle Tlow/Thigh = 2)
+    Fast,
+}
+
+/// Errors reported by the I2C transfer functions.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum I2cError {
+    /// The slave did not acknowledge its address or a data byte (AF)
+    Nack,
+    /// Misplaced start/stop condition on the bus (BERR)
+    Bus,
+    /// Another master won the arbitration (ARLO)
+    ArbitrationLost,
+    /// A flag did not show up within `I2C_TIMEOUT_CYCLES` polls
+    Timeout,
+}
+
+// -----------------------------------------------------------------------------
+// Enable I2C Peripheral Clock
+// -----------------------------------------------------------------------------
+/// Enables the clock for a specific I2C.
+///
+/// # Arguments
+/// * `i2c_base` - The base address of the I2C (e.g., `I2C3_BASE`)
+pub fn i2c_enable_clock(i2c_base: u32) {
+    match i2c_base {
+        I2C1_BASE => rcc_enable_peripheral(RccBus::Apb1, 21), // I2C1EN
+        I2C2_BASE => rcc_enable_peripheral(RccBus::Apb1, 22), // I2C2EN
+        I2C3_BASE => rcc_enable_peripheral(RccBus::Apb1, 23), // I2C3EN
+        _ => {
+            // Unknown I2C: do nothing
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Initialize I2C
+// -----------------------------------------------------------------------------
+/// Enables the I2C clock, resets the peripheral and programs the bus timing.
+///
+/// # Arguments
+/// * `i2c_base` - The base address of the I2C (e.g., `I2C3_BASE`)
+/// * `pclk_hz`  - APB1 clock frequency (2 MHz..50 MHz, at least 4 MHz for fast mode)
+/// * `speed`    - Standard (100 kHz) or fast (400 kHz) mode
+pub fn i2c_init(i2c_base: u32, pclk_hz: u32, speed: I2cSpeed) {
+    let freq_mhz = pclk_hz / 1_000_000;
+    assert!((2..=50).contains(&freq_mhz), "I2C PCLK must be 2..50 MHz");
+    assert!(
+        speed == I2cSpeed::Standard || freq_mhz >= 4,
+        "fast mode needs PCLK >= 4 MHz"
+    );
+
+    i2c_enable_clock(i2c_base);
+
+    // Software reset clears a bus state left over from before the reset
+    let cr1_addr = (i2c_base + I2C_CR1) as *mut u32;
+    reg_set_val(cr1_addr, 1 << I2C_CR1_SWRST_POS);
+    reg_set_val(cr1_addr, 0);
+
+    reg_set_bits((i2c_base + I2C_CR2) as *mut u32, freq_mhz, 0, 6);
+
+    let (ccr, trise) = match speed {
+        // Thigh = Tlow = CCR * Tpclk; max SCL rise time 1000 ns
+        I2cSpeed::Standard => ((pclk_hz / (2 * 100_000)).max(4), freq_mhz + 1),
+        // Tlow = 2 * Thigh = 2 * CCR * Tpclk; max SCL rise time 300 ns
+        I2cSpeed::Fast => (
+            (pclk_hz / (3 * 400_000)).max(1) | (1 << I2C_CCR_FS_POS),
+            freq_mhz * 300 / 1000 + 1,
+        ),
+    };
+    reg_set_val((i2c_base + I2C_CCR) as *mut u32, ccr);
+    reg_set_val((i2c_base + I2C_TRISE) as *mut u32, trise);
+
+    reg_set_bit(cr1_addr, I2C_CR1_PE_POS, true);
+}
+
+// -----------------------------------------------------------------------------
+// Transfer
+// -----------------------------------------------------------------------------
+/// Writes `data` to the slave at 7-bit address `addr` (START, address,
+/// data bytes, STOP).
+///
+/// # Arguments
+/// * `i2c_base` - The base address of the I2C
+/// * `addr`     - 7-bit slave address (not shifted)
+/// * `data`     - Bytes to write (typically a register address followed by values);
+///   an empty slice only probes whether the address is acknowledged
+pub fn i2c_write(i2c_base: u32, addr: u8, data: &[u8]) -> Result<(), I2cError> {
+    let result = i2c_write_inner(i2c_base, addr, data);
+    i2c_finish(i2c_base, result)
+}
+
+/// Reads `buf.len()` bytes from the slave at 7-bit address `addr`.
+///
+/// The last byte is NACKed and followed by STOP, as the protocol requires.
+///
+/// # Arguments
+/// * `i2c_base` - The base address of the I2C
+/// * `addr`     - 7-bit slave address (not shifted)
+/// * `buf`      - Buffer for the received bytes
+pub fn i2c_read(i2c_base: u32, addr: u8, buf: &mut [u8]) -> Result<(), I2cError> {
+    let result = i2c_read_inner(i2c_base, addr, buf);
+    i2c_finish(i2c_base, result)
+}
+
+fn i2c_write_inner(i2c_base: u32, addr: u8, data: &[u8]) -> Result<(), I2cError> {
+    i2c_start(i2c_base, addr << 1)?;
+    i2c_clear_addr(i2c_base);
+
+    let dr_addr = (i2c_base + I2C_DR) as *mut u32;
+    for &byte in data {
+        i2c_wait_flag(i2c_base, I2C_SR1_TXE_POS)?;
+        reg_set_val(dr_addr, byte as u32);
+    }
+    // BTF: the last byte has been shifted out and acknowledged
+    if !data.is_empty() {
+        i2c_wait_flag(i2c_base, I2C_SR1_BTF_POS)?;
+    }
+    i2c_stop(i2c_base);
+    Ok(())
+}
+
+fn i2c_read_inner(i2c_base: u32, addr: u8, buf: &mut [u8]) -> Result<(), I2cError> {
+    if buf.is_empty() {
+        return Ok(());
+    }
+
+    let cr1_addr = (i2c_base + I2C_CR1) as *mut u32;
+    let dr_addr = (i2c_base + I2C_DR) as *mut u32;
+
+    reg_set_bit(cr1_addr, I2C_CR1_ACK_POS, true);
+    i2c_start(i2c_base, (addr << 1) | 1)?;
+
+    let last = buf.len() - 1;
+    if last == 0 {
+        // Single byte: NACK and STOP must be set before ADDR is cleared
+        reg_set_bit(cr1_addr, I2C_CR1_ACK_POS, false);
+        i2c_clear_addr(i2c_base);
+        i2c_stop(i2c_base);
+    } else {
+        i2c_clear_addr(i2c_base);
+    }
+
+    for (i, byte) in buf.iter_mut().enumerate() {
+        if i == last && last != 0 {
+            // NACK the byte currently being received, then STOP
+            reg_set_bit(cr1_addr, I2C_CR1_ACK_POS, false);
+            i2c_stop(i2c_base);
+        }
+        i2c_wait_flag(i2c_base, I2C_SR1_RXNE_POS)?;
+        *byte = (unsafe { reg_read(dr_addr) } & 0xFF) as u8;
+    }
+    Ok(())
+}
+
+/// Generates START and sends the address byte (address << 1 | R/W).
+fn i2c_start(i2c_base: u32, address_byte: u8) -> Result<(), I2cError> {
+    if !reg_wait_bit(
+        (i2c_base + I2C_SR2) as *mut u32,
+        I2C_SR2_BUSY_POS,
+        false,
+        I2C_TIMEOUT_CYCLES,
+    ) {
+        return Err(I2cError::Timeout);
+    }
+
+    reg_set_bit((i2c_base + I2C_CR1) as *mut u32, I2C_CR1_START_POS, true);
+    i2c_wait_flag(i2c_base, I2C_SR1_SB_POS)?;
+
+    reg_set_val((i2c_base + I2C_DR) as *mut u32, address_byte as u32);
+    i2c_wait_flag(i2c_base, I2C_SR1_ADDR_POS)
+}
+
+/// Clears ADDR by reading SR1 followed by SR2.
+fn i2c_clear_addr(i2c_base: u32) {
+    unsafe {
+        let _ = reg_read_ro((i2c_base + I2C_SR1) as ReadOnlyRegister);
+        let _ = reg_read_ro((i2c_base + I2C_SR2) as ReadOnlyRegister);
+    }
+}
+
+fn i2c_stop(i2c_base: u32) {
+    reg_set_bit((i2c_base + I2C_CR1) as *mut u32, I2C_CR1_STOP_POS, true);
+}
+
+/// Polls SR1 until `bit` is set, failing on AF/BERR/ARLO or after
+/// `I2C_TIMEOUT_CYCLES` polls.
+fn i2c_wait_flag(i2c_base: u32, bit: u32) -> Result<(), I2cError> {
+    let sr1_addr = (i2c_base + I2C_SR1) as *mut u32;
+
+    for _ in 0..I2C_TIMEOUT_CYCLES {
+        let sr1 = unsafe { reg_read_ro(sr1_addr) };
+        if sr1 & (1 << I2C_SR1_AF_POS) != 0 {
+            return Err(I2cError::Nack);
+        }
+        if sr1 & (1 << I2C_SR1_BERR_POS) != 0 {
+            return Err(I2cError::Bus);
+        }
+        if sr1 & (1 << I2C_SR1_ARLO_POS) != 0 {
+            return Err(I2cError::ArbitrationLost);
+        }
+        if sr1 & (1 << bit) != 0 {
+            return Ok(());
+        }
+    }
+    Err(I2cError::Timeout)
+}
+
+/// On error: releases the bus with STOP (unless arbitration was lost, in
+/// which case the peripheral is already a slave) and clears the error flags.
+fn i2c_finish(i2c_base: u32, result: Result<(), I2cError>) -> Result<(), I2cError> {
+    if let Err(err) = result {
+        if err != I2cError::ArbitrationLost {
+            i2c_stop(i2c_base);
+        }
+        // Error flags are rc_w0: write 0 to clear, 1 leaves other flags unchanged
+        let errors = (1 << I2C_SR1_AF_POS) | (1 << I2C_SR1_BERR_POS) | (1 << I2C_SR1_ARLO_POS);
+        reg_set_val((i2c_base + I2C_SR1) as *mut u32, !errors & 0xFFFF);
+    }
+    result
+}
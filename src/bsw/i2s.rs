@@ -1,3 +1,180 @@
 // -----------------------------------------------------------------------------
 // STM32F429 I2S (Inter-IC Sound) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides helper functions to run SPI2/SPI3 in I2S master
+// transmit mode, e.g. to feed an external audio DAC (the STM32F429I-DISC1
+// has no audio codec of its own). The GPIO pins (CK, WS, SD and optionally
+// MCK) must be configured separately as alternate function AF5/AF6.
+//
+// Clocking
+// I2SxCLK comes from PLLI2S (`rcc_configure_plli2s`). The sampling rate is
+// set by the 8-bit I2SDIV and the ODD bit in SPI_I2SPR:
+//
+//   MCK output enabled:          Fs = I2SxCLK / (256 * (2 * I2SDIV + ODD))
+//   MCK off, 16-bit channel:     Fs = I2SxCLK / (32 * (2 * I2SDIV + ODD))
+//   MCK off, 32-bit channel:     Fs = I2SxCLK / (64 * (2 * I2SDIV + ODD))
+//
+// `i2s_init` computes div = 2 * I2SDIV + ODD = I2SxCLK / (factor * Fs),
+// rounded to nearest. Example: I2SxCLK = 86 MHz, Fs = 48 kHz with MCK:
+// 86e6 / (256 * 48000) = 6.998 -> div 7 (I2SDIV 3, ODD 1), Fs = 47.991 kHz.
+//
+// Reference: STM32F429 Reference Manual, section 28.4 (I2S functional
+//            description) and 28.5 (SPI/I2S registers)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::rcc::rcc_get_plli2s_clk_hz;
+use crate::bsw::reg_utils::*; // Register access helper functions
+use crate::bsw::spi::*; // SPI register offsets and clock enable
+
+// -----------------------------------------------------------------------------
+// I2S Bit Positions
+// -----------------------------------------------------------------------------
+// SPI_I2SCFGR
+pub const SPI_I2SCFGR_CHLEN_POS: u32 = 0; // Channel length (0 = 16 bit, 1 = 32 bit)
+pub const SPI_I2SCFGR_DATLEN_POS: u32 = 1; // Data length (2 bits)
+pub const SPI_I2SCFGR_CKPOL_POS: u32 = 3; // Steady state clock polarity
+pub const SPI_I2SCFGR_I2SSTD_POS: u32 = 4; // I2S standard selection (2 bits)
+pub const SPI_I2SCFGR_I2SCFG_POS: u32 = 8; // I2S configuration mode (2 bits)
+pub const SPI_I2SCFGR_I2SE_POS: u32 = 10; // I2S enable
+pub const SPI_I2SCFGR_I2SMOD_POS: u32 = 11; // I2S mode selection (1 = I2S)
+
+// SPI_I2SPR
+pub const SPI_I2SPR_ODD_POS: u32 = 8; // Odd factor for the prescaler
+pub const SPI_I2SPR_MCKOE_POS: u32 = 9; // Master clock output enable
+
+/// I2SCFG value for master transmit.
+const I2S_CFG_MASTER_TX: u32 = 0b10;
+
+// -----------------------------------------------------------------------------
+// I2S Configuration
+// -----------------------------------------------------------------------------
+/// Frame format (I2SSTD field).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum I2sStandard {
+    Philips = 0b00,
+    MsbJustified = 0b01,
+    LsbJustified = 0b10,
+    Pcm = 0b11,
+}
+
+/// Data and channel length (DATLEN and CHLEN fields).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum I2sDataLength {
+    /// 16-bit data in a 16-bit channel
+    Bits16,
+    /// 16-bit data in a 32-bit channel
+    Bits16Extended,
+    /// 24-bit data in a 32-bit channel
+    Bits24,
+    /// 32-bit data in a 32-bit channel
+    Bits32,
+}
+
+impl I2sDataLength {
+    /// Returns the (DATLEN, CHLEN) field values.
+    const fn fields(self) -> (u32, u32) {
+        match self {
+            I2sDataLength::Bits16 => (0b00, 0),
+            I2sDataLength::Bits16Extended => (0b00, 1),
+            I2sDataLength::Bits24 => (0b01, 1),
+            I2sDataLength::Bits32 => (0b10, 1),
+        }
+    }
+}
+
+/// I2S master transmit configuration.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct I2sConfig {
+    pub standard: I2sStandard,
+    pub data_length: I2sDataLength,
+    /// Sampling frequency Fs (e.g., 48_000)
+    pub audio_freq_hz: u32,
+    /// Output the master clock (256 * Fs) on the MCK pin
+    pub mclk_output: bool,
+}
+
+/// Reasons `i2s_init` cannot produce the requested sampling rate.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum I2sError {
+    /// PLLI2S is not running
+    ClockNotReady,
+    /// The required divider is outside 4..511 (I2SDIV 2..255)
+    PrescalerOutOfRange,
+}
+
+// -----------------------------------------------------------------------------
+// Initialize I2S
+// -----------------------------------------------------------------------------
+/// Configures SPI2/SPI3 as I2S master transmitter and enables it.
+///
+/// # Arguments
+/// * `spi_base` - SPI2_BASE or SPI3_BASE
+/// * `config`   - Frame format, data length and sampling frequency
+///
+/// # Returns
+/// * `Ok(())` if the interface runs, otherwise an `I2sError` (interface left
+///   disabled)
+pub fn i2s_init(spi_base: u32, config: I2sConfig) -> Result<(), I2sError> {
+    assert!(config.audio_freq_hz > 0, "audio frequency must be non-zero");
+
+    let i2s_clk_hz = rcc_get_plli2s_clk_hz();
+    if i2s_clk_hz == 0 {
+        return Err(I2sError::ClockNotReady);
+    }
+
+    let (datlen, chlen) = config.data_length.fields();
+    let factor = match (config.mclk_output, chlen) {
+        (true, _) => 256,
+        (false, 0) => 32,
+        (false, _) => 64,
+    };
+    let freq_step = factor * config.audio_freq_hz as u64;
+    let div = (i2s_clk_hz as u64 + freq_step / 2) / freq_step;
+    if !(4..=511).contains(&div) {
+        return Err(I2sError::PrescalerOutOfRange);
+    }
+
+    spi_enable_clock(spi_base);
+
+    let i2scfgr_addr = (spi_base + SPI_I2SCFGR) as *mut u32;
+    reg_set_val(i2scfgr_addr, 0); // Disabled while configuring
+
+    reg_set_val(
+        (spi_base + SPI_I2SPR) as *mut u32,
+        ((config.mclk_output as u32) << SPI_I2SPR_MCKOE_POS)
+            | (((div & 1) as u32) << SPI_I2SPR_ODD_POS)
+            | (div / 2) as u32,
+    );
+
+    reg_set_val(
+        i2scfgr_addr,
+        (1 << SPI_I2SCFGR_I2SMOD_POS)
+            | (I2S_CFG_MASTER_TX << SPI_I2SCFGR_I2SCFG_POS)
+            | ((config.standard as u32) << SPI_I2SCFGR_I2SSTD_POS)
+            | (datlen << SPI_I2SCFGR_DATLEN_POS)
+            | (chlen << SPI_I2SCFGR_CHLEN_POS),
+    );
+    reg_set_bit(i2scfgr_addr, SPI_I2SCFGR_I2SE_POS, true);
+
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Transmit
+// -----------------------------------------------------------------------------
+/// Writes one 16-bit sample, waiting until the transmit buffer is empty.
+///
+/// Channels alternate: in stereo, write the left sample, then the right one.
+/// The hardware keeps the order (SR CHSIDE tells which channel is next), so
+/// the stream must never skip a sample. 24/32-bit formats need two writes
+/// per sample (upper half-word first).
+///
+/// # Arguments
+/// * `spi_base` - SPI2_BASE or SPI3_BASE
+/// * `sample`   - Signed PCM sample
+pub fn i2s_write_sample(spi_base: u32, sample: i16) {
+    let sr_addr = (spi_base + SPI_SR) as *mut u32;
+    while !reg_read_bit(sr_addr, SPI_SR_TXE_POS) {}
+    reg_set_val((spi_base + SPI_DR) as *mut u32, sample as u16 as u32);
+}
@@ -1,3 +1,74 @@
 // -----------------------------------------------------------------------------
 // STM32F429 ITM (Instrumentation Trace Macrocell) utilities
 // -----------------------------------------------------------------------------
+//
+// The ITM sends software messages through 32 stimulus ports to the debugger,
+// over the SWO pin (PB3) of the ST-LINK. The debugger (OpenOCD, probe-rs,
+// STM32CubeProgrammer SWV) normally enables ITM, the stimulus ports and the
+// SWO baud rate itself; writes are simply dropped when no one listens.
+//
+// Reference: ARMv7-M Architecture Reference Manual, C1.7 (ITM)
+// -----------------------------------------------------------------------------
+
+use core::fmt;
+use core::ptr;
+
+use crate::bsw::reg_cpu_cortex_m4::*; // Cortex-M4 core register addresses
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// ITM Register Bits
+// -----------------------------------------------------------------------------
+const ITM_TCR_ITMENA_POS: u32 = 0; // ITM enable
+const ITM_STIM_FIFOREADY_POS: u32 = 0; // Stimulus port can accept data (read)
+
+/// Number of stimulus ports.
+pub const ITM_PORT_COUNT: u32 = 32;
+
+// -----------------------------------------------------------------------------
+// Stimulus Port Output
+// -----------------------------------------------------------------------------
+/// Returns true if ITM is enabled and `port` is enabled in TER.
+pub fn itm_port_enabled(port: u32) -> bool {
+    assert!(port < ITM_PORT_COUNT, "ITM port must be 0..31");
+    reg_read_bit(ITM_TCR_BASE as *mut u32, ITM_TCR_ITMENA_POS)
+        && reg_read_bit(ITM_TER_BASE as *mut u32, port)
+}
+
+/// Writes one byte to a stimulus port.
+///
+/// Waits until the port's FIFO can accept data. Returns immediately (the byte
+/// is dropped) if ITM or the port is not enabled, so it never hangs without
+/// a debugger attached.
+///
+/// # Arguments
+/// * `port` - Stimulus port (0..31); port 0 is the usual text console
+/// * `b`    - Byte to send
+pub fn itm_write_byte(port: u32, b: u8) {
+    if !itm_port_enabled(port) {
+        return;
+    }
+
+    let stim_addr = (ITM_STIM_BASE + port * 4) as *mut u32;
+    while !reg_read_bit(stim_addr, ITM_STIM_FIFOREADY_POS) {}
+
+    // An 8-bit write sends a 1-byte packet (a 32-bit write would send 4 bytes)
+    unsafe { ptr::write_volatile(stim_addr as *mut u8, b) };
+}
+
+/// Writes a string to a stimulus port.
+pub fn itm_write_str(port: u32, s: &str) {
+    for &b in s.as_bytes() {
+        itm_write_byte(port, b);
+    }
+}
+
+/// `core::fmt::Write` adapter for a stimulus port.
+pub struct ItmPort(pub u32);
+
+impl fmt::Write for ItmPort {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        itm_write_str(self.0, s);
+        Ok(())
+    }
+}
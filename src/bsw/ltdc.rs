@@ -1,3 +1,293 @@
 // -----------------------------------------------------------------------------
 // STM32F429 LTDC (LCD-TFT Display Controller) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions for a minimal LTDC
+// setup: panel timings, one framebuffer per layer, and the controller enable.
+//
+// Timing registers hold accumulated values (minus one), counted from the
+// start of the horizontal/vertical sync pulse:
+//
+//   | HSW | HBP |      active width      | HFP |
+//   SSCR: HSW-1
+//   BPCR: HSW+HBP-1
+//   AWCR: HSW+HBP+W-1
+//   TWCR: HSW+HBP+W+HFP-1        (same scheme vertically)
+//
+// Pixel clock (LCD_CLK)
+// The LTDC has no clock divider of its own: LCD_CLK comes from PLLSAI
+// (PLLSAI_R output divided by RCC_DCKCFGR PLLSAIDIVR). It must run before
+// `ltdc_init`. For the DISC1 panel (~6 MHz) with the 2 MHz PLL input
+// (HSI / PLLM = 16 MHz / 8, shared with the main PLL):
+// `rcc_configure_pllsai(96, 4, 4, 8)` (VCO 192 MHz, PLLSAI_R 48 MHz, /8).
+//
+// Not covered here: the LCD GPIOs (AF14, partly AF9) and the ILI9341
+// controller on the DISC1, which must be switched to its RGB interface
+// through SPI5 before it shows the LTDC output.
+//
+// Reference: STM32F429 Reference Manual, section 16.7 (LTDC registers)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::rcc::*; // RCC clock enable
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// LTDC Register Offsets (relative to LCD_TFT_BASE)
+// -----------------------------------------------------------------------------
+pub const LTDC_SSCR: u32 = 0x08; // Synchronization size configuration register
+pub const LTDC_BPCR: u32 = 0x0C; // Back porch configuration register
+pub const LTDC_AWCR: u32 = 0x10; // Active width configuration register
+pub const LTDC_TWCR: u32 = 0x14; // Total width configuration register
+pub const LTDC_GCR: u32 = 0x18; // Global control register
+pub const LTDC_SRCR: u32 = 0x24; // Shadow reload configuration register
+pub const LTDC_BCCR: u32 = 0x2C; // Background color configuration register
+pub const LTDC_IER: u32 = 0x34; // Interrupt enable register
+pub const LTDC_ISR: u32 = 0x38; // Interrupt status register
+pub const LTDC_ICR: u32 = 0x3C; // Interrupt clear register
+pub const LTDC_CPSR: u32 = 0x44; // Current position status register
+pub const LTDC_CDSR: u32 = 0x48; // Current display status register
+
+// Layer registers: offset + 0x80 * (layer - 1)
+pub const LTDC_LXCR: u32 = 0x84; // Layer x control register
+pub const LTDC_LXWHPCR: u32 = 0x88; // Layer x window horizontal position configuration
+pub const LTDC_LXWVPCR: u32 = 0x8C; // Layer x window vertical position configuration
+pub const LTDC_LXPFCR: u32 = 0x94; // Layer x pixel format configuration register
+pub const LTDC_LXCACR: u32 = 0x98; // Layer x constant alpha configuration register
+pub const LTDC_LXBFCR: u32 = 0xA0; // Layer x blending factors configuration register
+pub const LTDC_LXCFBAR: u32 = 0xAC; // Layer x color frame buffer address register
+pub const LTDC_LXCFBLR: u32 = 0xB0; // Layer x color frame buffer length register
+pub const LTDC_LXCFBLNR: u32 = 0xB4; // Layer x color frame buffer line number register
+pub const LTDC_LAYER_STRIDE: u32 = 0x80; // Distance between layer register blocks
+
+// -----------------------------------------------------------------------------
+// LTDC Bit Positions
+// -----------------------------------------------------------------------------
+// GCR
+pub const LTDC_GCR_LTDCEN_POS: u32 = 0; // LTDC enable
+pub const LTDC_GCR_PCPOL_POS: u32 = 28; // Pixel clock polarity (1 = inverted)
+pub const LTDC_GCR_DEPOL_POS: u32 = 29; // Data enable polarity (1 = active high)
+pub const LTDC_GCR_VSPOL_POS: u32 = 30; // Vertical sync polarity (1 = active high)
+pub const LTDC_GCR_HSPOL_POS: u32 = 31; // Horizontal sync polarity (1 = active high)
+
+// SRCR
+pub const LTDC_SRCR_IMR_POS: u32 = 0; // Immediate reload
+
+// LxCR
+pub const LTDC_LXCR_LEN_POS: u32 = 0; // Layer enable
+
+/// LTDC clock enable bit in RCC_APB2ENR.
+const RCC_APB2ENR_LTDCEN_BIT: u32 = 26;
+
+// -----------------------------------------------------------------------------
+// Panel Timing and Pixel Format
+// -----------------------------------------------------------------------------
+/// Panel timings, in pixel clocks (horizontal) and lines (vertical).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LtdcTiming {
+    pub hsync: u16,
+    pub hback_porch: u16,
+    pub width: u16,
+    pub hfront_porch: u16,
+    pub vsync: u16,
+    pub vback_porch: u16,
+    pub height: u16,
+    pub vfront_porch: u16,
+    /// HSYNC/VSYNC/DE are active high when true
+    pub hsync_active_high: bool,
+    pub vsync_active_high: bool,
+    pub de_active_high: bool,
+    /// Data sampled on the falling pixel clock edge when true
+    pub pclk_inverted: bool,
+}
+
+impl LtdcTiming {
+    /// ILI9341 240x320 panel of the STM32F429I-DISC1 (RGB interface).
+    pub const DISC1_ILI9341: LtdcTiming = LtdcTiming {
+        hsync: 10,
+        hback_porch: 20,
+        width: 240,
+        hfront_porch: 10,
+        vsync: 2,
+        vback_porch: 2,
+        height: 320,
+        vfront_porch: 4,
+        hsync_active_high: false,
+        vsync_active_high: false,
+        de_active_high: false,
+        pclk_inverted: false,
+    };
+}
+
+/// Layer pixel formats (LxPFCR PF field).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LtdcPixelFormat {
+    Argb8888 = 0b000,
+    Rgb888 = 0b001,
+    Rgb565 = 0b010,
+    Argb1555 = 0b011,
+    Argb4444 = 0b100,
+    L8 = 0b101,
+    Al44 = 0b110,
+    Al88 = 0b111,
+}
+
+impl LtdcPixelFormat {
+    /// Returns the number of bytes per pixel.
+    pub const fn bytes_per_pixel(self) -> u32 {
+        match self {
+            LtdcPixelFormat::Argb8888 => 4,
+            LtdcPixelFormat::Rgb888 => 3,
+            LtdcPixelFormat::Rgb565
+            | LtdcPixelFormat::Argb1555
+            | LtdcPixelFormat::Argb4444
+            | LtdcPixelFormat::Al88 => 2,
+            LtdcPixelFormat::L8 | LtdcPixelFormat::Al44 => 1,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Initialize LTDC
+// -----------------------------------------------------------------------------
+/// Enables the LTDC clock and programs the panel timings and polarities.
+///
+/// The controller stays disabled until `ltdc_enable`. LCD_CLK (PLLSAI)
+/// must already be running, see the module header.
+///
+/// # Arguments
+/// * `config` - Panel timings (e.g., `LtdcTiming::DISC1_ILI9341`)
+pub fn ltdc_init(config: LtdcTiming) {
+    assert!(
+        config.hsync > 0 && config.vsync > 0,
+        "sync pulse widths must be non-zero"
+    );
+
+    rcc_enable_peripheral(RccBus::Apb2, RCC_APB2ENR_LTDCEN_BIT);
+
+    let gcr_addr = (LCD_TFT_BASE + LTDC_GCR) as *mut u32;
+    reg_set_bit(gcr_addr, LTDC_GCR_LTDCEN_POS, false);
+
+    // Accumulated horizontal (bits 27:16) and vertical (bits 10:0) values
+    let accumulated = |h: u16, v: u16| ((h as u32 - 1) << 16) | (v as u32 - 1);
+    let h_bp = config.hsync + config.hback_porch;
+    let v_bp = config.vsync + config.vback_porch;
+    let h_active = h_bp + config.width;
+    let v_active = v_bp + config.height;
+    reg_set_val(
+        (LCD_TFT_BASE + LTDC_SSCR) as *mut u32,
+        accumulated(config.hsync, config.vsync),
+    );
+    reg_set_val(
+        (LCD_TFT_BASE + LTDC_BPCR) as *mut u32,
+        accumulated(h_bp, v_bp),
+    );
+    reg_set_val(
+        (LCD_TFT_BASE + LTDC_AWCR) as *mut u32,
+        accumulated(h_active, v_active),
+    );
+    reg_set_val(
+        (LCD_TFT_BASE + LTDC_TWCR) as *mut u32,
+        accumulated(
+            h_active + config.hfront_porch,
+            v_active + config.vfront_porch,
+        ),
+    );
+
+    reg_set_val(
+        gcr_addr,
+        ((config.hsync_active_high as u32) << LTDC_GCR_HSPOL_POS)
+            | ((config.vsync_active_high as u32) << LTDC_GCR_VSPOL_POS)
+            | ((config.de_active_high as u32) << LTDC_GCR_DEPOL_POS)
+            | ((config.pclk_inverted as u32) << LTDC_GCR_PCPOL_POS),
+    );
+
+    // Black background where no layer is shown
+    reg_set_val((LCD_TFT_BASE + LTDC_BCCR) as *mut u32, 0);
+}
+
+// -----------------------------------------------------------------------------
+// Layer Configuration
+// -----------------------------------------------------------------------------
+/// Returns the address of a layer register.
+fn ltdc_layer_reg(layer: u32, offset: u32) -> *mut u32 {
+    assert!(layer == 1 || layer == 2, "LTDC layer must be 1 or 2");
+    (LCD_TFT_BASE + offset + LTDC_LAYER_STRIDE * (layer - 1)) as *mut u32
+}
+
+/// Shows a framebuffer on a layer, in the top-left corner of the active area.
+///
+/// The framebuffer is read line by line without padding (pitch = width *
+/// bytes per pixel) and must stay valid while the layer is enabled. Call
+/// after `ltdc_init`; the new settings are applied immediately.
+///
+/// # Arguments
+/// * `layer`  - Layer number (1 or 2; layer 2 is drawn on top)
+/// * `addr`   - Framebuffer start address (SRAM or SDRAM)
+/// * `width`  - Framebuffer width in pixels
+/// * `height` - Framebuffer height in lines
+/// * `format` - Pixel format (e.g., `LtdcPixelFormat::Rgb565`)
+pub fn ltdc_set_layer_framebuffer(
+    layer: u32,
+    addr: u32,
+    width: u32,
+    height: u32,
+    format: LtdcPixelFormat,
+) {
+    assert!(width > 0 && height > 0, "framebuffer must not be empty");
+
+    // Window starts right after the accumulated back porch
+    let bpcr = unsafe { reg_read((LCD_TFT_BASE + LTDC_BPCR) as *mut u32) };
+    let h_start = ((bpcr >> 16) & 0xFFF) + 1;
+    let v_start = (bpcr & 0x7FF) + 1;
+
+    reg_set_bit(ltdc_layer_reg(layer, LTDC_LXCR), LTDC_LXCR_LEN_POS, false);
+    reg_set_val(
+        ltdc_layer_reg(layer, LTDC_LXWHPCR),
+        ((h_start + width - 1) << 16) | h_start,
+    );
+    reg_set_val(
+        ltdc_layer_reg(layer, LTDC_LXWVPCR),
+        ((v_start + height - 1) << 16) | v_start,
+    );
+    reg_set_val(ltdc_layer_reg(layer, LTDC_LXPFCR), format as u32);
+    reg_set_val(ltdc_layer_reg(layer, LTDC_LXCACR), 0xFF); // Opaque
+    reg_set_val(ltdc_layer_reg(layer, LTDC_LXCFBAR), addr);
+
+    // Pitch (bits 28:16) and line length + 3 (bits 12:0), in bytes
+    let line_bytes = width * format.bytes_per_pixel();
+    reg_set_val(
+        ltdc_layer_reg(layer, LTDC_LXCFBLR),
+        (line_bytes << 16) | (line_bytes + 3),
+    );
+    reg_set_val(ltdc_layer_reg(layer, LTDC_LXCFBLNR), height);
+
+    reg_set_bit(ltdc_layer_reg(layer, LTDC_LXCR), LTDC_LXCR_LEN_POS, true);
+
+    // Layer registers are shadowed: reload them now
+    reg_set_val(
+        (LCD_TFT_BASE + LTDC_SRCR) as *mut u32,
+        1 << LTDC_SRCR_IMR_POS,
+    );
+}
+
+// -----------------------------------------------------------------------------
+// Enable / Disable
+// -----------------------------------------------------------------------------
+/// Starts the LTDC: sync signals and pixel data are output from now on.
+pub fn ltdc_enable() {
+    reg_set_bit(
+        (LCD_TFT_BASE + LTDC_GCR) as *mut u32,
+        LTDC_GCR_LTDCEN_POS,
+        true,
+    );
+}
+
+/// Stops the LTDC.
+pub fn ltdc_disable() {
+    reg_set_bit(
+        (LCD_TFT_BASE + LTDC_GCR) as *mut u32,
+        LTDC_GCR_LTDCEN_POS,
+        false,
+    );
+}
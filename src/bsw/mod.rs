@@ -3,11 +3,41 @@
 //! Groups low-level CPU/MCU register access and startup code used by the application.
 //! Target: STM32F429ZI (Cortex-M4F)
 
+pub mod adc;
+pub mod can;
+pub mod context_cm4;
+pub mod crc;
+pub mod critical_cm4;
+pub mod dac;
+pub mod dcmi;
+pub mod dma;
+pub mod dwt_cm4;
+pub mod error;
+pub mod exti;
+pub mod fault_cm4;
 pub mod flash;
+pub mod fmc;
+pub mod fpu_cm4;
 pub mod gpio;
+pub mod i2c;
+pub mod i2s;
+pub mod itm_cm4;
+pub mod ltdc;
+pub mod mpu_cm4;
+pub mod nvic_cm4;
+pub mod otg;
 pub mod pwr;
 pub mod rcc;
 pub mod reg_cpu_cortex_m4;
 pub mod reg_mcu_stm32f429zi;
 pub mod reg_utils;
+pub mod rng;
+pub mod rtc;
+pub mod spi;
+#[cfg(target_arch = "arm")]
 pub mod startup_stm32f429zi;
+pub mod sys_cm4;
+pub mod syscfg;
+pub mod tim;
+pub mod uart;
+pub mod wdg;
@@ -1,3 +1,271 @@
 // -----------------------------------------------------------------------------
 // STM32F429 MPU (Memory Protection Unit) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides helper functions to configure the 8 regions of the
+// Cortex-M4 MPU.
+//
+// Region rules (ARMv7-M PMSA)
+// - Size is a power of two from 32 bytes to 4 GB, and the base address must
+//   be aligned to the size.
+// - Regions of 256 bytes or more are split into 8 equal subregions; each set
+//   bit in SRD disables one of them (the access then falls through to a
+//   lower-numbered region or the background map).
+// - Where regions overlap, the highest region number wins.
+//
+// Example: catch a stack overflow by making the lowest 256 bytes of the
+// SRAM1 stack area inaccessible
+// ```
+// mpu_configure_region(
+//     7,
+//     STACK_GUARD_BASE,
+//     MpuSize::Size256B,
+//     MpuAttributes::new().access(MpuAccess::NoAccess).execute_never(true),
+// );
+// mpu_enable(true);
+// ```
+//
+// Reference: Cortex-M4 Devices Generic User Guide, section 4.5 (MPU)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::reg_cpu_cortex_m4::*; // Cortex-M4 core register addresses
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// MPU Bit Positions
+// -----------------------------------------------------------------------------
+// MPU_CTRL
+pub const MPU_CTRL_ENABLE_POS: u32 = 0; // MPU enable
+pub const MPU_CTRL_HFNMIENA_POS: u32 = 1; // MPU enabled during HardFault/NMI
+pub const MPU_CTRL_PRIVDEFENA_POS: u32 = 2; // Background map for privileged code
+
+// MPU_RASR
+pub const MPU_RASR_ENABLE_POS: u32 = 0; // Region enable
+pub const MPU_RASR_SIZE_POS: u32 = 1; // Region size, 2^(SIZE + 1) bytes (5 bits)
+pub const MPU_RASR_SRD_POS: u32 = 8; // Subregion disable (8 bits)
+pub const MPU_RASR_B_POS: u32 = 16; // Bufferable
+pub const MPU_RASR_C_POS: u32 = 17; // Cacheable
+pub const MPU_RASR_S_POS: u32 = 18; // Shareable
+pub const MPU_RASR_TEX_POS: u32 = 19; // Type extension (3 bits)
+pub const MPU_RASR_AP_POS: u32 = 24; // Access permission (3 bits)
+pub const MPU_RASR_XN_POS: u32 = 28; // Execute never
+
+/// Number of MPU regions on the Cortex-M4.
+pub const MPU_REGION_COUNT: u32 = 8;
+
+// -----------------------------------------------------------------------------
+// Region Size and Attributes
+// -----------------------------------------------------------------------------
+/// Region size; the value is the RASR SIZE field (log2(bytes) - 1).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MpuSize {
+    Size32B = 4,
+    Size64B = 5,
+    Size128B = 6,
+    Size256B = 7,
+    Size512B = 8,
+    Size1KB = 9,
+    Size2KB = 10,
+    Size4KB = 11,
+    Size8KB = 12,
+    Size16KB = 13,
+    Size32KB = 14,
+    Size64KB = 15,
+    Size128KB = 16,
+    Size256KB = 17,
+    Size512KB = 18,
+    Size1MB = 19,
+    Size2MB = 20,
+    Size4MB = 21,
+    Size8MB = 22,
+    Size16MB = 23,
+    Size32MB = 24,
+    Size64MB = 25,
+    Size128MB = 26,
+    Size256MB = 27,
+    Size512MB = 28,
+    Size1GB = 29,
+    Size2GB = 30,
+    Size4GB = 31,
+}
+
+impl MpuSize {
+    /// Returns the region size in bytes.
+    pub const fn bytes(self) -> u64 {
+        1 << (self as u32 + 1)
+    }
+}
+
+/// Access permissions (RASR AP field) for privileged / unprivileged code.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MpuAccess {
+    /// No access at all
+    NoAccess = 0b000,
+    /// Privileged read/write, unprivileged no access
+    PrivRw = 0b001,
+    /// Privileged read/write, unprivileged read-only
+    PrivRwUserRo = 0b010,
+    /// Read/write for everyone
+    FullAccess = 0b011,
+    /// Privileged read-only, unprivileged no access
+    PrivRo = 0b101,
+    /// Read-only for everyone
+    ReadOnly = 0b110,
+}
+
+/// Region attributes, built with chained setters.
+///
+/// Defaults: full access, executable, normal memory (TEX = 0, C = 1, B = 0,
+/// not shareable: write-through, as for internal flash and SRAM), all
+/// subregions enabled.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MpuAttributes {
+    pub access: MpuAccess,
+    /// Instruction fetches fault when true
+    pub execute_never: bool,
+    /// TEX, C and B memory type bits (e.g. device memory: 0, false, true)
+    pub tex: u8,
+    pub cacheable: bool,
+    pub bufferable: bool,
+    pub shareable: bool,
+    /// Subregion disable mask (bit n disables subregion n)
+    pub subregion_disable: u8,
+}
+
+impl MpuAttributes {
+    /// Creates the default attributes.
+    pub const fn new() -> Self {
+        MpuAttributes {
+            access: MpuAccess::FullAccess,
+            execute_never: false,
+            tex: 0,
+            cacheable: true,
+            bufferable: false,
+            shareable: false,
+            subregion_disable: 0,
+        }
+    }
+
+    /// Sets the access permissions.
+    pub const fn access(mut self, access: MpuAccess) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Forbids (true) or allows (false) instruction fetches.
+    pub const fn execute_never(mut self, execute_never: bool) -> Self {
+        self.execute_never = execute_never;
+        self
+    }
+
+    /// Sets the memory type bits (TEX, C, B) and shareability.
+    pub const fn memory_type(
+        mut self,
+        tex: u8,
+        cacheable: bool,
+        bufferable: bool,
+        shareable: bool,
+    ) -> Self {
+        self.tex = tex;
+        self.cacheable = cacheable;
+        self.bufferable = bufferable;
+        self.shareable = shareable;
+        self
+    }
+
+    /// Sets the subregion disable mask (only for regions of 256 bytes or more).
+    pub const fn subregion_disable(mut self, mask: u8) -> Self {
+        self.subregion_disable = mask;
+        self
+    }
+
+    /// Returns the RASR attribute bits (everything but SIZE and ENABLE).
+    const fn rasr_bits(self) -> u32 {
+        ((self.execute_never as u32) << MPU_RASR_XN_POS)
+            | ((self.access as u32) << MPU_RASR_AP_POS)
+            | (((self.tex & 0b111) as u32) << MPU_RASR_TEX_POS)
+            | ((self.shareable as u32) << MPU_RASR_S_POS)
+            | ((self.cacheable as u32) << MPU_RASR_C_POS)
+            | ((self.bufferable as u32) << MPU_RASR_B_POS)
+            | ((self.subregion_disable as u32) << MPU_RASR_SRD_POS)
+    }
+}
+
+impl Default for MpuAttributes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Region Configuration
+// -----------------------------------------------------------------------------
+/// Programs and enables one MPU region (RNR, RBAR, RASR).
+///
+/// Reconfiguring a region that covers the code or stack in use is only safe
+/// with the MPU disabled.
+///
+/// # Arguments
+/// * `region` - Region number (0..7, higher numbers take priority)
+/// * `base`   - Region start address, aligned to `size`
+/// * `size`   - Region size
+/// * `attrs`  - Access permissions and memory attributes
+pub fn mpu_configure_region(region: u32, base: u32, size: MpuSize, attrs: MpuAttributes) {
+    assert!(region < MPU_REGION_COUNT, "MPU region must be 0..7");
+    assert!(
+        (base as u64).is_multiple_of(size.bytes()),
+        "MPU region base must be aligned to its size"
+    );
+    assert!(
+        attrs.subregion_disable == 0 || size as u32 >= MpuSize::Size256B as u32,
+        "subregions need a region of at least 256 bytes"
+    );
+
+    reg_set_val(MPU_RNR_BASE as *mut u32, region);
+    reg_set_val(MPU_RBAR_BASE as *mut u32, base);
+    reg_set_val(
+        MPU_RASR_BASE as *mut u32,
+        attrs.rasr_bits() | ((size as u32) << MPU_RASR_SIZE_POS) | (1 << MPU_RASR_ENABLE_POS),
+    );
+}
+
+/// Disables one MPU region.
+///
+/// # Arguments
+/// * `region` - Region number (0..7)
+pub fn mpu_disable_region(region: u32) {
+    assert!(region < MPU_REGION_COUNT, "MPU region must be 0..7");
+
+    reg_set_val(MPU_RNR_BASE as *mut u32, region);
+    reg_set_val(MPU_RASR_BASE as *mut u32, 0);
+}
+
+// -----------------------------------------------------------------------------
+// Enable / Disable
+// -----------------------------------------------------------------------------
+/// Enables the MPU.
+///
+/// # Arguments
+/// * `privileged_default` - Keep the default memory map as background region
+///   for privileged code (PRIVDEFENA); otherwise every access outside a
+///   configured region faults
+pub fn mpu_enable(privileged_default: bool) {
+    reg_set_val(
+        MPU_CTRL_BASE as *mut u32,
+        ((privileged_default as u32) << MPU_CTRL_PRIVDEFENA_POS) | (1 << MPU_CTRL_ENABLE_POS),
+    );
+    // The new settings apply to the instructions that follow
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        core::arch::asm!("dsb", "isb");
+    }
+}
+
+/// Disables the MPU (default memory map for everything).
+pub fn mpu_disable() {
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        core::arch::asm!("dmb");
+    }
+    reg_set_val(MPU_CTRL_BASE as *mut u32, 0);
+}
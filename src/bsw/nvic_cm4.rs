@@ -1,3 +1,144 @@
 // -----------------------------------------------------------------------------
 // STM32F429 NVIC (Nested Vectored Interrupt Controller) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides helper functions to enable, disable and clear the
+// external (peripheral) interrupts of the STM32F429 in the Cortex-M4 NVIC.
+//
+// Each IRQ number maps to one bit in a bank of 32-bit registers:
+// register index = irq / 32, bit = irq % 32. The ISER/ICER/ISPR/ICPR
+// registers are write-1-to-act, so no read-modify-write is needed.
+//
+// Priorities: the STM32F429 implements the upper 4 bits of each 8-bit
+// priority field (16 levels, 0 = highest). AIRCR.PRIGROUP splits them into
+// preemption priority (which IRQ may interrupt another) and sub-priority
+// (order among pending IRQs of the same preemption level):
+//   PRIGROUP 3: 4 preempt bits, 0 sub bits (16 / 1)
+//   PRIGROUP 4: 3 / 1, 5: 2 / 2, 6: 1 / 3, 7: 0 / 4
+// PRIGROUP 0..2 behave like 3 on this device.
+//
+// Reference: Cortex-M4 Devices Generic User Guide, section 4.2 (NVIC)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::reg_cpu_cortex_m4::*; // Cortex-M4 core register addresses
+use crate::bsw::reg_mcu_stm32f429zi::IRQn; // STM32F429 interrupt numbers
+use crate::bsw::reg_utils::*; // Register access helper functions
+use crate::bsw::sys_cm4::{AIRCR_PRIGROUP_POS, AIRCR_VECTKEY, AIRCR_VECTKEY_POS};
+
+/// Number of implemented priority bits (upper bits of each IPR byte).
+pub const NVIC_PRIO_BITS: u32 = 4;
+
+/// Returns the address of the bank register holding `irq` and its bit mask.
+fn nvic_bank(bank_base: u32, irq: IRQn) -> (*mut u32, u32) {
+    let irq = irq as u32;
+    ((bank_base + (irq / 32) * 4) as *mut u32, 1 << (irq % 32))
+}
+
+// -----------------------------------------------------------------------------
+// Enable / Disable
+// -----------------------------------------------------------------------------
+/// Enables an interrupt in the NVIC (ISER).
+///
+/// # Arguments
+/// * `irq` - The interrupt number (e.g., `IRQn::TIM2`)
+pub fn nvic_enable_irq(irq: IRQn) {
+    let (addr, mask) = nvic_bank(NVIC_ISER_BASE, irq);
+    reg_set_val(addr, mask);
+}
+
+/// Disables an interrupt in the NVIC (ICER).
+///
+/// # Arguments
+/// * `irq` - The interrupt number
+pub fn nvic_disable_irq(irq: IRQn) {
+    let (addr, mask) = nvic_bank(NVIC_ICER_BASE, irq);
+    reg_set_val(addr, mask);
+}
+
+/// Returns true if an interrupt is enabled in the NVIC.
+pub fn nvic_is_enabled(irq: IRQn) -> bool {
+    let (addr, mask) = nvic_bank(NVIC_ISER_BASE, irq);
+    unsafe { reg_read(addr) & mask != 0 }
+}
+
+// -----------------------------------------------------------------------------
+// Pending State
+// -----------------------------------------------------------------------------
+/// Clears the pending state of an interrupt (ICPR).
+///
+/// Useful before enabling an IRQ whose source may have fired earlier.
+pub fn nvic_clear_pending(irq: IRQn) {
+    let (addr, mask) = nvic_bank(NVIC_ICPR_BASE, irq);
+    reg_set_val(addr, mask);
+}
+
+/// Sets an interrupt pending from software (ISPR).
+pub fn nvic_set_pending(irq: IRQn) {
+    let (addr, mask) = nvic_bank(NVIC_ISPR_BASE, irq);
+    reg_set_val(addr, mask);
+}
+
+// -----------------------------------------------------------------------------
+// Priorities
+// -----------------------------------------------------------------------------
+/// Sets the priority grouping (AIRCR.PRIGROUP).
+///
+/// Set it once at startup, before assigning priorities: the meaning of every
+/// encoded priority depends on it.
+///
+/// # Arguments
+/// * `group` - PRIGROUP value (0..7), see the table at the top of this file
+pub fn nvic_set_priority_grouping(group: u32) {
+    assert!(group < 8, "PRIGROUP must be 0..7");
+    // VECTKEY must accompany every write; the other writable bits
+    // (SYSRESETREQ, VECTCLRACTIVE, VECTRESET) must be written as 0
+    reg_set_val(
+        AIRCR_BASE as *mut u32,
+        (AIRCR_VECTKEY << AIRCR_VECTKEY_POS) | (group << AIRCR_PRIGROUP_POS),
+    );
+}
+
+/// Returns the current priority grouping (AIRCR.PRIGROUP, 0..7).
+pub fn nvic_get_priority_grouping() -> u32 {
+    reg_read_bits(AIRCR_BASE as *mut u32, AIRCR_PRIGROUP_POS, 3)
+}
+
+/// Returns the number of (preempt, sub) priority bits for a grouping.
+pub const fn nvic_priority_bits(group: u32) -> (u32, u32) {
+    let preempt = if 7 - group < NVIC_PRIO_BITS {
+        7 - group
+    } else {
+        NVIC_PRIO_BITS
+    };
+    (preempt, NVIC_PRIO_BITS - preempt)
+}
+
+/// Encodes a (preempt, sub) pair into an 8-bit priority register value.
+///
+/// Values too large for the grouping's fields are truncated.
+///
+/// # Arguments
+/// * `group`   - PRIGROUP value, e.g. `nvic_get_priority_grouping()`
+/// * `preempt` - Preemption priority (0 = highest)
+/// * `sub`     - Sub-priority (0 = highest)
+///
+/// # Returns
+/// * The value for `nvic_set_priority`, e.g. group 5, preempt 1, sub 2:
+///   `0b01_10 << 4 = 0x60`
+pub const fn nvic_encode_priority(group: u32, preempt: u32, sub: u32) -> u8 {
+    let (preempt_bits, sub_bits) = nvic_priority_bits(group);
+    let preempt = preempt & ((1 << preempt_bits) - 1);
+    let sub = sub & ((1 << sub_bits) - 1);
+    (((preempt << sub_bits) | sub) << (8 - NVIC_PRIO_BITS)) as u8
+}
+
+/// Sets the priority of an interrupt (one IPR byte).
+///
+/// # Arguments
+/// * `irq`      - The interrupt number
+/// * `priority` - 8-bit priority, only the upper `NVIC_PRIO_BITS` are used
+///   (see `nvic_encode_priority`)
+pub fn nvic_set_priority(irq: IRQn, priority: u8) {
+    // IPR is byte-accessible: a byte write leaves the neighbouring IRQs alone
+    unsafe { reg_write_u8((NVIC_IPR_BASE + irq as u32) as *mut u8, priority) };
+}
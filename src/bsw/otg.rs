@@ -1,3 +1,320 @@
 // -----------------------------------------------------------------------------
 // STM32F429 OTG (On-The-Go) utilities
 // -----------------------------------------------------------------------------
+//
+// This module brings a USB OTG core up in full-speed device mode, far enough
+// for the host to see the device connect, reset the bus and finish speed
+// enumeration. Endpoint handling (control transfers, descriptors) is not
+// part of it.
+//
+// Both cores have the same register layout:
+// - OTG_FS (`USB_OTG_FS_BASE`): PA11 (DM) / PA12 (DP), AF10.
+// - OTG_HS (`USB_OTG_HS_BASE`) with its embedded full-speed PHY:
+//   PB14 (DM) / PB15 (DP), AF12. This is the core wired to the micro-USB
+//   connector (CN6) of the STM32F429I-DISC1.
+//
+// The cores need an exact 48 MHz clock (PLL48CK), e.g. from
+// `rcc_configure_pll_168mhz`; at 180 MHz SYSCLK there is none.
+//
+// Reference: STM32F429 Reference Manual, section 34 (USB on-the-go full-speed)
+// -----------------------------------------------------------------------------
+
+use core::hint;
+
+use crate::bsw::error::{BswError, Result};
+use crate::bsw::gpio::*; // GPIO alternate function configuration
+use crate::bsw::rcc::*; // RCC clock enable
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// OTG Core Global Register Offsets (relative to USB_OTG_xS_BASE)
+// -----------------------------------------------------------------------------
+pub const OTG_GOTGCTL: u32 = 0x000; // OTG control and status register
+pub const OTG_GOTGINT: u32 = 0x004; // OTG interrupt register
+pub const OTG_GAHBCFG: u32 = 0x008; // AHB configuration register
+pub const OTG_GUSBCFG: u32 = 0x00C; // USB configuration register
+pub const OTG_GRSTCTL: u32 = 0x010; // Reset register
+pub const OTG_GINTSTS: u32 = 0x014; // Core interrupt register
+pub const OTG_GINTMSK: u32 = 0x018; // Interrupt mask register
+pub const OTG_GRXSTSR: u32 = 0x01C; // Receive status debug read register
+pub const OTG_GRXSTSP: u32 = 0x020; // Receive status read and pop register
+pub const OTG_GRXFSIZ: u32 = 0x024; // Receive FIFO size register
+pub const OTG_DIEPTXF0: u32 = 0x028; // Endpoint 0 transmit FIFO size register
+pub const OTG_GCCFG: u32 = 0x038; // General core configuration register
+pub const OTG_CID: u32 = 0x03C; // Core ID register
+pub const OTG_DIEPTXF1: u32 = 0x104; // Endpoint 1 transmit FIFO size (+4 per endpoint)
+
+// -----------------------------------------------------------------------------
+// OTG Device-Mode Register Offsets
+// -----------------------------------------------------------------------------
+pub const OTG_DCFG: u32 = 0x800; // Device configuration register
+pub const OTG_DCTL: u32 = 0x804; // Device control register
+pub const OTG_DSTS: u32 = 0x808; // Device status register
+pub const OTG_DIEPMSK: u32 = 0x810; // Device IN endpoint common interrupt mask
+pub const OTG_DOEPMSK: u32 = 0x814; // Device OUT endpoint common interrupt mask
+pub const OTG_DAINT: u32 = 0x818; // Device all endpoints interrupt register
+pub const OTG_DAINTMSK: u32 = 0x81C; // Device all endpoints interrupt mask
+pub const OTG_DIEPCTL0: u32 = 0x900; // IN endpoint 0 control (+0x20 per endpoint)
+pub const OTG_DIEPTSIZ0: u32 = 0x910; // IN endpoint 0 transfer size
+pub const OTG_DOEPCTL0: u32 = 0xB00; // OUT endpoint 0 control (+0x20 per endpoint)
+pub const OTG_DOEPTSIZ0: u32 = 0xB10; // OUT endpoint 0 transfer size
+pub const OTG_PCGCCTL: u32 = 0xE00; // Power and clock gating control register
+pub const OTG_FIFO0: u32 = 0x1000; // Endpoint 0 data FIFO (+0x1000 per endpoint)
+
+// -----------------------------------------------------------------------------
+// OTG Register Bits
+// -----------------------------------------------------------------------------
+pub const OTG_GAHBCFG_GINTMSK_POS: u32 = 0; // Global interrupt enable
+pub const OTG_GUSBCFG_PHYSEL_POS: u32 = 6; // Full-speed serial transceiver select
+pub const OTG_GUSBCFG_TRDT_POS: u32 = 10; // USB turnaround time (4 bits)
+pub const OTG_GUSBCFG_FDMOD_POS: u32 = 30; // Force device mode
+pub const OTG_GRSTCTL_CSRST_POS: u32 = 0; // Core soft reset
+pub const OTG_GRSTCTL_AHBIDL_POS: u32 = 31; // AHB master idle
+pub const OTG_GINTSTS_RXFLVL_POS: u32 = 4; // RX FIFO non-empty
+pub const OTG_GINTSTS_USBSUSP_POS: u32 = 11; // USB suspend
+pub const OTG_GINTSTS_USBRST_POS: u32 = 12; // USB reset
+pub const OTG_GINTSTS_ENUMDNE_POS: u32 = 13; // Enumeration done
+pub const OTG_GINTSTS_IEPINT_POS: u32 = 18; // IN endpoint interrupt
+pub const OTG_GINTSTS_OEPINT_POS: u32 = 19; // OUT endpoint interrupt
+pub const OTG_GCCFG_PWRDWN_POS: u32 = 16; // 1 = transceiver active (power down off)
+pub const OTG_GCCFG_VBUSBSEN_POS: u32 = 19; // VBUS sensing "B" device
+pub const OTG_GCCFG_NOVBUSSENS_POS: u32 = 21; // VBUS sensing disable
+pub const OTG_DCFG_DSPD_POS: u32 = 0; // Device speed (2 bits)
+pub const OTG_DCFG_DAD_POS: u32 = 4; // Device address (7 bits)
+pub const OTG_DCTL_SDIS_POS: u32 = 1; // Soft disconnect (DP pull-up off)
+pub const OTG_DSTS_ENUMSPD_POS: u32 = 1; // Enumerated speed (2 bits)
+
+/// DCFG.DSPD / DSTS.ENUMSPD value for full speed on the embedded PHY.
+const OTG_SPEED_FULL_EMBEDDED_PHY: u32 = 0b11;
+
+/// Turnaround time for an AHB clock above 32 MHz (RM0090 Table 209).
+const OTG_TRDT_AHB_ABOVE_32MHZ: u32 = 0x6;
+
+/// FIFO RAM split in 32-bit words: RX FIFO, then the EP0 TX FIFO.
+const OTG_RX_FIFO_WORDS: u32 = 128;
+const OTG_EP0_TX_FIFO_WORDS: u32 = 64;
+
+/// Timeout for the AHB idle and soft reset handshakes.
+const OTG_RESET_TIMEOUT: u32 = 200_000;
+
+/// RCC_AHB1LPENR bit of the (unused) external ULPI PHY clock.
+const RCC_AHB1LPENR_OTGHSULPILPEN_POS: u32 = 30;
+
+/// USB OTG core and its full-speed pins.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OtgCore {
+    /// OTG_FS on PA11/PA12
+    Fs,
+    /// OTG_HS on PB14/PB15 with the embedded full-speed PHY (DISC1 connector)
+    HsInFsMode,
+}
+
+impl OtgCore {
+    /// Base address of the core's registers.
+    pub const fn base(self) -> u32 {
+        match self {
+            OtgCore::Fs => USB_OTG_FS_BASE,
+            OtgCore::HsInFsMode => USB_OTG_HS_BASE,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Core and Device Initialization
+// -----------------------------------------------------------------------------
+/// Enables the core's clock and configures its DM/DP pins.
+fn otg_clock_and_pins_init(core: OtgCore) {
+    match core {
+        OtgCore::Fs => {
+            rcc_enable_peripheral(RccBus::Ahb2, 7); // OTGFSEN
+            rcc_enable_gpio_clock(GPIOA_BASE);
+            for pin in [GPIO_PIN_11, GPIO_PIN_12] {
+                gpio_config_af_full(
+                    GPIOA_BASE,
+                    pin,
+                    10,
+                    GpioType::PushPull,
+                    PinSpeed::VeryHigh,
+                    GpioPull::None,
+                );
+            }
+        }
+        OtgCore::HsInFsMode => {
+            rcc_enable_peripheral(RccBus::Ahb1, 29); // OTGHSEN
+            // Without an external PHY the ULPI clock must stay off in Sleep
+            // mode, or the core stops working after a WFI
+            reg_set_bit(
+                (RCC_BASE + RCC_AHB1LPENR) as *mut u32,
+                RCC_AHB1LPENR_OTGHSULPILPEN_POS,
+                false,
+            );
+            rcc_enable_gpio_clock(GPIOB_BASE);
+            for pin in [GPIO_PIN_14, GPIO_PIN_15] {
+                gpio_config_af_full(
+                    GPIOB_BASE,
+                    pin,
+                    12,
+                    GpioType::PushPull,
+                    PinSpeed::VeryHigh,
+                    GpioPull::None,
+                );
+            }
+        }
+    }
+}
+
+/// Resets the core logic (GRSTCTL.CSRST) once the AHB master is idle.
+fn otg_core_soft_reset(base: u32) -> Result<()> {
+    let grstctl_addr = (base + OTG_GRSTCTL) as *mut u32;
+    if !reg_wait_bit(
+        grstctl_addr,
+        OTG_GRSTCTL_AHBIDL_POS,
+        true,
+        OTG_RESET_TIMEOUT,
+    ) {
+        return Err(BswError::Timeout);
+    }
+    reg_set_bit(grstctl_addr, OTG_GRSTCTL_CSRST_POS, true);
+    if !reg_wait_bit(
+        grstctl_addr,
+        OTG_GRSTCTL_CSRST_POS,
+        false,
+        OTG_RESET_TIMEOUT,
+    ) {
+        return Err(BswError::Timeout);
+    }
+    Ok(())
+}
+
+/// Initializes a USB OTG core as full-speed device and connects to the bus.
+///
+/// Steps (RM0090 34.17.1 and 34.17.3):
+/// 1. Core clock and DM/DP pins, full-speed PHY select, core soft reset.
+/// 2. Transceiver on (GCCFG.PWRDWN), VBUS sensing off (the DISC1 does not
+///    route VBUS to the FS core; the device is assumed to be bus powered).
+/// 3. Forced device mode (takes effect after 25 ms), turnaround time.
+/// 4. Device speed, FIFO sizes, interrupt masks for bus reset, enumeration
+///    done and suspend, then the DP pull-up (DCTL.SDIS cleared).
+///
+/// Afterwards the host detects the device; poll `otg_take_bus_reset` and
+/// `otg_take_enum_done` (or enable `IRQn::OTG_FS`/`IRQn::OTG_HS`) to follow
+/// the enumeration.
+///
+/// # Arguments
+/// * `core` - Which OTG core (and pins) to use
+///
+/// # Returns
+/// * `Ok(())` once the device is connected
+/// * `Err(BswError::Timeout)` if the core soft reset did not complete
+///   (typically the 48 MHz clock is missing)
+pub fn otg_device_init(core: OtgCore) -> Result<()> {
+    let base = core.base();
+    otg_clock_and_pins_init(core);
+
+    // Global interrupts off while configuring
+    reg_set_bit(
+        (base + OTG_GAHBCFG) as *mut u32,
+        OTG_GAHBCFG_GINTMSK_POS,
+        false,
+    );
+
+    let gusbcfg_addr = (base + OTG_GUSBCFG) as *mut u32;
+    reg_set_bit(gusbcfg_addr, OTG_GUSBCFG_PHYSEL_POS, true);
+    otg_core_soft_reset(base)?;
+
+    let gccfg_addr = (base + OTG_GCCFG) as *mut u32;
+    reg_set_bit(gccfg_addr, OTG_GCCFG_PWRDWN_POS, true);
+    reg_set_bit(gccfg_addr, OTG_GCCFG_VBUSBSEN_POS, false);
+    reg_set_bit(gccfg_addr, OTG_GCCFG_NOVBUSSENS_POS, true);
+
+    // Force device mode, wait the required 25 ms
+    reg_set_bit(gusbcfg_addr, OTG_GUSBCFG_FDMOD_POS, true);
+    reg_set_bits(
+        gusbcfg_addr,
+        OTG_TRDT_AHB_ABOVE_32MHZ,
+        OTG_GUSBCFG_TRDT_POS,
+        4,
+    );
+    for _ in 0..(rcc_get_hclk_hz() / 1_000) * 25 {
+        hint::spin_loop();
+    }
+
+    // Restart the PHY clock, full speed, address 0
+    reg_set_val((base + OTG_PCGCCTL) as *mut u32, 0);
+    let dcfg_addr = (base + OTG_DCFG) as *mut u32;
+    reg_set_bits(dcfg_addr, OTG_SPEED_FULL_EMBEDDED_PHY, OTG_DCFG_DSPD_POS, 2);
+    reg_set_bits(dcfg_addr, 0, OTG_DCFG_DAD_POS, 7);
+
+    // FIFO RAM: RX FIFO first, EP0 TX FIFO behind it (start << 16 | depth)
+    reg_set_val((base + OTG_GRXFSIZ) as *mut u32, OTG_RX_FIFO_WORDS);
+    reg_set_val(
+        (base + OTG_DIEPTXF0) as *mut u32,
+        (OTG_EP0_TX_FIFO_WORDS << 16) | OTG_RX_FIFO_WORDS,
+    );
+
+    // Clear pending interrupts (rc_w1), unmask the enumeration events
+    reg_set_val((base + OTG_GINTSTS) as *mut u32, 0xFFFF_FFFF);
+    reg_set_val(
+        (base + OTG_GINTMSK) as *mut u32,
+        (1 << OTG_GINTSTS_USBRST_POS)
+            | (1 << OTG_GINTSTS_ENUMDNE_POS)
+            | (1 << OTG_GINTSTS_USBSUSP_POS),
+    );
+    reg_set_bit(
+        (base + OTG_GAHBCFG) as *mut u32,
+        OTG_GAHBCFG_GINTMSK_POS,
+        true,
+    );
+
+    // Connect: enable the DP pull-up
+    reg_set_bit((base + OTG_DCTL) as *mut u32, OTG_DCTL_SDIS_POS, false);
+    Ok(())
+}
+
+/// Disconnects from the bus (DP pull-up off); the host sees a detach.
+pub fn otg_device_disconnect(core: OtgCore) {
+    reg_set_bit(
+        (core.base() + OTG_DCTL) as *mut u32,
+        OTG_DCTL_SDIS_POS,
+        true,
+    );
+}
+
+// -----------------------------------------------------------------------------
+// Enumeration Events
+// -----------------------------------------------------------------------------
+/// Returns true (and clears the flag) if the host reset the bus.
+///
+/// A reset is the first thing the host does after it saw the device
+/// connect; the device address then goes back to 0.
+pub fn otg_take_bus_reset(core: OtgCore) -> bool {
+    let gintsts_addr = (core.base() + OTG_GINTSTS) as *mut u32;
+    let pending = reg_read_bit(gintsts_addr, OTG_GINTSTS_USBRST_POS);
+    if pending {
+        reg_set_val(gintsts_addr, 1 << OTG_GINTSTS_USBRST_POS);
+        reg_set_bits((core.base() + OTG_DCFG) as *mut u32, 0, OTG_DCFG_DAD_POS, 7);
+    }
+    pending
+}
+
+/// Returns true (and clears the flag) once speed enumeration finished.
+///
+/// From here on the host sends SETUP packets to endpoint 0.
+pub fn otg_take_enum_done(core: OtgCore) -> bool {
+    let gintsts_addr = (core.base() + OTG_GINTSTS) as *mut u32;
+    let pending = reg_read_bit(gintsts_addr, OTG_GINTSTS_ENUMDNE_POS);
+    if pending {
+        reg_set_val(gintsts_addr, 1 << OTG_GINTSTS_ENUMDNE_POS);
+    }
+    pending
+}
+
+/// Returns true if the enumerated speed is full speed (DSTS.ENUMSPD).
+pub fn otg_is_full_speed(core: OtgCore) -> bool {
+    reg_read_bits(
+        (core.base() + OTG_DSTS) as *mut u32,
+        OTG_DSTS_ENUMSPD_POS,
+        2,
+    ) == OTG_SPEED_FULL_EMBEDDED_PHY
+}
@@ -8,6 +8,15 @@
 // Reference: STM32F429 Reference Manual, section 5 (PWR)
 // -----------------------------------------------------------------------------
 
+#[cfg(target_arch = "arm")]
+use core::arch::asm;
+
+use crate::bsw::error::{BswError, Result};
+use crate::bsw::rcc::{
+    RCC_CFGR, RCC_CFGR_SWS_PLL, RCC_CFGR_SWS_POS, RCC_CR, RCC_CR_PLLON_POS, RccBus,
+    rcc_enable_peripheral, rcc_enable_power_clock, rcc_get_sysclk_hz,
+};
+use crate::bsw::reg_cpu_cortex_m4::SCR_BASE;
 use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
 use crate::bsw::reg_utils::*; // Register access helper functions
 
@@ -19,6 +28,42 @@ use crate::bsw::reg_utils::*; // Register access helper functions
 pub const PWR_CR: u32 = 0x00; // Power control register
 pub const PWR_CSR: u32 = 0x04; // Power control/status register
 
+// Layout check (RM0090 5.5.3)
+const _: () = assert!(PWR_CR == 0x00 && PWR_CSR == PWR_CR + 4);
+
+// -----------------------------------------------------------------------------
+// PWR_CR Bit Positions
+// -----------------------------------------------------------------------------
+pub const PWR_CR_LPDS_POS: u32 = 0; // Low-power regulator in Stop mode
+pub const PWR_CR_PDDS_POS: u32 = 1; // Power-down deepsleep (1 = Standby)
+pub const PWR_CR_CWUF_POS: u32 = 2; // Clear wakeup flag
+pub const PWR_CR_CSBF_POS: u32 = 3; // Clear standby flag
+pub const PWR_CR_DBP_POS: u32 = 8; // Disable backup domain write protection
+pub const PWR_CR_FPDS_POS: u32 = 9; // Flash power-down in Stop mode
+pub const PWR_CR_VOS_POS: u32 = 14; // Regulator voltage scaling (2 bits)
+pub const PWR_CR_ODEN_POS: u32 = 16; // Over-drive enable
+pub const PWR_CR_ODSWEN_POS: u32 = 17; // Over-drive switching enable
+
+// -----------------------------------------------------------------------------
+// PWR_CSR Bit Positions
+// -----------------------------------------------------------------------------
+pub const PWR_CSR_WUF_POS: u32 = 0; // Wakeup flag
+pub const PWR_CSR_SBF_POS: u32 = 1; // Standby flag (woken from Standby)
+pub const PWR_CSR_BRR_POS: u32 = 3; // Backup regulator ready
+pub const PWR_CSR_EWUP_POS: u32 = 8; // Enable WKUP pin (PA0)
+pub const PWR_CSR_BRE_POS: u32 = 9; // Backup regulator enable
+
+/// SCR bit selecting deep sleep (Stop/Standby) instead of Sleep on WFI.
+const SCR_SLEEPDEEP_POS: u32 = 2;
+
+// VOS[1:0] values (note: the field value is NOT the scale number)
+pub const PWR_VOS_SCALE3: u32 = 0b01; // HCLK <= 120 MHz, lowest consumption
+pub const PWR_VOS_SCALE2: u32 = 0b10; // HCLK <= 144 MHz (168 MHz with over-drive)
+pub const PWR_VOS_SCALE1: u32 = 0b11; // HCLK <= 168 MHz (180 MHz with over-drive)
+
+/// Highest SYSCLK allowed with voltage scale 3.
+pub const PWR_SCALE3_MAX_HZ: u32 = 120_000_000;
+
 // -----------------------------------------------------------------------------
 // Voltage Regulator Scaling
 // -----------------------------------------------------------------------------
@@ -29,7 +74,7 @@ pub const PWR_CSR: u32 = 0x04; // Power control/status register
 ///
 /// # Arguments
 /// * `scale` - Voltage scale (0..3), written to VOS[1:0] (bits 15:14) of PWR_CR.
-///             See reference manual for allowed values.
+///   See reference manual for allowed values.
 pub fn pwr_set_regulator_voltage_scale(scale: u32) {
     // The voltage scale is set in bits 15:14 of the PWR_CR register.
     let pwr_cr_addr = (PWR_BASE + PWR_CR) as *mut u32;
@@ -42,20 +87,274 @@ pub fn pwr_set_regulator_voltage_scale(scale: u32) {
 /// Enables the overdrive mode required for 180 MHz operation.
 ///
 /// This function enables the overdrive mode and waits for it to become ready.
-/// It then enables the overdrive switching and does not return until the
-/// hardware is ready.
+/// It then enables the overdrive switching and waits until the regulator
+/// has switched.
 ///
 /// Reference: RM0090 Over-drive mode
-pub fn pwr_enable_overdrive() {
+///
+/// # Returns
+/// * `Ok(())` once over-drive is active
+/// * `Err(BswError::Timeout)` if ODRDY or ODSWRDY was not set in time; the
+///   system must then stay at or below 168 MHz
+pub fn pwr_enable_overdrive() -> Result<()> {
     // Enable the overdrive mode (ODEN bit, bit 16)
     let pwr_cr_addr = (PWR_BASE + PWR_CR) as *mut u32;
-    reg_set_bit(pwr_cr_addr, 16, true);
+    reg_set_bit(pwr_cr_addr, PWR_CR_ODEN_POS, true);
 
     // Wait until the overdrive is ready (ODRDY bit, bit 16 in PWR_CSR)
     let pwr_csr_addr = (PWR_BASE + PWR_CSR) as *mut u32;
-    let _ = reg_wait_bit(pwr_csr_addr, 16, true, 100_000);
+    if !reg_wait_bit(pwr_csr_addr, 16, true, 100_000) {
+        return Err(BswError::Timeout);
+    }
+
+    // Enable the overdrive switching (ODSWEN bit, bit 17) and wait for it
+    // (ODSWRDY bit, bit 17 in PWR_CSR)
+    reg_set_bit(pwr_cr_addr, PWR_CR_ODSWEN_POS, true);
+    if !reg_wait_bit(pwr_csr_addr, 17, true, 100_000) {
+        return Err(BswError::Timeout);
+    }
+
+    Ok(())
+}
 
-    // Enable the overdrive switching (ODSWEN bit, bit 17)
+// -----------------------------------------------------------------------------
+// Backup Domain Access
+// -----------------------------------------------------------------------------
+/// Enables write access to the backup domain (RTC, RTC backup registers,
+/// backup SRAM) by setting DBP (bit 8) in PWR_CR.
+///
+/// The PWR clock must be enabled first (`rcc_enable_power_clock`).
+pub fn pwr_enable_backup_access() {
     let pwr_cr_addr = (PWR_BASE + PWR_CR) as *mut u32;
-    reg_set_bit(pwr_cr_addr, 17, true);
+    reg_set_bit(pwr_cr_addr, PWR_CR_DBP_POS, true);
+}
+
+/// Returns true if backup domain write access (DBP) is enabled.
+pub fn pwr_backup_access_enabled() -> bool {
+    reg_read_bit((PWR_BASE + PWR_CR) as *mut u32, PWR_CR_DBP_POS)
+}
+
+// -----------------------------------------------------------------------------
+// Backup SRAM
+// -----------------------------------------------------------------------------
+// The 4 KB backup SRAM (BKPSRAM_BASE) is part of the backup domain: it is
+// writable only with DBP set and keeps its content through resets. With the
+// backup regulator on (BRE) it is also retained in Standby and, powered from
+// VBAT, while VDD is off. Without it the content is lost in those modes.
+//
+// The `.noinit` section of memory.x is placed in the same 4 KB; the
+// accessors below address the whole region, including those variables.
+
+/// Size of the backup SRAM in bytes.
+pub const BKPSRAM_SIZE: u32 = 4 * 1024;
+/// Size of the backup SRAM in 32-bit words.
+pub const BKPSRAM_WORDS: u32 = BKPSRAM_SIZE / 4;
+
+/// RCC_AHB1ENR bit of the backup SRAM interface clock.
+const RCC_AHB1ENR_BKPSRAMEN_POS: u32 = 18;
+
+/// Makes the backup SRAM readable and writable.
+///
+/// Enables the PWR clock, backup domain write access (DBP) and the backup
+/// SRAM interface clock, then sets up retention.
+///
+/// # Arguments
+/// * `retain` - Also keep the content in Standby and VBAT mode (backup
+///   regulator on, see `pwr_enable_backup_regulator`)
+///
+/// # Returns
+/// * `Ok(())` once the backup SRAM is accessible
+/// * `Err(BswError::Timeout)` if the backup regulator did not become ready
+pub fn pwr_backup_sram_init(retain: bool) -> Result<()> {
+    rcc_enable_power_clock();
+    pwr_enable_backup_access();
+    rcc_enable_peripheral(RccBus::Ahb1, RCC_AHB1ENR_BKPSRAMEN_POS);
+    pwr_enable_backup_regulator(retain)
+}
+
+/// Switches the backup regulator on or off (BRE) and waits for BRR.
+///
+/// The regulator keeps the backup SRAM content in Standby and VBAT mode, at
+/// the cost of a few uA. The setting itself lives in the backup domain and
+/// survives resets. Needs backup domain access (`pwr_enable_backup_access`).
+///
+/// # Returns
+/// * `Ok(())` once BRR follows BRE
+/// * `Err(BswError::Timeout)` if the regulator did not become ready
+pub fn pwr_enable_backup_regulator(enable: bool) -> Result<()> {
+    let pwr_csr_addr = (PWR_BASE + PWR_CSR) as *mut u32;
+    reg_set_bit(pwr_csr_addr, PWR_CSR_BRE_POS, enable);
+    if enable && !reg_wait_bit(pwr_csr_addr, PWR_CSR_BRR_POS, true, 100_000) {
+        return Err(BswError::Timeout);
+    }
+    Ok(())
+}
+
+/// Writes a word of the backup SRAM.
+///
+/// # Arguments
+/// * `offset` - Word offset (0..1023)
+/// * `val`    - Value to store
+pub fn bkpsram_write(offset: u32, val: u32) {
+    assert!(offset < BKPSRAM_WORDS, "offset must be 0..1023");
+    reg_set_val((BKPSRAM_BASE + offset * 4) as *mut u32, val);
+}
+
+/// Reads a word of the backup SRAM.
+///
+/// # Arguments
+/// * `offset` - Word offset (0..1023)
+pub fn bkpsram_read(offset: u32) -> u32 {
+    assert!(offset < BKPSRAM_WORDS, "offset must be 0..1023");
+    unsafe { reg_read((BKPSRAM_BASE + offset * 4) as *mut u32) }
+}
+
+/// Returns the whole backup SRAM as a word slice.
+///
+/// # Safety
+/// The caller must make sure no other reference to the backup SRAM exists
+/// while the slice is alive: no second slice, and no `.noinit` variable in
+/// use (both alias the same memory). `pwr_backup_sram_init` must have been
+/// called, otherwise accesses read zeros and writes are ignored.
+pub unsafe fn bkpsram_as_mut_slice() -> &'static mut [u32] {
+    unsafe { core::slice::from_raw_parts_mut(BKPSRAM_BASE as *mut u32, BKPSRAM_WORDS as usize) }
+}
+
+// -----------------------------------------------------------------------------
+// Low-Power Run Configuration
+// -----------------------------------------------------------------------------
+/// Disables the over-drive mode (ODSWEN first, then ODEN).
+pub fn pwr_disable_overdrive() {
+    let pwr_cr_addr = (PWR_BASE + PWR_CR) as *mut u32;
+    reg_set_bit(pwr_cr_addr, PWR_CR_ODSWEN_POS, false);
+    reg_set_bit(pwr_cr_addr, PWR_CR_ODEN_POS, false);
+}
+
+/// Puts the regulator into its lowest-consumption Run configuration.
+///
+/// The STM32F429 has no dedicated "low-power run" regulator mode (unlike the
+/// L-series MRLP/LPRUN bits). The equivalent is over-drive off and voltage
+/// scale 3 (regulator at its lowest Run voltage). The Stop mode settings
+/// (LPDS, FPDS) are left alone; `pwr_enter_stop` selects those.
+///
+/// Hard preconditions (RM0090, power controller chapter), checked with
+/// `assert!`:
+/// - SYSCLK runs from HSI or HSE: over-drive may only be left that way
+/// - the PLL is off: VOS writes only take effect while it is off (with the
+///   PLL on they wait for the next PLL enable)
+/// - SYSCLK is 120 MHz or less (scale 3 limit)
+///
+/// `rcc_switch_to_hsi` establishes all three (see `app::low_power`).
+/// Combine with `rcc_disable_all_unused` to gate unused peripheral clocks.
+pub fn pwr_enable_low_power_run() {
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    assert!(
+        reg_read_bits(cfgr_addr, RCC_CFGR_SWS_POS, 2) != RCC_CFGR_SWS_PLL,
+        "switch SYSCLK to HSI or HSE before leaving over-drive"
+    );
+    assert!(
+        !reg_read_bit((RCC_BASE + RCC_CR) as *mut u32, RCC_CR_PLLON_POS),
+        "turn the PLL off before changing the voltage scale"
+    );
+    let sysclk_hz = rcc_get_sysclk_hz();
+    assert!(
+        sysclk_hz <= PWR_SCALE3_MAX_HZ,
+        "lower SYSCLK to 120 MHz or less before voltage scale 3"
+    );
+
+    pwr_disable_overdrive();
+    pwr_set_regulator_voltage_scale(PWR_VOS_SCALE3);
+}
+
+// -----------------------------------------------------------------------------
+// Low-Power Modes
+// -----------------------------------------------------------------------------
+// Sleep:   CPU clock stopped, peripherals run. Any interrupt wakes up.
+// Stop:    All 1.2 V domain clocks stopped, SRAM and registers kept. An EXTI
+//          line (GPIO, RTC alarm/wakeup, ...) wakes up; execution continues
+//          after the WFI with the HSI as system clock.
+// Standby: 1.2 V domain off, SRAM and registers lost (except the backup
+//          domain). WKUP pin rising edge, RTC alarm/wakeup, NRST or IWDG
+//          wake up; execution restarts from reset with SBF set in PWR_CSR.
+
+/// Regulator state in Stop mode (LPDS).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StopRegulator {
+    /// Main regulator on: faster wakeup, higher consumption
+    MainOn,
+    /// Low-power regulator: lowest consumption, longer wakeup time
+    LowPower,
+}
+
+/// Enters Sleep mode until the next interrupt (WFI with SLEEPDEEP clear).
+pub fn pwr_enter_sleep() {
+    reg_set_bit(SCR_BASE as *mut u32, SCR_SLEEPDEEP_POS, false);
+    pwr_wfi();
+}
+
+/// Enters Stop mode until an EXTI wakeup event.
+///
+/// After wakeup the system clock is the HSI (16 MHz): the PLL and HSE are
+/// off. Call `system_clock_setup` (or the application's clock setup) again
+/// before relying on any clock frequency.
+///
+/// # Arguments
+/// * `regulator` - Regulator state during Stop mode
+pub fn pwr_enter_stop(regulator: StopRegulator) {
+    let pwr_cr_addr = (PWR_BASE + PWR_CR) as *mut u32;
+    reg_set_bit(pwr_cr_addr, PWR_CR_PDDS_POS, false);
+    reg_set_bit(
+        pwr_cr_addr,
+        PWR_CR_LPDS_POS,
+        regulator == StopRegulator::LowPower,
+    );
+
+    let scr_addr = SCR_BASE as *mut u32;
+    reg_set_bit(scr_addr, SCR_SLEEPDEEP_POS, true);
+    pwr_wfi();
+    // Back from Stop: plain WFI must mean Sleep again
+    reg_set_bit(scr_addr, SCR_SLEEPDEEP_POS, false);
+}
+
+/// Enters Standby mode. Never returns: wakeup restarts from reset.
+///
+/// Clears the wakeup flag first, otherwise a pending WUF wakes the MCU
+/// again immediately.
+pub fn pwr_enter_standby() -> ! {
+    pwr_clear_wakeup_flag();
+
+    reg_set_bit((PWR_BASE + PWR_CR) as *mut u32, PWR_CR_PDDS_POS, true);
+    reg_set_bit(SCR_BASE as *mut u32, SCR_SLEEPDEEP_POS, true);
+
+    loop {
+        pwr_wfi();
+    }
+}
+
+/// Clears the wakeup flag WUF (via CWUF).
+pub fn pwr_clear_wakeup_flag() {
+    reg_set_bit((PWR_BASE + PWR_CR) as *mut u32, PWR_CR_CWUF_POS, true);
+}
+
+/// Returns true if the MCU was woken from Standby (SBF), and clears the flag.
+pub fn pwr_take_standby_flag() -> bool {
+    let was_standby = reg_read_bit((PWR_BASE + PWR_CSR) as *mut u32, PWR_CSR_SBF_POS);
+    reg_set_bit((PWR_BASE + PWR_CR) as *mut u32, PWR_CR_CSBF_POS, true);
+    was_standby
+}
+
+/// Enables or disables the WKUP pin (PA0) as Standby wakeup source.
+///
+/// When enabled, PA0 is forced to input pull-down and a rising edge wakes
+/// the MCU from Standby.
+pub fn pwr_configure_wakeup_pin(enable: bool) {
+    reg_set_bit((PWR_BASE + PWR_CSR) as *mut u32, PWR_CSR_EWUP_POS, enable);
+}
+
+/// Waits for an interrupt; DSB completes pending writes (e.g., SLEEPDEEP) first.
+fn pwr_wfi() {
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        asm!("dsb", "wfi", options(nostack, preserves_flags))
+    };
 }
@@ -9,8 +9,12 @@
 // Reference: STM32F429 Reference Manual, section 7.3 (RCC registers, page 226)
 // -----------------------------------------------------------------------------
 
+use crate::bsw::error::{self, BswError};
+use crate::bsw::flash::{FLASH_ACR, VoltageRange, flash_configure_for_clock};
+use crate::bsw::gpio::GpioPort;
 use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
 use crate::bsw::reg_utils::*; // Register access helper functions
+use crate::bsw::tim::*; // Timer registers for the SYSCLK measurement
 
 // -----------------------------------------------------------------------------
 // RCC Register Offsets (relative to RCC_BASE)
@@ -40,6 +44,125 @@ pub const RCC_BDCR: u32 = 0x70; // Backup domain control register
 pub const RCC_CSR: u32 = 0x74; // Control/status register
 pub const RCC_SSCGR: u32 = 0x80; // Spread spectrum clock generation register
 pub const RCC_PLLI2SCFGR: u32 = 0x84; // PLLI2S configuration register
+pub const RCC_PLLSAICFGR: u32 = 0x88; // PLLSAI configuration register
+pub const RCC_DCKCFGR: u32 = 0x8C; // Dedicated clock configuration register
+
+// Layout checks (RM0090 7.3.25): each ENR sits 0x20 above its RSTR and each
+// LPENR 0x20 above its ENR, so a typo in one offset breaks a pair below.
+const _: () = {
+    assert!(RCC_AHB1ENR == 0x30 && RCC_APB1ENR == 0x40 && RCC_BDCR == 0x70);
+    assert!(RCC_AHB1ENR == RCC_AHB1RSTR + 0x20 && RCC_AHB1LPENR == RCC_AHB1ENR + 0x20);
+    assert!(RCC_AHB2ENR == RCC_AHB2RSTR + 0x20 && RCC_AHB2LPENR == RCC_AHB2ENR + 0x20);
+    assert!(RCC_AHB3ENR == RCC_AHB3RSTR + 0x20 && RCC_AHB3LPENR == RCC_AHB3ENR + 0x20);
+    assert!(RCC_APB1ENR == RCC_APB1RSTR + 0x20 && RCC_APB1LPENR == RCC_APB1ENR + 0x20);
+    assert!(RCC_APB2ENR == RCC_APB2RSTR + 0x20 && RCC_APB2LPENR == RCC_APB2ENR + 0x20);
+    assert!(RCC_AHB2ENR == RCC_AHB1ENR + 4 && RCC_AHB3ENR == RCC_AHB2ENR + 4);
+    assert!(RCC_APB2ENR == RCC_APB1ENR + 4);
+    assert!(RCC_CSR == RCC_BDCR + 4);
+    assert!(RCC_PLLSAICFGR == RCC_PLLI2SCFGR + 4 && RCC_DCKCFGR == RCC_PLLSAICFGR + 4);
+};
+
+// -----------------------------------------------------------------------------
+// Generic Peripheral Clock Enable/Disable
+// -----------------------------------------------------------------------------
+/// Bus a peripheral clock is gated on; selects the RCC enable register.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RccBus {
+    Ahb1, // RCC_AHB1ENR: GPIO, DMA, CRC, ETH, OTG HS, ...
+    Ahb2, // RCC_AHB2ENR: DCMI, CRYP, HASH, RNG, OTG FS
+    Ahb3, // RCC_AHB3ENR: FMC
+    Apb1, // RCC_APB1ENR: TIM2..7/12..14, USART2/3, UART4/5/7/8, SPI2/3, I2C, CAN, PWR, DAC
+    Apb2, // RCC_APB2ENR: TIM1/8..11, USART1/6, ADC, SPI1/4/5/6, SYSCFG, SAI1, LTDC
+}
+
+impl RccBus {
+    /// Offset of the bus's clock enable register (relative to RCC_BASE).
+    pub const fn enr_offset(self) -> u32 {
+        match self {
+            RccBus::Ahb1 => RCC_AHB1ENR,
+            RccBus::Ahb2 => RCC_AHB2ENR,
+            RccBus::Ahb3 => RCC_AHB3ENR,
+            RccBus::Apb1 => RCC_APB1ENR,
+            RccBus::Apb2 => RCC_APB2ENR,
+        }
+    }
+
+    /// Offset of the bus's reset register (relative to RCC_BASE).
+    pub const fn rstr_offset(self) -> u32 {
+        match self {
+            RccBus::Ahb1 => RCC_AHB1RSTR,
+            RccBus::Ahb2 => RCC_AHB2RSTR,
+            RccBus::Ahb3 => RCC_AHB3RSTR,
+            RccBus::Apb1 => RCC_APB1RSTR,
+            RccBus::Apb2 => RCC_APB2RSTR,
+        }
+    }
+}
+
+/// Enables the clock of a peripheral.
+///
+/// # Arguments
+/// * `bus` - Bus the peripheral is on
+/// * `bit` - Enable bit position in that bus's ENR register (RM0090 7.3.10-7.3.14)
+///
+/// # Example
+/// ```ignore
+/// rcc_enable_peripheral(RccBus::Apb1, 0); // TIM2EN
+/// rcc_enable_peripheral(RccBus::Apb2, 4); // USART1EN
+/// ```
+pub fn rcc_enable_peripheral(bus: RccBus, bit: u32) {
+    let enr_addr = (RCC_BASE + bus.enr_offset()) as *mut u32;
+    reg_set_bit(enr_addr, bit, true);
+}
+
+/// Disables the clock of a peripheral.
+///
+/// A peripheral whose clock is off keeps its register contents but cannot be
+/// accessed; reads return 0 and writes are ignored.
+///
+/// # Arguments
+/// * `bus` - Bus the peripheral is on
+/// * `bit` - Enable bit position in that bus's ENR register
+pub fn rcc_disable_peripheral(bus: RccBus, bit: u32) {
+    rcc_disable_peripheral_clock(bus.enr_offset(), bit);
+}
+
+/// Returns true if the clock of a peripheral is enabled.
+///
+/// Use it to check that a clock came up before touching the peripheral:
+/// with the clock off, register writes are silently ignored.
+///
+/// # Arguments
+/// * `bus` - Bus the peripheral is on
+/// * `bit` - Enable bit position in that bus's ENR register
+pub fn rcc_is_peripheral_enabled(bus: RccBus, bit: u32) -> bool {
+    let enr_addr = (RCC_BASE + bus.enr_offset()) as *mut u32;
+    reg_read_bit(enr_addr, bit)
+}
+
+/// Resets a peripheral: all its registers return to their reset values.
+///
+/// Pulses the peripheral's bit in the bus's RSTR register. Useful to
+/// recover a peripheral stuck in a bad state (e.g., an I2C with BUSY stuck
+/// high) without a system reset. The clock enable is not affected, but the
+/// peripheral must be configured again afterwards.
+///
+/// # Arguments
+/// * `bus` - Bus the peripheral is on
+/// * `bit` - Reset bit position in that bus's RSTR register (same position
+///   as the enable bit in ENR)
+///
+/// # Example
+/// ```ignore
+/// rcc_reset_peripheral(RccBus::Apb1, 21); // I2C1RST
+/// ```
+pub fn rcc_reset_peripheral(bus: RccBus, bit: u32) {
+    let rstr_addr = (RCC_BASE + bus.rstr_offset()) as *mut u32;
+    reg_set_bit(rstr_addr, bit, true);
+    // Read back so the reset is asserted for at least a few bus cycles
+    let _ = reg_read_bit(rstr_addr, bit);
+    reg_set_bit(rstr_addr, bit, false);
+}
 
 // -----------------------------------------------------------------------------
 // Enable GPIO Port Clock
@@ -56,61 +179,43 @@ pub const RCC_PLLI2SCFGR: u32 = 0x84; // PLLI2S configuration register
 /// rcc_enable_gpio_clock(GPIOA_BASE);
 /// ```
 pub fn rcc_enable_gpio_clock(port: u32) {
-    // RCC_AHB1ENR controls the clock gating for all GPIO ports on AHB1
-    let rcc_ahb1enr_addr = (RCC_BASE + RCC_AHB1ENR) as *mut u32;
+    rcc_enable_gpio_clock_on(&mut MmioBus, port);
+}
 
-    match port {
-        GPIOA_BASE => {
-            // Enable the 0th bit of the RCC AHB1ENR register for GPIOA
-            reg_set_bit(rcc_ahb1enr_addr, 0, true);
-        }
-        GPIOB_BASE => {
-            // Enable the 1st bit of the RCC AHB1ENR register for GPIOB
-            reg_set_bit(rcc_ahb1enr_addr, 1, true);
-        }
-        GPIOC_BASE => {
-            // Enable the 2nd bit of the RCC AHB1ENR register for GPIOC
-            reg_set_bit(rcc_ahb1enr_addr, 2, true);
-        }
-        GPIOD_BASE => {
-            // Enable the 3rd bit of the RCC AHB1ENR register for GPIOD
-            reg_set_bit(rcc_ahb1enr_addr, 3, true);
-        }
-        GPIOE_BASE => {
-            // Enable the 4th bit of the RCC AHB1ENR register for GPIOE
-            reg_set_bit(rcc_ahb1enr_addr, 4, true);
-        }
-        GPIOF_BASE => {
-            // Enable the 5th bit of the RCC AHB1ENR register for GPIOF
-            reg_set_bit(rcc_ahb1enr_addr, 5, true);
-        }
-        GPIOG_BASE => {
-            // Enable the 6th bit of the RCC AHB1ENR register for GPIOG
-            reg_set_bit(rcc_ahb1enr_addr, 6, true);
-        }
-        GPIOH_BASE => {
-            // Enable the 7th bit of the RCC AHB1ENR register for GPIOH
-            reg_set_bit(rcc_ahb1enr_addr, 7, true);
-        }
-        GPIOI_BASE => {
-            // Enable the 8th bit of the RCC AHB1ENR register for GPIOI
-            reg_set_bit(rcc_ahb1enr_addr, 8, true);
-        }
-        GPIOJ_BASE => {
-            // Enable the 9th bit of the RCC AHB1ENR register for GPIOJ
-            reg_set_bit(rcc_ahb1enr_addr, 9, true);
-        }
-        GPIOK_BASE => {
-            // Enable the 10th bit of the RCC AHB1ENR register for GPIOK
-            reg_set_bit(rcc_ahb1enr_addr, 10, true);
-        }
-        // Add more ports here if needed (GPIOL_BASE, etc.)
-        _ => {
-            // Unknown port: do nothing
-        }
+/// Enables the clock for a GPIO port through a register bus.
+///
+/// Same as `rcc_enable_gpio_clock`; with a `MockBus` the AHB1ENR update
+/// can be checked in unit tests.
+///
+/// # Arguments
+/// * `regs` - Register bus to access RCC through
+/// * `port` - The base address of the GPIO port (e.g., `GPIOA_BASE`).
+pub fn rcc_enable_gpio_clock_on(regs: &mut impl RegisterBus, port: u32) {
+    // Unknown port: do nothing
+    if let Some(port) = GpioPort::from_base(port) {
+        rcc_enable_gpio_port_clock_on(regs, port);
     }
 }
 
+/// Enables the clock for a GPIO port given as `GpioPort`.
+///
+/// # Example
+/// ```ignore
+/// rcc_enable_gpio_port_clock(GpioPort::G);
+/// ```
+pub fn rcc_enable_gpio_port_clock(port: GpioPort) {
+    rcc_enable_gpio_port_clock_on(&mut MmioBus, port);
+}
+
+/// Enables the clock for a `GpioPort` through a register bus.
+///
+/// # Arguments
+/// * `regs` - Register bus to access RCC through
+/// * `port` - The GPIO port
+pub fn rcc_enable_gpio_port_clock_on(regs: &mut impl RegisterBus, port: GpioPort) {
+    regs.set_bit(RCC_BASE + RccBus::Ahb1.enr_offset(), port.rcc_bit(), true);
+}
+
 // -----------------------------------------------------------------------------
 // Enable Power and SYSCFG Peripheral Clocks
 // -----------------------------------------------------------------------------
@@ -126,6 +231,66 @@ pub fn rcc_enable_syscfg_clock() {
     reg_set_bit(rcc_apb2enr_addr, 14, true);
 }
 
+// -----------------------------------------------------------------------------
+// Enable USART/UART Peripheral Clock
+// -----------------------------------------------------------------------------
+/// Enables the clock for a specific USART/UART.
+///
+/// USART1/USART6 are on APB2, the others on APB1.
+///
+/// # Arguments
+/// * `usart` - The base address of the USART (e.g., `USART1_BASE`).
+pub fn rcc_enable_usart_clock(usart: u32) {
+    match usart {
+        USART1_BASE => rcc_enable_peripheral(RccBus::Apb2, 4), // USART1EN
+        USART6_BASE => rcc_enable_peripheral(RccBus::Apb2, 5), // USART6EN
+        USART2_BASE => rcc_enable_peripheral(RccBus::Apb1, 17), // USART2EN
+        USART3_BASE => rcc_enable_peripheral(RccBus::Apb1, 18), // USART3EN
+        UART4_BASE => rcc_enable_peripheral(RccBus::Apb1, 19), // UART4EN
+        UART5_BASE => rcc_enable_peripheral(RccBus::Apb1, 20), // UART5EN
+        UART7_BASE => rcc_enable_peripheral(RccBus::Apb1, 30), // UART7EN
+        UART8_BASE => rcc_enable_peripheral(RccBus::Apb1, 31), // UART8EN
+        _ => {
+            // Unknown USART: do nothing
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Disable Peripheral Clocks (Clock Gating)
+// -----------------------------------------------------------------------------
+/// Disables the clock of one peripheral (inverse of the enable helpers).
+///
+/// Raw-offset form of `rcc_disable_peripheral`, for code that already works
+/// with ENR offsets (e.g. next to `rcc_disable_all_unused`).
+///
+/// # Arguments
+/// * `enr_offset` - Enable register offset (`RCC_AHB1ENR` .. `RCC_APB2ENR`)
+/// * `bit`        - Enable bit position of the peripheral in that register
+pub fn rcc_disable_peripheral_clock(enr_offset: u32, bit: u32) {
+    let enr_addr = (RCC_BASE + enr_offset) as *mut u32;
+    reg_set_bit(enr_addr, bit, false);
+}
+
+/// Disables every clock in one enable register except those in `keep_mask`.
+///
+/// Call once per bus register, e.g. to keep only GPIOA and GPIOG on AHB1:
+/// ```ignore
+/// rcc_disable_all_unused(RCC_AHB1ENR, (1 << 0) | (1 << 6) | (1 << 20));
+/// ```
+/// Remember the clocks the system itself depends on: CCMDATARAMEN (AHB1 bit
+/// 20) if anything lives in CCM RAM, PWREN (APB1 bit 28) for the backup
+/// domain and voltage scaling, and the clock of the debug/report USART.
+///
+/// # Arguments
+/// * `enr_offset` - Enable register offset (`RCC_AHB1ENR` .. `RCC_APB2ENR`)
+/// * `keep_mask`  - Enable bits to leave untouched
+pub fn rcc_disable_all_unused(enr_offset: u32, keep_mask: u32) {
+    let enr_addr = (RCC_BASE + enr_offset) as *mut u32;
+    // Only ever clear bits: reserved bits read as 0 and stay 0
+    reg_modify(enr_addr, |value| value & keep_mask);
+}
+
 // -----------------------------------------------------------------------------
 // Configure Main PLL for 180 MHz SYSCLK
 // -----------------------------------------------------------------------------
@@ -133,7 +298,12 @@ pub fn rcc_enable_syscfg_clock() {
 ///
 /// This sets up the PLL multipliers/dividers and bus prescalers, enables the PLL,
 /// waits for it to lock, and switches SYSCLK to the PLL output.
-pub fn rcc_configure_pll_180mhz() {
+///
+/// # Returns
+/// * `Ok(())` once SYSCLK runs from the PLL
+/// * `Err(BswError::Timeout)` if the PLL did not lock or SYSCLK did not
+///   switch; SYSCLK then stays on HSI
+pub fn rcc_configure_pll_180mhz() -> error::Result<()> {
     // Set PLLM (bits 0..5) to 8 (input divider)
     let pllcfgr_addr = (RCC_BASE + RCC_PLLCFGR) as *mut u32;
     reg_set_bits(pllcfgr_addr, 8, 0, 6);
@@ -161,7 +331,9 @@ pub fn rcc_configure_pll_180mhz() {
     reg_set_bit(cr_addr, 24, true);
 
     // Wait until PLL is ready (PLLRDY, bit 25)
-    let _ = reg_wait_bit(cr_addr, 25, true, 100_000);
+    if !reg_wait_bit(cr_addr, 25, true, 100_000) {
+        return Err(BswError::Timeout);
+    }
 
     // Switch SYSCLK source to PLL (SW bits 0..1 = 0b10)
     reg_set_bits(cfgr_addr, 0b10, 0, 2);
@@ -176,11 +348,380 @@ pub fn rcc_configure_pll_180mhz() {
         100_000,   // Timeout cycles
     );
     if !switched {
-        // Optionally handle timeout error here
-        // e.g., panic!("SYSCLK switch to PLL failed");
+        return Err(BswError::Timeout);
     }
 
-    // PLLSAI not needed for this board
+    // PLLSAI (LCD/SAI clocks) is started separately, see rcc_configure_pllsai
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Configure Main PLL for 168 MHz SYSCLK (USB compatible)
+// -----------------------------------------------------------------------------
+/// Configures the main PLL for 168 MHz SYSCLK and exactly 48 MHz PLL48CK.
+///
+/// Required before bringing up USB OTG FS (and used by SDIO/RNG): at
+/// 180 MHz no PLLQ divides the 360 MHz VCO down to 48 MHz, and USB needs
+/// 48 MHz within 0.25 %, which only the HSE crystal provides.
+///
+/// - HSE 8 MHz / PLLM 4 = 2 MHz VCO input, x PLLN 168 = 336 MHz VCO
+/// - SYSCLK = 336 / PLLP 2 = 168 MHz, PLL48CK = 336 / PLLQ 7 = 48 MHz
+/// - APB1 = HCLK / 4 = 42 MHz, APB2 = HCLK / 2 = 84 MHz
+/// - Flash: 5 wait states, prefetch and caches on
+///
+/// 168 MHz runs at the reset voltage scale 1 without over-drive. Coming
+/// from 180 MHz, prefer `system_clock_configure(SysClockConfig::HSE_168MHZ)`,
+/// which also turns over-drive off.
+///
+/// # Returns
+/// * `Ok(())` once SYSCLK runs from the PLL
+/// * `Err(BswError::NotReady)` if HSE did not start,
+///   `Err(BswError::Timeout)` if the PLL did not lock or SYSCLK did not
+///   switch; SYSCLK then stays on HSI
+pub fn rcc_configure_pll_168mhz() -> error::Result<()> {
+    // Wait states first: SYSCLK must never outrun the flash
+    flash_configure_for_clock(168_000_000, VoltageRange::V2_7To3_6);
+    rcc_configure_pll(PllSource::Hse, 168_000_000)?;
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// HSE Oscillator and Configurable PLL
+// -----------------------------------------------------------------------------
+pub const RCC_CR_HSEON_POS: u32 = 16; // HSE clock enable
+pub const RCC_CR_HSERDY_POS: u32 = 17; // HSE clock ready flag
+pub const RCC_CR_PLLON_POS: u32 = 24; // Main PLL enable
+pub const RCC_CR_PLLRDY_POS: u32 = 25; // Main PLL clock ready flag
+pub const RCC_PLLCFGR_PLLSRC_POS: u32 = 22; // Main PLL entry clock source (1 = HSE)
+pub const RCC_CR_PLLI2SON_POS: u32 = 26; // PLLI2S enable
+pub const RCC_CR_PLLI2SRDY_POS: u32 = 27; // PLLI2S clock ready flag
+pub const RCC_CR_PLLSAION_POS: u32 = 28; // PLLSAI enable
+pub const RCC_CR_PLLSAIRDY_POS: u32 = 29; // PLLSAI clock ready flag
+pub const RCC_DCKCFGR_PLLSAIDIVR_POS: u32 = 16; // LCD clock division factor (2 bits)
+pub const RCC_CFGR_I2SSRC_POS: u32 = 23; // I2S clock source (0 = PLLI2S, 1 = I2S_CKIN)
+pub const RCC_CFGR_SWS_POS: u32 = 2; // System clock switch status (2 bits)
+pub const RCC_CFGR_SWS_PLL: u32 = 0b10; // SWS value while SYSCLK runs from the PLL
+
+/// Target VCO input frequency (PLLM output). 2 MHz minimizes PLL jitter.
+const PLL_VCO_INPUT_HZ: u32 = 2_000_000;
+/// Allowed VCO output range (RM0090 7.3.2).
+const PLL_VCO_MIN_HZ: u32 = 100_000_000;
+const PLL_VCO_MAX_HZ: u32 = 432_000_000;
+/// Highest SYSCLK of the STM32F429 (with over-drive).
+const SYSCLK_MAX_HZ: u32 = 180_000_000;
+/// Maximum APB1/APB2 clocks.
+const PCLK1_MAX_HZ: u32 = 45_000_000;
+const PCLK2_MAX_HZ: u32 = 90_000_000;
+/// Clock wanted on the PLL48CK output (USB OTG FS, SDIO, RNG).
+const PLL48_TARGET_HZ: u32 = 48_000_000;
+
+/// Input clock of the main PLL.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PllSource {
+    Hsi, // 16 MHz internal RC oscillator
+    Hse, // External crystal (HSE_VALUE_HZ, 8 MHz on the DISC1)
+}
+
+/// Errors reported by the clock configuration.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RccError {
+    /// HSE did not become ready (crystal missing or not oscillating)
+    HseNotReady,
+    /// The main PLL did not lock
+    PllNotReady,
+    /// SYSCLK did not switch to the requested source
+    SwitchFailed,
+    /// The requested SYSCLK cannot be produced by the PLL
+    UnsupportedFrequency,
+    /// PLLSAI did not lock
+    PllSaiNotReady,
+    /// PLLSAI factors out of range (or VCO outside 100..432 MHz)
+    InvalidPllSaiConfig,
+    /// PLLI2S did not lock
+    PllI2sNotReady,
+    /// PLLI2S factors out of range (or VCO outside 100..432 MHz)
+    InvalidPllI2sConfig,
+}
+
+impl From<RccError> for BswError {
+    fn from(err: RccError) -> Self {
+        match err {
+            RccError::HseNotReady => BswError::NotReady,
+            RccError::PllNotReady
+            | RccError::SwitchFailed
+            | RccError::PllSaiNotReady
+            | RccError::PllI2sNotReady => BswError::Timeout,
+            RccError::UnsupportedFrequency
+            | RccError::InvalidPllSaiConfig
+            | RccError::InvalidPllI2sConfig => BswError::InvalidArgument,
+        }
+    }
+}
+
+/// Turns on the HSE oscillator and waits for it to become ready.
+///
+/// # Arguments
+/// * `timeout_cycles` - Max polls of HSERDY (0 = wait forever)
+///
+/// # Returns
+/// * `true` if HSERDY was set in time. On timeout HSE is switched off again.
+pub fn rcc_enable_hse(timeout_cycles: u32) -> bool {
+    let cr_addr = (RCC_BASE + RCC_CR) as *mut u32;
+    reg_set_bit(cr_addr, RCC_CR_HSEON_POS, true);
+
+    if reg_wait_bit(cr_addr, RCC_CR_HSERDY_POS, true, timeout_cycles) {
+        true
+    } else {
+        reg_set_bit(cr_addr, RCC_CR_HSEON_POS, false);
+        false
+    }
+}
+
+/// Switches SYSCLK to HSI and turns the main PLL off.
+///
+/// Needed before changing the PLL factors or the regulator voltage scale,
+/// which both only take effect while the PLL is off.
+///
+/// # Returns
+/// * `Ok(())` once SYSCLK runs from HSI and the PLL is off
+/// * `Err(RccError::SwitchFailed)` if SWS did not follow
+pub fn rcc_switch_to_hsi() -> Result<(), RccError> {
+    let cr_addr = (RCC_BASE + RCC_CR) as *mut u32;
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+
+    reg_set_bits(cfgr_addr, 0b00, 0, 2);
+    if !reg_wait_bits(cfgr_addr, 0b00, 0x3, 2, 100_000) {
+        return Err(RccError::SwitchFailed);
+    }
+    reg_set_bit(cr_addr, RCC_CR_PLLON_POS, false);
+    let _ = reg_wait_bit(cr_addr, RCC_CR_PLLRDY_POS, false, 100_000);
+    Ok(())
+}
+
+/// Returns the APB prescaler code (PPREx) for the smallest divider that keeps
+/// `hclk_hz / div` at or below `max_hz`.
+fn rcc_apb_prescaler(hclk_hz: u32, max_hz: u32) -> u32 {
+    match hclk_hz.div_ceil(max_hz) {
+        0 | 1 => 0b000, // /1
+        2 => 0b100,     // /2
+        3 | 4 => 0b101, // /4
+        5..=8 => 0b110, // /8
+        _ => 0b111,     // /16
+    }
+}
+
+/// Configures the main PLL for a given SYSCLK and switches to it.
+///
+/// The VCO input is set to 2 MHz (PLLM = 8 for HSI, 4 for the 8 MHz HSE),
+/// the smallest PLLP that keeps the VCO within 100..432 MHz is used, and
+/// PLLQ is set so that PLL48CK is at or just below 48 MHz. AHB runs at
+/// SYSCLK; APB1/APB2 are divided down to at most 45/90 MHz.
+///
+/// The caller is responsible for the flash wait states and, above 168 MHz,
+/// the over-drive mode (see `system_clock_configure`), which must be set before
+/// switching to a higher frequency.
+///
+/// # Arguments
+/// * `source`    - PLL input clock. For `PllSource::Hse`, HSE is started here
+/// * `target_hz` - Desired SYSCLK, a multiple of 1 MHz up to 180 MHz
+///
+/// # Returns
+/// * `Ok(())` once SYSCLK runs from the PLL, otherwise an `RccError`. On
+///   error SYSCLK is left on HSI, so the system keeps running.
+pub fn rcc_configure_pll(source: PllSource, target_hz: u32) -> Result<(), RccError> {
+    if target_hz == 0 || target_hz > SYSCLK_MAX_HZ {
+        return Err(RccError::UnsupportedFrequency);
+    }
+
+    // Find PLLP (2, 4, 6, 8) and PLLN with VCO = 2 MHz * PLLN = target * PLLP
+    let (pllp, vco_hz) = [2u32, 4, 6, 8]
+        .iter()
+        .map(|&p| (p, target_hz * p))
+        .find(|&(_, vco)| {
+            (PLL_VCO_MIN_HZ..=PLL_VCO_MAX_HZ).contains(&vco) && vco % PLL_VCO_INPUT_HZ == 0
+        })
+        .ok_or(RccError::UnsupportedFrequency)?;
+    let plln = vco_hz / PLL_VCO_INPUT_HZ;
+    let pllq = vco_hz.div_ceil(PLL48_TARGET_HZ).clamp(2, 15);
+
+    let pll_src_hz = match source {
+        PllSource::Hsi => HSI_VALUE_HZ,
+        PllSource::Hse => {
+            if !rcc_enable_hse(100_000) {
+                return Err(RccError::HseNotReady);
+            }
+            HSE_VALUE_HZ
+        }
+    };
+    let pllm = pll_src_hz / PLL_VCO_INPUT_HZ;
+
+    let cr_addr = (RCC_BASE + RCC_CR) as *mut u32;
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    let pllcfgr_addr = (RCC_BASE + RCC_PLLCFGR) as *mut u32;
+
+    // The PLL can only be reconfigured while it is off: run from HSI meanwhile
+    rcc_switch_to_hsi()?;
+
+    reg_set_bits(pllcfgr_addr, pllm, 0, 6); // PLLM (bits 5:0)
+    reg_set_bits(pllcfgr_addr, plln, 6, 9); // PLLN (bits 14:6)
+    reg_set_bits(pllcfgr_addr, pllp / 2 - 1, 16, 2); // PLLP (bits 17:16): 00 = /2
+    reg_set_bit(
+        pllcfgr_addr,
+        RCC_PLLCFGR_PLLSRC_POS,
+        source == PllSource::Hse,
+    );
+    reg_set_bits(pllcfgr_addr, pllq, 24, 4); // PLLQ (bits 27:24)
+
+    // Bus prescalers: AHB /1, APB1 <= 45 MHz, APB2 <= 90 MHz
+    reg_set_bits(cfgr_addr, 0, 4, 4);
+    reg_set_bits(cfgr_addr, rcc_apb_prescaler(target_hz, PCLK1_MAX_HZ), 10, 3);
+    reg_set_bits(cfgr_addr, rcc_apb_prescaler(target_hz, PCLK2_MAX_HZ), 13, 3);
+
+    reg_set_bit(cr_addr, RCC_CR_PLLON_POS, true);
+    if !reg_wait_bit(cr_addr, RCC_CR_PLLRDY_POS, true, 100_000) {
+        return Err(RccError::PllNotReady);
+    }
+
+    // Switch SYSCLK to the PLL (SW = 0b10) and wait for SWS to follow
+    reg_set_bits(cfgr_addr, 0b10, 0, 2);
+    if !reg_wait_bits(cfgr_addr, 0b10, 0x3, 2, 100_000) {
+        return Err(RccError::SwitchFailed);
+    }
+
+    Ok(())
+}
+
+/// Returns the VCO input frequency shared by all PLLs (`f_src / PLLM`).
+fn rcc_pll_vco_input_hz() -> u32 {
+    let pllcfgr_addr = (RCC_BASE + RCC_PLLCFGR) as *mut u32;
+    let pllm = reg_read_bits(pllcfgr_addr, 0, 6);
+    let pll_src_hz = if reg_read_bit(pllcfgr_addr, RCC_PLLCFGR_PLLSRC_POS) {
+        HSE_VALUE_HZ
+    } else {
+        HSI_VALUE_HZ
+    };
+    if pllm < 2 { 0 } else { pll_src_hz / pllm }
+}
+
+/// Configures and starts PLLSAI (LCD-TFT, SAI and 48 MHz clocks).
+///
+/// PLLSAI shares the main PLL input clock and PLLM divider, so its VCO
+/// input is `f_src / PLLM` (2 MHz after `rcc_configure_pll` or
+/// `rcc_configure_pll_180mhz`). Outputs:
+/// - PLLSAI_Q = VCO / q: SAI clock (further divided in RCC_DCKCFGR)
+/// - PLLSAI_R = VCO / r: LCD_CLK = PLLSAI_R / div_r
+///
+/// Example: LCD_CLK = 6 MHz for the DISC1 panel with a 2 MHz VCO input:
+/// `rcc_configure_pllsai(96, 4, 4, 8)` (VCO 192 MHz, R 48 MHz, /8).
+///
+/// # Arguments
+/// * `n`     - VCO multiplier PLLSAIN (50..432)
+/// * `q`     - PLLSAIQ divider (2..15)
+/// * `r`     - PLLSAIR divider (2..7)
+/// * `div_r` - LCD clock divider PLLSAIDIVR (2, 4, 8 or 16)
+///
+/// # Returns
+/// * `Ok(())` once PLLSAI is locked, otherwise an `RccError`. On timeout
+///   PLLSAI is switched off again.
+pub fn rcc_configure_pllsai(n: u32, q: u32, r: u32, div_r: u32) -> Result<(), RccError> {
+    let div_r_code = match div_r {
+        2 => 0b00,
+        4 => 0b01,
+        8 => 0b10,
+        16 => 0b11,
+        _ => return Err(RccError::InvalidPllSaiConfig),
+    };
+    if !(50..=432).contains(&n) || !(2..=15).contains(&q) || !(2..=7).contains(&r) {
+        return Err(RccError::InvalidPllSaiConfig);
+    }
+
+    if !(PLL_VCO_MIN_HZ..=PLL_VCO_MAX_HZ).contains(&(rcc_pll_vco_input_hz() * n)) {
+        return Err(RccError::InvalidPllSaiConfig);
+    }
+
+    // PLLSAI can only be reconfigured while it is off
+    let cr_addr = (RCC_BASE + RCC_CR) as *mut u32;
+    reg_set_bit(cr_addr, RCC_CR_PLLSAION_POS, false);
+    let _ = reg_wait_bit(cr_addr, RCC_CR_PLLSAIRDY_POS, false, 100_000);
+
+    let pllsaicfgr_addr = (RCC_BASE + RCC_PLLSAICFGR) as *mut u32;
+    reg_set_bits(pllsaicfgr_addr, n, 6, 9); // PLLSAIN (bits 14:6)
+    reg_set_bits(pllsaicfgr_addr, q, 24, 4); // PLLSAIQ (bits 27:24)
+    reg_set_bits(pllsaicfgr_addr, r, 28, 3); // PLLSAIR (bits 30:28)
+    reg_set_bits(
+        (RCC_BASE + RCC_DCKCFGR) as *mut u32,
+        div_r_code,
+        RCC_DCKCFGR_PLLSAIDIVR_POS,
+        2,
+    );
+
+    reg_set_bit(cr_addr, RCC_CR_PLLSAION_POS, true);
+    if !reg_wait_bit(cr_addr, RCC_CR_PLLSAIRDY_POS, true, 100_000) {
+        reg_set_bit(cr_addr, RCC_CR_PLLSAION_POS, false);
+        return Err(RccError::PllSaiNotReady);
+    }
+
+    Ok(())
+}
+
+/// Configures and starts PLLI2S, and selects it as the I2S clock.
+///
+/// Like PLLSAI, PLLI2S runs from the main PLL input clock divided by PLLM
+/// (2 MHz after `rcc_configure_pll`). I2SCLK = VCO input * n / r; pick n
+/// and r so that I2SCLK divides well into the wanted audio rate (see the
+/// i2s module), e.g. `rcc_configure_plli2s(129, 3)` gives 86 MHz.
+///
+/// # Arguments
+/// * `n` - VCO multiplier PLLI2SN (50..432)
+/// * `r` - PLLI2SR divider (2..7)
+///
+/// # Returns
+/// * `Ok(())` once PLLI2S is locked, otherwise an `RccError`. On timeout
+///   PLLI2S is switched off again.
+pub fn rcc_configure_plli2s(n: u32, r: u32) -> Result<(), RccError> {
+    if !(50..=432).contains(&n) || !(2..=7).contains(&r) {
+        return Err(RccError::InvalidPllI2sConfig);
+    }
+    if !(PLL_VCO_MIN_HZ..=PLL_VCO_MAX_HZ).contains(&(rcc_pll_vco_input_hz() * n)) {
+        return Err(RccError::InvalidPllI2sConfig);
+    }
+
+    // PLLI2S can only be reconfigured while it is off
+    let cr_addr = (RCC_BASE + RCC_CR) as *mut u32;
+    reg_set_bit(cr_addr, RCC_CR_PLLI2SON_POS, false);
+    let _ = reg_wait_bit(cr_addr, RCC_CR_PLLI2SRDY_POS, false, 100_000);
+
+    let plli2scfgr_addr = (RCC_BASE + RCC_PLLI2SCFGR) as *mut u32;
+    reg_set_bits(plli2scfgr_addr, n, 6, 9); // PLLI2SN (bits 14:6)
+    reg_set_bits(plli2scfgr_addr, r, 28, 3); // PLLI2SR (bits 30:28)
+    reg_set_bit(
+        (RCC_BASE + RCC_CFGR) as *mut u32,
+        RCC_CFGR_I2SSRC_POS,
+        false,
+    );
+
+    reg_set_bit(cr_addr, RCC_CR_PLLI2SON_POS, true);
+    if !reg_wait_bit(cr_addr, RCC_CR_PLLI2SRDY_POS, true, 100_000) {
+        reg_set_bit(cr_addr, RCC_CR_PLLI2SON_POS, false);
+        return Err(RccError::PllI2sNotReady);
+    }
+
+    Ok(())
+}
+
+/// Returns the I2S kernel clock (PLLI2S R output) in Hz, or 0 if PLLI2S is
+/// not running.
+pub fn rcc_get_plli2s_clk_hz() -> u32 {
+    if !reg_read_bit((RCC_BASE + RCC_CR) as *mut u32, RCC_CR_PLLI2SRDY_POS) {
+        return 0;
+    }
+    let plli2scfgr_addr = (RCC_BASE + RCC_PLLI2SCFGR) as *mut u32;
+    let n = reg_read_bits(plli2scfgr_addr, 6, 9);
+    let r = reg_read_bits(plli2scfgr_addr, 28, 3);
+    if r < 2 {
+        return 0;
+    }
+    rcc_pll_vco_input_hz() * n / r
 }
 
 // -----------------------------------------------------------------------------
@@ -190,6 +731,7 @@ pub fn rcc_configure_pll_180mhz() {
 ///
 /// You can output HSI, LSE, HSE, or PLL clock to the MCO1 pin (PA8).
 /// See RM0090 Table 14 for details.
+#[allow(clippy::upper_case_acronyms)]
 pub enum McoSource {
     HSI = 0b00, // High-speed internal clock
     LSE = 0b01, // Low-speed external clock
@@ -222,3 +764,460 @@ pub fn rcc_enable_mco1_output(mco_source: McoSource, prescaler: Div) {
     reg_set_bits(rcc_cfgr, mco_source as u32, 21, 2); // Set MCO1 source (bits 22:21)
     reg_set_bits(rcc_cfgr, prescaler as u32, 24, 3); // Set MCO1 prescaler (bits 26:24)
 }
+
+// -----------------------------------------------------------------------------
+// LSE Oscillator and RTC Clock (RCC_BDCR)
+// -----------------------------------------------------------------------------
+// RCC_BDCR is in the backup domain: backup domain write access must be
+// enabled first (`rcc_enable_power_clock` + `pwr_enable_backup_access`).
+pub const RCC_BDCR_LSEON_POS: u32 = 0; // LSE oscillator enable
+pub const RCC_BDCR_LSERDY_POS: u32 = 1; // LSE oscillator ready
+pub const RCC_BDCR_RTCSEL_POS: u32 = 8; // RTC clock source selection (2 bits)
+pub const RCC_BDCR_RTCEN_POS: u32 = 15; // RTC clock enable
+pub const RCC_BDCR_BDRST_POS: u32 = 16; // Backup domain software reset
+
+/// RTC clock source (RTCSEL[1:0]).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RtcClockSource {
+    None = 0b00,
+    Lse = 0b01,    // 32.768 kHz external crystal
+    Lsi = 0b10,    // ~32 kHz internal RC oscillator
+    HseDiv = 0b11, // HSE divided by RTCPRE
+}
+
+/// Turns on the LSE oscillator and waits for it to become ready.
+///
+/// # Arguments
+/// * `timeout_cycles` - Max polls of LSERDY (0 = wait forever); the LSE
+///   crystal can take up to a few seconds to start
+///
+/// # Returns
+/// * `true` if LSERDY was set in time
+pub fn rcc_enable_lse(timeout_cycles: u32) -> bool {
+    let bdcr_addr = (RCC_BASE + RCC_BDCR) as *mut u32;
+    reg_set_bit(bdcr_addr, RCC_BDCR_LSEON_POS, true);
+    reg_wait_bit(bdcr_addr, RCC_BDCR_LSERDY_POS, true, timeout_cycles)
+}
+
+/// Selects the RTC clock source and enables the RTC clock.
+///
+/// RTCSEL can only be changed once after a backup domain reset: if another
+/// source is already selected, the backup domain is reset first (this clears
+/// the RTC and its backup registers).
+///
+/// # Arguments
+/// * `source` - RTC clock source (must already be running)
+pub fn rcc_set_rtc_clock(source: RtcClockSource) {
+    let bdcr_addr = (RCC_BASE + RCC_BDCR) as *mut u32;
+
+    let current = reg_read_bits(bdcr_addr, RCC_BDCR_RTCSEL_POS, 2);
+    if current != RtcClockSource::None as u32 && current != source as u32 {
+        // Keep LSEON across the reset so an already running LSE stays on
+        let lse_on = reg_read_bit(bdcr_addr, RCC_BDCR_LSEON_POS);
+        reg_set_bit(bdcr_addr, RCC_BDCR_BDRST_POS, true);
+        reg_set_bit(bdcr_addr, RCC_BDCR_BDRST_POS, false);
+        if lse_on {
+            reg_set_bit(bdcr_addr, RCC_BDCR_LSEON_POS, true);
+            let _ = reg_wait_bit(bdcr_addr, RCC_BDCR_LSERDY_POS, true, 0);
+        }
+    }
+
+    reg_set_bits(bdcr_addr, source as u32, RCC_BDCR_RTCSEL_POS, 2);
+    reg_set_bit(bdcr_addr, RCC_BDCR_RTCEN_POS, true);
+}
+
+// -----------------------------------------------------------------------------
+// Reset Flags (RCC_CSR)
+// -----------------------------------------------------------------------------
+// The reset flags are sticky: they accumulate across resets until cleared
+// with RMVF, and are only cleared by a power-on reset otherwise.
+pub const RCC_CSR_RMVF_POS: u32 = 24; // Remove reset flags
+pub const RCC_CSR_BORRSTF_POS: u32 = 25; // BOR reset flag
+pub const RCC_CSR_PINRSTF_POS: u32 = 26; // PIN (NRST) reset flag
+pub const RCC_CSR_PORRSTF_POS: u32 = 27; // POR/PDR reset flag
+pub const RCC_CSR_SFTRSTF_POS: u32 = 28; // Software reset flag
+pub const RCC_CSR_IWDGRSTF_POS: u32 = 29; // Independent watchdog reset flag
+pub const RCC_CSR_WWDGRSTF_POS: u32 = 30; // Window watchdog reset flag
+pub const RCC_CSR_LPWRRSTF_POS: u32 = 31; // Low-power reset flag
+
+/// Returns `true` if the given reset flag is set in RCC_CSR.
+///
+/// # Arguments
+/// * `flag_pos` - Bit position of the flag (e.g., `RCC_CSR_IWDGRSTF_POS`)
+pub fn rcc_reset_flag_is_set(flag_pos: u32) -> bool {
+    let rcc_csr_addr = (RCC_BASE + RCC_CSR) as *mut u32;
+    reg_read_bit(rcc_csr_addr, flag_pos)
+}
+
+/// Clears all reset flags in RCC_CSR (sets RMVF).
+pub fn rcc_clear_reset_flags() {
+    let rcc_csr_addr = (RCC_BASE + RCC_CSR) as *mut u32;
+    reg_set_bit(rcc_csr_addr, RCC_CSR_RMVF_POS, true);
+}
+
+/// Cause of the last reset, decoded from the RCC_CSR flags.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ResetCause {
+    /// Power-on / power-down reset
+    PorReset,
+    /// Brown-out reset (supply dropped below the BOR threshold)
+    BorReset,
+    /// External reset through the NRST pin
+    PinReset,
+    /// Independent watchdog timeout
+    IwdgReset,
+    /// Window watchdog timeout or early refresh
+    WwdgReset,
+    /// Software reset (AIRCR SYSRESETREQ)
+    SoftReset,
+    /// Illegal Stop/Standby entry (option bytes nRST_STOP/nRST_STDBY)
+    LowPowerReset,
+    /// No flag set (e.g., flags already cleared since the last reset)
+    Unknown,
+}
+
+/// Returns the cause of the last reset.
+///
+/// Every internal reset also pulls NRST low (setting PINRSTF) and a POR
+/// also sets BORRSTF, so the flags are checked from most to least specific.
+/// Flags accumulate until cleared: call this early in `main`, then
+/// `rcc_clear_reset_flags`, so the next reset reports only its own cause.
+pub fn rcc_get_reset_cause() -> ResetCause {
+    let csr = unsafe { reg_read_ro((RCC_BASE + RCC_CSR) as ReadOnlyRegister) };
+    let flag = |pos: u32| csr & (1 << pos) != 0;
+
+    if flag(RCC_CSR_LPWRRSTF_POS) {
+        ResetCause::LowPowerReset
+    } else if flag(RCC_CSR_IWDGRSTF_POS) {
+        ResetCause::IwdgReset
+    } else if flag(RCC_CSR_WWDGRSTF_POS) {
+        ResetCause::WwdgReset
+    } else if flag(RCC_CSR_SFTRSTF_POS) {
+        ResetCause::SoftReset
+    } else if flag(RCC_CSR_PORRSTF_POS) {
+        ResetCause::PorReset
+    } else if flag(RCC_CSR_BORRSTF_POS) {
+        ResetCause::BorReset
+    } else if flag(RCC_CSR_PINRSTF_POS) {
+        ResetCause::PinReset
+    } else {
+        ResetCause::Unknown
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Clock Frequency Query
+// -----------------------------------------------------------------------------
+pub const HSI_VALUE_HZ: u32 = 16_000_000; // Internal RC oscillator
+pub const HSE_VALUE_HZ: u32 = 8_000_000; // STM32F429I-DISC1 crystal (X2)
+
+/// Returns the current SYSCLK frequency in Hz.
+///
+/// Reads the active source from RCC_CFGR (SWS) and, when running from the
+/// PLL, computes `f_src / PLLM * PLLN / PLLP` from RCC_PLLCFGR.
+pub fn rcc_get_sysclk_hz() -> u32 {
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    match reg_read_bits(cfgr_addr, 2, 2) {
+        0b00 => HSI_VALUE_HZ,
+        0b01 => HSE_VALUE_HZ,
+        _ => {
+            let pllcfgr_addr = (RCC_BASE + RCC_PLLCFGR) as *mut u32;
+            let pll_src_hz = if reg_read_bit(pllcfgr_addr, 22) {
+                HSE_VALUE_HZ
+            } else {
+                HSI_VALUE_HZ
+            };
+            let pllm = reg_read_bits(pllcfgr_addr, 0, 6); // Bits 5:0
+            let plln = reg_read_bits(pllcfgr_addr, 6, 9); // Bits 14:6
+            let pllp = (reg_read_bits(pllcfgr_addr, 16, 2) + 1) * 2; // 00: /2 ... 11: /8
+            if pllm < 2 {
+                // PLLM = 0/1 is a wrong configuration (RM0090 7.3.2)
+                return 0;
+            }
+            (pll_src_hz / pllm) * plln / pllp
+        }
+    }
+}
+
+/// Returns the AHB clock (HCLK) in Hz: SYSCLK divided by the HPRE prescaler.
+pub fn rcc_get_hclk_hz() -> u32 {
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    // HPRE[3:0] (bits 7:4): 0xxx = /1, 1000 = /2 ... 1111 = /512 (no /32)
+    let hpre = reg_read_bits(cfgr_addr, 4, 4);
+    let shift = match hpre {
+        0b1000..=0b1011 => hpre - 0b0111, // /2, /4, /8, /16
+        0b1100..=0b1111 => hpre - 0b0110, // /64, /128, /256, /512
+        _ => 0,
+    };
+    rcc_get_sysclk_hz() >> shift
+}
+
+/// Converts a PPREx field to a right shift: 0xx = /1, 100 = /2 ... 111 = /16.
+fn rcc_ppre_shift(ppre: u32) -> u32 {
+    if ppre & 0b100 != 0 {
+        (ppre & 0b011) + 1
+    } else {
+        0
+    }
+}
+
+/// Returns the APB1 peripheral clock (PCLK1) in Hz: HCLK divided by PPRE1.
+pub fn rcc_get_pclk1_hz() -> u32 {
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    rcc_get_hclk_hz() >> rcc_ppre_shift(reg_read_bits(cfgr_addr, 10, 3))
+}
+
+/// Returns the APB2 peripheral clock (PCLK2) in Hz: HCLK divided by PPRE2.
+pub fn rcc_get_pclk2_hz() -> u32 {
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    rcc_get_hclk_hz() >> rcc_ppre_shift(reg_read_bits(cfgr_addr, 13, 3))
+}
+
+/// Returns the clock of the timers on APB1 (TIM2..7, TIM12..14) in Hz.
+///
+/// The timer clock is PCLK1 if the APB1 prescaler is 1, otherwise 2 x PCLK1
+/// (TIMPRE = 0, RM0090 7.2).
+pub fn rcc_get_apb1_timer_clk_hz() -> u32 {
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    if rcc_ppre_shift(reg_read_bits(cfgr_addr, 10, 3)) == 0 {
+        rcc_get_pclk1_hz()
+    } else {
+        rcc_get_pclk1_hz() * 2
+    }
+}
+
+/// Returns the clock of the timers on APB2 (TIM1, TIM8..11) in Hz.
+///
+/// Same rule as `rcc_get_apb1_timer_clk_hz`, based on PCLK2.
+pub fn rcc_get_apb2_timer_clk_hz() -> u32 {
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    if rcc_ppre_shift(reg_read_bits(cfgr_addr, 13, 3)) == 0 {
+        rcc_get_pclk2_hz()
+    } else {
+        rcc_get_pclk2_hz() * 2
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Clock Tree Self-Test
+// -----------------------------------------------------------------------------
+/// First mismatch found by `rcc_check_pll_180mhz`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RccCheckError {
+    /// PLLRDY is not set
+    PllNotLocked,
+    /// SWS does not report the PLL as system clock
+    SysclkSource,
+    /// PLLM differs from 8
+    PllM,
+    /// PLLN differs from 180
+    PllN,
+    /// PLLP differs from /2
+    PllP,
+    /// AHB prescaler differs from /1
+    AhbPrescaler,
+    /// APB1 prescaler differs from /4
+    Apb1Prescaler,
+    /// APB2 prescaler differs from /2
+    Apb2Prescaler,
+    /// Flash latency is below the 5 wait states needed at 180 MHz
+    FlashLatency,
+}
+
+/// Reads back the clock configuration and compares it with what
+/// `rcc_configure_pll_180mhz` (and `system_clock_setup`) programs.
+///
+/// # Returns
+/// * `Ok(())` if every field matches, otherwise the first mismatch
+pub fn rcc_check_pll_180mhz() -> Result<(), RccCheckError> {
+    let cr_addr = (RCC_BASE + RCC_CR) as *mut u32;
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    let pllcfgr_addr = (RCC_BASE + RCC_PLLCFGR) as *mut u32;
+    let acr_addr = (FLASH_INTERFACE_BASE + FLASH_ACR) as *mut u32;
+
+    let checks = [
+        (
+            reg_read_bit(cr_addr, RCC_CR_PLLRDY_POS) as u32,
+            1,
+            RccCheckError::PllNotLocked,
+        ),
+        (
+            reg_read_bits(cfgr_addr, 2, 2),
+            0b10,
+            RccCheckError::SysclkSource,
+        ),
+        (reg_read_bits(pllcfgr_addr, 0, 6), 8, RccCheckError::PllM),
+        (reg_read_bits(pllcfgr_addr, 6, 9), 180, RccCheckError::PllN),
+        (
+            reg_read_bits(pllcfgr_addr, 16, 2),
+            0b00,
+            RccCheckError::PllP,
+        ),
+        (
+            reg_read_bits(cfgr_addr, 4, 4) & 0b1000,
+            0,
+            RccCheckError::AhbPrescaler,
+        ),
+        (
+            reg_read_bits(cfgr_addr, 10, 3),
+            0b101,
+            RccCheckError::Apb1Prescaler,
+        ),
+        (
+            reg_read_bits(cfgr_addr, 13, 3),
+            0b100,
+            RccCheckError::Apb2Prescaler,
+        ),
+    ];
+    for (actual, expected, error) in checks {
+        if actual != expected {
+            return Err(error);
+        }
+    }
+
+    if reg_read_bits(acr_addr, 0, 4) < 5 {
+        return Err(RccCheckError::FlashLatency);
+    }
+    Ok(())
+}
+
+const RCC_CFGR_RTCPRE_POS: u32 = 16; // HSE division factor for RTC clock (5 bits)
+/// TIM11_OR TI1_RMP value connecting channel 1 to the HSE_RTC clock.
+const TIM11_OR_TI1_HSE_RTC: u32 = 0b10;
+/// HSE_RTC frequency used as measurement reference (must be <= 1 MHz).
+const RCC_VERIFY_REF_HZ: u32 = 1_000_000;
+/// Input capture prescaler: one capture every 8 reference edges.
+const RCC_VERIFY_EDGES_PER_CAPTURE: u32 = 8;
+/// Number of capture intervals averaged.
+const RCC_VERIFY_CAPTURES: u32 = 16;
+
+/// Measures SYSCLK against the HSE crystal and checks it is within tolerance.
+///
+/// TIM11 (clocked from APB2) captures the HSE_RTC clock (HSE / RTCPRE,
+/// 1 MHz) on channel 1; the timer ticks counted over 128 us give the timer
+/// clock, which is scaled back to SYSCLK with the AHB/APB2 prescalers. The
+/// resolution is about 1 / 23_000 at 180 MHz.
+///
+/// Turns on the HSE if needed. RTCPRE is changed during the measurement and
+/// restored afterwards, so do not run this while the RTC is clocked from HSE.
+/// With the PLL fed from the HSI, expect up to ~1 % deviation (HSI accuracy).
+///
+/// # Arguments
+/// * `expected_hz`  - SYSCLK frequency the configuration should produce
+/// * `tolerance_hz` - Allowed deviation (e.g., `expected_hz / 50` for 2 %)
+///
+/// # Returns
+/// * `true` if the measured SYSCLK is within tolerance, `false` if it is
+///   not or the measurement failed (HSE not starting, no captures)
+pub fn rcc_verify_sysclk(expected_hz: u32, tolerance_hz: u32) -> bool {
+    if !rcc_enable_hse(100_000) {
+        return false;
+    }
+
+    let cfgr_addr = (RCC_BASE + RCC_CFGR) as *mut u32;
+    let saved_rtcpre = reg_read_bits(cfgr_addr, RCC_CFGR_RTCPRE_POS, 5);
+    reg_set_bits(
+        cfgr_addr,
+        HSE_VALUE_HZ / RCC_VERIFY_REF_HZ,
+        RCC_CFGR_RTCPRE_POS,
+        5,
+    );
+
+    timer_enable_clock(TIM11_BASE);
+    reg_set_val((TIM11_BASE + TIM_CR1) as *mut u32, 0);
+    reg_set_val((TIM11_BASE + TIM_PSC) as *mut u32, 0);
+    reg_set_val((TIM11_BASE + TIM_ARR) as *mut u32, 0xFFFF);
+    reg_set_val((TIM11_BASE + TIM_OR) as *mut u32, TIM11_OR_TI1_HSE_RTC);
+    // CC1S = 01 (input, TI1), IC1PSC = 11 (capture every 8 edges)
+    reg_set_val((TIM11_BASE + TIM_CCMR1) as *mut u32, 0b01 | (0b11 << 2));
+    reg_set_val((TIM11_BASE + TIM_CCER) as *mut u32, 1); // CC1E
+    reg_set_val((TIM11_BASE + TIM_EGR) as *mut u32, 1 << TIM_EGR_UG_POS);
+    reg_set_val((TIM11_BASE + TIM_CR1) as *mut u32, 1 << TIM_CR1_CEN_POS);
+
+    // The first capture only provides the starting point
+    let sr_addr = (TIM11_BASE + TIM_SR) as *mut u32;
+    let ccr1_addr = (TIM11_BASE + TIM_CCR1) as *mut u32;
+    let mut previous: Option<u32> = None;
+    let mut total_ticks: u32 = 0;
+    let mut captured = true;
+    for _ in 0..=RCC_VERIFY_CAPTURES {
+        // CC1IF (bit 1); reading CCR1 clears it
+        if !reg_wait_bit(sr_addr, 1, true, 1_000_000) {
+            captured = false;
+            break;
+        }
+        let capture = unsafe { reg_read(ccr1_addr) } & 0xFFFF;
+        if let Some(previous) = previous {
+            total_ticks += capture.wrapping_sub(previous) & 0xFFFF;
+        }
+        previous = Some(capture);
+    }
+
+    // Restore: stop TIM11, release the remap and the RTC prescaler
+    reg_set_val((TIM11_BASE + TIM_CR1) as *mut u32, 0);
+    reg_set_val((TIM11_BASE + TIM_CCER) as *mut u32, 0);
+    reg_set_val((TIM11_BASE + TIM_OR) as *mut u32, 0);
+    reg_set_bits(cfgr_addr, saved_rtcpre, RCC_CFGR_RTCPRE_POS, 5);
+
+    if !captured {
+        return false;
+    }
+
+    // Timer clock = ticks / measurement time; SYSCLK / timer clock is the
+    // prescaler ratio, which does not depend on the actual frequencies
+    let window_edges = (RCC_VERIFY_CAPTURES * RCC_VERIFY_EDGES_PER_CAPTURE) as u64;
+    let timer_hz = total_ticks as u64 * RCC_VERIFY_REF_HZ as u64 / window_edges;
+    let measured_hz =
+        timer_hz * rcc_get_sysclk_hz() as u64 / rcc_get_apb2_timer_clk_hz().max(1) as u64;
+
+    measured_hz.abs_diff(expected_hz as u64) <= tolerance_hz as u64
+}
+
+// -----------------------------------------------------------------------------
+// Unit tests (host): cargo test --target x86_64-unknown-linux-gnu
+// -----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AHB1ENR: u32 = RCC_BASE + RCC_AHB1ENR;
+
+    #[test]
+    fn gpio_clock_sets_port_bit() {
+        let mut regs = MockBus::default();
+        rcc_enable_gpio_clock_on(&mut regs, GPIOA_BASE);
+        assert_eq!(regs.read(AHB1ENR), 1 << 0);
+
+        rcc_enable_gpio_clock_on(&mut regs, GPIOG_BASE);
+        assert_eq!(regs.read(AHB1ENR), (1 << 6) | (1 << 0));
+
+        rcc_enable_gpio_clock_on(&mut regs, GPIOK_BASE);
+        assert_eq!(regs.read(AHB1ENR), (1 << 10) | (1 << 6) | (1 << 0));
+    }
+
+    #[test]
+    fn gpio_clock_keeps_other_ahb1_clocks() {
+        let mut regs = MockBus::default();
+        regs.write(AHB1ENR, 1 << 22); // DMA2EN
+        rcc_enable_gpio_clock_on(&mut regs, GPIOD_BASE);
+        assert_eq!(regs.read(AHB1ENR), (1 << 22) | (1 << 3));
+    }
+
+    #[test]
+    fn gpio_port_clock_matches_address_variant() {
+        for port in GpioPort::ALL {
+            let mut by_enum = MockBus::default();
+            let mut by_address = MockBus::default();
+            rcc_enable_gpio_port_clock_on(&mut by_enum, port);
+            rcc_enable_gpio_clock_on(&mut by_address, port.base());
+            assert_eq!(by_enum.read(AHB1ENR), by_address.read(AHB1ENR));
+        }
+    }
+
+    #[test]
+    fn gpio_clock_ignores_unknown_port() {
+        let mut regs = MockBus::default();
+        rcc_enable_gpio_clock_on(&mut regs, USART2_BASE);
+        assert!(regs.regs.is_empty());
+    }
+}
@@ -104,6 +104,9 @@ pub const TIM8_BASE: u32 = 0x4001_0400; // TIM8
 pub const USART1_BASE: u32 = 0x4001_1000; // USART1
 pub const USART6_BASE: u32 = 0x4001_1400; // USART6
 pub const ADC1_BASE: u32 = 0x4001_2000; // ADC1-3 shared base
+pub const ADC2_BASE: u32 = 0x4001_2100; // ADC2
+pub const ADC3_BASE: u32 = 0x4001_2200; // ADC3
+pub const ADC_COMMON_BASE: u32 = 0x4001_2300; // ADC common registers (CSR/CCR/CDR)
 
 pub const LCD_TFT_BASE: u32 = 0x4001_6800; // LCD-TFT
 pub const SAI1_BASE: u32 = 0x4001_5800; // SAI1
@@ -179,7 +182,8 @@ pub const RESERVED_FFFF_FFFF: u32 = 0xFFFF_FFFF; // End of address space
 /// Interrupt Request Numbers (IRQn) for STM32F4 external interrupts.
 /// These values map to the NVIC external interrupt lines (not core exceptions).
 /// Enable/disable via NVIC_ISERx/ICERx at the Cortex-M NVIC base (see core module).
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum IRQn {
     WWDG = 0,                // WWDG Window Watchdog interrupt; Address: 0x0000 0040
     PVD = 1,                 // PVD PVD through EXTI line detection interrupt; Address: 0x0000 0044
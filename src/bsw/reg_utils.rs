@@ -12,8 +12,12 @@
 //! API overview
 //! - reg_assert_mask_fits
 //! - reg_read
+//! - reg_read_ro
 //! - reg_write
+//! - reg_read_u16 / reg_write_u16
+//! - reg_read_u8 / reg_write_u8
 //! - reg_set_bits
+//! - reg_replace_bits
 //! - reg_set_bit
 //! - reg_set_val
 //! - reg_read_bit
@@ -26,12 +30,35 @@
 //! - reg_toggle_bit
 //! - reg_toggle_bits
 //! - reg_modify
+//! - reg_modify_atomic
 //! - reg_wait_bit
+//! - reg_wait_bit_timed
 //! - reg_wait_bits
+//! - reg_wait_either
 //! - reg_test_and_set
 //! - reg_test_and_clear
 //! - reg_count_set_bits
 //! - reg_find_first_set
+//! - reg_apply_script
+//! - reg_apply_script_with_waits
+//! - reg_apply_script_with_waits_on
+//!
+//! Checked variants (return `RegError` instead of panicking)
+//! - reg_check_field
+//! - reg_try_set_bit
+//! - reg_try_set_bits
+//! - reg_try_read_bit
+//! - reg_try_read_bits
+//!
+//! Bit-band helpers
+//! - bitband_peripheral_addr
+//! - bitband_sram_addr
+//! - bitband_write
+//! - bitband_read
+//!
+//! Typed register access
+//! - Reg<ReadOnly> / Reg<ReadWrite>
+//! - RegisterBus (MmioBus on target, MockBus in unit tests)
 //!
 //! In‑place (RAM) helpers
 //! - reg_write_inplace
@@ -44,13 +71,22 @@
 //! - reg_set_val_inplace
 //! - reg_read_val_inplace
 
+use core::fmt;
 use core::hint;
+use core::marker::PhantomData;
 use core::ptr;
 
+use crate::bsw::critical_cm4::with_critical_section;
+use crate::bsw::error::BswError;
+
 /// A type alias for a hardware register address (pointer to a 32‑bit register).
 /// Makes the intent of pointers clearer in code.
 pub type RegisterAddress = *mut u32;
 
+/// Address of a register that is only read (status, input data, ...).
+/// `*mut u32` coerces to it, so existing `RegisterAddress` values can be passed.
+pub type ReadOnlyRegister = *const u32;
+
 /// Common bit mask helpers
 pub mod bit_masks {
     /// Create a mask with `n` consecutive 1 bits (from bit 0).
@@ -121,6 +157,26 @@ pub unsafe fn reg_read(addr: RegisterAddress) -> u32 {
     unsafe { ptr::read_volatile(addr) }
 }
 
+/// Read a 32‑bit value from a register through a const pointer.
+///
+/// Use this for status and input registers: the `*const` type documents that
+/// the code never writes them, which matters for write‑1‑to‑clear flags where
+/// a read‑modify‑write would clear every pending flag.
+///
+/// Safety
+/// - Unsafe because it dereferences a raw pointer.
+/// - Only use valid hardware register addresses.
+///
+/// Example
+/// ```ignore
+/// let sr = unsafe { reg_read_ro((USART2_BASE + USART_SR) as ReadOnlyRegister) };
+/// ```
+#[inline(always)]
+pub unsafe fn reg_read_ro(addr: ReadOnlyRegister) -> u32 {
+    debug_assert!((addr as usize & 0x3) == 0, "unaligned register address");
+    unsafe { ptr::read_volatile(addr) }
+}
+
 /// Write a 32‑bit value to a memory‑mapped register.
 ///
 /// Safety
@@ -137,6 +193,81 @@ pub unsafe fn reg_write(addr: RegisterAddress, value: u32) {
     unsafe { ptr::write_volatile(addr, value) }
 }
 
+/// Read a 16‑bit value from a memory‑mapped register (halfword access).
+///
+/// Access width matters for some peripherals: the bus transfer has exactly
+/// the width of the pointer type, and a 32‑bit access is not always
+/// equivalent. Examples:
+/// - SPI_DR in 16‑bit frame mode, or in 8‑bit mode with data packing
+/// - GPIO BSRR halves (offset 0x18 sets, 0x1A resets pins) written alone
+/// - FMC/FSMC external memories with a 16‑bit data bus
+///
+/// Safety
+/// - Unsafe because it dereferences a raw pointer.
+/// - Only use valid hardware register addresses (2‑byte aligned).
+///
+/// Example
+/// ```ignore
+/// let data = unsafe { reg_read_u16(0x4001_300C as *mut u16) }; // SPI1_DR
+/// ```
+#[inline(always)]
+pub unsafe fn reg_read_u16(addr: *mut u16) -> u16 {
+    debug_assert!((addr as usize & 0x1) == 0, "unaligned register address");
+    unsafe { ptr::read_volatile(addr) }
+}
+
+/// Write a 16‑bit value to a memory‑mapped register (halfword access).
+///
+/// A halfword write only touches those 16 bits; it is not a 32‑bit
+/// read‑modify‑write (see `reg_read_u16` for when this matters).
+///
+/// Safety
+/// - Unsafe because it dereferences a raw pointer.
+/// - Only use valid hardware register addresses (2‑byte aligned).
+///
+/// Example
+/// ```ignore
+/// unsafe { reg_write_u16(0x4002_181A as *mut u16, 1 << 13); } // GPIOG_BSRR reset half: PG13 low
+/// ```
+#[inline(always)]
+pub unsafe fn reg_write_u16(addr: *mut u16, value: u16) {
+    debug_assert!((addr as usize & 0x1) == 0, "unaligned register address");
+    unsafe { ptr::write_volatile(addr, value) }
+}
+
+/// Read an 8‑bit value from a memory‑mapped register (byte access).
+///
+/// Safety
+/// - Unsafe because it dereferences a raw pointer.
+/// - Only use valid hardware register addresses.
+///
+/// Example
+/// ```ignore
+/// let byte = unsafe { reg_read_u8(0x4001_300C as *mut u8) }; // SPI1_DR, 8-bit frame
+/// ```
+#[inline(always)]
+pub unsafe fn reg_read_u8(addr: *mut u8) -> u8 {
+    unsafe { ptr::read_volatile(addr) }
+}
+
+/// Write an 8‑bit value to a memory‑mapped register (byte access).
+///
+/// Needed e.g. for SPI_DR in 8‑bit frame mode on peripherals with a data
+/// FIFO, where a wider write would queue more than one frame.
+///
+/// Safety
+/// - Unsafe because it dereferences a raw pointer.
+/// - Only use valid hardware register addresses.
+///
+/// Example
+/// ```ignore
+/// unsafe { reg_write_u8(0x4001_300C as *mut u8, 0xA5); } // SPI1_DR
+/// ```
+#[inline(always)]
+pub unsafe fn reg_write_u8(addr: *mut u8, value: u8) {
+    unsafe { ptr::write_volatile(addr, value) }
+}
+
 /// Set a group of bits (bit field) in a register without touching other bits.
 ///
 /// Arguments
@@ -154,6 +285,36 @@ pub unsafe fn reg_write(addr: RegisterAddress, value: u32) {
 /// reg_set_bits(0x4800_0000 as RegisterAddress, 0b10, 4, 2);
 /// ```
 pub fn reg_set_bits(reg_addr: RegisterAddress, new_bits_val: u32, bit_position: u32, n_bits: u32) {
+    reg_replace_bits(reg_addr, new_bits_val, bit_position, n_bits);
+}
+
+/// Set a bit field like `reg_set_bits` and return the value it had before.
+///
+/// Arguments
+/// - `reg_addr`: Register address
+/// - `new_bits_val`: New value for the field (must fit in `n_bits`)
+/// - `bit_position`: Starting bit position (0 = least significant bit)
+/// - `n_bits`: Number of bits in the field (1..=32)
+///
+/// Returns
+/// - The previous field value, right‑aligned
+///
+/// Safety
+/// - Only use valid hardware register addresses.
+///
+/// Example
+/// ```ignore
+/// // Temporarily run a peripheral with prescaler 0, then restore it
+/// let old = reg_replace_bits(psc_addr, 0, 0, 16);
+/// // ...
+/// reg_set_bits(psc_addr, old, 0, 16);
+/// ```
+pub fn reg_replace_bits(
+    reg_addr: RegisterAddress,
+    new_bits_val: u32,
+    bit_position: u32,
+    n_bits: u32,
+) -> u32 {
     assert!(
         n_bits > 0 && n_bits <= 32,
         "n_bits must be between 1 and 32"
@@ -176,6 +337,7 @@ pub fn reg_set_bits(reg_addr: RegisterAddress, new_bits_val: u32, bit_position:
         let mask = (((1u64 << n_bits) - 1) as u32) << bit_position;
         let updated_value = (reg_value & !mask) | ((new_bits_val << bit_position) & mask);
         reg_write(reg_addr, updated_value);
+        (reg_value & mask) >> bit_position
     }
 }
 
@@ -288,6 +450,110 @@ pub fn reg_read_bits(reg_addr: RegisterAddress, bit_position: u32, n_bits: u32)
     }
 }
 
+// -----------------------------------------------------------------------------
+// Checked Variants
+// -----------------------------------------------------------------------------
+// The helpers above `assert!` their arguments: a bad bit position is a bug
+// in a driver and panics (which halts the MCU). When the position or width
+// is computed at runtime from outside input, the `reg_try_` variants below
+// check the same conditions and return a `RegError` instead, without
+// touching the register.
+
+/// Why a checked register access was rejected.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RegError {
+    /// `bit_position` is not 0..31
+    BitPositionOutOfRange,
+    /// `n_bits` is 0, above 32, or the field extends past bit 31
+    NBitsOutOfRange,
+    /// The value has bits set outside the `n_bits` wide field
+    ValueDoesNotFit,
+}
+
+impl fmt::Display for RegError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            RegError::BitPositionOutOfRange => "bit position out of range",
+            RegError::NBitsOutOfRange => "field width out of range",
+            RegError::ValueDoesNotFit => "value does not fit in field",
+        };
+        f.write_str(text)
+    }
+}
+
+impl From<RegError> for BswError {
+    fn from(_: RegError) -> Self {
+        BswError::InvalidArgument
+    }
+}
+
+/// Checks a bit field description and a value for it.
+///
+/// Arguments
+/// - `bit_position`: Starting bit position (0..31)
+/// - `n_bits`: Number of bits in the field (1..=32, ending at bit 31 at most)
+/// - `value`: Field value (must fit in `n_bits`)
+///
+/// Returns
+/// - `Ok(())` if `reg_set_bits` would accept the arguments
+pub const fn reg_check_field(bit_position: u32, n_bits: u32, value: u32) -> Result<(), RegError> {
+    if bit_position >= 32 {
+        return Err(RegError::BitPositionOutOfRange);
+    }
+    if n_bits == 0 || n_bits > 32 - bit_position {
+        return Err(RegError::NBitsOutOfRange);
+    }
+    if value & !bit_masks::mask_n_bits(n_bits) != 0 {
+        return Err(RegError::ValueDoesNotFit);
+    }
+    Ok(())
+}
+
+/// Like `reg_set_bit`, but returns an error for a bad `bit_position`.
+pub fn reg_try_set_bit(
+    reg_addr: RegisterAddress,
+    bit_position: u32,
+    bit_val: bool,
+) -> Result<(), RegError> {
+    reg_check_field(bit_position, 1, 0)?;
+    reg_set_bit(reg_addr, bit_position, bit_val);
+    Ok(())
+}
+
+/// Like `reg_set_bits`, but returns an error instead of panicking.
+///
+/// Example
+/// ```ignore
+/// // Channel number from a host command: reject instead of halting
+/// reg_try_set_bits(smpr_addr, sample_time, channel * 3, 3)?;
+/// ```
+pub fn reg_try_set_bits(
+    reg_addr: RegisterAddress,
+    new_bits_val: u32,
+    bit_position: u32,
+    n_bits: u32,
+) -> Result<(), RegError> {
+    reg_check_field(bit_position, n_bits, new_bits_val)?;
+    reg_set_bits(reg_addr, new_bits_val, bit_position, n_bits);
+    Ok(())
+}
+
+/// Like `reg_read_bit`, but returns an error for a bad `bit_position`.
+pub fn reg_try_read_bit(reg_addr: RegisterAddress, bit_position: u32) -> Result<bool, RegError> {
+    reg_check_field(bit_position, 1, 0)?;
+    Ok(reg_read_bit(reg_addr, bit_position))
+}
+
+/// Like `reg_read_bits`, but returns an error instead of panicking.
+pub fn reg_try_read_bits(
+    reg_addr: RegisterAddress,
+    bit_position: u32,
+    n_bits: u32,
+) -> Result<u32, RegError> {
+    reg_check_field(bit_position, n_bits, 0)?;
+    Ok(reg_read_bits(reg_addr, bit_position, n_bits))
+}
+
 /// Clear a single bit (set it to 0).
 ///
 /// Convenience wrapper for `reg_set_bit(reg_addr, bit_position, false)`.
@@ -457,7 +723,7 @@ pub fn reg_toggle_bits(reg_addr: RegisterAddress, toggle_mask: u32, bit_position
 ///
 /// Note
 /// - This is not atomic. If interrupts or other code can also write this register,
-///   use proper synchronization.
+///   use `reg_modify_atomic` instead.
 ///
 /// Safety
 /// - Only use valid hardware register addresses.
@@ -481,6 +747,31 @@ where
     }
 }
 
+/// Read, change, and write back a register with interrupts masked.
+///
+/// Same as `reg_modify`, but an interrupt handler cannot modify the register
+/// between the read and the write (its change would otherwise be lost).
+///
+/// Arguments
+/// - `reg_addr`: Register address
+/// - `modify_fn`: Function that gets the current value and returns the new value
+///   (runs with interrupts masked, keep it short)
+///
+/// Safety
+/// - Only use valid hardware register addresses.
+///
+/// Example
+/// ```ignore
+/// // Main loop and an ISR both change bits of GPIOG_ODR
+/// reg_modify_atomic(0x4002_1814 as RegisterAddress, |val| val | (1 << 13));
+/// ```
+pub fn reg_modify_atomic<F>(reg_addr: RegisterAddress, modify_fn: F)
+where
+    F: FnOnce(u32) -> u32,
+{
+    with_critical_section(|| reg_modify(reg_addr, modify_fn));
+}
+
 /// Wait until one bit becomes a given value (busy‑wait/poll).
 ///
 /// Arguments
@@ -509,20 +800,54 @@ pub fn reg_wait_bit(
     expected_value: bool,
     timeout_cycles: u32,
 ) -> bool {
+    reg_wait_bit_timed(reg_addr, bit_position, expected_value, timeout_cycles).is_ok()
+}
+
+/// Wait until one bit becomes a given value and report how long it took.
+///
+/// Same polling as `reg_wait_bit`, for driver bring-up: characterize PLL
+/// lock time, flash busy time, etc.
+///
+/// Arguments
+/// - `reg_addr`: Register address
+/// - `bit_position`: Bit index to check
+/// - `expected_value`: true for 1, false for 0
+/// - `timeout_cycles`: Max read attempts before giving up (0 = wait forever)
+///
+/// Returns
+/// - Ok(polls): number of reads that did not match yet (0 = matched on the
+///   first read); saturates at u32::MAX when waiting forever
+/// - Err(()) if it timed out
+///
+/// Safety
+/// - Only use valid hardware register addresses.
+///
+/// Example
+/// ```ignore
+/// // RCC_CR: how many polls until PLLRDY (bit 25)?
+/// if let Ok(polls) = reg_wait_bit_timed(0x4002_3800 as RegisterAddress, 25, true, 100_000) {
+///     serial_println!("PLL locked after {} polls", polls);
+/// }
+/// ```
+#[allow(clippy::result_unit_err)]
+pub fn reg_wait_bit_timed(
+    reg_addr: RegisterAddress,
+    bit_position: u32,
+    expected_value: bool,
+    timeout_cycles: u32,
+) -> Result<u32, ()> {
     assert!(bit_position < 32, "bit_position must be less than 32");
 
-    let mut cycles = 0;
+    let mut cycles: u32 = 0;
 
     loop {
         if reg_read_bit(reg_addr, bit_position) == expected_value {
-            return true;
+            return Ok(cycles);
         }
         hint::spin_loop();
-        if timeout_cycles > 0 {
-            cycles += 1;
-            if cycles >= timeout_cycles {
-                return false;
-            }
+        cycles = cycles.saturating_add(1);
+        if timeout_cycles > 0 && cycles >= timeout_cycles {
+            return Err(());
         }
     }
 }
@@ -573,6 +898,75 @@ pub fn reg_wait_bits(
     }
 }
 
+/// Outcome of `reg_wait_either`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WaitResult {
+    /// The success bit was set
+    Ok,
+    /// The error bit was set
+    Error,
+    /// Neither bit was set before the timeout
+    Timeout,
+}
+
+/// Wait until either a success bit or an error bit is set (busy‑wait/poll).
+///
+/// Many operations end with one of two flags: I2C start (SB/ADDR vs AF),
+/// flash programming (EOP vs error bits), ... Both bits are checked on every
+/// read, so an error is seen immediately instead of after a timeout.
+///
+/// Arguments
+/// - `reg_addr`: Register address
+/// - `ok_bit`: Bit index (0..31) that signals success
+/// - `err_bit`: Bit index (0..31) that signals an error
+/// - `timeout_cycles`: Max read attempts before giving up (0 = wait forever)
+///
+/// Returns
+/// - `WaitResult::Ok`, `WaitResult::Error` or `WaitResult::Timeout`
+///   (if both bits are set in the same read, `Error` wins)
+///
+/// Safety
+/// - Only use valid hardware register addresses.
+///
+/// Example
+/// ```ignore
+/// // I2C1_SR1: wait for ADDR (bit 1) or AF (bit 10)
+/// match reg_wait_either(0x4000_5414 as RegisterAddress, 1, 10, 10_000) {
+///     WaitResult::Ok => { /* address acknowledged */ }
+///     WaitResult::Error => { /* NACK */ }
+///     WaitResult::Timeout => { /* bus stuck */ }
+/// }
+/// ```
+#[must_use]
+pub fn reg_wait_either(
+    reg_addr: RegisterAddress,
+    ok_bit: u32,
+    err_bit: u32,
+    timeout_cycles: u32,
+) -> WaitResult {
+    assert!(ok_bit < 32, "ok_bit must be less than 32");
+    assert!(err_bit < 32, "err_bit must be less than 32");
+
+    let mut cycles = 0;
+
+    loop {
+        let value = unsafe { reg_read(reg_addr) };
+        if value & (1 << err_bit) != 0 {
+            return WaitResult::Error;
+        }
+        if value & (1 << ok_bit) != 0 {
+            return WaitResult::Ok;
+        }
+        hint::spin_loop();
+        if timeout_cycles > 0 {
+            cycles += 1;
+            if cycles >= timeout_cycles {
+                return WaitResult::Timeout;
+            }
+        }
+    }
+}
+
 /// Test‑and‑set one bit: return the old value, then set it to 1.
 ///
 /// Arguments
@@ -687,6 +1081,373 @@ pub fn reg_find_first_set(reg_addr: RegisterAddress) -> Option<u32> {
     }
 }
 
+/// One step of a register init script (see `reg_apply_script_with_waits`).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RegOp {
+    /// Write a whole 32‑bit value: `Write(address, value)`
+    Write(u32, u32),
+    /// Poll until a bit has a value:
+    /// `WaitBit(address, bit_position, expected_value, timeout_cycles)`
+    /// (`timeout_cycles` = 0 waits forever, like `reg_wait_bit`)
+    WaitBit(u32, u32, bool, u32),
+    /// Busy‑wait for a number of spin-loop iterations: `Delay(cycles)`
+    Delay(u32),
+}
+
+/// Write a sequence of (address, value) pairs in order.
+///
+/// Useful for bring-up sequences copied from the reference manual, where every
+/// step is a plain register write.
+///
+/// Arguments
+/// - `script`: List of `(address, value)` pairs, applied from first to last
+///
+/// Safety
+/// - Only use valid hardware register addresses.
+///
+/// Example
+/// ```ignore
+/// reg_apply_script(&[
+///     (0x4002_3830, 0x0000_0040), // RCC_AHB1ENR: GPIOG clock on
+///     (0x4002_1800, 0x0400_0000), // GPIOG_MODER: PG13 output
+/// ]);
+/// ```
+pub fn reg_apply_script(script: &[(u32, u32)]) {
+    for &(addr, value) in script {
+        reg_set_val(addr as RegisterAddress, value);
+    }
+}
+
+/// Run a register init script made of writes, bit waits and delays.
+///
+/// All wait/timeout handling is done here, so an init sequence (SDRAM command
+/// sequence, display init table, ...) can be written as data.
+///
+/// Arguments
+/// - `script`: List of `RegOp` steps, executed from first to last
+///
+/// Returns
+/// - Ok(()) if every step completed
+/// - Err(index) with the index of the first `WaitBit` step that timed out
+///   (the steps after it are not executed)
+///
+/// Safety
+/// - Only use valid hardware register addresses.
+///
+/// Example
+/// ```ignore
+/// // Turn on HSE and wait for HSERDY, then give it some settling time
+/// let result = reg_apply_script_with_waits(&[
+///     RegOp::Write(0x4002_3800, 0x0001_0083),
+///     RegOp::WaitBit(0x4002_3800, 17, true, 100_000),
+///     RegOp::Delay(1_000),
+/// ]);
+/// ```
+pub fn reg_apply_script_with_waits(script: &[RegOp]) -> Result<(), usize> {
+    reg_apply_script_with_waits_on(&mut MmioBus, script)
+}
+
+/// Run a register init script through a register bus.
+///
+/// Same as `reg_apply_script_with_waits`; with a `MockBus` the writes and
+/// wait results can be checked in unit tests.
+///
+/// Arguments
+/// - `regs`: Register bus to run the script on
+/// - `script`: List of `RegOp` steps, executed from first to last
+///
+/// Returns
+/// - Ok(()) or Err(index of the `WaitBit` step that timed out)
+pub fn reg_apply_script_with_waits_on(
+    regs: &mut impl RegisterBus,
+    script: &[RegOp],
+) -> Result<(), usize> {
+    for (index, op) in script.iter().enumerate() {
+        match *op {
+            RegOp::Write(addr, value) => regs.write(addr, value),
+            RegOp::WaitBit(addr, bit_position, expected_value, timeout_cycles) => {
+                assert!(bit_position < 32, "bit_position must be less than 32");
+                let mut cycles: u32 = 0;
+                while (regs.read(addr) >> bit_position) & 1 != expected_value as u32 {
+                    hint::spin_loop();
+                    cycles = cycles.saturating_add(1);
+                    if timeout_cycles > 0 && cycles >= timeout_cycles {
+                        return Err(index);
+                    }
+                }
+            }
+            RegOp::Delay(cycles) => {
+                for _ in 0..cycles {
+                    hint::spin_loop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Bit-band regions of the Cortex-M4 (1 MB each).
+///
+/// On the STM32F429 the peripheral region covers APB1, APB2 and AHB1
+/// (GPIO, RCC, DMA, ...), but NOT AHB2 (0x5000_0000: USB OTG FS, RNG, DCMI)
+/// or AHB3 (FMC). The SRAM region covers SRAM1..SRAM3, but NOT the CCM RAM.
+pub const BITBAND_PERIPH_BASE: u32 = 0x4000_0000;
+pub const BITBAND_PERIPH_ALIAS: u32 = 0x4200_0000;
+pub const BITBAND_SRAM_BASE: u32 = 0x2000_0000;
+pub const BITBAND_SRAM_ALIAS: u32 = 0x2200_0000;
+const BITBAND_REGION_SIZE: u32 = 0x0010_0000;
+
+/// Compute the bit-band alias address of one bit in the peripheral region.
+///
+/// Arguments
+/// - `reg_addr`: Register address in 0x4000_0000..0x400F_FFFF
+/// - `bit`: Bit index (0..31)
+///
+/// Returns
+/// - Alias word address: `0x4200_0000 + (offset * 32) + (bit * 4)`
+///
+/// Example
+/// ```ignore
+/// // GPIOG_ODR (0x4002_1814) bit 13
+/// let alias = bitband_peripheral_addr(0x4002_1814 as RegisterAddress, 13);
+/// ```
+pub fn bitband_peripheral_addr(reg_addr: RegisterAddress, bit: u32) -> RegisterAddress {
+    bitband_alias(
+        reg_addr as u32,
+        bit,
+        BITBAND_PERIPH_BASE,
+        BITBAND_PERIPH_ALIAS,
+    )
+}
+
+/// Compute the bit-band alias address of one bit in the SRAM region.
+///
+/// Arguments
+/// - `addr`: Word address in 0x2000_0000..0x200F_FFFF
+/// - `bit`: Bit index (0..31)
+///
+/// Returns
+/// - Alias word address: `0x2200_0000 + (offset * 32) + (bit * 4)`
+pub fn bitband_sram_addr(addr: RegisterAddress, bit: u32) -> RegisterAddress {
+    bitband_alias(addr as u32, bit, BITBAND_SRAM_BASE, BITBAND_SRAM_ALIAS)
+}
+
+fn bitband_alias(addr: u32, bit: u32, base: u32, alias: u32) -> RegisterAddress {
+    assert!(bit < 32, "bit must be less than 32");
+    assert!(
+        addr >= base && addr - base < BITBAND_REGION_SIZE,
+        "address is not in a bit-band region"
+    );
+    // Word address: bit 0 of the alias word maps to bit (bit) of the word
+    let offset = (addr & !0x3) - base;
+    (alias + offset * 32 + bit * 4) as RegisterAddress
+}
+
+/// Write one bit atomically through its bit-band alias.
+///
+/// Unlike `reg_set_bit`, this cannot lose an update made by an ISR between
+/// a read and a write: the bus performs the read-modify-write as one locked
+/// transfer.
+///
+/// Arguments
+/// - `reg_addr`: Register or SRAM word address in a bit-band region
+/// - `bit`: Bit index (0..31)
+/// - `val`: New bit value
+///
+/// Limitation
+/// - The hardware still reads and writes back the whole word. On registers
+///   with write-1-to-clear or read-clear flags (e.g., EXTI_PR, USART_SR,
+///   DMA_LIFCR) this can clear other flags; use the register's own
+///   set/clear mechanism (BSRR, IFCR, ...) there.
+/// - Panics if the address is outside the bit-band regions (see above).
+///
+/// Example
+/// ```ignore
+/// bitband_write(0x4002_1814 as RegisterAddress, 13, true); // PG13 high
+/// ```
+pub fn bitband_write(reg_addr: RegisterAddress, bit: u32, val: bool) {
+    let alias = bitband_alias_any(reg_addr, bit);
+    unsafe { ptr::write_volatile(alias, val as u32) }
+}
+
+/// Read one bit through its bit-band alias.
+///
+/// Arguments
+/// - `reg_addr`: Register or SRAM word address in a bit-band region
+/// - `bit`: Bit index (0..31)
+///
+/// Returns
+/// - The bit value
+pub fn bitband_read(reg_addr: RegisterAddress, bit: u32) -> bool {
+    let alias = bitband_alias_any(reg_addr, bit);
+    unsafe { ptr::read_volatile(alias) & 1 != 0 }
+}
+
+/// Alias address in whichever bit-band region contains `addr`.
+fn bitband_alias_any(addr: RegisterAddress, bit: u32) -> RegisterAddress {
+    if (addr as u32) >= BITBAND_PERIPH_BASE {
+        bitband_peripheral_addr(addr, bit)
+    } else {
+        bitband_sram_addr(addr, bit)
+    }
+}
+
+/// Access mode marker: the register can only be read.
+pub struct ReadOnly;
+/// Access mode marker: the register can be read and written.
+pub struct ReadWrite;
+
+/// A register address tagged with its access mode.
+///
+/// Zero-cost (one `u32`): `Reg<ReadOnly>` simply has no write methods, so
+/// writing a status or input register is a compile error instead of a
+/// silent flag clear.
+///
+/// Example
+/// ```ignore
+/// const USART2_SR: Reg<ReadOnly> = Reg::new(USART2_BASE + USART_SR);
+/// const USART2_DR: Reg<ReadWrite> = Reg::new(USART2_BASE + USART_DR);
+///
+/// if USART2_SR.read_bit(USART_SR_TXE_POS) {
+///     USART2_DR.write(b'A' as u32);
+/// }
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Reg<A> {
+    addr: u32,
+    _mode: PhantomData<A>,
+}
+
+impl<A> Reg<A> {
+    /// Creates a register handle for `addr` (must be 4-byte aligned).
+    pub const fn new(addr: u32) -> Self {
+        Reg {
+            addr,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Returns the register address.
+    pub const fn addr(&self) -> u32 {
+        self.addr
+    }
+
+    /// Reads the whole register.
+    pub fn read(&self) -> u32 {
+        unsafe { reg_read_ro(self.addr as ReadOnlyRegister) }
+    }
+
+    /// Reads a single bit.
+    pub fn read_bit(&self, bit_position: u32) -> bool {
+        assert!(bit_position < 32, "bit_position must be less than 32");
+        self.read() & (1u32 << bit_position) != 0
+    }
+
+    /// Reads `n_bits` starting at `bit_position`, right-aligned.
+    pub fn read_bits(&self, bit_position: u32, n_bits: u32) -> u32 {
+        reg_read_bits(self.addr as RegisterAddress, bit_position, n_bits)
+    }
+}
+
+impl Reg<ReadWrite> {
+    /// Writes the whole register.
+    pub fn write(&self, value: u32) {
+        reg_set_val(self.addr as RegisterAddress, value);
+    }
+
+    /// Sets or clears a single bit (read-modify-write).
+    pub fn set_bit(&self, bit_position: u32, bit_val: bool) {
+        reg_set_bit(self.addr as RegisterAddress, bit_position, bit_val);
+    }
+
+    /// Writes a bit field (read-modify-write).
+    pub fn set_bits(&self, new_bits_val: u32, bit_position: u32, n_bits: u32) {
+        reg_set_bits(
+            self.addr as RegisterAddress,
+            new_bits_val,
+            bit_position,
+            n_bits,
+        );
+    }
+}
+
+/// Register access through an exchangeable bus.
+///
+/// Driver logic written against this trait runs on the MCU with `MmioBus`
+/// and in host unit tests with `MockBus`, where the resulting register
+/// values can be asserted.
+pub trait RegisterBus {
+    /// Reads the 32‑bit register at `addr`.
+    fn read(&self, addr: u32) -> u32;
+
+    /// Writes the 32‑bit register at `addr`.
+    fn write(&mut self, addr: u32, val: u32);
+
+    /// Sets or clears a single bit (read-modify-write).
+    fn set_bit(&mut self, addr: u32, bit_position: u32, bit_val: bool) {
+        assert!(bit_position < 32, "bit_position must be less than 32");
+        let value = self.read(addr);
+        let updated = if bit_val {
+            value | (1u32 << bit_position)
+        } else {
+            value & !(1u32 << bit_position)
+        };
+        self.write(addr, updated);
+    }
+
+    /// Writes an `n_bits` wide field at `bit_position` (read-modify-write).
+    fn set_bits(&mut self, addr: u32, val: u32, bit_position: u32, n_bits: u32) {
+        assert!(
+            n_bits > 0 && bit_position + n_bits <= 32,
+            "bit range exceeds register size"
+        );
+        let field_mask = bit_masks::mask_n_bits(n_bits);
+        assert!(val & !field_mask == 0, "val does not fit in n_bits");
+        let value = self.read(addr) & !(field_mask << bit_position);
+        self.write(addr, value | (val << bit_position));
+    }
+
+    /// Reads an `n_bits` wide field at `bit_position`.
+    fn read_bits(&self, addr: u32, bit_position: u32, n_bits: u32) -> u32 {
+        assert!(
+            n_bits > 0 && bit_position + n_bits <= 32,
+            "bit range exceeds register size"
+        );
+        (self.read(addr) >> bit_position) & bit_masks::mask_n_bits(n_bits)
+    }
+}
+
+/// The memory-mapped hardware registers.
+pub struct MmioBus;
+
+impl RegisterBus for MmioBus {
+    fn read(&self, addr: u32) -> u32 {
+        unsafe { reg_read_ro(addr as ReadOnlyRegister) }
+    }
+
+    fn write(&mut self, addr: u32, val: u32) {
+        reg_set_val(addr as RegisterAddress, val);
+    }
+}
+
+/// Register file in a map for unit tests: unwritten registers read as 0.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MockBus {
+    pub regs: std::collections::HashMap<u32, u32>,
+}
+
+#[cfg(test)]
+impl RegisterBus for MockBus {
+    fn read(&self, addr: u32) -> u32 {
+        self.regs.get(&addr).copied().unwrap_or(0)
+    }
+
+    fn write(&mut self, addr: u32, val: u32) {
+        self.regs.insert(addr, val);
+    }
+}
+
 /// Legacy and in-place helpers
 ///
 /// Purpose
@@ -768,3 +1529,286 @@ pub fn reg_read_val_inplace(reg: &u32, rdmask: u32, pos: u32) -> u32 {
     reg_assert_mask_fits(rdmask, pos);
     (unsafe { core::ptr::read_volatile(reg) } >> pos) & rdmask
 }
+
+// -----------------------------------------------------------------------------
+// Unit tests (host): cargo test --target x86_64-unknown-linux-gnu
+// -----------------------------------------------------------------------------
+// A plain `u32` in RAM stands in for the register, so the address-based
+// helpers run unchanged and only the bit math is under test.
+#[cfg(test)]
+mod tests {
+    use super::bit_masks::{mask_n_bits, single_bit};
+    use super::*;
+
+    fn addr(reg: &mut u32) -> RegisterAddress {
+        reg as *mut u32
+    }
+
+    #[test]
+    fn mask_n_bits_boundaries() {
+        assert_eq!(mask_n_bits(0), 0);
+        assert_eq!(mask_n_bits(1), 0x1);
+        assert_eq!(mask_n_bits(16), 0xFFFF);
+        assert_eq!(mask_n_bits(31), 0x7FFF_FFFF);
+        assert_eq!(mask_n_bits(32), 0xFFFF_FFFF);
+        assert_eq!(mask_n_bits(33), 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn single_bit_boundaries() {
+        assert_eq!(single_bit(0), 0x1);
+        assert_eq!(single_bit(31), 0x8000_0000);
+        assert_eq!(single_bit(32), 0);
+    }
+
+    #[test]
+    fn check_field_limits() {
+        assert_eq!(reg_check_field(0, 32, 0xFFFF_FFFF), Ok(()));
+        assert_eq!(reg_check_field(31, 1, 1), Ok(()));
+        assert_eq!(
+            reg_check_field(32, 1, 0),
+            Err(RegError::BitPositionOutOfRange)
+        );
+        assert_eq!(reg_check_field(4, 0, 0), Err(RegError::NBitsOutOfRange));
+        assert_eq!(reg_check_field(30, 3, 0), Err(RegError::NBitsOutOfRange));
+        assert_eq!(reg_check_field(4, 2, 0b100), Err(RegError::ValueDoesNotFit));
+    }
+
+    #[test]
+    fn try_set_bits_leaves_register_on_error() {
+        let mut reg = 0x1234_5678;
+        assert_eq!(
+            reg_try_set_bits(addr(&mut reg), 0b100, 4, 2),
+            Err(RegError::ValueDoesNotFit)
+        );
+        assert_eq!(
+            reg_try_set_bit(addr(&mut reg), 32, true),
+            Err(RegError::BitPositionOutOfRange)
+        );
+        assert_eq!(reg, 0x1234_5678);
+
+        assert_eq!(reg_try_set_bits(addr(&mut reg), 0xF, 28, 4), Ok(()));
+        assert_eq!(reg, 0xF234_5678);
+        assert_eq!(reg_try_read_bits(addr(&mut reg), 28, 4), Ok(0xF));
+        assert_eq!(
+            reg_try_read_bits(addr(&mut reg), 28, 8),
+            Err(RegError::NBitsOutOfRange)
+        );
+        assert_eq!(reg_try_read_bit(addr(&mut reg), 31), Ok(true));
+    }
+
+    /// Bus that records every write in order.
+    #[derive(Default)]
+    struct LogBus {
+        regs: MockBus,
+        writes: Vec<(u32, u32)>,
+    }
+
+    impl RegisterBus for LogBus {
+        fn read(&self, addr: u32) -> u32 {
+            self.regs.read(addr)
+        }
+
+        fn write(&mut self, addr: u32, val: u32) {
+            self.writes.push((addr, val));
+            self.regs.write(addr, val);
+        }
+    }
+
+    #[test]
+    fn script_runs_steps_in_order() {
+        let mut regs = LogBus::default();
+        let result = reg_apply_script_with_waits_on(
+            &mut regs,
+            &[
+                RegOp::Write(0x4002_3800, 0x1),
+                RegOp::Delay(3),
+                RegOp::Write(0x4002_3808, 0x2),
+                RegOp::Write(0x4002_3800, 0x3),
+            ],
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            regs.writes,
+            [(0x4002_3800, 0x1), (0x4002_3808, 0x2), (0x4002_3800, 0x3)]
+        );
+    }
+
+    #[test]
+    fn script_wait_step_succeeds() {
+        let mut regs = MockBus::default();
+        // The first write sets the bit the wait step polls for
+        let result = reg_apply_script_with_waits_on(
+            &mut regs,
+            &[
+                RegOp::Write(0x4002_3800, 1 << 17),
+                RegOp::WaitBit(0x4002_3800, 17, true, 10),
+                RegOp::WaitBit(0x4002_3800, 16, false, 10),
+                RegOp::Write(0x4002_3808, 0x2),
+            ],
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(regs.read(0x4002_3808), 0x2);
+    }
+
+    #[test]
+    fn script_wait_timeout_reports_step_and_stops() {
+        let mut regs = MockBus::default();
+        let result = reg_apply_script_with_waits_on(
+            &mut regs,
+            &[
+                RegOp::Write(0x4002_3800, 0x1),
+                RegOp::WaitBit(0x4002_3800, 0, true, 10),
+                RegOp::WaitBit(0x4002_3800, 17, true, 10),
+                RegOp::Write(0x4002_3808, 0x2),
+            ],
+        );
+        assert_eq!(result, Err(2));
+        assert_eq!(regs.read(0x4002_3808), 0);
+    }
+
+    #[test]
+    fn wait_either_first_bit_wins() {
+        let mut reg = 1 << 1;
+        assert_eq!(reg_wait_either(addr(&mut reg), 1, 10, 10), WaitResult::Ok);
+    }
+
+    #[test]
+    fn wait_either_second_bit_wins() {
+        let mut reg = 1 << 10;
+        assert_eq!(
+            reg_wait_either(addr(&mut reg), 1, 10, 10),
+            WaitResult::Error
+        );
+        // Both set in the same read: the error is reported
+        reg |= 1 << 1;
+        assert_eq!(
+            reg_wait_either(addr(&mut reg), 1, 10, 10),
+            WaitResult::Error
+        );
+    }
+
+    #[test]
+    fn wait_either_times_out() {
+        let mut reg = !((1 << 1) | (1 << 10));
+        assert_eq!(
+            reg_wait_either(addr(&mut reg), 1, 10, 10),
+            WaitResult::Timeout
+        );
+    }
+
+    #[test]
+    fn set_bits_places_field_and_keeps_others() {
+        let mut reg = 0xFFFF_FFFF;
+        reg_set_bits(addr(&mut reg), 0b10, 4, 2);
+        assert_eq!(reg, 0xFFFF_FFEF);
+
+        let mut reg = 0;
+        reg_set_bits(addr(&mut reg), 0xA, 28, 4);
+        assert_eq!(reg, 0xA000_0000);
+    }
+
+    #[test]
+    fn set_bits_full_width() {
+        let mut reg = 0x1234_5678;
+        reg_set_bits(addr(&mut reg), 0xDEAD_BEEF, 0, 32);
+        assert_eq!(reg, 0xDEAD_BEEF);
+    }
+
+    #[test]
+    fn replace_bits_returns_previous_field() {
+        let mut reg = 0x0000_0A50;
+        assert_eq!(reg_replace_bits(addr(&mut reg), 0x3, 4, 4), 0x5);
+        assert_eq!(reg, 0x0000_0A30);
+    }
+
+    #[test]
+    fn read_bits_is_right_aligned() {
+        let mut reg = 0x8765_4321;
+        assert_eq!(reg_read_bits(addr(&mut reg), 8, 4), 0x3);
+        assert_eq!(reg_read_bits(addr(&mut reg), 28, 4), 0x8);
+        assert_eq!(reg_read_bits(addr(&mut reg), 0, 32), 0x8765_4321);
+    }
+
+    #[test]
+    fn set_and_read_single_bit() {
+        let mut reg = 0;
+        reg_set_bit(addr(&mut reg), 31, true);
+        assert!(reg_read_bit(addr(&mut reg), 31));
+        assert_eq!(reg, 0x8000_0000);
+        reg_set_bit(addr(&mut reg), 31, false);
+        assert_eq!(reg, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "new_bits_val does not fit in n_bits")]
+    fn set_bits_rejects_value_wider_than_field() {
+        let mut reg = 0;
+        reg_set_bits(addr(&mut reg), 0b100, 0, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "bit range exceeds register size")]
+    fn set_bits_rejects_field_past_bit_31() {
+        let mut reg = 0;
+        reg_set_bits(addr(&mut reg), 0b1, 31, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "n_bits must be between 1 and 32")]
+    fn set_bits_rejects_zero_width() {
+        let mut reg = 0;
+        reg_set_bits(addr(&mut reg), 0, 0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "bit_position must be less than 32")]
+    fn set_bit_rejects_position_32() {
+        let mut reg = 0;
+        reg_set_bit(addr(&mut reg), 32, true);
+    }
+
+    #[test]
+    fn inplace_single_bit_helpers() {
+        let mut reg = 0;
+        reg_set_bit_inplace(&mut reg, 0);
+        reg_set_bit_inplace(&mut reg, 31);
+        assert_eq!(reg, 0x8000_0001);
+        reg_toggle_bit_inplace(&mut reg, 0);
+        assert!(!reg_read_bit_inplace(&reg, 0));
+        reg_clear_bit_inplace(&mut reg, 31);
+        assert_eq!(reg, 0);
+    }
+
+    #[test]
+    fn inplace_field_helpers() {
+        let mut reg = 0xFFFF_FFFF;
+        reg_clear_val_inplace(&mut reg, 0xF, 8);
+        assert_eq!(reg, 0xFFFF_F0FF);
+        reg_set_val_inplace(&mut reg, 0x5, 0xF, 8);
+        assert_eq!(reg, 0xFFFF_F5FF);
+        assert_eq!(reg_read_val_inplace(&reg, 0xF, 8), 0x5);
+        assert_eq!(reg_read_val_inplace(&reg, 0xFF, 24), 0xFF);
+    }
+
+    #[test]
+    #[should_panic(expected = "mask << bit_position exceeds 32-bit register width")]
+    fn inplace_rejects_mask_past_bit_31() {
+        let mut reg = 0;
+        reg_set_val_inplace(&mut reg, 0x1, 0x3, 31);
+    }
+
+    #[test]
+    #[should_panic(expected = "val has bits outside setmask")]
+    fn inplace_rejects_value_outside_mask() {
+        let mut reg = 0;
+        reg_set_val_inplace(&mut reg, 0x10, 0xF, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "bit_position must be less than 32")]
+    fn inplace_rejects_position_32() {
+        let mut reg = 0;
+        reg_set_bit_inplace(&mut reg, 32);
+    }
+}
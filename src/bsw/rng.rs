@@ -1,3 +1,110 @@
 // -----------------------------------------------------------------------------
 // STM32F429 RNG (Random Number Generator) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides a polled driver for the true random number generator.
+// The RNG is clocked by PLL48CLK (the PLL "Q" output, 48 MHz after
+// `system_clock_setup`); a new 32-bit value is ready every 40 RNG clock
+// periods.
+//
+// Reference: STM32F429 Reference Manual, section 24 (RNG)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::rcc::{RccBus, rcc_enable_peripheral};
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// RNG Register Offsets (relative to RNG_BASE)
+// -----------------------------------------------------------------------------
+pub const RNG_CR: u32 = 0x00; // Control register
+pub const RNG_SR: u32 = 0x04; // Status register
+pub const RNG_DR: u32 = 0x08; // Data register
+
+// -----------------------------------------------------------------------------
+// RNG Register Bits
+// -----------------------------------------------------------------------------
+pub const RNG_CR_RNGEN_POS: u32 = 2; // RNG enable
+pub const RNG_SR_DRDY_POS: u32 = 0; // Data ready
+pub const RNG_SR_CECS_POS: u32 = 1; // Clock error current status
+pub const RNG_SR_SECS_POS: u32 = 2; // Seed error current status
+pub const RNG_SR_CEIS_POS: u32 = 5; // Clock error interrupt status
+pub const RNG_SR_SEIS_POS: u32 = 6; // Seed error interrupt status
+
+/// RCC_AHB2ENR bit of the RNG clock.
+const RCC_AHB2ENR_RNGEN_POS: u32 = 6;
+
+/// Errors reported by the RNG.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RngError {
+    /// The RNG clock is too slow (PLL48CLK < HCLK / 16). Fix the PLLQ
+    /// configuration; the RNG resumes by itself once the clock is correct.
+    ClockError,
+    /// Abnormal sequence in the seed (stuck or alternating bits). Recover by
+    /// calling `rng_init` again (RNGEN off/on restarts the generator) and
+    /// discarding the value.
+    SeedError,
+}
+
+// -----------------------------------------------------------------------------
+// Initialize RNG
+// -----------------------------------------------------------------------------
+/// Enables the RNG clock and (re)starts the generator.
+///
+/// Calling it again is the recovery for `RngError::SeedError`.
+pub fn rng_init() {
+    rcc_enable_peripheral(RccBus::Ahb2, RCC_AHB2ENR_RNGEN_POS);
+
+    let cr_addr = (RNG_BASE + RNG_CR) as *mut u32;
+    reg_set_bit(cr_addr, RNG_CR_RNGEN_POS, false);
+
+    // Clear the sticky error flags (rc_w0)
+    let sr_addr = (RNG_BASE + RNG_SR) as *mut u32;
+    reg_set_bit(sr_addr, RNG_SR_CEIS_POS, false);
+    reg_set_bit(sr_addr, RNG_SR_SEIS_POS, false);
+
+    reg_set_bit(cr_addr, RNG_CR_RNGEN_POS, true);
+}
+
+// -----------------------------------------------------------------------------
+// Read Random Numbers
+// -----------------------------------------------------------------------------
+/// Waits for the next random value.
+///
+/// # Returns
+/// * `Ok(value)`, or the error reported in RNG_SR (see `RngError` for the
+///   recovery)
+pub fn rng_next_u32() -> Result<u32, RngError> {
+    let sr_addr = (RNG_BASE + RNG_SR) as *mut u32;
+
+    loop {
+        let sr = unsafe { reg_read_ro(sr_addr) };
+        if sr & (1 << RNG_SR_SECS_POS) != 0 {
+            return Err(RngError::SeedError);
+        }
+        if sr & (1 << RNG_SR_CECS_POS) != 0 {
+            return Err(RngError::ClockError);
+        }
+        if sr & (1 << RNG_SR_DRDY_POS) != 0 {
+            return Ok(unsafe { reg_read((RNG_BASE + RNG_DR) as *mut u32) });
+        }
+    }
+}
+
+/// Fills a buffer with random bytes.
+///
+/// Seed errors are recovered automatically (re-init, value discarded); a
+/// clock error is returned since it needs a clock configuration fix.
+pub fn rng_fill(buf: &mut [u8]) -> Result<(), RngError> {
+    for chunk in buf.chunks_mut(4) {
+        let value = loop {
+            match rng_next_u32() {
+                Ok(value) => break value,
+                Err(RngError::SeedError) => rng_init(),
+                Err(err) => return Err(err),
+            }
+        };
+        chunk.copy_from_slice(&value.to_le_bytes()[..chunk.len()]);
+    }
+    Ok(())
+}
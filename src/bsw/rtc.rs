@@ -0,0 +1,479 @@
+// -----------------------------------------------------------------------------
+// STM32F429 RTC (Real-Time Clock) utilities
+// -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions for the RTC and its
+// backup registers on the STM32F429 microcontroller.
+//
+// The RTC and the backup registers live in the backup domain, which is write
+// protected after reset. Call `rcc_enable_power_clock()` and
+// `pwr_enable_backup_access()` before writing to them (`rtc_backup_write`
+// does this itself).
+//
+// Most RTC registers are additionally protected by the RTC write protection
+// (WPR key sequence), see `rtc_write_protect_disable`.
+//
+// `rtc_init` does all of this and starts the calendar from the 32.768 kHz
+// LSE crystal. The calendar keeps running across resets (and from VBAT),
+// so `rtc_init` leaves an already initialized calendar untouched.
+//
+// Reference: STM32F429 Reference Manual, section 26.6 (RTC registers)
+// -----------------------------------------------------------------------------
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::bsw::exti::*; // EXTI lines for the RTC interrupts
+use crate::bsw::nvic_cm4::*; // NVIC interrupt enable
+use crate::bsw::pwr::{pwr_backup_access_enabled, pwr_enable_backup_access};
+use crate::bsw::rcc::{RtcClockSource, rcc_enable_lse, rcc_enable_power_clock, rcc_set_rtc_clock};
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// RTC Register Offsets (relative to RTC_BKP_BASE)
+// -----------------------------------------------------------------------------
+pub const RTC_TR: u32 = 0x00; // Time register
+pub const RTC_DR: u32 = 0x04; // Date register
+pub const RTC_CR: u32 = 0x08; // Control register
+pub const RTC_ISR: u32 = 0x0C; // Initialization and status register
+pub const RTC_PRER: u32 = 0x10; // Prescaler register
+pub const RTC_WUTR: u32 = 0x14; // Wakeup timer register
+pub const RTC_CALIBR: u32 = 0x18; // Calibration register
+pub const RTC_ALRMAR: u32 = 0x1C; // Alarm A register
+pub const RTC_ALRMBR: u32 = 0x20; // Alarm B register
+pub const RTC_WPR: u32 = 0x24; // Write protection register
+pub const RTC_SSR: u32 = 0x28; // Sub second register
+pub const RTC_SHIFTR: u32 = 0x2C; // Shift control register
+pub const RTC_TSTR: u32 = 0x30; // Time stamp time register
+pub const RTC_TSDR: u32 = 0x34; // Time stamp date register
+pub const RTC_TSSSR: u32 = 0x38; // Time stamp sub second register
+pub const RTC_CALR: u32 = 0x3C; // Calibration register
+pub const RTC_TAFCR: u32 = 0x40; // Tamper and alternate function configuration register
+pub const RTC_ALRMASSR: u32 = 0x44; // Alarm A sub second register
+pub const RTC_ALRMBSSR: u32 = 0x48; // Alarm B sub second register
+pub const RTC_BKP0R: u32 = 0x50; // Backup register 0 (BKP1R..BKP19R follow every 4 bytes)
+
+/// Number of 32-bit RTC backup registers (RTC_BKP0R..RTC_BKP19R).
+pub const RTC_BKP_COUNT: u32 = 20;
+
+// -----------------------------------------------------------------------------
+// RTC Register Bits
+// -----------------------------------------------------------------------------
+pub const RTC_CR_WUCKSEL_POS: u32 = 0; // Wakeup clock selection WUCKSEL[2:0]
+pub const RTC_CR_ALRAE_POS: u32 = 8; // Alarm A enable
+pub const RTC_CR_WUTE_POS: u32 = 10; // Wakeup timer enable
+pub const RTC_CR_ALRAIE_POS: u32 = 12; // Alarm A interrupt enable
+pub const RTC_CR_WUTIE_POS: u32 = 14; // Wakeup timer interrupt enable
+
+pub const RTC_ISR_ALRAWF_POS: u32 = 0; // Alarm A write flag
+pub const RTC_ISR_WUTWF_POS: u32 = 2; // Wakeup timer write flag
+pub const RTC_ISR_INITS_POS: u32 = 4; // Initialization status (calendar set)
+pub const RTC_ISR_RSF_POS: u32 = 5; // Registers synchronization flag
+pub const RTC_ISR_INITF_POS: u32 = 6; // Initialization flag (init mode entered)
+pub const RTC_ISR_INIT_POS: u32 = 7; // Initialization mode
+pub const RTC_ISR_ALRAF_POS: u32 = 8; // Alarm A flag
+pub const RTC_ISR_WUTF_POS: u32 = 10; // Wakeup timer flag
+
+pub const RTC_ALRMAR_MSK4_POS: u32 = 31; // Alarm A date/weekday mask (1 = don't care)
+
+// WPR unlock keys (written in this order)
+pub const RTC_WPR_KEY1: u32 = 0xCA;
+pub const RTC_WPR_KEY2: u32 = 0x53;
+/// Any other value re-enables the write protection.
+pub const RTC_WPR_LOCK: u32 = 0xFF;
+
+/// WUCKSEL value: ck_spre (1 Hz) clock, WUTR counts seconds.
+const RTC_WUCKSEL_CK_SPRE: u32 = 0b100;
+
+/// Timeout for the ALRAWF/WUTWF handshakes (a few RTC clock cycles).
+const RTC_WRITE_FLAG_TIMEOUT: u32 = 100_000;
+/// Timeout for INITF/RSF (up to 2 RTCCLK periods, ~60 us with LSE).
+const RTC_INIT_TIMEOUT: u32 = 1_000_000;
+/// Timeout for LSERDY (the crystal can take up to ~2 s to start).
+const RTC_LSE_TIMEOUT: u32 = 100_000_000;
+
+/// PRER values for 1 Hz ck_spre from the 32.768 kHz LSE:
+/// 32768 / (127 + 1) / (255 + 1) = 1 Hz.
+const RTC_PREDIV_A_LSE: u32 = 127;
+const RTC_PREDIV_S_LSE: u32 = 255;
+
+/// Errors reported by the RTC calendar functions.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RtcError {
+    /// The LSE crystal did not start
+    LseNotReady,
+    /// INITF (init mode) or RSF (shadow registers) did not show up in time
+    Timeout,
+    /// Time or date field out of range
+    InvalidValue,
+}
+
+/// Time of day (24-hour format).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RtcTime {
+    pub hours: u8,   // 0..23
+    pub minutes: u8, // 0..59
+    pub seconds: u8, // 0..59
+}
+
+/// Calendar date.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RtcDate {
+    pub year: u16,   // 2000..2099
+    pub month: u8,   // 1..12
+    pub day: u8,     // 1..31
+    pub weekday: u8, // 1 = Monday .. 7 = Sunday
+}
+
+// -----------------------------------------------------------------------------
+// Backup Registers
+// -----------------------------------------------------------------------------
+/// Writes a value to an RTC backup register.
+///
+/// Backup registers keep their content across system resets, Standby and
+/// VBAT mode, which makes them the place for a boot flag or an image marker.
+/// A tamper event (if enabled) erases all of them. Backup domain write
+/// access is enabled here if it is not already (PWR clock and DBP), and
+/// left enabled.
+///
+/// # Arguments
+/// * `index` - Backup register number (0..19)
+/// * `val`   - Value to store
+pub fn rtc_backup_write(index: u32, val: u32) {
+    assert!(index < RTC_BKP_COUNT, "backup register index must be 0..19");
+    if !pwr_backup_access_enabled() {
+        rcc_enable_power_clock();
+        pwr_enable_backup_access();
+    }
+
+    let bkp_addr = (RTC_BKP_BASE + RTC_BKP0R + index * 4) as *mut u32;
+    reg_set_val(bkp_addr, val);
+    debug_assert!(
+        rtc_backup_read(index) == val,
+        "backup register write ignored (backup domain locked)"
+    );
+}
+
+/// Reads an RTC backup register.
+///
+/// # Arguments
+/// * `index` - Backup register number (0..19)
+pub fn rtc_backup_read(index: u32) -> u32 {
+    assert!(index < RTC_BKP_COUNT, "backup register index must be 0..19");
+    let bkp_addr = (RTC_BKP_BASE + RTC_BKP0R + index * 4) as *mut u32;
+    unsafe { reg_read(bkp_addr) }
+}
+
+// -----------------------------------------------------------------------------
+// Write Protection
+// -----------------------------------------------------------------------------
+/// Unlocks the RTC registers (key sequence 0xCA, 0x53 into WPR).
+///
+/// Backup domain write access must be enabled first (see module notes).
+pub fn rtc_write_protect_disable() {
+    let wpr_addr = (RTC_BKP_BASE + RTC_WPR) as *mut u32;
+    reg_set_val(wpr_addr, RTC_WPR_KEY1);
+    reg_set_val(wpr_addr, RTC_WPR_KEY2);
+}
+
+/// Locks the RTC registers again.
+pub fn rtc_write_protect_enable() {
+    reg_set_val((RTC_BKP_BASE + RTC_WPR) as *mut u32, RTC_WPR_LOCK);
+}
+
+/// Enters initialization mode (calendar stopped) and waits for INITF.
+///
+/// Write protection must be disabled.
+fn rtc_enter_init_mode() -> Result<(), RtcError> {
+    let isr_addr = (RTC_BKP_BASE + RTC_ISR) as *mut u32;
+    // Set INIT; writing 1 to the rc_w0 flags leaves them unchanged
+    reg_set_val(isr_addr, 0xFFFF_FFFF);
+    if reg_wait_bit(isr_addr, RTC_ISR_INITF_POS, true, RTC_INIT_TIMEOUT) {
+        Ok(())
+    } else {
+        Err(RtcError::Timeout)
+    }
+}
+
+/// Leaves initialization mode (the calendar restarts) and waits until the
+/// shadow registers hold the new values (RSF).
+fn rtc_exit_init_mode() -> Result<(), RtcError> {
+    let isr_addr = (RTC_BKP_BASE + RTC_ISR) as *mut u32;
+    rtc_clear_isr_flag(RTC_ISR_RSF_POS);
+    reg_set_bit(isr_addr, RTC_ISR_INIT_POS, false);
+    if reg_wait_bit(isr_addr, RTC_ISR_RSF_POS, true, RTC_INIT_TIMEOUT) {
+        Ok(())
+    } else {
+        Err(RtcError::Timeout)
+    }
+}
+
+/// Runs `write` in initialization mode with write protection disabled.
+fn rtc_write_calendar(write: impl FnOnce()) -> Result<(), RtcError> {
+    rtc_write_protect_disable();
+    let result = rtc_enter_init_mode().and_then(|()| {
+        write();
+        rtc_exit_init_mode()
+    });
+    if result.is_err() {
+        reg_set_bit(
+            (RTC_BKP_BASE + RTC_ISR) as *mut u32,
+            RTC_ISR_INIT_POS,
+            false,
+        );
+    }
+    rtc_write_protect_enable();
+    result
+}
+
+/// Clears one rc_w0 flag in RTC_ISR.
+///
+/// Writes 1 to every other flag (no effect) and preserves INIT, so flags set
+/// by hardware during the access are not lost as with a read-modify-write.
+fn rtc_clear_isr_flag(flag_pos: u32) {
+    let isr_addr = (RTC_BKP_BASE + RTC_ISR) as *mut u32;
+    let init = unsafe { reg_read_ro(isr_addr) } & (1 << RTC_ISR_INIT_POS);
+    reg_set_val(
+        isr_addr,
+        !((1 << flag_pos) | (1 << RTC_ISR_INIT_POS)) | init,
+    );
+}
+
+// -----------------------------------------------------------------------------
+// Initialization and Calendar
+// -----------------------------------------------------------------------------
+/// Starts the RTC from the LSE crystal.
+///
+/// Enables the PWR clock and backup domain access, starts the LSE, selects it
+/// as RTC clock and programs the prescalers for a 1 Hz calendar. If the
+/// calendar was already initialized (INITS set, e.g. after a reset with VBAT
+/// present), the prescalers and the time are kept.
+///
+/// # Returns
+/// * `Ok(())`, or `RtcError::LseNotReady` if the crystal does not start
+pub fn rtc_init() -> Result<(), RtcError> {
+    rcc_enable_power_clock();
+    pwr_enable_backup_access();
+
+    if !rcc_enable_lse(RTC_LSE_TIMEOUT) {
+        return Err(RtcError::LseNotReady);
+    }
+    rcc_set_rtc_clock(RtcClockSource::Lse);
+
+    if reg_read_bit((RTC_BKP_BASE + RTC_ISR) as *mut u32, RTC_ISR_INITS_POS) {
+        return Ok(());
+    }
+
+    // PREDIV_S must be written before PREDIV_A (two separate writes)
+    rtc_write_calendar(|| {
+        let prer_addr = (RTC_BKP_BASE + RTC_PRER) as *mut u32;
+        reg_set_val(prer_addr, RTC_PREDIV_S_LSE);
+        reg_set_val(prer_addr, (RTC_PREDIV_A_LSE << 16) | RTC_PREDIV_S_LSE);
+    })
+}
+
+/// Sets the time of day (24-hour format).
+///
+/// # Arguments
+/// * `hour` - Hour (0..23)
+/// * `min`  - Minute (0..59)
+/// * `sec`  - Second (0..59)
+pub fn rtc_set_time(hour: u8, min: u8, sec: u8) -> Result<(), RtcError> {
+    if hour >= 24 || min >= 60 || sec >= 60 {
+        return Err(RtcError::InvalidValue);
+    }
+
+    // BCD fields: HT/HU bits 21:16, MNT/MNU bits 14:8, ST/SU bits 6:0, PM = 0
+    let tr = ((rtc_to_bcd(hour) as u32) << 16)
+        | ((rtc_to_bcd(min) as u32) << 8)
+        | rtc_to_bcd(sec) as u32;
+
+    rtc_write_calendar(|| reg_set_val((RTC_BKP_BASE + RTC_TR) as *mut u32, tr))
+}
+
+/// Sets the date.
+///
+/// # Arguments
+/// * `year`    - Year (2000..2099)
+/// * `month`   - Month (1..12)
+/// * `day`     - Day of the month (1..31)
+/// * `weekday` - Day of the week (1 = Monday .. 7 = Sunday)
+pub fn rtc_set_date(year: u16, month: u8, day: u8, weekday: u8) -> Result<(), RtcError> {
+    if !(2000..=2099).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || !(1..=7).contains(&weekday)
+    {
+        return Err(RtcError::InvalidValue);
+    }
+
+    // BCD fields: YT/YU bits 23:16, WDU bits 15:13, MT/MU bits 12:8, DT/DU bits 5:0
+    let dr = ((rtc_to_bcd((year - 2000) as u8) as u32) << 16)
+        | ((weekday as u32) << 13)
+        | ((rtc_to_bcd(month) as u32) << 8)
+        | rtc_to_bcd(day) as u32;
+
+    rtc_write_calendar(|| reg_set_val((RTC_BKP_BASE + RTC_DR) as *mut u32, dr))
+}
+
+/// Reads the time of day.
+pub fn rtc_get_time() -> RtcTime {
+    // Reading TR freezes the DR shadow register until DR is read
+    let tr = unsafe { reg_read((RTC_BKP_BASE + RTC_TR) as *mut u32) };
+    let _ = unsafe { reg_read((RTC_BKP_BASE + RTC_DR) as *mut u32) };
+
+    RtcTime {
+        hours: rtc_from_bcd(((tr >> 16) & 0x3F) as u8),
+        minutes: rtc_from_bcd(((tr >> 8) & 0x7F) as u8),
+        seconds: rtc_from_bcd((tr & 0x7F) as u8),
+    }
+}
+
+/// Reads the date.
+pub fn rtc_get_date() -> RtcDate {
+    let dr = unsafe { reg_read((RTC_BKP_BASE + RTC_DR) as *mut u32) };
+
+    RtcDate {
+        year: 2000 + rtc_from_bcd(((dr >> 16) & 0xFF) as u8) as u16,
+        month: rtc_from_bcd(((dr >> 8) & 0x1F) as u8),
+        day: rtc_from_bcd((dr & 0x3F) as u8),
+        weekday: ((dr >> 13) & 0x7) as u8,
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Wakeup Timer and Alarm A
+// -----------------------------------------------------------------------------
+// Both raise an interrupt through EXTI (wakeup: line 22, alarm: line 17) and
+// can therefore also wake the MCU from Stop mode. The RTC clock (LSE or LSI)
+// must be selected and enabled in RCC_BDCR, and the calendar running.
+
+/// Set by `RTC_WKUP_Handler` on every wakeup timer event.
+static RTC_WAKEUP_EVENT: AtomicBool = AtomicBool::new(false);
+/// Set by `RTC_Alarm_Handler` when alarm A fires.
+static RTC_ALARM_A_EVENT: AtomicBool = AtomicBool::new(false);
+
+/// Starts the periodic wakeup timer with interrupt.
+///
+/// Uses the 1 Hz ck_spre clock, so the period is `seconds` (1..=65535).
+///
+/// # Arguments
+/// * `seconds` - Wakeup period in seconds
+pub fn rtc_set_wakeup_timer(seconds: u16) {
+    assert!(seconds > 0, "wakeup period must be at least 1 s");
+
+    let cr_addr = (RTC_BKP_BASE + RTC_CR) as *mut u32;
+
+    rtc_write_protect_disable();
+
+    // WUTR and WUCKSEL can only be written while WUTE = 0 and WUTWF = 1
+    reg_set_bit(cr_addr, RTC_CR_WUTE_POS, false);
+    let _ = reg_wait_bit(
+        (RTC_BKP_BASE + RTC_ISR) as *mut u32,
+        RTC_ISR_WUTWF_POS,
+        true,
+        RTC_WRITE_FLAG_TIMEOUT,
+    );
+
+    // The timer counts WUT + 1 clock cycles
+    reg_set_val((RTC_BKP_BASE + RTC_WUTR) as *mut u32, seconds as u32 - 1);
+    reg_set_bits(cr_addr, RTC_WUCKSEL_CK_SPRE, RTC_CR_WUCKSEL_POS, 3);
+
+    rtc_clear_isr_flag(RTC_ISR_WUTF_POS);
+    reg_set_bit(cr_addr, RTC_CR_WUTIE_POS, true);
+    reg_set_bit(cr_addr, RTC_CR_WUTE_POS, true);
+
+    rtc_write_protect_enable();
+
+    // Route the wakeup event: EXTI line 22, rising edge -> RTC_WKUP IRQ
+    exti_set_trigger(EXTI_LINE_RTC_WAKEUP, ExtiTrigger::Rising);
+    exti_clear_pending(EXTI_LINE_RTC_WAKEUP);
+    exti_enable_interrupt(EXTI_LINE_RTC_WAKEUP);
+    nvic_enable_irq(IRQn::RTC_WKUP);
+}
+
+/// Sets alarm A to fire every day at `hour:min:sec` (24-hour format).
+///
+/// # Arguments
+/// * `hour` - Hour (0..23)
+/// * `min`  - Minute (0..59)
+/// * `sec`  - Second (0..59)
+pub fn rtc_set_alarm_a(hour: u8, min: u8, sec: u8) {
+    assert!(hour < 24 && min < 60 && sec < 60, "invalid alarm time");
+
+    let cr_addr = (RTC_BKP_BASE + RTC_CR) as *mut u32;
+
+    // BCD fields: HT/HU bits 21:16, MNT/MNU bits 14:8, ST/SU bits 6:0, PM = 0
+    let alrmar = (1 << RTC_ALRMAR_MSK4_POS) // Ignore the date: fire every day
+        | ((rtc_to_bcd(hour) as u32) << 16)
+        | ((rtc_to_bcd(min) as u32) << 8)
+        | rtc_to_bcd(sec) as u32;
+
+    rtc_write_protect_disable();
+
+    // ALRMAR can only be written while ALRAE = 0 and ALRAWF = 1
+    reg_set_bit(cr_addr, RTC_CR_ALRAE_POS, false);
+    let _ = reg_wait_bit(
+        (RTC_BKP_BASE + RTC_ISR) as *mut u32,
+        RTC_ISR_ALRAWF_POS,
+        true,
+        RTC_WRITE_FLAG_TIMEOUT,
+    );
+
+    reg_set_val((RTC_BKP_BASE + RTC_ALRMAR) as *mut u32, alrmar);
+
+    rtc_clear_isr_flag(RTC_ISR_ALRAF_POS);
+    reg_set_bit(cr_addr, RTC_CR_ALRAIE_POS, true);
+    reg_set_bit(cr_addr, RTC_CR_ALRAE_POS, true);
+
+    rtc_write_protect_enable();
+
+    // Route the alarm event: EXTI line 17, rising edge -> RTC_Alarm IRQ
+    exti_set_trigger(EXTI_LINE_RTC_ALARM, ExtiTrigger::Rising);
+    exti_clear_pending(EXTI_LINE_RTC_ALARM);
+    exti_enable_interrupt(EXTI_LINE_RTC_ALARM);
+    nvic_enable_irq(IRQn::RTC_Alarm);
+}
+
+/// Returns true (once) if a wakeup timer event occurred since the last call.
+pub fn rtc_take_wakeup_event() -> bool {
+    RTC_WAKEUP_EVENT.swap(false, Ordering::AcqRel)
+}
+
+/// Returns true (once) if alarm A fired since the last call.
+pub fn rtc_take_alarm_a_event() -> bool {
+    RTC_ALARM_A_EVENT.swap(false, Ordering::AcqRel)
+}
+
+/// Converts a binary value (0..99) to BCD.
+fn rtc_to_bcd(val: u8) -> u8 {
+    ((val / 10) << 4) | (val % 10)
+}
+
+/// Converts a BCD value to binary.
+fn rtc_from_bcd(bcd: u8) -> u8 {
+    (bcd >> 4) * 10 + (bcd & 0x0F)
+}
+
+// -----------------------------------------------------------------------------
+// Interrupt Handlers
+// -----------------------------------------------------------------------------
+/// RTC wakeup interrupt (EXTI line 22): clears the flags and records the event.
+#[unsafe(no_mangle)]
+pub extern "C" fn RTC_WKUP_Handler() {
+    if reg_read_bit((RTC_BKP_BASE + RTC_ISR) as *mut u32, RTC_ISR_WUTF_POS) {
+        rtc_clear_isr_flag(RTC_ISR_WUTF_POS);
+        RTC_WAKEUP_EVENT.store(true, Ordering::Release);
+    }
+    exti_clear_pending(EXTI_LINE_RTC_WAKEUP);
+}
+
+/// RTC alarm interrupt (EXTI line 17): clears the flags and records the event.
+#[unsafe(no_mangle)]
+pub extern "C" fn RTC_Alarm_Handler() {
+    if reg_read_bit((RTC_BKP_BASE + RTC_ISR) as *mut u32, RTC_ISR_ALRAF_POS) {
+        rtc_clear_isr_flag(RTC_ISR_ALRAF_POS);
+        RTC_ALARM_A_EVENT.store(true, Ordering::Release);
+    }
+    exti_clear_pending(EXTI_LINE_RTC_ALARM);
+}
@@ -1,3 +1,217 @@
 // -----------------------------------------------------------------------------
 // STM32F429 SPI (Serial Peripheral Interface) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides a polled SPI master driver (8-bit frames, full duplex,
+// software NSS). The chip select is an ordinary GPIO driven by the caller,
+// and SCK/MISO/MOSI must be configured as alternate function separately
+// (AF5 for SPI1/2/4/5/6, AF6 for SPI3).
+//
+// On the STM32F429I-DISC1, SPI5 (PF7/PF8/PF9) connects the L3GD20 gyroscope
+// and the LCD controller.
+//
+// Example:
+// ```
+// let config = SpiConfig::new()
+//     .prescaler(SpiPrescaler::Div16)
+//     .mode(true, true); // CPOL = 1, CPHA = 1 (SPI mode 3)
+// spi_init(SPI5_BASE, config);
+// let id = spi_transfer_byte(SPI5_BASE, 0x8F);
+// ```
+//
+// Reference: STM32F429 Reference Manual, section 28.5 (SPI registers)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::rcc::{RccBus, rcc_enable_peripheral};
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// SPI Register Offsets (relative to SPIx base address)
+// -----------------------------------------------------------------------------
+pub const SPI_CR1: u32 = 0x00; // Control register 1
+pub const SPI_CR2: u32 = 0x04; // Control register 2
+pub const SPI_SR: u32 = 0x08; // Status register
+pub const SPI_DR: u32 = 0x0C; // Data register
+pub const SPI_CRCPR: u32 = 0x10; // CRC polynomial register
+pub const SPI_RXCRCR: u32 = 0x14; // RX CRC register
+pub const SPI_TXCRCR: u32 = 0x18; // TX CRC register
+pub const SPI_I2SCFGR: u32 = 0x1C; // I2S configuration register
+pub const SPI_I2SPR: u32 = 0x20; // I2S prescaler register
+
+// -----------------------------------------------------------------------------
+// SPI Register Bits
+// -----------------------------------------------------------------------------
+pub const SPI_CR1_CPHA_POS: u32 = 0; // Clock phase
+pub const SPI_CR1_CPOL_POS: u32 = 1; // Clock polarity
+pub const SPI_CR1_MSTR_POS: u32 = 2; // Master selection
+pub const SPI_CR1_BR_POS: u32 = 3; // Baud rate control (bits 5:3)
+pub const SPI_CR1_SPE_POS: u32 = 6; // SPI enable
+pub const SPI_CR1_LSBFIRST_POS: u32 = 7; // Frame format
+pub const SPI_CR1_SSI_POS: u32 = 8; // Internal slave select
+pub const SPI_CR1_SSM_POS: u32 = 9; // Software slave management
+pub const SPI_SR_RXNE_POS: u32 = 0; // Receive buffer not empty
+pub const SPI_SR_TXE_POS: u32 = 1; // Transmit buffer empty
+pub const SPI_SR_BSY_POS: u32 = 7; // Busy flag
+
+// -----------------------------------------------------------------------------
+// Configuration
+// -----------------------------------------------------------------------------
+/// SCK = PCLK / divider (PCLK2 for SPI1/4/5/6, PCLK1 for SPI2/3).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SpiPrescaler {
+    Div2 = 0b000,
+    Div4 = 0b001,
+    Div8 = 0b010,
+    Div16 = 0b011,
+    Div32 = 0b100,
+    Div64 = 0b101,
+    Div128 = 0b110,
+    Div256 = 0b111,
+}
+
+/// SPI master configuration, built with chained setters.
+///
+/// Defaults: PCLK/8, mode 0 (CPOL = 0, CPHA = 0), MSB first.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SpiConfig {
+    pub prescaler: SpiPrescaler,
+    /// Clock idles high when true
+    pub cpol: bool,
+    /// Data captured on the second clock edge when true
+    pub cpha: bool,
+    /// Least significant bit first when true
+    pub lsb_first: bool,
+}
+
+impl SpiConfig {
+    /// Creates the default configuration.
+    pub const fn new() -> Self {
+        SpiConfig {
+            prescaler: SpiPrescaler::Div8,
+            cpol: false,
+            cpha: false,
+            lsb_first: false,
+        }
+    }
+
+    /// Sets the baud rate prescaler.
+    pub const fn prescaler(mut self, prescaler: SpiPrescaler) -> Self {
+        self.prescaler = prescaler;
+        self
+    }
+
+    /// Sets clock polarity and phase (SPI mode = CPOL * 2 + CPHA).
+    pub const fn mode(mut self, cpol: bool, cpha: bool) -> Self {
+        self.cpol = cpol;
+        self.cpha = cpha;
+        self
+    }
+
+    /// Selects LSB-first (true) or MSB-first (false) bit order.
+    pub const fn lsb_first(mut self, lsb_first: bool) -> Self {
+        self.lsb_first = lsb_first;
+        self
+    }
+}
+
+impl Default for SpiConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Enable SPI Peripheral Clock
+// -----------------------------------------------------------------------------
+/// Enables the clock for a specific SPI.
+///
+/// # Arguments
+/// * `spi_base` - The base address of the SPI (e.g., `SPI5_BASE`)
+pub fn spi_enable_clock(spi_base: u32) {
+    match spi_base {
+        SPI2_BASE => rcc_enable_peripheral(RccBus::Apb1, 14), // SPI2EN
+        SPI3_BASE => rcc_enable_peripheral(RccBus::Apb1, 15), // SPI3EN
+        SPI1_BASE => rcc_enable_peripheral(RccBus::Apb2, 12), // SPI1EN
+        SPI4_BASE => rcc_enable_peripheral(RccBus::Apb2, 13), // SPI4EN
+        SPI5_BASE => rcc_enable_peripheral(RccBus::Apb2, 20), // SPI5EN
+        SPI6_BASE => rcc_enable_peripheral(RccBus::Apb2, 21), // SPI6EN
+        _ => {
+            // Unknown SPI: do nothing
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Initialize SPI
+// -----------------------------------------------------------------------------
+/// Enables the SPI clock and configures it as master with software NSS.
+///
+/// # Arguments
+/// * `spi_base` - The base address of the SPI (e.g., `SPI5_BASE`)
+/// * `config`   - Prescaler, clock mode and bit order
+pub fn spi_init(spi_base: u32, config: SpiConfig) {
+    spi_enable_clock(spi_base);
+
+    let cr1_addr = (spi_base + SPI_CR1) as *mut u32;
+
+    // Disable the SPI while changing the configuration
+    reg_set_val(cr1_addr, 0);
+
+    // No interrupts, no DMA, Motorola frame format
+    reg_set_val((spi_base + SPI_CR2) as *mut u32, 0);
+
+    // SSM + SSI keep the internal NSS high, otherwise the master faults (MODF)
+    let cr1 = ((config.cpha as u32) << SPI_CR1_CPHA_POS)
+        | ((config.cpol as u32) << SPI_CR1_CPOL_POS)
+        | (1 << SPI_CR1_MSTR_POS)
+        | ((config.prescaler as u32) << SPI_CR1_BR_POS)
+        | ((config.lsb_first as u32) << SPI_CR1_LSBFIRST_POS)
+        | (1 << SPI_CR1_SSI_POS)
+        | (1 << SPI_CR1_SSM_POS);
+    reg_set_val(cr1_addr, cr1);
+    reg_set_bit(cr1_addr, SPI_CR1_SPE_POS, true);
+}
+
+// -----------------------------------------------------------------------------
+// Transfer
+// -----------------------------------------------------------------------------
+/// Sends one byte and returns the byte received at the same time.
+///
+/// # Arguments
+/// * `spi_base` - The base address of the SPI
+/// * `b`        - Byte to send (use a dummy byte such as 0xFF to only read)
+///
+/// # Returns
+/// * The byte clocked in on MISO
+pub fn spi_transfer_byte(spi_base: u32, b: u8) -> u8 {
+    let sr_addr = (spi_base + SPI_SR) as *mut u32;
+    let dr_addr = (spi_base + SPI_DR) as *mut u32;
+
+    while !reg_read_bit(sr_addr, SPI_SR_TXE_POS) {}
+    reg_set_val(dr_addr, b as u32);
+    while !reg_read_bit(sr_addr, SPI_SR_RXNE_POS) {}
+    (unsafe { reg_read(dr_addr) } & 0xFF) as u8
+}
+
+/// Full-duplex transfer of a buffer: `tx[i]` is sent while `rx[i]` is received.
+///
+/// # Arguments
+/// * `spi_base` - The base address of the SPI
+/// * `tx`       - Bytes to send
+/// * `rx`       - Buffer for the received bytes (same length as `tx`)
+pub fn spi_transfer(spi_base: u32, tx: &[u8], rx: &mut [u8]) {
+    assert!(tx.len() == rx.len(), "tx and rx must have the same length");
+
+    for (&out, inp) in tx.iter().zip(rx.iter_mut()) {
+        *inp = spi_transfer_byte(spi_base, out);
+    }
+}
+
+/// Waits until the SPI is idle (BSY clear).
+///
+/// Call this before releasing the chip select or disabling the SPI.
+pub fn spi_wait_idle(spi_base: u32) {
+    let sr_addr = (spi_base + SPI_SR) as *mut u32;
+    while reg_read_bit(sr_addr, SPI_SR_BSY_POS) {}
+}
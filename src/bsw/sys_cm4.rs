@@ -1,3 +1,207 @@
 // -----------------------------------------------------------------------------
 // STM32F429 SYS (System Control Block) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides helper functions for the Cortex-M4 System Control
+// Block (SCB) registers that are not tied to one specific feature:
+// - System reset request through AIRCR.
+// - Vector table relocation through VTOR.
+//
+// Vector table placement
+// - The startup code's `VECTOR_TABLE` (initial stack pointer followed by the
+//   handlers) is placed by memory.x at the very start of FLASH (0x0800_0000).
+//   After reset VTOR is 0 and flash is aliased at 0x0000_0000, so the core
+//   finds the table without any setup.
+// - An application started by a bootloader is linked at an offset (FLASH
+//   ORIGIN moved in memory.x, e.g. to 0x0800_8000). The bootloader only jumps
+//   to its Reset_Handler; exceptions would still go through the bootloader's
+//   table, so the application must point VTOR at its own table first:
+//
+//   ```
+//   // First statement of main (or of SystemInit), before enabling any IRQ
+//   cortex_set_vector_table(0x0800_8000);
+//   ```
+//
+// - A table copied to SRAM (to patch handlers at runtime) works the same
+//   way; align the copy to `VTOR_ALIGNMENT`.
+//
+// AIRCR writes are ignored unless the upper half-word holds VECTKEY
+// (0x05FA); reads return 0xFA05 there instead.
+//
+// Jumping to another image (`cortex_jump_to`), e.g. the ST system
+// bootloader for a field update, must leave the core as close to its reset
+// state as possible, because the target expects to start from reset.
+// Before the call, the application should:
+// - stop every peripheral it started (DMA streams first, then timers,
+//   USARTs, USB, ...) and gate their clocks, or reset them through
+//   `rcc_reset_peripheral`;
+// - switch SYSCLK back to HSI with the PLL off (`rcc_switch_to_hsi`): the ST
+//   bootloader assumes the reset clock tree;
+// - for the system bootloader, alias system memory at 0x0000_0000
+//   (`syscfg_set_memory_remap(MemoryRemap::SystemFlash)`).
+// `cortex_jump_to` itself masks interrupts, stops SysTick, disables and
+// clears every NVIC interrupt and resets the stack pointer.
+//
+// Reference: Cortex-M4 Devices Generic User Guide, section 4.3 (SCB)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::reg_cpu_cortex_m4::*; // Cortex-M4 core register addresses
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// AIRCR Bits
+// -----------------------------------------------------------------------------
+pub const AIRCR_VECTKEY: u32 = 0x05FA; // Register key, required for every write
+pub const AIRCR_VECTKEY_POS: u32 = 16; // VECTKEY field (bits 31:16)
+pub const AIRCR_SYSRESETREQ_POS: u32 = 2; // System reset request
+pub const AIRCR_PRIGROUP_POS: u32 = 8; // Interrupt priority grouping (3 bits)
+
+// -----------------------------------------------------------------------------
+// VTOR
+// -----------------------------------------------------------------------------
+/// Required alignment of a vector table, in bytes.
+///
+/// The table must be aligned to its size rounded up to a power of two: 16
+/// core exceptions + 91 IRQs = 107 words = 428 bytes, hence 512.
+pub const VTOR_ALIGNMENT: u32 = 512;
+
+// -----------------------------------------------------------------------------
+// System Reset
+// -----------------------------------------------------------------------------
+/// Resets the whole MCU (core and peripherals) through AIRCR SYSRESETREQ.
+///
+/// Pending memory accesses are completed first (DSB), the priority grouping
+/// is preserved in the write, and the core then waits until the reset takes
+/// effect. After the reset, `rcc_get_reset_cause` reports `SoftReset`.
+pub fn cortex_system_reset() -> ! {
+    let aircr_addr = AIRCR_BASE as *mut u32;
+    let prigroup = reg_read_bits(aircr_addr, AIRCR_PRIGROUP_POS, 3);
+
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        core::arch::asm!("dsb");
+    }
+    reg_set_val(
+        aircr_addr,
+        (AIRCR_VECTKEY << AIRCR_VECTKEY_POS)
+            | (prigroup << AIRCR_PRIGROUP_POS)
+            | (1 << AIRCR_SYSRESETREQ_POS),
+    );
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        core::arch::asm!("dsb");
+    }
+
+    // The reset is not instantaneous
+    loop {}
+}
+
+// -----------------------------------------------------------------------------
+// Vector Table Relocation
+// -----------------------------------------------------------------------------
+/// Points VTOR at a vector table.
+///
+/// Do this with interrupts disabled or before enabling any: an exception
+/// taken while the new table is incomplete jumps to garbage.
+///
+/// # Arguments
+/// * `base` - Address of the table (its first word is the initial SP),
+///   aligned to `VTOR_ALIGNMENT`
+pub fn cortex_set_vector_table(base: u32) {
+    assert!(
+        base.is_multiple_of(VTOR_ALIGNMENT),
+        "vector table must be 512-byte aligned"
+    );
+
+    reg_set_val(VTOR_BASE as *mut u32, base);
+    // Make sure the next exception uses the new table
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        core::arch::asm!("dsb", "isb");
+    }
+}
+
+/// Returns the current vector table address (VTOR).
+pub fn cortex_get_vector_table() -> u32 {
+    unsafe { reg_read(VTOR_BASE as *mut u32) }
+}
+
+// -----------------------------------------------------------------------------
+// Jump to Another Image
+// -----------------------------------------------------------------------------
+/// Start of the system memory holding the ST bootloader (AN2606).
+pub const SYSTEM_MEMORY_BASE: u32 = 0x1FFF_0000;
+
+/// Number of NVIC ICER/ICPR registers covering the 91 IRQs.
+const NVIC_IRQ_REGS: u32 = 3;
+/// ICSR bit clearing a pending SysTick exception.
+const ICSR_PENDSTCLR_POS: u32 = 25;
+
+/// Starts the image whose vector table is at `address`. Never returns.
+///
+/// Reads the initial SP from `address` and the reset vector from
+/// `address + 4`, masks interrupts, stops SysTick, disables and clears all
+/// NVIC interrupts, optionally points VTOR at the image, loads MSP (and
+/// selects it, in case the caller runs on PSP) and branches to the reset
+/// vector. PRIMASK is cleared again right before the branch: nothing can
+/// fire anymore, and the target expects the reset value.
+///
+/// See the module notes for the cleanup the caller has to do first.
+///
+/// # Arguments
+/// * `address` - Vector table of the image, e.g. `SYSTEM_MEMORY_BASE` or an
+///   application bootloader in flash
+/// * `set_vtor` - Point VTOR at `address` (needs `VTOR_ALIGNMENT`). Images
+///   that set VTOR themselves do not need it
+pub fn cortex_jump_to(address: u32, set_vtor: bool) -> ! {
+    let stack_pointer = unsafe { reg_read(address as *mut u32) };
+    let reset_vector = unsafe { reg_read((address + 4) as *mut u32) };
+    assert!(
+        reset_vector & 1 == 1,
+        "reset vector must be a Thumb address (no image at this address?)"
+    );
+
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        core::arch::asm!("cpsid i");
+    }
+
+    // SysTick off, no pending SysTick exception left behind
+    reg_set_val(STCSR_BASE as *mut u32, 0);
+    reg_set_val(ICSR_BASE as *mut u32, 1 << ICSR_PENDSTCLR_POS);
+
+    for i in 0..NVIC_IRQ_REGS {
+        reg_set_val((NVIC_ICER_BASE + i * 4) as *mut u32, 0xFFFF_FFFF);
+        reg_set_val((NVIC_ICPR_BASE + i * 4) as *mut u32, 0xFFFF_FFFF);
+    }
+
+    if set_vtor {
+        cortex_set_vector_table(address);
+    }
+
+    cortex_start_image(stack_pointer, reset_vector)
+}
+
+/// Loads MSP, selects it (CONTROL = 0) and branches to `entry`.
+#[cfg(target_arch = "arm")]
+fn cortex_start_image(stack_pointer: u32, entry: u32) -> ! {
+    unsafe {
+        core::arch::asm!(
+            "msr control, {zero}",
+            "isb",
+            "msr msp, {sp}",
+            "cpsie i",
+            "bx {entry}",
+            zero = in(reg) 0u32,
+            sp = in(reg) stack_pointer,
+            entry = in(reg) entry,
+            options(noreturn),
+        )
+    }
+}
+
+/// Host builds (unit tests) have no image to start.
+#[cfg(not(target_arch = "arm"))]
+fn cortex_start_image(_stack_pointer: u32, _entry: u32) -> ! {
+    panic!("cortex_jump_to requires a Cortex-M target")
+}
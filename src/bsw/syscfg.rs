@@ -0,0 +1,145 @@
+// -----------------------------------------------------------------------------
+// STM32F429 SYSCFG (System Configuration Controller) utilities
+// -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions for the SYSCFG block:
+// - Memory remap (MEMRMP): which memory is aliased at address 0x0000_0000
+//   (and thus where the core fetches the vector table after a reset unless
+//   VTOR says otherwise).
+// - EXTI source selection (EXTICR1..4): which GPIO port drives EXTI lines
+//   0..15. Each register holds 4 lines, 4 bits per line:
+//
+//     line:      0..3      4..7      8..11     12..15
+//     register:  EXTICR1   EXTICR2   EXTICR3   EXTICR4
+//     field:     bits (line % 4) * 4 + 3 .. (line % 4) * 4
+//
+// The SYSCFG clock (APB2) must be enabled before any register is written.
+//
+// Reference: STM32F429 Reference Manual, section 9.2 (SYSCFG registers)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::gpio::GpioPort;
+use crate::bsw::rcc::rcc_enable_syscfg_clock;
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// SYSCFG Register Offsets (relative to SYSCFG_BASE)
+// -----------------------------------------------------------------------------
+pub const SYSCFG_MEMRMP: u32 = 0x00; // Memory remap register
+pub const SYSCFG_PMC: u32 = 0x04; // Peripheral mode configuration register
+pub const SYSCFG_EXTICR1: u32 = 0x08; // EXTI lines 0..3 source port
+pub const SYSCFG_EXTICR2: u32 = 0x0C; // EXTI lines 4..7 source port
+pub const SYSCFG_EXTICR3: u32 = 0x10; // EXTI lines 8..11 source port
+pub const SYSCFG_EXTICR4: u32 = 0x14; // EXTI lines 12..15 source port
+pub const SYSCFG_CMPCR: u32 = 0x20; // Compensation cell control register
+
+// -----------------------------------------------------------------------------
+// SYSCFG Bit Positions
+// -----------------------------------------------------------------------------
+pub const SYSCFG_MEMRMP_MEM_MODE_POS: u32 = 0; // Memory mapping selection (3 bits)
+
+/// Memory aliased at address 0x0000_0000 (MEMRMP MEM_MODE).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MemoryRemap {
+    /// Main flash memory (default when booting with BOOT0 = 0)
+    MainFlash = 0b000,
+    /// System flash memory (ST bootloader)
+    SystemFlash = 0b001,
+    /// FMC bank 1 (NOR/PSRAM 1 and 2)
+    FmcBank1 = 0b010,
+    /// Embedded SRAM1 (112 KB)
+    Sram1 = 0b011,
+    /// FMC SDRAM bank 1
+    FmcSdram = 0b100,
+}
+
+// -----------------------------------------------------------------------------
+// Enable SYSCFG Clock
+// -----------------------------------------------------------------------------
+/// Enables the SYSCFG clock (APB2ENR SYSCFGEN).
+pub fn syscfg_enable_clock() {
+    rcc_enable_syscfg_clock();
+}
+
+// -----------------------------------------------------------------------------
+// Memory Remap
+// -----------------------------------------------------------------------------
+/// Selects the memory aliased at address 0x0000_0000.
+///
+/// Does not move VTOR: after remapping, set VTOR explicitly if interrupts
+/// should use the vector table of the newly mapped memory.
+///
+/// # Arguments
+/// * `mode` - Memory to map at 0x0000_0000
+pub fn syscfg_set_memory_remap(mode: MemoryRemap) {
+    syscfg_enable_clock();
+    reg_set_bits(
+        (SYSCFG_BASE + SYSCFG_MEMRMP) as *mut u32,
+        mode as u32,
+        SYSCFG_MEMRMP_MEM_MODE_POS,
+        3,
+    );
+}
+
+// -----------------------------------------------------------------------------
+// EXTI Source Selection
+// -----------------------------------------------------------------------------
+/// Returns the EXTICR register offset and field position for an EXTI line.
+///
+/// # Arguments
+/// * `line` - EXTI line (0..15)
+///
+/// # Returns
+/// * `(offset, pos)` - Offset relative to SYSCFG_BASE and bit position of
+///   the line's 4-bit field
+pub const fn syscfg_exticr_location(line: u32) -> (u32, u32) {
+    assert!(line < 16, "GPIO EXTI line must be 0..15");
+    (SYSCFG_EXTICR1 + (line / 4) * 4, (line % 4) * 4)
+}
+
+/// Routes pin `line` of a GPIO port to EXTI line `line`.
+///
+/// Line n can only be driven by pin n of one port at a time; selecting a
+/// new port replaces the previous one. Enables the SYSCFG clock.
+///
+/// # Arguments
+/// * `line` - EXTI line = pin number (0..15)
+/// * `port` - The base address of the GPIO port (e.g., `GPIOA_BASE`)
+pub fn syscfg_exti_select(line: u32, port: u32) {
+    let Some(port) = GpioPort::from_base(port) else {
+        panic!("port must be a GPIO port base address");
+    };
+
+    syscfg_enable_clock();
+
+    // Field value: 0 = PA, 1 = PB, ..., 10 = PK
+    let (offset, pos) = syscfg_exticr_location(line);
+    reg_set_bits((SYSCFG_BASE + offset) as *mut u32, port as u32, pos, 4);
+}
+
+// -----------------------------------------------------------------------------
+// Unit tests (host): cargo test --target x86_64-unknown-linux-gnu
+// -----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exticr_location_first_and_last_line_of_register() {
+        assert_eq!(syscfg_exticr_location(0), (SYSCFG_EXTICR1, 0));
+        assert_eq!(syscfg_exticr_location(3), (SYSCFG_EXTICR1, 12));
+    }
+
+    #[test]
+    fn exticr_location_next_registers() {
+        assert_eq!(syscfg_exticr_location(4), (SYSCFG_EXTICR2, 0));
+        assert_eq!(syscfg_exticr_location(15), (SYSCFG_EXTICR4, 12));
+    }
+
+    #[test]
+    #[should_panic(expected = "GPIO EXTI line must be 0..15")]
+    fn exticr_location_rejects_line_16() {
+        syscfg_exticr_location(16);
+    }
+}
@@ -1,3 +1,581 @@
 // -----------------------------------------------------------------------------
 // STM32F429 TIM (Timer) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions for the timers
+// TIM1..TIM14. The register layout is shared; not every timer implements
+// every register (e.g., basic timers TIM6/TIM7 have no capture/compare).
+//
+// Timer clocks: when an APB prescaler is not 1, the timers on that bus run
+// at twice the APB clock. After `system_clock_setup` (APB1 = HCLK/4,
+// APB2 = HCLK/2) this gives 90 MHz for APB1 timers (TIM2..7, TIM12..14) and
+// 180 MHz for APB2 timers (TIM1, TIM8..11).
+//
+// Update (overflow) rate: f_tim / ((PSC + 1) * (ARR + 1))
+//
+// Reference: STM32F429 Reference Manual, sections 17-20 (TIM registers)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::rcc::*; // RCC peripheral clock enable
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// TIM Register Offsets (relative to TIMx base address)
+// -----------------------------------------------------------------------------
+pub const TIM_CR1: u32 = 0x00; // Control register 1
+pub const TIM_CR2: u32 = 0x04; // Control register 2
+pub const TIM_SMCR: u32 = 0x08; // Slave mode control register
+pub const TIM_DIER: u32 = 0x0C; // DMA/interrupt enable register
+pub const TIM_SR: u32 = 0x10; // Status register
+pub const TIM_EGR: u32 = 0x14; // Event generation register
+pub const TIM_CCMR1: u32 = 0x18; // Capture/compare mode register 1
+pub const TIM_CCMR2: u32 = 0x1C; // Capture/compare mode register 2
+pub const TIM_CCER: u32 = 0x20; // Capture/compare enable register
+pub const TIM_CNT: u32 = 0x24; // Counter
+pub const TIM_PSC: u32 = 0x28; // Prescaler
+pub const TIM_ARR: u32 = 0x2C; // Auto-reload register
+pub const TIM_RCR: u32 = 0x30; // Repetition counter register (TIM1/TIM8)
+pub const TIM_CCR1: u32 = 0x34; // Capture/compare register 1
+pub const TIM_CCR2: u32 = 0x38; // Capture/compare register 2
+pub const TIM_CCR3: u32 = 0x3C; // Capture/compare register 3
+pub const TIM_CCR4: u32 = 0x40; // Capture/compare register 4
+pub const TIM_BDTR: u32 = 0x44; // Break and dead-time register (TIM1/TIM8)
+pub const TIM_DCR: u32 = 0x48; // DMA control register
+pub const TIM_DMAR: u32 = 0x4C; // DMA address for full transfer
+pub const TIM_OR: u32 = 0x50; // Option register (TIM2, TIM5, TIM11 input remap)
+
+// -----------------------------------------------------------------------------
+// TIM Register Bits
+// -----------------------------------------------------------------------------
+pub const TIM_CR1_CEN_POS: u32 = 0; // Counter enable
+pub const TIM_CR1_DIR_POS: u32 = 4; // Direction (0: up, 1: down)
+pub const TIM_CR1_ARPE_POS: u32 = 7; // Auto-reload preload enable
+pub const TIM_CR2_MMS_POS: u32 = 4; // Master mode selection MMS[2:0]
+pub const TIM_SMCR_SMS_POS: u32 = 0; // Slave mode selection SMS[2:0]
+pub const TIM_DIER_UIE_POS: u32 = 0; // Update interrupt enable
+pub const TIM_SR_UIF_POS: u32 = 0; // Update interrupt flag
+pub const TIM_EGR_UG_POS: u32 = 0; // Update generation
+pub const TIM_CCMR_OC1PE_POS: u32 = 3; // Output compare 1 preload enable (CCMR1/CCMR2)
+pub const TIM_CCMR_OC1M_POS: u32 = 4; // Output compare 1 mode OC1M[2:0] (CCMR1/CCMR2)
+pub const TIM_CCMR_IC1PSC_POS: u32 = 2; // Input capture 1 prescaler IC1PSC[1:0] (CCMR1/CCMR2)
+pub const TIM_CCMR_IC1F_POS: u32 = 4; // Input capture 1 filter IC1F[3:0] (CCMR1/CCMR2)
+pub const TIM_CCER_CC1P_POS: u32 = 1; // Capture/compare 1 polarity (+4 per channel)
+pub const TIM_CCER_CC1NP_POS: u32 = 3; // Capture/compare 1 complementary polarity (+4 per channel)
+pub const TIM_BDTR_MOE_POS: u32 = 15; // Main output enable (TIM1/TIM8)
+
+/// OCxM value for PWM mode 1: output active while CNT < CCRx.
+const TIM_OCM_PWM1: u32 = 0b110;
+
+// -----------------------------------------------------------------------------
+// Enable Timer Clock
+// -----------------------------------------------------------------------------
+/// Enables the bus clock of a timer.
+///
+/// TIM2..TIM7 and TIM12..TIM14 are on APB1, TIM1 and TIM8..TIM11 on APB2.
+///
+/// # Arguments
+/// * `tim_base` - The base address of the timer (e.g., `TIM2_BASE`)
+pub fn timer_enable_clock(tim_base: u32) {
+    match tim_base {
+        TIM2_BASE => rcc_enable_peripheral(RccBus::Apb1, 0), // TIM2EN
+        TIM3_BASE => rcc_enable_peripheral(RccBus::Apb1, 1), // TIM3EN
+        TIM4_BASE => rcc_enable_peripheral(RccBus::Apb1, 2), // TIM4EN
+        TIM5_BASE => rcc_enable_peripheral(RccBus::Apb1, 3), // TIM5EN
+        TIM6_BASE => rcc_enable_peripheral(RccBus::Apb1, 4), // TIM6EN
+        TIM7_BASE => rcc_enable_peripheral(RccBus::Apb1, 5), // TIM7EN
+        TIM12_BASE => rcc_enable_peripheral(RccBus::Apb1, 6), // TIM12EN
+        TIM13_BASE => rcc_enable_peripheral(RccBus::Apb1, 7), // TIM13EN
+        TIM14_BASE => rcc_enable_peripheral(RccBus::Apb1, 8), // TIM14EN
+        TIM1_BASE => rcc_enable_peripheral(RccBus::Apb2, 0), // TIM1EN
+        TIM8_BASE => rcc_enable_peripheral(RccBus::Apb2, 1), // TIM8EN
+        TIM9_BASE => rcc_enable_peripheral(RccBus::Apb2, 16), // TIM9EN
+        TIM10_BASE => rcc_enable_peripheral(RccBus::Apb2, 17), // TIM10EN
+        TIM11_BASE => rcc_enable_peripheral(RccBus::Apb2, 18), // TIM11EN
+        _ => {
+            // Unknown timer: do nothing
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Master Mode (TRGO) Output
+// -----------------------------------------------------------------------------
+/// Event routed to the TRGO output (MMS[2:0] in CR2).
+///
+/// TRGO can trigger the ADC/DAC or act as the clock/start of a slave timer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TimMasterMode {
+    Reset = 0b000,        // UG bit (or slave mode reset)
+    Enable = 0b001,       // Counter enable (CEN)
+    Update = 0b010,       // Update event: one pulse per period
+    ComparePulse = 0b011, // CC1IF set (capture or compare match)
+    Oc1Ref = 0b100,       // OC1REF signal
+    Oc2Ref = 0b101,       // OC2REF signal
+    Oc3Ref = 0b110,       // OC3REF signal
+    Oc4Ref = 0b111,       // OC4REF signal
+}
+
+/// Runs a timer freely and emits TRGO on the selected event.
+///
+/// With `TimMasterMode::Update`, TRGO pulses once per period, i.e. at
+/// `f_tim / ((prescaler + 1) * (period + 1))`. Example: TIM2 at 90 MHz with
+/// prescaler 0 and period 8_999 gives a 10 kHz trigger.
+///
+/// # Arguments
+/// * `tim_base`  - The base address of a timer with TRGO (TIM1..TIM8)
+/// * `prescaler` - PSC value (counter clock = f_tim / (prescaler + 1))
+/// * `period`    - ARR value (period = period + 1 counter ticks)
+/// * `mode`      - Event routed to TRGO
+pub fn timer_trgo_init(tim_base: u32, prescaler: u32, period: u32, mode: TimMasterMode) {
+    timer_enable_clock(tim_base);
+
+    let cr1_addr = (tim_base + TIM_CR1) as *mut u32;
+
+    // Stop the counter while reconfiguring
+    reg_set_bit(cr1_addr, TIM_CR1_CEN_POS, false);
+
+    reg_set_val((tim_base + TIM_PSC) as *mut u32, prescaler & 0xFFFF);
+    reg_set_val((tim_base + TIM_ARR) as *mut u32, period);
+    reg_set_bits(
+        (tim_base + TIM_CR2) as *mut u32,
+        mode as u32,
+        TIM_CR2_MMS_POS,
+        3,
+    );
+
+    // PSC is buffered: force an update so it takes effect now, then drop the
+    // UIF flag this sets
+    reg_set_val((tim_base + TIM_EGR) as *mut u32, 1 << TIM_EGR_UG_POS);
+    timer_clear_update_flag(tim_base);
+
+    reg_set_bit(cr1_addr, TIM_CR1_ARPE_POS, true);
+    reg_set_bit(cr1_addr, TIM_CR1_CEN_POS, true);
+}
+
+// -----------------------------------------------------------------------------
+// Basic Periodic Interrupt
+// -----------------------------------------------------------------------------
+/// Starts a timer that raises the update interrupt once per period.
+///
+/// Intended for the basic timers TIM6/TIM7, but works with any timer. Only
+/// the timer side is configured; the NVIC line must be enabled separately
+/// (see `app::set_timers` for a complete example):
+///
+/// ```ignore
+/// timer_basic_init(TIM7_BASE, 89, 999); // 90 MHz / 90 / 1000 = 1 kHz
+/// nvic_enable_irq(IRQn::TIM7);
+/// // in TIM7_Handler: timer_clear_update_flag(TIM7_BASE);
+/// ```
+///
+/// # Arguments
+/// * `tim_base`  - The base address of the timer (e.g., `TIM6_BASE`)
+/// * `prescaler` - PSC value (0..65535; counter clock = f_tim / (prescaler + 1))
+/// * `period`    - ARR value (period = period + 1 counter ticks); 16-bit
+///   except on the 32-bit timers TIM2/TIM5
+pub fn timer_basic_init(tim_base: u32, prescaler: u32, period: u32) {
+    assert!(prescaler <= 0xFFFF, "timer prescaler must fit in 16 bits");
+    assert!(
+        tim_base == TIM2_BASE || tim_base == TIM5_BASE || period <= 0xFFFF,
+        "timer period must fit in 16 bits"
+    );
+
+    timer_enable_clock(tim_base);
+
+    let cr1_addr = (tim_base + TIM_CR1) as *mut u32;
+
+    // Stop the counter while reconfiguring
+    reg_set_bit(cr1_addr, TIM_CR1_CEN_POS, false);
+
+    reg_set_val((tim_base + TIM_PSC) as *mut u32, prescaler);
+    reg_set_val((tim_base + TIM_ARR) as *mut u32, period);
+
+    // Load PSC now, and drop the UIF flag this sets so the first interrupt
+    // comes after one full period
+    reg_set_val((tim_base + TIM_EGR) as *mut u32, 1 << TIM_EGR_UG_POS);
+    timer_clear_update_flag(tim_base);
+
+    reg_set_bit((tim_base + TIM_DIER) as *mut u32, TIM_DIER_UIE_POS, true);
+    reg_set_bit(cr1_addr, TIM_CR1_ARPE_POS, true);
+    reg_set_bit(cr1_addr, TIM_CR1_CEN_POS, true);
+}
+
+/// Clears the update interrupt flag (SR.UIF).
+///
+/// Call this first thing in the timer's interrupt handler, otherwise the
+/// interrupt re-enters immediately.
+pub fn timer_clear_update_flag(tim_base: u32) {
+    // SR flags are rc_w0: writing 1 to the other bits leaves them unchanged
+    reg_set_val(
+        (tim_base + TIM_SR) as *mut u32,
+        !(1 << TIM_SR_UIF_POS) & 0xFFFF,
+    );
+}
+
+// -----------------------------------------------------------------------------
+// PWM Output
+// -----------------------------------------------------------------------------
+/// Returns the CCRx address of a capture/compare channel (1..4).
+fn timer_ccr_addr(tim_base: u32, channel: u32) -> *mut u32 {
+    assert!((1..=4).contains(&channel), "timer channel must be 1..4");
+    (tim_base + TIM_CCR1 + (channel - 1) * 4) as *mut u32
+}
+
+/// Starts PWM mode 1 on one channel of a timer.
+///
+/// The output is high for `duty` counter ticks out of `period + 1`, so
+/// `duty = 0` is always low and `duty > period` always high. The PWM
+/// frequency is `f_tim / ((prescaler + 1) * (period + 1))`.
+///
+/// The pin must be configured separately as alternate function (AF1 for
+/// TIM1/TIM2, AF2 for TIM3..TIM5, AF3 for TIM8..TIM11).
+///
+/// # Arguments
+/// * `tim_base`  - The base address of a timer with capture/compare channels
+/// * `channel`   - Capture/compare channel (1..4)
+/// * `prescaler` - PSC value (0..65535; counter clock = f_tim / (prescaler + 1))
+/// * `period`    - ARR value (period = period + 1 counter ticks); 16-bit
+///   except on the 32-bit timers TIM2/TIM5
+/// * `duty`      - Initial CCRx value
+pub fn timer_pwm_init(tim_base: u32, channel: u32, prescaler: u32, period: u32, duty: u32) {
+    assert!(prescaler <= 0xFFFF, "timer prescaler must fit in 16 bits");
+    assert!(
+        tim_base == TIM2_BASE || tim_base == TIM5_BASE || period <= 0xFFFF,
+        "timer period must fit in 16 bits"
+    );
+    let ccr_addr = timer_ccr_addr(tim_base, channel);
+
+    timer_enable_clock(tim_base);
+
+    let cr1_addr = (tim_base + TIM_CR1) as *mut u32;
+
+    // Stop the counter while reconfiguring
+    reg_set_bit(cr1_addr, TIM_CR1_CEN_POS, false);
+
+    reg_set_val((tim_base + TIM_PSC) as *mut u32, prescaler);
+    reg_set_val((tim_base + TIM_ARR) as *mut u32, period);
+
+    // Channels 1/2 live in CCMR1, channels 3/4 in CCMR2 (8 bits each)
+    let ccmr_addr = if channel <= 2 {
+        (tim_base + TIM_CCMR1) as *mut u32
+    } else {
+        (tim_base + TIM_CCMR2) as *mut u32
+    };
+    let shift = ((channel - 1) % 2) * 8;
+    // CCxS = 00 (output), PWM mode 1, preload so duty changes apply per period
+    reg_set_bits(ccmr_addr, 0, shift, 2);
+    reg_set_bits(ccmr_addr, TIM_OCM_PWM1, shift + TIM_CCMR_OC1M_POS, 3);
+    reg_set_bit(ccmr_addr, shift + TIM_CCMR_OC1PE_POS, true);
+
+    reg_set_val(ccr_addr, duty);
+
+    // CCxE (active high polarity)
+    let ccer_addr = (tim_base + TIM_CCER) as *mut u32;
+    reg_set_bits(ccer_addr, 0b01, (channel - 1) * 4, 2);
+
+    // Advanced timers gate all outputs with MOE
+    if tim_base == TIM1_BASE || tim_base == TIM8_BASE {
+        reg_set_bit((tim_base + TIM_BDTR) as *mut u32, TIM_BDTR_MOE_POS, true);
+    }
+
+    // Load PSC/CCRx now, then drop the UIF flag this sets
+    reg_set_val((tim_base + TIM_EGR) as *mut u32, 1 << TIM_EGR_UG_POS);
+    timer_clear_update_flag(tim_base);
+
+    reg_set_bit(cr1_addr, TIM_CR1_ARPE_POS, true);
+    reg_set_bit(cr1_addr, TIM_CR1_CEN_POS, true);
+}
+
+/// Changes the PWM duty of a channel; takes effect at the next update event.
+///
+/// # Arguments
+/// * `tim_base` - The base address of the timer
+/// * `channel`  - Capture/compare channel (1..4)
+/// * `duty`     - New CCRx value (0..=period + 1)
+pub fn timer_set_duty(tim_base: u32, channel: u32, duty: u32) {
+    reg_set_val(timer_ccr_addr(tim_base, channel), duty);
+}
+
+// -----------------------------------------------------------------------------
+// Input Capture
+// -----------------------------------------------------------------------------
+/// Signal edge(s) that capture the counter into CCRx.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TimCaptureEdge {
+    Rising,
+    Falling,
+    Both,
+}
+
+/// IC filter: f_sampling = f_tim, N = 8 (ignores pulses shorter than 8
+/// timer clocks, i.e. < 89 ns at 90 MHz).
+const TIM_IC_FILTER_FCK_N8: u32 = 0b0011;
+
+/// Returns the largest ARR value: TIM2 and TIM5 are 32-bit, the others 16-bit.
+fn timer_max_period(tim_base: u32) -> u32 {
+    if tim_base == TIM2_BASE || tim_base == TIM5_BASE {
+        0xFFFF_FFFF
+    } else {
+        0xFFFF
+    }
+}
+
+/// Returns the CCMRx address and the bit offset of a channel's 8-bit field.
+fn timer_ccmr_slot(tim_base: u32, channel: u32) -> (*mut u32, u32) {
+    let ccmr_addr = if channel <= 2 {
+        (tim_base + TIM_CCMR1) as *mut u32
+    } else {
+        (tim_base + TIM_CCMR2) as *mut u32
+    };
+    (ccmr_addr, ((channel - 1) % 2) * 8)
+}
+
+/// Starts a timer as free-running counter that captures one channel's input.
+///
+/// The counter runs at the full timer clock (PSC = 0) over its whole range
+/// (ARR = 0xFFFF, or 0xFFFF_FFFF on TIM2/TIM5); every selected edge copies
+/// it into CCRx and sets CCxIF. Both the capture (CCxIE) and the update
+/// (UIE, counter overflow) interrupts are enabled, so the NVIC line of the
+/// timer only has to be enabled. The pin must be configured as alternate
+/// function (see `timer_pwm_init`).
+///
+/// Example: frequency from two rising edges, TIM3 channel 1 at 90 MHz
+/// ```ignore
+/// timer_input_capture_init(TIM3_BASE, 1, TimCaptureEdge::Rising);
+/// nvic_enable_irq(IRQn::TIM3);
+///
+/// // in TIM3_Handler (LAST and OVERFLOWS kept in statics):
+/// if timer_take_update_flag(TIM3_BASE) {
+///     overflows += 1;
+/// }
+/// if timer_capture_pending(TIM3_BASE, 1) {
+///     let now = timer_read_capture(TIM3_BASE, 1);
+///     // e.g. last = 60_000, now = 15_000, overflows = 1:
+///     // ticks = 1 * 65_536 + 15_000 - 60_000 = 20_536
+///     if let Some(ticks) = timer_capture_delta(last, now, overflows, 0xFFFF) {
+///         let hz = rcc_get_apb1_timer_clk_hz() as u64 / ticks; // 4382 Hz
+///     }
+///     last = now;
+///     overflows = 0;
+/// }
+/// ```
+/// If the overflow and the capture are pending together, the overflow came
+/// first only when the captured value is small (below half the range);
+/// count it for the next period otherwise.
+///
+/// # Arguments
+/// * `tim_base` - The base address of a timer with capture/compare channels
+/// * `channel`  - Capture/compare channel (1..4)
+/// * `edge`     - Edge(s) that trigger a capture
+pub fn timer_input_capture_init(tim_base: u32, channel: u32, edge: TimCaptureEdge) {
+    assert!((1..=4).contains(&channel), "timer channel must be 1..4");
+
+    timer_enable_clock(tim_base);
+
+    let cr1_addr = (tim_base + TIM_CR1) as *mut u32;
+    reg_set_bit(cr1_addr, TIM_CR1_CEN_POS, false);
+
+    reg_set_val((tim_base + TIM_PSC) as *mut u32, 0);
+    reg_set_val((tim_base + TIM_ARR) as *mut u32, timer_max_period(tim_base));
+
+    // CCxS = 01 (input, mapped on TIx), no prescaler (every edge), filter
+    let (ccmr_addr, shift) = timer_ccmr_slot(tim_base, channel);
+    reg_set_bits(ccmr_addr, 0b01, shift, 2);
+    reg_set_bits(ccmr_addr, 0b00, shift + TIM_CCMR_IC1PSC_POS, 2);
+    reg_set_bits(
+        ccmr_addr,
+        TIM_IC_FILTER_FCK_N8,
+        shift + TIM_CCMR_IC1F_POS,
+        4,
+    );
+
+    // Polarity (CCxNP:CCxP = 00 rising, 01 falling, 11 both) and CCxE
+    let ccer_addr = (tim_base + TIM_CCER) as *mut u32;
+    let ccer_shift = (channel - 1) * 4;
+    let (np, p) = match edge {
+        TimCaptureEdge::Rising => (false, false),
+        TimCaptureEdge::Falling => (false, true),
+        TimCaptureEdge::Both => (true, true),
+    };
+    reg_set_bit(ccer_addr, ccer_shift + TIM_CCER_CC1P_POS, p);
+    reg_set_bit(ccer_addr, ccer_shift + TIM_CCER_CC1NP_POS, np);
+    reg_set_bit(ccer_addr, ccer_shift, true);
+
+    // Load PSC, then start with no stale flags
+    reg_set_val((tim_base + TIM_EGR) as *mut u32, 1 << TIM_EGR_UG_POS);
+    reg_set_val((tim_base + TIM_SR) as *mut u32, 0);
+
+    let dier_addr = (tim_base + TIM_DIER) as *mut u32;
+    reg_set_bit(dier_addr, channel, true); // CCxIE
+    reg_set_bit(dier_addr, TIM_DIER_UIE_POS, true);
+    reg_set_bit(cr1_addr, TIM_CR1_CEN_POS, true);
+}
+
+/// Returns true if a new capture is waiting in CCRx (SR.CCxIF).
+pub fn timer_capture_pending(tim_base: u32, channel: u32) -> bool {
+    assert!((1..=4).contains(&channel), "timer channel must be 1..4");
+    reg_read_bit((tim_base + TIM_SR) as *mut u32, channel)
+}
+
+/// Returns the last captured counter value; reading CCRx clears CCxIF.
+///
+/// # Arguments
+/// * `tim_base` - The base address of the timer
+/// * `channel`  - Capture/compare channel (1..4)
+pub fn timer_read_capture(tim_base: u32, channel: u32) -> u32 {
+    unsafe { reg_read(timer_ccr_addr(tim_base, channel)) }
+}
+
+/// Returns true (and clears the flag) if the counter overflowed since the
+/// last call (SR.UIF).
+pub fn timer_take_update_flag(tim_base: u32) -> bool {
+    let pending = reg_read_bit((tim_base + TIM_SR) as *mut u32, TIM_SR_UIF_POS);
+    if pending {
+        timer_clear_update_flag(tim_base);
+    }
+    pending
+}
+
+/// Returns the number of timer ticks between two captures.
+///
+/// # Arguments
+/// * `previous`  - Earlier capture
+/// * `current`   - Later capture
+/// * `overflows` - Counter overflows (update events) between the two
+/// * `period`    - ARR value the counter wraps at
+///
+/// # Returns
+/// * The tick count, or `None` if the inputs cannot be consistent
+///   (`current` before `previous` with too few overflows, e.g. a stale
+///   `previous` or a missed overflow)
+pub const fn timer_capture_delta(
+    previous: u32,
+    current: u32,
+    overflows: u32,
+    period: u32,
+) -> Option<u64> {
+    (overflows as u64 * (period as u64 + 1) + current as u64).checked_sub(previous as u64)
+}
+
+// -----------------------------------------------------------------------------
+// Quadrature Encoder
+// -----------------------------------------------------------------------------
+/// Encoder interface mode (SMCR.SMS), i.e. which inputs' edges are counted.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TimEncoderMode {
+    /// Count on TI2 edges, depending on TI1 level (2 counts per cycle)
+    Ti1 = 0b001,
+    /// Count on TI1 edges, depending on TI2 level (2 counts per cycle)
+    Ti2 = 0b010,
+    /// Count on both TI1 and TI2 edges (4 counts per cycle)
+    Both = 0b011,
+}
+
+/// Counting direction reported by CR1.DIR.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TimDirection {
+    Up,
+    Down,
+}
+
+/// Configures a timer as quadrature encoder counter on its channels 1 and 2.
+///
+/// Both channels are mapped as inputs on TI1/TI2 with the input filter,
+/// non-inverted, and the counter wraps over its whole range (ARR = max).
+/// The A/B pins must be configured as alternate function (AF1 for TIM2,
+/// AF2 for TIM3..TIM5). Swap the wires (or `TimEncoderMode`) to invert the
+/// counting direction.
+///
+/// # Arguments
+/// * `tim_base` - The base address of the timer (TIM2..TIM5)
+/// * `mode`     - Which input edges are counted
+pub fn timer_encoder_init(tim_base: u32, mode: TimEncoderMode) {
+    assert!(
+        matches!(tim_base, TIM2_BASE | TIM3_BASE | TIM4_BASE | TIM5_BASE),
+        "encoder mode is supported on TIM2..TIM5"
+    );
+
+    timer_enable_clock(tim_base);
+
+    let cr1_addr = (tim_base + TIM_CR1) as *mut u32;
+    reg_set_bit(cr1_addr, TIM_CR1_CEN_POS, false);
+
+    reg_set_val((tim_base + TIM_PSC) as *mut u32, 0);
+    reg_set_val((tim_base + TIM_ARR) as *mut u32, timer_max_period(tim_base));
+
+    // CC1S = 01 (TI1), CC2S = 01 (TI2), both filtered, no prescaler
+    let ccmr1_addr = (tim_base + TIM_CCMR1) as *mut u32;
+    for shift in [0, 8] {
+        reg_set_bits(ccmr1_addr, 0b01, shift, 2);
+        reg_set_bits(ccmr1_addr, 0b00, shift + TIM_CCMR_IC1PSC_POS, 2);
+        reg_set_bits(
+            ccmr1_addr,
+            TIM_IC_FILTER_FCK_N8,
+            shift + TIM_CCMR_IC1F_POS,
+            4,
+        );
+    }
+
+    // Non-inverted inputs: CC1P/CC1NP and CC2P/CC2NP cleared
+    let ccer_addr = (tim_base + TIM_CCER) as *mut u32;
+    for shift in [0, 4] {
+        reg_set_bit(ccer_addr, shift + TIM_CCER_CC1P_POS, false);
+        reg_set_bit(ccer_addr, shift + TIM_CCER_CC1NP_POS, false);
+    }
+
+    reg_set_bits(
+        (tim_base + TIM_SMCR) as *mut u32,
+        mode as u32,
+        TIM_SMCR_SMS_POS,
+        3,
+    );
+
+    reg_set_val((tim_base + TIM_CNT) as *mut u32, 0);
+    reg_set_bit(cr1_addr, TIM_CR1_CEN_POS, true);
+}
+
+/// Returns the current encoder position (CNT).
+pub fn timer_encoder_count(tim_base: u32) -> u32 {
+    unsafe { reg_read((tim_base + TIM_CNT) as *mut u32) }
+}
+
+/// Returns the direction of the last encoder step (CR1.DIR).
+pub fn timer_encoder_direction(tim_base: u32) -> TimDirection {
+    if reg_read_bit((tim_base + TIM_CR1) as *mut u32, TIM_CR1_DIR_POS) {
+        TimDirection::Down
+    } else {
+        TimDirection::Up
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Unit tests (host): cargo test --target x86_64-unknown-linux-gnu
+// -----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_delta_without_overflow() {
+        assert_eq!(timer_capture_delta(1_000, 5_000, 0, 0xFFFF), Some(4_000));
+        assert_eq!(timer_capture_delta(7, 7, 0, 0xFFFF), Some(0));
+    }
+
+    #[test]
+    fn capture_delta_across_wraparound() {
+        // Example from `timer_input_capture_init`
+        assert_eq!(timer_capture_delta(60_000, 15_000, 1, 0xFFFF), Some(20_536));
+        assert_eq!(
+            timer_capture_delta(0xFFFF_FFF0, 0x10, 1, 0xFFFF_FFFF),
+            Some(0x20)
+        );
+        assert_eq!(timer_capture_delta(100, 50, 3, 999), Some(2_950));
+    }
+
+    #[test]
+    fn capture_delta_rejects_inconsistent_inputs() {
+        // Counter went backwards without an overflow: stale capture or
+        // missed update event
+        assert_eq!(timer_capture_delta(60_000, 15_000, 0, 0xFFFF), None);
+    }
+}
@@ -1,3 +1,405 @@
 // -----------------------------------------------------------------------------
 // STM32F429 UART (Universal Asynchronous Receiver-Transmitter) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions for polled operation of
+// the USART/UART peripherals (8 data bits, no parity, 1 stop bit).
+//
+// The GPIO pins must be configured separately, e.g. for USART1 on the
+// STM32F429I-DISC1 (connected to the ST-LINK virtual COM port):
+// `gpio_config_usart_tx(GPIOA_BASE, GPIO_PIN_9, 7)` and
+// `gpio_config_usart_rx(GPIOA_BASE, GPIO_PIN_10, 7)`.
+//
+// USART2 can also receive in the background: `usart2_rx_interrupt_enable`
+// lets `USART2_Handler` push every received byte into a ring buffer, which
+// the main loop drains with `usart_read_byte_nonblocking`.
+//
+// Reference: STM32F429 Reference Manual, section 30.6 (USART registers)
+// -----------------------------------------------------------------------------
+
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, AtomicUsize, Ordering};
+
+use crate::bsw::dma::*; // DMA streams for the transmit path
+use crate::bsw::nvic_cm4::nvic_enable_irq;
+use crate::bsw::rcc::rcc_enable_usart_clock;
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// USART Register Offsets (relative to USARTx base address)
+// -----------------------------------------------------------------------------
+pub const USART_SR: u32 = 0x00; // Status register
+pub const USART_DR: u32 = 0x04; // Data register
+pub const USART_BRR: u32 = 0x08; // Baud rate register
+pub const USART_CR1: u32 = 0x0C; // Control register 1
+pub const USART_CR2: u32 = 0x10; // Control register 2
+pub const USART_CR3: u32 = 0x14; // Control register 3
+pub const USART_GTPR: u32 = 0x18; // Guard time and prescaler register
+
+// -----------------------------------------------------------------------------
+// USART Register Bits
+// -----------------------------------------------------------------------------
+pub const USART_SR_ORE_POS: u32 = 3; // Overrun error
+pub const USART_SR_RXNE_POS: u32 = 5; // Read data register not empty
+pub const USART_SR_TC_POS: u32 = 6; // Transmission complete
+pub const USART_SR_TXE_POS: u32 = 7; // Transmit data register empty
+pub const USART_CR1_RE_POS: u32 = 2; // Receiver enable
+pub const USART_CR1_TE_POS: u32 = 3; // Transmitter enable
+pub const USART_CR1_RXNEIE_POS: u32 = 5; // RXNE interrupt enable
+pub const USART_CR1_UE_POS: u32 = 13; // USART enable
+pub const USART_CR3_DMAT_POS: u32 = 7; // DMA enable transmitter
+
+// -----------------------------------------------------------------------------
+// Initialize USART
+// -----------------------------------------------------------------------------
+/// Enables the USART clock and configures 8N1 at the requested baud rate.
+///
+/// # Arguments
+/// * `usart_base` - The base address of the USART (e.g., `USART1_BASE`)
+/// * `pclk_hz`    - Frequency of the APB clock feeding this USART (APB2 for USART1/6)
+/// * `baud`       - Desired baud rate (e.g., 115_200)
+pub fn usart_init(usart_base: u32, pclk_hz: u32, baud: u32) {
+    assert!(baud > 0, "baud must be non-zero");
+
+    rcc_enable_usart_clock(usart_base);
+
+    let cr1_addr = (usart_base + USART_CR1) as *mut u32;
+
+    // Disable the USART while changing the configuration
+    reg_set_val(cr1_addr, 0);
+
+    // With 16x oversampling, BRR holds USARTDIV * 16 = pclk / baud
+    // (mantissa in bits 15:4, fraction in bits 3:0). Round to nearest.
+    let brr = (pclk_hz + baud / 2) / baud;
+    reg_set_val((usart_base + USART_BRR) as *mut u32, brr & 0xFFFF);
+
+    // 1 stop bit, no flow control
+    reg_set_val((usart_base + USART_CR2) as *mut u32, 0);
+    reg_set_val((usart_base + USART_CR3) as *mut u32, 0);
+
+    // Enable transmitter, receiver, then the USART itself
+    reg_set_val(
+        cr1_addr,
+        (1 << USART_CR1_TE_POS) | (1 << USART_CR1_RE_POS) | (1 << USART_CR1_UE_POS),
+    );
+}
+
+// -----------------------------------------------------------------------------
+// Transmit
+// -----------------------------------------------------------------------------
+/// Writes one byte, waiting until the transmit data register is empty.
+///
+/// # Arguments
+/// * `usart_base` - The base address of the USART
+/// * `byte`       - Byte to send
+pub fn usart_write_byte(usart_base: u32, byte: u8) {
+    let sr_addr = (usart_base + USART_SR) as *mut u32;
+    while !reg_read_bit(sr_addr, USART_SR_TXE_POS) {}
+    reg_set_val((usart_base + USART_DR) as *mut u32, byte as u32);
+}
+
+/// Writes all bytes of a slice.
+pub fn usart_write(usart_base: u32, data: &[u8]) {
+    for &byte in data {
+        usart_write_byte(usart_base, byte);
+    }
+}
+
+/// Writes a string (no newline translation).
+pub fn usart_write_str(usart_base: u32, s: &str) {
+    usart_write(usart_base, s.as_bytes());
+}
+
+/// Waits until the last byte has left the shift register (TC set).
+///
+/// Call this before a reset or before disabling the USART, otherwise the
+/// final characters are lost.
+pub fn usart_flush(usart_base: u32) {
+    let sr_addr = (usart_base + USART_SR) as *mut u32;
+    while !reg_read_bit(sr_addr, USART_SR_TC_POS) {}
+}
+
+// -----------------------------------------------------------------------------
+// DMA Transmit
+// -----------------------------------------------------------------------------
+/// Returns the DMA channel of a USART's TX request on a stream, or `None`
+/// if that stream has no request line from the USART (RM0090 Tables 42/43).
+pub const fn usart_tx_dma_channel(usart_base: u32, dma_base: u32, stream: u32) -> Option<u32> {
+    match (usart_base, dma_base, stream) {
+        (USART1_BASE, DMA2_BASE, 7) => Some(4),
+        (USART2_BASE, DMA1_BASE, 6) => Some(4),
+        (USART3_BASE, DMA1_BASE, 3) => Some(4),
+        (USART3_BASE, DMA1_BASE, 4) => Some(7),
+        (UART4_BASE, DMA1_BASE, 4) => Some(4),
+        (UART5_BASE, DMA1_BASE, 7) => Some(4),
+        (USART6_BASE, DMA2_BASE, 6) | (USART6_BASE, DMA2_BASE, 7) => Some(5),
+        (UART7_BASE, DMA1_BASE, 1) => Some(5),
+        (UART8_BASE, DMA1_BASE, 0) => Some(5),
+        _ => None,
+    }
+}
+
+/// Starts sending a buffer with DMA and returns immediately.
+///
+/// The stream copies one byte into DR per TXE request, so the CPU is free
+/// during the transfer. Poll `usart_tx_dma_busy` (or wait with
+/// `usart_tx_dma_wait`) before starting the next one. The buffer is
+/// `'static` because it is read after this function has returned.
+///
+/// Example: `usart_write_dma(USART1_BASE, DMA2_BASE, 7, b"hello\r\n")`
+///
+/// # Arguments
+/// * `usart_base` - The base address of the USART (initialized)
+/// * `dma_base`   - DMA1_BASE or DMA2_BASE, see `usart_tx_dma_channel`
+/// * `stream`     - Stream with a request line from this USART's TX
+/// * `data`       - Bytes to send (1..=`DMA_MAX_TRANSFER_ITEMS`)
+pub fn usart_write_dma(usart_base: u32, dma_base: u32, stream: u32, data: &'static [u8]) {
+    assert!(
+        !data.is_empty() && data.len() as u32 <= DMA_MAX_TRANSFER_ITEMS,
+        "DMA transfer length must be 1..65535"
+    );
+    let Some(channel) = usart_tx_dma_channel(usart_base, dma_base, stream) else {
+        panic!("no USART TX request on this DMA stream");
+    };
+
+    dma_enable_clock(dma_base);
+    dma_stream_disable(dma_base, stream);
+    dma_clear_flags(dma_base, stream);
+
+    reg_set_val(
+        dma_stream_reg(dma_base, stream, DMA_SXPAR),
+        usart_base + USART_DR,
+    );
+    reg_set_val(
+        dma_stream_reg(dma_base, stream, DMA_SXM0AR),
+        data.as_ptr() as u32,
+    );
+    reg_set_val(
+        dma_stream_reg(dma_base, stream, DMA_SXNDTR),
+        data.len() as u32,
+    );
+    reg_set_val(dma_stream_reg(dma_base, stream, DMA_SXFCR), 0); // Direct mode
+    let cr_addr = dma_stream_reg(dma_base, stream, DMA_SXCR);
+    reg_set_val(
+        cr_addr,
+        (channel << DMA_SXCR_CHSEL_POS)
+            | (DMA_SIZE_BYTE << DMA_SXCR_MSIZE_POS)
+            | (DMA_SIZE_BYTE << DMA_SXCR_PSIZE_POS)
+            | (1 << DMA_SXCR_MINC_POS)
+            | (DMA_DIR_MEM_TO_PERIPH << DMA_SXCR_DIR_POS),
+    );
+
+    // TC is cleared by writing 0, so it only reports the end of this transfer
+    reg_set_bit((usart_base + USART_SR) as *mut u32, USART_SR_TC_POS, false);
+    reg_set_bit(
+        (usart_base + USART_CR3) as *mut u32,
+        USART_CR3_DMAT_POS,
+        true,
+    );
+    reg_set_bit(cr_addr, DMA_SXCR_EN_POS, true);
+}
+
+/// Returns true while a DMA transmit on the stream is still running.
+///
+/// The hardware clears EN after the last byte was handed to the USART (or
+/// on a transfer error); the USART may still be shifting it out, see
+/// `usart_flush`.
+pub fn usart_tx_dma_busy(dma_base: u32, stream: u32) -> bool {
+    reg_read_bit(dma_stream_reg(dma_base, stream, DMA_SXCR), DMA_SXCR_EN_POS)
+}
+
+/// Waits for a DMA transmit to finish (TCIF) and releases the stream.
+///
+/// # Returns
+/// * `Ok(())` once every byte was handed to the USART, otherwise the
+///   `DmaError` of the stream
+pub fn usart_tx_dma_wait(usart_base: u32, dma_base: u32, stream: u32) -> Result<(), DmaError> {
+    let result = dma_wait_complete(dma_base, stream, 0);
+    reg_set_bit(
+        (usart_base + USART_CR3) as *mut u32,
+        USART_CR3_DMAT_POS,
+        false,
+    );
+    result
+}
+
+// -----------------------------------------------------------------------------
+// Receive
+// -----------------------------------------------------------------------------
+/// Reads one byte, waiting until one is received.
+pub fn usart_read_byte(usart_base: u32) -> u8 {
+    let sr_addr = (usart_base + USART_SR) as *mut u32;
+    while !reg_read_bit(sr_addr, USART_SR_RXNE_POS) {}
+    (unsafe { reg_read((usart_base + USART_DR) as *mut u32) } & 0xFF) as u8
+}
+
+// -----------------------------------------------------------------------------
+// Interrupt-Driven Receive (USART2)
+// -----------------------------------------------------------------------------
+// Single-producer/single-consumer ring buffer: only `USART2_Handler` writes
+// `RX_HEAD` and the slots, only the main loop writes `RX_TAIL`. One slot is
+// kept free to tell "full" from "empty".
+const USART_RX_BUFFER_SIZE: usize = 128;
+
+static RX_BUFFER: [AtomicU8; USART_RX_BUFFER_SIZE] =
+    [const { AtomicU8::new(0) }; USART_RX_BUFFER_SIZE];
+static RX_HEAD: AtomicUsize = AtomicUsize::new(0);
+static RX_TAIL: AtomicUsize = AtomicUsize::new(0);
+static RX_OVERRUN: AtomicBool = AtomicBool::new(false);
+
+/// Starts background reception on USART2 (already set up with `usart_init`).
+///
+/// Enables RXNEIE and the USART2 IRQ; from then on, do not use
+/// `usart_read_byte` on USART2 since the handler consumes every byte.
+pub fn usart2_rx_interrupt_enable() {
+    RX_HEAD.store(0, Ordering::Relaxed);
+    RX_TAIL.store(0, Ordering::Relaxed);
+    RX_OVERRUN.store(false, Ordering::Relaxed);
+
+    reg_set_bit(
+        (USART2_BASE + USART_CR1) as *mut u32,
+        USART_CR1_RXNEIE_POS,
+        true,
+    );
+    nvic_enable_irq(IRQn::USART2);
+}
+
+/// Returns the next received byte, or `None` if the buffer is empty.
+pub fn usart_read_byte_nonblocking() -> Option<u8> {
+    let tail = RX_TAIL.load(Ordering::Relaxed);
+    if tail == RX_HEAD.load(Ordering::Acquire) {
+        return None;
+    }
+    let byte = RX_BUFFER[tail].load(Ordering::Relaxed);
+    RX_TAIL.store((tail + 1) % USART_RX_BUFFER_SIZE, Ordering::Release);
+    Some(byte)
+}
+
+/// Returns the number of received bytes waiting in the buffer.
+pub fn usart_rx_available() -> usize {
+    let head = RX_HEAD.load(Ordering::Acquire);
+    let tail = RX_TAIL.load(Ordering::Relaxed);
+    (head + USART_RX_BUFFER_SIZE - tail) % USART_RX_BUFFER_SIZE
+}
+
+/// Returns whether bytes were lost since the last call, and clears the flag.
+///
+/// Bytes are lost when the ring buffer is full or when the hardware
+/// overruns (ORE) because the handler was blocked for too long.
+pub fn usart_rx_take_overrun() -> bool {
+    RX_OVERRUN.swap(false, Ordering::Relaxed)
+}
+
+/// USART2 interrupt: moves the received byte into the ring buffer.
+#[unsafe(no_mangle)]
+pub extern "C" fn USART2_Handler() {
+    let sr = unsafe { reg_read_ro((USART2_BASE + USART_SR) as ReadOnlyRegister) };
+    if sr & ((1 << USART_SR_RXNE_POS) | (1 << USART_SR_ORE_POS)) == 0 {
+        return;
+    }
+
+    // Reading SR then DR clears both RXNE and ORE
+    let byte = (unsafe { reg_read((USART2_BASE + USART_DR) as *mut u32) } & 0xFF) as u8;
+    if sr & (1 << USART_SR_ORE_POS) != 0 {
+        RX_OVERRUN.store(true, Ordering::Relaxed);
+    }
+
+    let head = RX_HEAD.load(Ordering::Relaxed);
+    let next = (head + 1) % USART_RX_BUFFER_SIZE;
+    if next == RX_TAIL.load(Ordering::Acquire) {
+        // Buffer full: drop the byte
+        RX_OVERRUN.store(true, Ordering::Relaxed);
+        return;
+    }
+    RX_BUFFER[head].store(byte, Ordering::Relaxed);
+    RX_HEAD.store(next, Ordering::Release);
+}
+
+// -----------------------------------------------------------------------------
+// Formatted Output (core::fmt::Write)
+// -----------------------------------------------------------------------------
+/// A USART used as a text output, so `write!`/`writeln!` work without std.
+///
+/// Example
+/// ```ignore
+/// use core::fmt::Write;
+/// let mut port = SerialPort::new(USART1_BASE, true);
+/// writeln!(port, "SYSCLK = {} Hz", rcc_get_sysclk_hz()).ok();
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SerialPort {
+    /// Base address of the (initialized) USART
+    pub usart_base: u32,
+    /// Translate `\n` into `\r\n` for terminal programs
+    pub crlf: bool,
+}
+
+impl SerialPort {
+    /// Creates a text output on an already initialized USART (`usart_init`).
+    pub const fn new(usart_base: u32, crlf: bool) -> Self {
+        SerialPort { usart_base, crlf }
+    }
+}
+
+impl fmt::Write for SerialPort {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &byte in s.as_bytes() {
+            if self.crlf && byte == b'\n' {
+                usart_write_byte(self.usart_base, b'\r');
+            }
+            usart_write_byte(self.usart_base, byte);
+        }
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Global Logger
+// -----------------------------------------------------------------------------
+// One SerialPort can be registered as the target of `serial_print!` /
+// `serial_println!` (and of panic messages). Stored as atomics so it can be
+// read from any context; 0 means "no logger".
+static LOGGER_BASE: AtomicU32 = AtomicU32::new(0);
+static LOGGER_CRLF: AtomicBool = AtomicBool::new(false);
+
+/// Routes `serial_print!`/`serial_println!` output to `port`.
+pub fn set_logger(port: SerialPort) {
+    LOGGER_CRLF.store(port.crlf, Ordering::Relaxed);
+    LOGGER_BASE.store(port.usart_base, Ordering::Release);
+}
+
+/// Returns the registered logger, if any.
+pub fn logger() -> Option<SerialPort> {
+    match LOGGER_BASE.load(Ordering::Acquire) {
+        0 => None,
+        base => Some(SerialPort::new(base, LOGGER_CRLF.load(Ordering::Relaxed))),
+    }
+}
+
+/// Writes formatted text to the logger. Used by `serial_print!`; output is
+/// silently dropped if no logger is set.
+pub fn serial_print_fmt(args: fmt::Arguments) {
+    if let Some(mut port) = logger() {
+        let _ = fmt::Write::write_fmt(&mut port, args);
+    }
+}
+
+/// Prints to the logger set with `set_logger` (like `print!`).
+#[macro_export]
+macro_rules! serial_print {
+    ($($arg:tt)*) => {
+        $crate::bsw::uart::serial_print_fmt(format_args!($($arg)*))
+    };
+}
+
+/// Prints a line to the logger set with `set_logger` (like `println!`).
+#[macro_export]
+macro_rules! serial_println {
+    () => {
+        $crate::serial_print!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::bsw::uart::serial_print_fmt(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
@@ -1,3 +1,85 @@
 // -----------------------------------------------------------------------------
 // STM32F429 WDG (Watchdog) utilities
 // -----------------------------------------------------------------------------
+//
+// This module provides constants and helper functions for the Independent
+// Watchdog (IWDG). The IWDG is clocked by the 32 kHz LSI and, once started,
+// cannot be stopped except by a reset. If it is not fed (reloaded) before the
+// down-counter reaches 0, it resets the MCU.
+//
+// Reference: STM32F429 Reference Manual, section 21 (IWDG)
+// -----------------------------------------------------------------------------
+
+use crate::bsw::reg_mcu_stm32f429zi::*; // MCU register base addresses and constants
+use crate::bsw::reg_utils::*; // Register access helper functions
+
+// -----------------------------------------------------------------------------
+// IWDG Register Offsets (relative to IWDG_BASE)
+// -----------------------------------------------------------------------------
+pub const IWDG_KR: u32 = 0x00; // Key register
+pub const IWDG_PR: u32 = 0x04; // Prescaler register
+pub const IWDG_RLR: u32 = 0x08; // Reload register
+pub const IWDG_SR: u32 = 0x0C; // Status register
+
+// -----------------------------------------------------------------------------
+// IWDG Key Values (written to IWDG_KR)
+// -----------------------------------------------------------------------------
+pub const IWDG_KEY_RELOAD: u32 = 0xAAAA; // Reload the counter ("feed")
+pub const IWDG_KEY_UNLOCK: u32 = 0x5555; // Enable write access to PR and RLR
+pub const IWDG_KEY_START: u32 = 0xCCCC; // Start the watchdog
+
+/// Maximum reload value (RLR is 12 bits wide).
+pub const IWDG_RELOAD_MAX: u32 = 0x0FFF;
+
+/// LSI clock divider (PR[2:0]). One counter tick = divider / 32 kHz.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum IwdgPrescaler {
+    Div4 = 0,   // 125 us tick, max timeout ~0.5 s
+    Div8 = 1,   // 250 us tick, max timeout ~1 s
+    Div16 = 2,  // 500 us tick, max timeout ~2 s
+    Div32 = 3,  // 1 ms tick, max timeout ~4 s
+    Div64 = 4,  // 2 ms tick, max timeout ~8 s
+    Div128 = 5, // 4 ms tick, max timeout ~16 s
+    Div256 = 6, // 8 ms tick, max timeout ~32 s
+}
+
+// -----------------------------------------------------------------------------
+// Start the Independent Watchdog
+// -----------------------------------------------------------------------------
+/// Configures and starts the IWDG.
+///
+/// The timeout is `divider * (reload + 1) / 32_000` seconds, e.g.
+/// `IwdgPrescaler::Div32` and reload 99 gives ~100 ms.
+///
+/// # Arguments
+/// * `prescaler` - LSI clock divider
+/// * `reload`    - Reload value (0..=0xFFF)
+pub fn iwdg_start(prescaler: IwdgPrescaler, reload: u32) {
+    assert!(reload <= IWDG_RELOAD_MAX, "reload must fit in 12 bits");
+
+    let kr_addr = (IWDG_BASE + IWDG_KR) as *mut u32;
+    let sr_addr = (IWDG_BASE + IWDG_SR) as *mut u32;
+
+    // Start the watchdog (this also turns on the LSI)
+    reg_set_val(kr_addr, IWDG_KEY_START);
+
+    // Unlock PR/RLR and program the timeout
+    reg_set_val(kr_addr, IWDG_KEY_UNLOCK);
+    reg_set_val((IWDG_BASE + IWDG_PR) as *mut u32, prescaler as u32);
+    reg_set_val((IWDG_BASE + IWDG_RLR) as *mut u32, reload);
+
+    // Wait until both PVU (bit 0) and RVU (bit 1) are cleared, i.e. the
+    // values have been transferred to the LSI clock domain
+    let _ = reg_wait_bits(sr_addr, 0, 0b11, 0, 100_000);
+
+    // Load the new reload value into the counter
+    iwdg_feed();
+}
+
+// -----------------------------------------------------------------------------
+// Feed the Independent Watchdog
+// -----------------------------------------------------------------------------
+/// Reloads the IWDG counter. Must be called periodically once started.
+pub fn iwdg_feed() {
+    reg_set_val((IWDG_BASE + IWDG_KR) as *mut u32, IWDG_KEY_RELOAD);
+}
@@ -2,41 +2,101 @@
 //!
 //! A minimal bare-metal Rust application for the STM32F429I-DISC1 board.
 
-#![no_std] // Do not link the Rust standard library (required for embedded)
-#![no_main] // Disable all Rust-level entry points
+// Unit tests (`cargo test --target x86_64-unknown-linux-gnu`) build with std
+// and the test harness' entry point; everything else is bare metal.
+#![cfg_attr(not(test), no_std)] // Do not link the Rust standard library (required for embedded)
+#![cfg_attr(not(test), no_main)] // Disable all Rust-level entry points
 #![allow(clippy::empty_loop)]
 #![allow(dead_code)]
+#![cfg_attr(test, allow(unused_imports))]
 
+use crate::app::blinker::*;
 use crate::app::led::*;
 use crate::app::system_clock::*;
 use crate::app::systick_delay::*;
+#[cfg(feature = "watchdog-selftest")]
+use crate::app::watchdog_selftest::watchdog_selftest;
+use crate::bsw::rcc::rcc_get_hclk_hz;
 
 use core::panic::PanicInfo;
 
 mod app;
 mod bsw;
+mod util;
 
 /// The main entry point for the application.
 ///
 /// # Safety
 /// This function is marked unsafe because it accesses a mutable static variable.
+#[cfg(not(test))]
 #[unsafe(no_mangle)]
 fn main() -> ! {
-    system_clock_setup();
-    systick_init();
+    // On failure SYSCLK stays on the 16 MHz HSI. Everything below derives its
+    // timing from the actual clock, so keep running and flag it on the red LED.
+    let clock_ok = system_clock_setup().is_ok();
+
+    #[cfg(feature = "watchdog-selftest")]
+    watchdog_selftest();
+
+    systick_init(SystickClkSource::CoreDiv8, rcc_get_hclk_hz());
     led_init();
     system_clock_output_pa8();
+    if !clock_ok {
+        LED_RED.init();
+        LED_RED.on();
+    }
 
+    let mut heartbeat = Blinker::new(LED_GREEN, 1000);
+    heartbeat.start(millis());
     loop {
-        led_toggle();
-        delay_ms(1000);
+        heartbeat.update(millis());
     }
 }
 
 /// Panic handler for the application.
 ///
-/// Traps the MCU in an infinite loop on panic.
+/// Traps the MCU in an infinite loop on panic. With the `panic-report`
+/// feature, the panic location and message are reported first.
+#[cfg(not(test))]
 #[panic_handler]
 fn panic_handler(_info: &PanicInfo) -> ! {
+    #[cfg(feature = "panic-report")]
+    panic_report(_info);
+
     loop {}
 }
+
+/// Writes "panicked at file:line:column: message" to ITM stimulus port 0
+/// and to the serial logger (if one is set, see `bsw::uart::set_logger`).
+#[cfg(feature = "panic-report")]
+fn panic_report(info: &PanicInfo) {
+    use crate::bsw::itm_cm4::ItmPort;
+    use crate::bsw::uart::logger;
+    use core::fmt::Write;
+
+    // Nothing else should run (or print) from here on
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        core::arch::asm!("cpsid i")
+    };
+
+    let mut itm = ItmPort(0);
+    let mut serial = logger();
+    let mut report = |args: core::fmt::Arguments| {
+        let _ = itm.write_fmt(args);
+        if let Some(port) = serial.as_mut() {
+            let _ = port.write_fmt(args);
+        }
+    };
+
+    match info.location() {
+        Some(loc) => report(format_args!(
+            "\npanicked at {}:{}:{}: ",
+            loc.file(),
+            loc.line(),
+            loc.column()
+        )),
+        None => report(format_args!("\npanicked: ")),
+    }
+    report(format_args!("{}\n", info.message()));
+}
@@ -0,0 +1,416 @@
+// -----------------------------------------------------------------------------
+// COBS message framing
+// -----------------------------------------------------------------------------
+//
+// Consistent Overhead Byte Stuffing (COBS) removes every 0x00 from a message
+// so that 0x00 can be used as an unambiguous frame delimiter on a byte stream
+// (e.g., the USART). After noise or a lost byte the receiver resynchronizes
+// at the next 0x00.
+//
+// Encoding overhead is at most 1 byte per 254 bytes of payload, plus 1.
+//
+// Wire format: `cobs_encode(payload)` followed by a single 0x00 delimiter.
+//
+// Example (test vector from the COBS paper / Wikipedia):
+// ```ignore
+// let mut out = [0u8; 8];
+// let n = cobs_encode(&[0x11, 0x22, 0x00, 0x33], &mut out);
+// assert_eq!(&out[..n], &[0x03, 0x11, 0x22, 0x02, 0x33]);
+// ```
+// -----------------------------------------------------------------------------
+
+/// Errors reported while decoding a COBS frame.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CobsError {
+    /// The output buffer cannot hold the decoded data
+    OutputTooSmall,
+    /// A 0x00 byte was found inside the encoded data
+    UnexpectedZero,
+    /// A code byte points past the end of the encoded data
+    Truncated,
+}
+
+/// Maximum encoded length (without the delimiter) for a payload of `len` bytes.
+pub const fn cobs_max_encoded_len(len: usize) -> usize {
+    len + len / 254 + 1
+}
+
+// -----------------------------------------------------------------------------
+// Encode
+// -----------------------------------------------------------------------------
+/// COBS-encodes `input` into `output` and returns the encoded length.
+///
+/// The 0x00 frame delimiter is not appended.
+///
+/// # Arguments
+/// * `input`  - Payload (may contain any byte values)
+/// * `output` - Destination, at least `cobs_max_encoded_len(input.len())` bytes
+pub fn cobs_encode(input: &[u8], output: &mut [u8]) -> usize {
+    assert!(
+        output.len() >= cobs_max_encoded_len(input.len()),
+        "output buffer too small for COBS encoding"
+    );
+
+    let mut code_index = 0; // Where the current block's code byte goes
+    let mut write = 1;
+    let mut code: u8 = 1; // Distance to the next zero (block length + 1)
+
+    for (i, &byte) in input.iter().enumerate() {
+        if byte == 0 {
+            output[code_index] = code;
+            code_index = write;
+            write += 1;
+            code = 1;
+        } else {
+            output[write] = byte;
+            write += 1;
+            code += 1;
+            if code == 0xFF && i + 1 < input.len() {
+                // Maximum block length reached: close it without an implied zero
+                // (at the end of the input the final write below closes it)
+                output[code_index] = code;
+                code_index = write;
+                write += 1;
+                code = 1;
+            }
+        }
+    }
+    output[code_index] = code;
+
+    write
+}
+
+// -----------------------------------------------------------------------------
+// Decode
+// -----------------------------------------------------------------------------
+/// Decodes one COBS frame (without its 0x00 delimiter) into `output`.
+///
+/// # Arguments
+/// * `input`  - Encoded bytes of a single frame
+/// * `output` - Destination for the decoded payload
+///
+/// # Returns
+/// * `Ok(len)` with the decoded length, or a `CobsError`
+pub fn cobs_decode(input: &[u8], output: &mut [u8]) -> Result<usize, CobsError> {
+    let mut read = 0;
+    let mut write = 0;
+
+    while read < input.len() {
+        let code = input[read];
+        if code == 0 {
+            return Err(CobsError::UnexpectedZero);
+        }
+        read += 1;
+
+        for _ in 1..code {
+            let byte = *input.get(read).ok_or(CobsError::Truncated)?;
+            if byte == 0 {
+                return Err(CobsError::UnexpectedZero);
+            }
+            *output.get_mut(write).ok_or(CobsError::OutputTooSmall)? = byte;
+            read += 1;
+            write += 1;
+        }
+
+        // Every block except a maximum-length one and the last one ends in a zero
+        if code != 0xFF && read < input.len() {
+            *output.get_mut(write).ok_or(CobsError::OutputTooSmall)? = 0;
+            write += 1;
+        }
+    }
+
+    Ok(write)
+}
+
+/// Decodes a COBS frame in place and returns the decoded length.
+///
+/// Works because decoded data is never longer than the encoded data and the
+/// write position never overtakes the read position.
+pub fn cobs_decode_in_place(buf: &mut [u8]) -> Result<usize, CobsError> {
+    let mut read = 0;
+    let mut write = 0;
+
+    while read < buf.len() {
+        let code = buf[read];
+        if code == 0 {
+            return Err(CobsError::UnexpectedZero);
+        }
+        read += 1;
+
+        for _ in 1..code {
+            let byte = *buf.get(read).ok_or(CobsError::Truncated)?;
+            if byte == 0 {
+                return Err(CobsError::UnexpectedZero);
+            }
+            buf[write] = byte;
+            read += 1;
+            write += 1;
+        }
+
+        if code != 0xFF && read < buf.len() {
+            buf[write] = 0;
+            write += 1;
+        }
+    }
+
+    Ok(write)
+}
+
+// -----------------------------------------------------------------------------
+// Stream Framer
+// -----------------------------------------------------------------------------
+/// Collects a received byte stream into COBS frames.
+///
+/// Feed every received byte (e.g., drained from the USART RX buffer); when a
+/// 0x00 delimiter completes a valid frame, the decoded payload is returned.
+/// Frames that overflow the `N`-byte buffer or fail to decode are dropped and
+/// counted, and reception resumes at the next delimiter.
+///
+/// Example
+/// ```ignore
+/// let mut framer: Framer<64> = Framer::new();
+/// while let Some(byte) = usart_read_byte_nonblocking() {
+///     if let Some(frame) = framer.feed(byte) {
+///         handle_message(frame);
+///     }
+/// }
+/// ```
+pub struct Framer<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    overflowed: bool,
+    dropped: u32,
+}
+
+impl<const N: usize> Default for Framer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Framer<N> {
+    /// Creates an empty framer.
+    pub const fn new() -> Self {
+        Framer {
+            buf: [0; N],
+            len: 0,
+            overflowed: false,
+            dropped: 0,
+        }
+    }
+
+    /// Feeds one received byte. Returns the decoded payload when `byte` is
+    /// the delimiter closing a valid frame.
+    ///
+    /// Back-to-back delimiters (line idle or resync) return `None`. An
+    /// encoded empty payload (0x01 0x00 on the wire) is a valid frame and
+    /// returns `Some(&[])`.
+    pub fn feed(&mut self, byte: u8) -> Option<&[u8]> {
+        if byte != 0 {
+            if self.len < N {
+                self.buf[self.len] = byte;
+                self.len += 1;
+            } else {
+                self.overflowed = true;
+            }
+            return None;
+        }
+
+        // Delimiter: close the current frame
+        let len = self.len;
+        let overflowed = self.overflowed;
+        self.len = 0;
+        self.overflowed = false;
+
+        if len == 0 {
+            // Back-to-back delimiters (idle/resync): nothing to report
+            return None;
+        }
+        if overflowed {
+            self.dropped = self.dropped.wrapping_add(1);
+            return None;
+        }
+
+        match cobs_decode_in_place(&mut self.buf[..len]) {
+            Ok(decoded) => Some(&self.buf[..decoded]),
+            Err(_) => {
+                self.dropped = self.dropped.wrapping_add(1);
+                None
+            }
+        }
+    }
+
+    /// Discards any partially received frame.
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.overflowed = false;
+    }
+
+    /// Number of frames dropped (overflow or invalid encoding).
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Unit tests (host): cargo test --target x86_64-unknown-linux-gnu
+// -----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes `input` and checks the result against `expected`, then decodes
+    /// `expected` (both ways) and checks it gives `input` back.
+    fn check_vector(input: &[u8], expected: &[u8]) {
+        let mut encoded = [0u8; 300];
+        let n = cobs_encode(input, &mut encoded);
+        assert_eq!(&encoded[..n], expected);
+
+        let mut decoded = [0u8; 300];
+        let n = cobs_decode(expected, &mut decoded).unwrap();
+        assert_eq!(&decoded[..n], input);
+
+        let mut buf = [0u8; 300];
+        buf[..expected.len()].copy_from_slice(expected);
+        let n = cobs_decode_in_place(&mut buf[..expected.len()]).unwrap();
+        assert_eq!(&buf[..n], input);
+    }
+
+    /// Bytes `first..=last` followed by `suffix`.
+    fn run(first: u8, last: u8, suffix: &[u8]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = (first..=last).collect();
+        bytes.extend_from_slice(suffix);
+        bytes
+    }
+
+    #[test]
+    fn reference_vectors_short() {
+        check_vector(&[], &[0x01]);
+        check_vector(&[0x00], &[0x01, 0x01]);
+        check_vector(&[0x00, 0x00], &[0x01, 0x01, 0x01]);
+        check_vector(&[0x00, 0x11, 0x00], &[0x01, 0x02, 0x11, 0x01]);
+        check_vector(&[0x11, 0x22, 0x00, 0x33], &[0x03, 0x11, 0x22, 0x02, 0x33]);
+        check_vector(&[0x11, 0x22, 0x33, 0x44], &[0x05, 0x11, 0x22, 0x33, 0x44]);
+        check_vector(&[0x11, 0x00, 0x00, 0x00], &[0x02, 0x11, 0x01, 0x01, 0x01]);
+    }
+
+    #[test]
+    fn reference_vectors_254_byte_runs() {
+        // 01..FE: 254 non-zero bytes fill exactly one block
+        let mut expected = vec![0xFF];
+        expected.extend(1..=0xFE);
+        check_vector(&run(0x01, 0xFE, &[]), &expected);
+
+        // 00 01..FE
+        let mut input = vec![0x00];
+        input.extend(1..=0xFE);
+        let mut expected = vec![0x01, 0xFF];
+        expected.extend(1..=0xFE);
+        check_vector(&input, &expected);
+
+        // 01..FF: the 255th byte starts a new block
+        let mut expected = vec![0xFF];
+        expected.extend(1..=0xFE);
+        expected.extend([0x02, 0xFF]);
+        check_vector(&run(0x01, 0xFF, &[]), &expected);
+
+        // 02..FF 00
+        let mut expected = vec![0xFF];
+        expected.extend(2..=0xFF);
+        expected.extend([0x01, 0x01]);
+        check_vector(&run(0x02, 0xFF, &[0x00]), &expected);
+
+        // 03..FF 00 01
+        let mut expected = vec![0xFE];
+        expected.extend(3..=0xFF);
+        expected.extend([0x02, 0x01]);
+        check_vector(&run(0x03, 0xFF, &[0x00, 0x01]), &expected);
+    }
+
+    #[test]
+    fn round_trip_all_lengths() {
+        let mut seed: u32 = 1;
+        for len in 0..600usize {
+            let input: Vec<u8> = (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    // About one zero in eight, and long zero-free runs
+                    let byte = (seed >> 16) as u8;
+                    if len.is_multiple_of(3) || !byte.is_multiple_of(8) {
+                        byte | 1
+                    } else {
+                        0
+                    }
+                })
+                .collect();
+
+            let mut encoded = vec![0u8; cobs_max_encoded_len(len)];
+            let n = cobs_encode(&input, &mut encoded);
+            assert!(!encoded[..n].contains(&0), "len {len}");
+
+            let mut decoded = vec![0u8; len];
+            assert_eq!(cobs_decode(&encoded[..n], &mut decoded), Ok(len));
+            assert_eq!(decoded, input, "len {len}");
+        }
+    }
+
+    #[test]
+    fn decode_errors() {
+        let mut out = [0u8; 8];
+        assert_eq!(
+            cobs_decode(&[0x03, 0x11], &mut out),
+            Err(CobsError::Truncated)
+        );
+        assert_eq!(
+            cobs_decode(&[0x03, 0x00, 0x11], &mut out),
+            Err(CobsError::UnexpectedZero)
+        );
+        assert_eq!(
+            cobs_decode(&[0x05, 1, 2, 3, 4], &mut out[..3]),
+            Err(CobsError::OutputTooSmall)
+        );
+    }
+
+    /// Feeds `bytes` and returns the last frame reported, if any.
+    fn feed_all<const N: usize>(framer: &mut Framer<N>, bytes: &[u8]) -> Option<Vec<u8>> {
+        let mut frame = None;
+        for &byte in bytes {
+            if let Some(payload) = framer.feed(byte) {
+                frame = Some(payload.to_vec());
+            }
+        }
+        frame
+    }
+
+    #[test]
+    fn framer_delivers_frames_and_skips_idle_delimiters() {
+        let mut framer: Framer<16> = Framer::new();
+        assert_eq!(feed_all(&mut framer, &[0x00, 0x00]), None);
+        assert_eq!(
+            feed_all(&mut framer, &[0x03, 0x11, 0x22, 0x02, 0x33, 0x00]),
+            Some(vec![0x11, 0x22, 0x00, 0x33])
+        );
+        assert_eq!(feed_all(&mut framer, &[0x01, 0x00]), Some(vec![]));
+        assert_eq!(framer.dropped(), 0);
+    }
+
+    #[test]
+    fn framer_resyncs_after_garbage() {
+        let mut framer: Framer<16> = Framer::new();
+        // Tail of a frame whose start was lost: the code byte points too far
+        assert_eq!(feed_all(&mut framer, &[0x07, 0x42, 0x00]), None);
+        assert_eq!(framer.dropped(), 1);
+        assert_eq!(
+            feed_all(&mut framer, &[0x05, 0x11, 0x22, 0x33, 0x44, 0x00]),
+            Some(vec![0x11, 0x22, 0x33, 0x44])
+        );
+    }
+
+    #[test]
+    fn framer_drops_overflowing_frame() {
+        let mut framer: Framer<4> = Framer::new();
+        assert_eq!(feed_all(&mut framer, &[0x06, 1, 2, 3, 4, 5, 0x00]), None);
+        assert_eq!(framer.dropped(), 1);
+        assert_eq!(feed_all(&mut framer, &[0x03, 1, 2, 0x00]), Some(vec![1, 2]));
+    }
+}
@@ -0,0 +1,255 @@
+// -----------------------------------------------------------------------------
+// Memory copy/fill routines
+// -----------------------------------------------------------------------------
+//
+// Word-optimized `fast_copy` / `fast_set` for `no_std` builds. When source and
+// destination share the same alignment, the bulk of the data is moved as
+// 32-bit words and only the unaligned head and the tail are moved as bytes;
+// otherwise the copy falls back to bytes.
+//
+// With the `mem-intrinsics` feature enabled they are also exported as the
+// `memcpy` / `memset` symbols the compiler emits calls to for large struct
+// moves and `[0u8; N]` initialization. The export is limited to the bare-metal
+// target: on the host, exporting `memcpy` / `memset` would override the C
+// library's for the whole test binary, including the test harness and std.
+//
+// All accesses are volatile on purpose: plain loops would be recognized by
+// LLVM as "this is a memcpy" and turned back into a call to `memcpy`, which
+// would recurse forever once these functions *are* `memcpy`.
+// -----------------------------------------------------------------------------
+
+use core::ptr;
+
+/// Word size used by the fast paths (bytes).
+const WORD: usize = 4;
+
+/// Returns how many bytes must be handled one by one before `addr` is word aligned.
+#[inline(always)]
+const fn bytes_to_alignment(addr: usize) -> usize {
+    (WORD - (addr & (WORD - 1))) & (WORD - 1)
+}
+
+// -----------------------------------------------------------------------------
+// Copy
+// -----------------------------------------------------------------------------
+/// Copies `len` bytes from `src` to `dst`.
+///
+/// # Arguments
+/// * `dst` - Destination start address
+/// * `src` - Source start address
+/// * `len` - Number of bytes to copy
+///
+/// # Safety
+/// - `src` must be valid for `len` bytes of reads and `dst` for `len` bytes of writes.
+/// - The regions must not overlap (same contract as `memcpy`).
+pub unsafe fn fast_copy(dst: *mut u8, src: *const u8, len: usize) {
+    let mut d = dst;
+    let mut s = src;
+    let mut remaining = len;
+
+    unsafe {
+        // Word copy is only possible if both pointers can be aligned together
+        if (d as usize & (WORD - 1)) == (s as usize & (WORD - 1)) {
+            // Head: bytes until both are word aligned
+            let head = bytes_to_alignment(d as usize).min(remaining);
+            for _ in 0..head {
+                ptr::write_volatile(d, ptr::read_volatile(s));
+                d = d.add(1);
+                s = s.add(1);
+            }
+            remaining -= head;
+
+            // Body: whole words
+            let mut dw = d as *mut u32;
+            let mut sw = s as *const u32;
+            while remaining >= WORD {
+                ptr::write_volatile(dw, ptr::read_volatile(sw));
+                dw = dw.add(1);
+                sw = sw.add(1);
+                remaining -= WORD;
+            }
+            d = dw as *mut u8;
+            s = sw as *const u8;
+        }
+
+        // Tail (or everything, for mismatched alignment): bytes
+        while remaining > 0 {
+            ptr::write_volatile(d, ptr::read_volatile(s));
+            d = d.add(1);
+            s = s.add(1);
+            remaining -= 1;
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Fill
+// -----------------------------------------------------------------------------
+/// Sets `len` bytes starting at `dst` to `val`.
+///
+/// # Arguments
+/// * `dst` - Destination start address
+/// * `val` - Byte value to write
+/// * `len` - Number of bytes to write
+///
+/// # Safety
+/// - `dst` must be valid for `len` bytes of writes.
+pub unsafe fn fast_set(dst: *mut u8, val: u8, len: usize) {
+    let mut d = dst;
+    let mut remaining = len;
+
+    unsafe {
+        // Head: bytes until word aligned
+        let head = bytes_to_alignment(d as usize).min(remaining);
+        for _ in 0..head {
+            ptr::write_volatile(d, val);
+            d = d.add(1);
+        }
+        remaining -= head;
+
+        // Body: the byte replicated into all four lanes of a word
+        let word = u32::from_ne_bytes([val; WORD]);
+        let mut dw = d as *mut u32;
+        while remaining >= WORD {
+            ptr::write_volatile(dw, word);
+            dw = dw.add(1);
+            remaining -= WORD;
+        }
+        d = dw as *mut u8;
+
+        // Tail
+        while remaining > 0 {
+            ptr::write_volatile(d, val);
+            d = d.add(1);
+            remaining -= 1;
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Compiler intrinsics (optional)
+// -----------------------------------------------------------------------------
+/// `memcpy` symbol backed by `fast_copy` (feature `mem-intrinsics`).
+///
+/// # Safety
+/// Same contract as C `memcpy`.
+#[cfg(all(feature = "mem-intrinsics", target_os = "none"))]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn memcpy(dest: *mut u8, src: *const u8, n: usize) -> *mut u8 {
+    unsafe { fast_copy(dest, src, n) };
+    dest
+}
+
+/// `memset` symbol backed by `fast_set` (feature `mem-intrinsics`).
+///
+/// # Safety
+/// Same contract as C `memset` (only the low byte of `c` is used).
+#[cfg(all(feature = "mem-intrinsics", target_os = "none"))]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn memset(s: *mut u8, c: i32, n: usize) -> *mut u8 {
+    unsafe { fast_set(s, c as u8, n) };
+    s
+}
+
+// -----------------------------------------------------------------------------
+// Unit tests (host): cargo test --target x86_64-unknown-linux-gnu
+// -----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Word-aligned test buffer, so the offsets below decide the alignment.
+    #[repr(align(4))]
+    struct Buf([u8; 64]);
+
+    /// Bytes 1, 2, 3, ... (never 0, so a missed byte shows up).
+    fn pattern() -> Buf {
+        Buf(core::array::from_fn(|i| i as u8 + 1))
+    }
+
+    /// Runs `fast_copy` for `len` bytes at the given offsets and compares
+    /// the whole destination with `copy_from_slice`.
+    fn check_copy(dst_off: usize, src_off: usize, len: usize) {
+        let src = pattern();
+        let mut dst = Buf([0; 64]);
+        let mut expected = [0u8; 64];
+        expected[dst_off..dst_off + len].copy_from_slice(&src.0[src_off..src_off + len]);
+
+        unsafe {
+            fast_copy(
+                dst.0.as_mut_ptr().add(dst_off),
+                src.0.as_ptr().add(src_off),
+                len,
+            )
+        };
+        assert_eq!(
+            dst.0, expected,
+            "dst_off {dst_off} src_off {src_off} len {len}"
+        );
+    }
+
+    /// Runs `fast_set` for `len` bytes at `off` and compares with `fill`.
+    fn check_set(off: usize, len: usize) {
+        let mut dst = pattern();
+        let mut expected = pattern().0;
+        expected[off..off + len].fill(0xA5);
+
+        unsafe { fast_set(dst.0.as_mut_ptr().add(off), 0xA5, len) };
+        assert_eq!(dst.0, expected, "off {off} len {len}");
+    }
+
+    #[test]
+    fn copy_aligned() {
+        check_copy(0, 0, 32);
+        check_copy(4, 8, 40);
+    }
+
+    #[test]
+    fn copy_unaligned_head_and_tail() {
+        for off in 1..4 {
+            for len in [5, 13, 30] {
+                check_copy(off, off, len);
+            }
+        }
+    }
+
+    #[test]
+    fn copy_mismatched_alignment() {
+        check_copy(1, 2, 31);
+        check_copy(0, 3, 17);
+    }
+
+    #[test]
+    fn copy_zero_and_sub_word_lengths() {
+        for off in 0..4 {
+            for len in 0..4 {
+                check_copy(off, off, len);
+                check_copy(off, 3 - off, len);
+            }
+        }
+    }
+
+    #[test]
+    fn set_aligned() {
+        check_set(0, 32);
+        check_set(8, 41);
+    }
+
+    #[test]
+    fn set_unaligned_head_and_tail() {
+        for off in 1..4 {
+            for len in [5, 13, 30] {
+                check_set(off, len);
+            }
+        }
+    }
+
+    #[test]
+    fn set_zero_and_sub_word_lengths() {
+        for off in 0..4 {
+            for len in 0..4 {
+                check_set(off, len);
+            }
+        }
+    }
+}
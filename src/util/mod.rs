@@ -0,0 +1,7 @@
+//! Utility (util) root module.
+//!
+//! Hardware-independent helpers (memory routines, data encoding, ...) used by
+//! the bsw and app layers. Nothing in here touches peripheral registers.
+
+pub mod framing;
+pub mod mem;